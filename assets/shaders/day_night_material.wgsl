@@ -0,0 +1,9 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0) var<uniform> tint_color: vec4<f32>;
+@group(2) @binding(1) var<uniform> strength: f32;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4f(tint_color.rgb, strength);
+}
@@ -0,0 +1,38 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0) var<uniform> fire_color: vec4<f32>;
+@group(2) @binding(1) var<uniform> fireball_radius: f32;
+@group(2) @binding(2) var<uniform> progress: f32;
+@group(2) @binding(3) var<uniform> opacity: f32;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    // Distance from the center of explosion relative to the half size of mesh.
+    let p: vec2f = mesh.uv * 2.0 - vec2f(1.0);
+    let dist: f32 = length(p);
+    let angle: f32 = atan2(p.y, p.x);
+
+    // Ragged edge of fireball.
+    let flicker: f32 = 0.06 * sin(angle * 7.0 + progress * 20.0)
+        + 0.04 * sin(angle * 13.0 - progress * 31.0);
+    let radius: f32 = fireball_radius * (1.0 + flicker);
+
+    var color: vec4f = vec4f(0.0);
+    if (dist < radius) {
+        let t: f32 = dist / max(radius, 0.0001);
+        let core_color: vec3f = vec3f(1.0, 0.95, 0.7);
+        color = vec4f(mix(core_color, fire_color.rgb, smoothstep(0.0, 0.9, t)), opacity);
+    }
+
+    // Hot ring on the edge of fireball.
+    let ring: f32 = (1.0 - smoothstep(0.0, 0.06, abs(dist - radius))) * opacity;
+    color = vec4f(mix(color.rgb, vec3f(1.0, 0.8, 0.3), ring), max(color.a, ring));
+
+    // Shockwave runs ahead of fireball and fades out.
+    let wave_radius: f32 = sqrt(progress);
+    let wave: f32 = (1.0 - smoothstep(0.0, 0.03, abs(dist - wave_radius))) * (1.0 - progress) * 0.5;
+
+    let res_alpha: f32 = color.a + wave * (1.0 - color.a);
+    let res_rgb: vec3f = (color.rgb * color.a + vec3f(1.0) * wave * (1.0 - color.a)) / max(res_alpha, 0.0001);
+    return vec4f(res_rgb, res_alpha);
+}
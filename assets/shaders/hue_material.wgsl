@@ -3,11 +3,13 @@
 @group(2) @binding(0) var<uniform> hue_offset: f32;
 @group(2) @binding(1) var color_texture: texture_2d<f32>;
 @group(2) @binding(2) var color_sampler: sampler;
+@group(2) @binding(3) var<uniform> alpha: f32;
 
 @fragment
 fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
     let texel = textureSample(color_texture, color_sampler, mesh.uv);
-    return rotate_hue(texel, hue_offset);
+    let color = rotate_hue(texel, hue_offset);
+    return vec4f(color.rgb, color.a * alpha);
 }
 
 fn rotate_hue(rgba: vec4f, offset: f32) -> vec4f {
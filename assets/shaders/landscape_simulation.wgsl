@@ -0,0 +1,60 @@
+struct Params {
+    width: u32,
+    height: u32,
+    // Count of pixels in a row of the buffer, including padding.
+    stride: u32,
+    steps: u32,
+    circles_count: u32,
+    rock_color: u32,
+    rock_resistance: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Colors of pixels of landscape row by row from the top one,
+// zero is an empty pixel.
+@group(0) @binding(1) var<storage, read_write> pixels: array<u32>;
+// Circles of explosions: x, row and radius.
+@group(0) @binding(2) var<storage, read> circles: array<vec4<i32>>;
+
+@compute @workgroup_size(8, 8, 1)
+fn destroy(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let index = id.y * params.stride + id.x;
+    let color = pixels[index];
+    if color == 0u {
+        return;
+    }
+    var resistance = 0.0;
+    if color == params.rock_color {
+        resistance = params.rock_resistance;
+    }
+    let point = vec2<f32>(vec2<i32>(id.xy));
+    for (var i = 0u; i < params.circles_count; i++) {
+        let circle = circles[i];
+        let radius = f32(circle.z);
+        if distance(point, vec2<f32>(circle.xy)) < radius * (1.0 - resistance) {
+            pixels[index] = 0u;
+            return;
+        }
+    }
+}
+
+// Every invocation drops pixels of its own column by one row per step.
+@compute @workgroup_size(64, 1, 1)
+fn subside(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width {
+        return;
+    }
+    for (var step = 0u; step < params.steps; step++) {
+        for (var row = params.height - 1u; row > 0u; row--) {
+            let index = row * params.stride + id.x;
+            let above = index - params.stride;
+            if pixels[index] == 0u && pixels[above] != 0u {
+                pixels[index] = pixels[above];
+                pixels[above] = 0u;
+            }
+        }
+    }
+}
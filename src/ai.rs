@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::cloak::Cloaked;
+use crate::components::Position;
+use crate::decoy::Decoy;
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::AppState;
+use crate::landscape::Landscape;
+use crate::launch::LaunchOptions;
+use crate::rules::{
+    clamp_gun_angle, clamp_gun_power, GameRules, Shot, MAX_GUN_ANGLE, MAX_GUN_POWER, MIN_GUN_ANGLE,
+};
+use crate::scanner::Revealed;
+use crate::simulation::ShootCommand;
+use crate::tank::{AimingTank, Tank, TankSet};
+use crate::teams::Team;
+use crate::G;
+
+/// Time of "thinking" of bot before a shot (seconds).
+const THINKING_TIME: f32 = 1.;
+/// Steps of angle and power of shots checked by bot.
+const ANGLE_STEP: f32 = 5.;
+const POWER_STEP: f32 = 5.;
+/// Flight time simulated for every checked shot (seconds).
+const PREDICTION_DURATION: f32 = 10.;
+const MAX_PREDICTION_POINTS: usize = 3000;
+/// Bot doesn't shoot closer than this distance to its own tank.
+const SAFE_DISTANCE: f32 = 60.;
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                ai_aiming_system
+                    .before(TankSet::Aiming)
+                    .run_if(in_state(AppState::Aiming)),
+                assign_launch_bots_system.run_if(resource_exists::<LaunchOptions>),
+            ),
+        );
+    }
+}
+
+/// Skill of bot in aiming.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AiDifficulty {
+    Easy,
+    Normal,
+    /// Bot shoots the best shot which it has found.
+    #[default]
+    Hard,
+}
+
+impl AiDifficulty {
+    /// Maximal error of angle (degrees) and power of bot's shots.
+    pub fn aim_error(&self) -> f32 {
+        match self {
+            Self::Easy => 10.,
+            Self::Normal => 4.,
+            Self::Hard => 0.,
+        }
+    }
+}
+
+/// Tank with this component is controlled by bot.
+#[derive(Debug, Default, Clone, Component)]
+pub struct AiController {
+    difficulty: AiDifficulty,
+    thinking_time: f32,
+    planned_shot: Option<Shot>,
+}
+
+impl AiController {
+    pub fn new(difficulty: AiDifficulty) -> Self {
+        Self {
+            difficulty,
+            ..default()
+        }
+    }
+}
+
+/// Gives tanks of players to bots according to `--ai` launch option.
+fn assign_launch_bots_system(
+    mut commands: Commands,
+    launch_options: Res<LaunchOptions>,
+    new_tanks_query: Query<(Entity, &Tank), Added<Tank>>,
+) {
+    for (entity, tank) in new_tanks_query.iter() {
+        if launch_options.is_bot(tank.player_number) {
+            commands
+                .entity(entity)
+                .insert(AiController::new(launch_options.ai_difficulty));
+        }
+    }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn ai_aiming_system(
+    time: Res<Time>,
+    game_field: Option<Res<GameField>>,
+    game_rng: Option<ResMut<GameRng>>,
+    rules: Res<GameRules>,
+    mut ai_tanks: Query<(&mut Tank, &Position, &mut AiController, Option<&Team>), With<AimingTank>>,
+    targets_query: Query<
+        (&Position, Has<Cloaked>, Has<Revealed>, Option<&Team>),
+        (With<Tank>, Without<AimingTank>),
+    >,
+    decoys_query: Query<(&Position, &Decoy)>,
+    mut shoot_commands: EventWriter<ShootCommand>,
+) {
+    let (Some(game_field), Some(mut game_rng)) = (game_field, game_rng) else {
+        return;
+    };
+    for (mut tank, &Position(position), mut ai, ai_team) in ai_tanks.iter_mut() {
+        let ai_player = tank.player_number;
+        let is_teammate = |player_number: u8| {
+            rules
+                .teams
+                .is_some_and(|teams| teams.are_teammates(player_number, ai_player))
+        };
+        let shot = match ai.planned_shot {
+            Some(shot) => shot,
+            None => {
+                // Bot doesn't see cloaked tanks unless they have been revealed.
+                let targets: Vec<Vec2> = targets_query
+                    .iter()
+                    .filter(|&(_, cloaked, revealed, _)| !cloaked || revealed)
+                    .filter(|&(.., team)| ai_team.is_none() || team != ai_team)
+                    .map(|(p, ..)| p.0)
+                    // Bot can't tell decoys of opponents from real tanks.
+                    .chain(
+                        decoys_query
+                            .iter()
+                            .filter(|(_, decoy)| !is_teammate(decoy.player_number))
+                            .filter(|(_, decoy)| decoy.player_number != tank.player_number)
+                            .map(|(p, _)| p.0),
+                    )
+                    .collect();
+                let mut shot = plan_shot(
+                    &tank,
+                    position,
+                    game_field.wind_power,
+                    &game_field.landscape,
+                    &targets,
+                );
+                let aim_error = ai.difficulty.aim_error();
+                if aim_error > 0. {
+                    let rng = &mut *game_rng;
+                    shot.angle = clamp_gun_angle(shot.angle + rng.gen_range(-aim_error..aim_error));
+                    shot.power = clamp_gun_power(shot.power + rng.gen_range(-aim_error..aim_error));
+                }
+                ai.planned_shot = Some(shot);
+                // Show the aim while bot is "thinking"
+                tank.set_gun_angle(shot.angle);
+                tank.set_gun_power(shot.power);
+                shot
+            }
+        };
+
+        ai.thinking_time += time.delta_seconds();
+        if ai.thinking_time >= THINKING_TIME {
+            ai.thinking_time = 0.;
+            ai.planned_shot = None;
+            shoot_commands.send(ShootCommand(shot));
+        }
+    }
+}
+
+/// Returns shot which hits the landscape closest to one of targets.
+fn plan_shot(
+    tank: &Tank,
+    position: Vec2,
+    wind: f32,
+    landscape: &Landscape,
+    targets: &[Vec2],
+) -> Shot {
+    let (width, height) = landscape.size();
+    let borders = (width as i32, height as i32);
+    let acceleration = Vec2::new(wind, -G);
+
+    let mut best_shot = Shot {
+        player: tank.player_number,
+        angle: tank.gun_angle_deg(),
+        power: tank.power,
+        weapon: None,
+    };
+    let mut best_distance = f32::MAX;
+    let mut probe = tank.clone();
+    let angles_count = ((MAX_GUN_ANGLE - MIN_GUN_ANGLE) / ANGLE_STEP) as usize;
+    let powers_count = (MAX_GUN_POWER / POWER_STEP) as usize;
+    for angle_index in 1..angles_count {
+        let angle = MIN_GUN_ANGLE + ANGLE_STEP * angle_index as f32;
+        for power_index in 1..=powers_count {
+            let power = POWER_STEP * power_index as f32;
+            probe.set_gun_angle(angle);
+            probe.set_gun_power(power);
+            let missile = probe.shoot(position, acceleration);
+            let impact = missile
+                .predict_path(PREDICTION_DURATION, borders, MAX_PREDICTION_POINTS)
+                .into_iter()
+                .find(|&(x, y)| y <= 0 || landscape.is_not_empty(x, y));
+            let Some((x, y)) = impact else {
+                continue;
+            };
+            let impact = Vec2::new(x as f32, y as f32);
+            if impact.distance(position) < SAFE_DISTANCE {
+                continue;
+            }
+            let distance = targets
+                .iter()
+                .map(|target| target.distance(impact))
+                .fold(f32::MAX, f32::min);
+            if distance < best_distance {
+                best_distance = distance;
+                best_shot.angle = angle;
+                best_shot.power = power;
+            }
+        }
+    }
+    best_shot
+}
@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+
+use crate::components::{Owner, Position};
+use crate::game_field::GameField;
+use crate::game_plugin::{AimingMode, AppState};
+use crate::input::PlayerAction;
+use crate::missile::{spawn_missile, Missile};
+use crate::orbital_strike::Reticle;
+use crate::tank::{AimingTank, TankShotEvent};
+use crate::weapons::{WeaponDefinition, WeaponKind, Weapons};
+use crate::G;
+
+const BOMBS_COUNT: usize = 5;
+/// Width of line of bombs around the target.
+const SWEEP_WIDTH: f32 = 160.;
+/// Distance between the top of game field and the plane.
+const PLANE_ALTITUDE: f32 = 15.;
+/// Speed of plane (pixels per second).
+const PLANE_SPEED: f32 = 300.;
+/// Horizontal speed of dropped bombs.
+const BOMB_POWER: f32 = 15.;
+
+pub struct AirstrikePlugin;
+
+impl Plugin for AirstrikePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            call_airstrike_system
+                .run_if(in_state(AppState::Aiming))
+                .run_if(in_state(AimingMode::Reticle)),
+        )
+        .add_systems(Update, fly_planes_system)
+        .add_systems(OnEnter(AppState::RoundSetup), despawn_planes_system);
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct Plane {
+    /// Direction of flight along x-axis (1 or -1).
+    direction: f32,
+    /// X-coordinates of bombs which haven't been dropped yet,
+    /// in order of their dropping.
+    bombs: Vec<f32>,
+    weapon: Option<WeaponDefinition>,
+    owner: Option<Owner>,
+}
+
+impl Plane {
+    /// Removes bombs which the plane has passed by reaching given
+    /// x-coordinate and returns their x-coordinates.
+    fn drop_bombs(&mut self, x: f32) -> Vec<f32> {
+        let count = self
+            .bombs
+            .iter()
+            .take_while(|&&bomb_x| self.direction * (x - bomb_x) >= 0.)
+            .count();
+        self.bombs.drain(..count).collect()
+    }
+}
+
+/// Returns x-coordinates of bombs of airstrike in order of their dropping.
+fn bomb_positions(target_x: f32, direction: f32) -> impl Iterator<Item = f32> {
+    let step = SWEEP_WIDTH / (BOMBS_COUNT - 1) as f32;
+    let start_x = target_x - direction * SWEEP_WIDTH / 2.;
+    (0..BOMBS_COUNT).map(move |i| start_x + direction * step * i as f32)
+}
+
+/// Calls airstrike onto the reticle when player fires. Bombs are
+/// dropped by the plane as a line along its sweep.
+fn call_airstrike_system(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    weapons: Res<Weapons>,
+    mut actions: EventReader<PlayerAction>,
+    reticles: Query<&Reticle>,
+    aiming_tanks: Query<Entity, With<AimingTank>>,
+    mut shot_events: EventWriter<TankShotEvent>,
+) {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    let (Ok(reticle), Ok(tank_entity)) = (reticles.get_single(), aiming_tanks.get_single()) else {
+        return;
+    };
+    if !fire || reticle.kind != WeaponKind::Airstrike {
+        return;
+    }
+    info!("Airstrike has been called to x={}", reticle.x);
+    airstrike(
+        &mut commands,
+        &game_field,
+        reticle.x,
+        reticle.direction,
+        weapons.of_kind(WeaponKind::Airstrike),
+        Some(Owner(tank_entity)),
+    );
+    shot_events.send(TankShotEvent { tank_entity });
+}
+
+/// Plane flies across the top of game field in given direction
+/// and drops a line of bombs around the target.
+pub fn airstrike(
+    commands: &mut Commands,
+    game_field: &GameField,
+    target_x: f32,
+    direction: f32,
+    weapon: Option<&WeaponDefinition>,
+    owner: Option<Owner>,
+) {
+    let width = game_field.width as f32;
+    let altitude = game_field.height as f32 - PLANE_ALTITUDE;
+    let bombs = bomb_positions(target_x, direction)
+        .filter(|x| (0. ..width).contains(x))
+        .collect();
+
+    // Plane starts its flight behind the border of game field.
+    let start_x = if direction > 0. { 0. } else { width };
+    let plane_entity = commands
+        .spawn((
+            SpriteBundle {
+                texture: game_field.plane_texture.clone(),
+                sprite: Sprite {
+                    flip_x: direction < 0.,
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(start_x, altitude, 3.)),
+                ..default()
+            },
+            Position(Vec2::new(start_x, altitude)),
+            Plane {
+                direction,
+                bombs,
+                weapon: weapon.cloned(),
+                owner,
+            },
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(plane_entity);
+}
+
+fn fly_planes_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_field: Option<Res<GameField>>,
+    mut planes: Query<(Entity, &mut Plane, &mut Position)>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let width = game_field.width as f32;
+    let acceleration = Vec2::new(game_field.wind_power, -G);
+    for (entity, mut plane, mut position) in planes.iter_mut() {
+        position.0.x += plane.direction * PLANE_SPEED * time.delta_seconds();
+        for x in plane.drop_bombs(position.0.x) {
+            let missile = Missile::new(
+                Vec2::new(x, position.0.y),
+                plane.direction * 90.,
+                BOMB_POWER,
+                acceleration,
+            );
+            spawn_missile(
+                &mut commands,
+                &game_field,
+                missile,
+                plane.weapon.as_ref(),
+                plane.owner,
+            );
+        }
+        if !(0. ..=width).contains(&position.0.x) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn despawn_planes_system(mut commands: Commands, planes: Query<Entity, With<Plane>>) {
+    for entity in planes.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bomb_positions() {
+        let positions: Vec<f32> = bomb_positions(200., 1.).collect();
+        assert_eq!(positions, vec![120., 160., 200., 240., 280.]);
+        let positions: Vec<f32> = bomb_positions(200., -1.).collect();
+        assert_eq!(positions, vec![280., 240., 200., 160., 120.]);
+    }
+
+    #[test]
+    fn test_drop_bombs() {
+        let mut plane = Plane {
+            direction: -1.,
+            bombs: bomb_positions(200., -1.).collect(),
+            weapon: None,
+            owner: None,
+        };
+        assert!(plane.drop_bombs(300.).is_empty());
+        assert_eq!(plane.drop_bombs(240.), vec![280., 240.]);
+        assert!(plane.drop_bombs(230.).is_empty());
+        assert_eq!(plane.drop_bombs(0.), vec![200., 160., 120.]);
+        assert!(plane.bombs.is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use bevy::prelude::*;
+
+use crate::decoy::DecoyDestroyedEvent;
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::hazards::{HazardEvent, HazardKind};
+use crate::range::TargetHitEvent;
+use crate::settings::{HudLayout, Settings};
+use crate::supply::SupplyDropEvent;
+use crate::tank::{TankDamagedEvent, TankDestroyedEvent};
+use crate::turn::{RoundEndedEvent, SuddenDeathEvent, TurnStartedEvent};
+use crate::weapons::Weapons;
+
+/// Damage of one explosion which is announced as a direct hit.
+const DIRECT_HIT_DAMAGE: u8 = 50;
+/// Time (seconds) during which an announcement is shown.
+const SHOW_TIME: f32 = 3.;
+/// Time (seconds) at the end of showing during which an announcement fades out.
+const FADE_TIME: f32 = 1.;
+const MAX_VISIBLE: usize = 4;
+const FONT_SIZE: f32 = 24.;
+/// Size of font of announcements in broadcast layout of HUD,
+/// so the kill-feed is readable on streams.
+const BROADCAST_FONT_SIZE: f32 = 36.;
+
+/// Generates announcements from game events.
+pub struct AnnouncementsPlugin;
+
+impl Plugin for AnnouncementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AnnouncementEvent>()
+            .add_systems(Update, announce_game_events_system);
+    }
+}
+
+/// Shows announcements at the top of the screen.
+pub struct AnnouncementsOverlayPlugin;
+
+impl Plugin for AnnouncementsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            setup_announcements_overlay.after(setup_game_field),
+        )
+        .add_systems(
+            Update,
+            (show_announcements_system, fade_announcements_system)
+                .chain()
+                .after(announce_game_events_system),
+        );
+    }
+}
+
+/// Short message for players about something that happened in the game.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct AnnouncementEvent {
+    pub text: String,
+}
+
+#[derive(Component)]
+struct AnnouncementsOverlay;
+
+#[derive(Component)]
+struct Announcement {
+    /// Time (seconds) passed since the announcement has been shown.
+    age: f32,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn announce_game_events_system(
+    mut turn_started_events: EventReader<TurnStartedEvent>,
+    mut damaged_events: EventReader<TankDamagedEvent>,
+    mut destroyed_events: EventReader<TankDestroyedEvent>,
+    mut sudden_death_events: EventReader<SuddenDeathEvent>,
+    mut round_ended_events: EventReader<RoundEndedEvent>,
+    mut decoy_destroyed_events: EventReader<DecoyDestroyedEvent>,
+    mut target_hit_events: EventReader<TargetHitEvent>,
+    mut supply_events: EventReader<SupplyDropEvent>,
+    mut hazard_events: EventReader<HazardEvent>,
+    weapons: Res<Weapons>,
+    game_rng: Option<Res<GameRng>>,
+    mut announcements: EventWriter<AnnouncementEvent>,
+) {
+    let mut announce = |text: String| announcements.send(AnnouncementEvent { text });
+    for event in damaged_events.read() {
+        let is_enemy = event
+            .attacker
+            .is_some_and(|attacker| attacker != event.player_number);
+        if is_enemy && event.damage >= DIRECT_HIT_DAMAGE {
+            announce("Direct hit!".to_string());
+        }
+    }
+    for event in destroyed_events.read() {
+        let text = match event.killer {
+            None => format!("Player {} eliminated", event.player_number),
+            Some(_) => event.to_string(),
+        };
+        announce(text);
+    }
+    for event in decoy_destroyed_events.read() {
+        announce(format!("Decoy of Player {} popped", event.player_number));
+    }
+    for event in target_hit_events.read() {
+        announce(format!("Target hit! Score: {}", event.score));
+    }
+    for event in supply_events.read() {
+        announce(format!(
+            "Player {} picked up {}",
+            event.player_number,
+            event.item.name(&weapons)
+        ));
+    }
+    for event in hazard_events.read() {
+        let text = match event.kind {
+            HazardKind::MeteorShower => "Meteor shower!",
+            HazardKind::Airstrike => "Airstrike incoming!",
+        };
+        announce(text.to_string());
+    }
+    for _ in sudden_death_events.read() {
+        announce("Sudden death!".to_string());
+    }
+    for event in round_ended_events.read() {
+        let text = match (event.winning_team, event.winner) {
+            (Some(team), _) => format!("Team {} wins the round", team),
+            (None, Some(winner)) => format!("Player {} wins the round", winner),
+            (None, None) => "Nobody survived".to_string(),
+        };
+        announce(text);
+    }
+    for event in turn_started_events.read() {
+        // Seed is shown at start of the round, so the round can be replayed.
+        if let (1, false, Some(game_rng)) = (event.turn_number, event.extra_shot, &game_rng) {
+            announce(format!("Seed of the round: {}", game_rng.seed()));
+        }
+        announce(format!("Player {}'s turn", event.player_number));
+    }
+}
+
+fn setup_announcements_overlay(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    overlay_query: Query<(), With<AnnouncementsOverlay>>,
+) {
+    if !overlay_query.is_empty() {
+        return;
+    }
+    // Broadcast panel of players is docked at the top of the screen.
+    let top = match settings.hud_layout {
+        HudLayout::Standard => 40.,
+        HudLayout::Broadcast => 140.,
+    };
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(top),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+        AnnouncementsOverlay,
+    ));
+}
+
+fn show_announcements_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    settings: Res<Settings>,
+    mut announcements: EventReader<AnnouncementEvent>,
+    overlay_query: Query<Entity, With<AnnouncementsOverlay>>,
+    shown_query: Query<(Entity, &Announcement)>,
+) {
+    let (Some(game_field), Ok(overlay)) = (game_field, overlay_query.get_single()) else {
+        announcements.clear();
+        return;
+    };
+    let font_size = match settings.hud_layout {
+        HudLayout::Standard => FONT_SIZE,
+        HudLayout::Broadcast => BROADCAST_FONT_SIZE,
+    };
+    let mut shown: Vec<_> = shown_query.iter().collect();
+    for event in announcements.read() {
+        commands.entity(overlay).with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    event.text.clone(),
+                    TextStyle {
+                        font: game_field.font.clone(),
+                        font_size,
+                        color: Color::WHITE,
+                    },
+                ),
+                Announcement { age: 0. },
+            ));
+        });
+        // Oldest announcements are removed to free space for new ones.
+        if shown.len() + 1 > MAX_VISIBLE {
+            shown.sort_by(|a, b| b.1.age.total_cmp(&a.1.age));
+            let (entity, _) = shown.remove(0);
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn fade_announcements_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut announcements_query: Query<(Entity, &mut Announcement, &mut Text)>,
+) {
+    for (entity, mut announcement, mut text) in announcements_query.iter_mut() {
+        announcement.age += time.delta_seconds();
+        if announcement.age >= SHOW_TIME {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = announcement_alpha(announcement.age);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+/// Returns opacity of announcement shown during given time.
+fn announcement_alpha(age: f32) -> f32 {
+    ((SHOW_TIME - age) / FADE_TIME).clamp(0., 1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announcement_alpha() {
+        assert_eq!(announcement_alpha(0.), 1.);
+        assert_eq!(announcement_alpha(SHOW_TIME - FADE_TIME), 1.);
+        assert_eq!(announcement_alpha(SHOW_TIME - FADE_TIME / 2.), 0.5);
+        assert_eq!(announcement_alpha(SHOW_TIME), 0.);
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::explosion::Explosion;
+use crate::game_field::GameField;
+use crate::game_plugin::AppState;
+use crate::missile::{missile_moving_system2, Missile};
+use crate::tank::AllTanksPlacedEvent;
+use crate::turn::TurnEndedEvent;
+
+/// Count of turns (of all players) during which the field exists.
+const FIELD_TURNS: u32 = 4;
+/// Factor of gravity inside of the field.
+const GRAVITY_FACTOR: f32 = -0.5;
+const SHIMMER_FREQUENCY: f32 = 2.;
+
+pub struct AntiGravityPlugin;
+
+impl Plugin for AntiGravityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                finish_field_creation_system,
+                field_effect_system.after(missile_moving_system2),
+                expire_fields_system,
+                shimmer_fields_system,
+            ),
+        )
+        .add_systems(OnEnter(AppState::RoundSetup), despawn_fields_system);
+    }
+}
+
+/// Missile which creates anti-gravity field where it lands.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct AntiGravityCharge {
+    pub radius: f32,
+}
+
+/// Zone where gravity is inverted for missiles.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct AntiGravityField {
+    pub position: Vec2,
+    pub radius: f32,
+    pub turns_left: u32,
+}
+
+impl AntiGravityField {
+    #[inline]
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.position.distance_squared(point) <= self.radius * self.radius
+    }
+}
+
+/// Acceleration of the missile before it has flown into a field.
+#[derive(Debug, Clone, Copy, Component)]
+struct InsideField {
+    acceleration: Vec2,
+}
+
+pub fn spawn_field(
+    commands: &mut Commands,
+    game_field: &GameField,
+    position: Vec2,
+    charge: AntiGravityCharge,
+) {
+    debug!("Create anti-gravity field at {:?}", position);
+    let circle = shapes::Circle {
+        radius: charge.radius,
+        ..default()
+    };
+    let field_entity = commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&circle),
+                spatial: SpatialBundle::from_transform(Transform::from_translation(
+                    position.extend(0.5),
+                )),
+                ..default()
+            },
+            Fill::color(Color::rgba(0.6, 0.3, 1., 0.15)),
+            Stroke::new(Color::rgba(0.8, 0.6, 1., 0.6), 1.),
+            AntiGravityField {
+                position,
+                radius: charge.radius,
+                turns_left: FIELD_TURNS,
+            },
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(field_entity);
+}
+
+/// Finishes the turn if nothing else is flying or exploding,
+/// because the charge doesn't explode.
+fn finish_field_creation_system(
+    new_fields: Query<(), Added<AntiGravityField>>,
+    missiles: Query<(), With<Missile>>,
+    explosions: Query<(), With<Explosion>>,
+    mut placed_events: EventWriter<AllTanksPlacedEvent>,
+) {
+    if !new_fields.is_empty() && missiles.is_empty() && explosions.is_empty() {
+        placed_events.send(AllTanksPlacedEvent);
+    }
+}
+
+/// Changes gravity for missiles which fly in and out of fields.
+fn field_effect_system(
+    mut commands: Commands,
+    fields: Query<&AntiGravityField>,
+    mut missiles: Query<(Entity, &mut Missile, Option<&InsideField>)>,
+) {
+    for (entity, mut missile, inside_field) in missiles.iter_mut() {
+        let position = missile.cur_pos();
+        let is_inside = fields.iter().any(|field| field.contains(position));
+        match inside_field {
+            None if is_inside => {
+                let acceleration = missile.acceleration();
+                missile
+                    .set_acceleration(Vec2::new(acceleration.x, acceleration.y * GRAVITY_FACTOR));
+                commands.entity(entity).insert(InsideField { acceleration });
+            }
+            Some(inside_field) if !is_inside => {
+                missile.set_acceleration(inside_field.acceleration);
+                commands.entity(entity).remove::<InsideField>();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn expire_fields_system(
+    mut commands: Commands,
+    mut ended_events: EventReader<TurnEndedEvent>,
+    mut fields: Query<(Entity, &mut AntiGravityField)>,
+) {
+    for _ in ended_events.read() {
+        for (entity, mut field) in fields.iter_mut() {
+            field.turns_left = field.turns_left.saturating_sub(1);
+            if field.turns_left == 0 {
+                debug!("Anti-gravity field at {:?} has vanished", field.position);
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn shimmer_fields_system(
+    time: Res<Time>,
+    mut fields: Query<(&mut Fill, &mut Stroke), With<AntiGravityField>>,
+) {
+    let phase = time.elapsed_seconds() * SHIMMER_FREQUENCY * std::f32::consts::TAU;
+    for (mut fill, mut stroke) in fields.iter_mut() {
+        fill.color.set_a(0.15 + 0.05 * phase.sin());
+        stroke.color.set_a(0.6 + 0.3 * (1.3 * phase).cos());
+    }
+}
+
+fn despawn_fields_system(mut commands: Commands, fields: Query<Entity, With<AntiGravityField>>) {
+    for entity in fields.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_contains() {
+        let field = AntiGravityField {
+            position: Vec2::new(100., 100.),
+            radius: 50.,
+            turns_left: FIELD_TURNS,
+        };
+        assert!(field.contains(Vec2::new(100., 100.)));
+        assert!(field.contains(Vec2::new(130., 140.)));
+        assert!(!field.contains(Vec2::new(131., 141.)));
+    }
+}
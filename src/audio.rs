@@ -0,0 +1,306 @@
+use bevy::audio::{SpatialScale, Volume};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::camera::MainCamera;
+use crate::chat::is_chat_open;
+use crate::components::Position;
+use crate::explosion::Explosion;
+use crate::game_field::GameField;
+use crate::settings::{AudioSettings, Settings};
+use crate::tank::{Health, TankDamagedEvent, TankLandedEvent, TankShotEvent, SOFT_LANDING_SPEED};
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_sounds_system)
+            .add_systems(Update, attach_listener_system)
+            .add_systems(
+                PostUpdate,
+                (
+                    tank_fire_sound_system,
+                    explosion_sound_system,
+                    tank_damaged_sound_system,
+                    tank_landed_sound_system,
+                    dirt_collapse_sound_system.run_if(resource_exists::<GameField>),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_mute_system
+                        .run_if(resource_exists::<ButtonInput<KeyCode>>)
+                        .run_if(not(is_chat_open)),
+                    update_sfx_volume_system.run_if(resource_changed::<Settings>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Max relative change of pitch of sound effects.
+const PITCH_VARIATION: f32 = 0.08;
+/// Alarm sounds when health of tank falls below this value.
+const LOW_HEALTH: u8 = 25;
+/// Impact speed of tank at which its landing sounds at full volume.
+const LOUD_LANDING_SPEED: f32 = 3. * SOFT_LANDING_SPEED;
+/// Distance between "ears" of the listener in the scaled space of
+/// positional sounds. Positions are scaled so that a half of the camera
+/// view fits into this distance.
+const EARS_GAP: f32 = 2.;
+
+/// Recorded sound which is played with given speed, so the same
+/// recording may sound differently in different effects.
+#[derive(Clone)]
+struct Sample {
+    source: Handle<AudioSource>,
+    speed: f32,
+}
+
+/// Samples of sound effects. One of samples of the same effect
+/// is chosen randomly every time it is played. Effect without
+/// samples is silent.
+#[derive(Resource)]
+struct SoundBank {
+    fire: Vec<Sample>,
+    explosion: Vec<Sample>,
+    impact: Vec<Sample>,
+    tank_landing: Vec<Sample>,
+    dirt_collapse: Vec<Sample>,
+    shield_hit: Vec<Sample>,
+    low_health_alarm: Vec<Sample>,
+}
+
+fn load_sounds_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let tank_fire: Handle<AudioSource> = asset_server.load("sounds/tank_fire.ogg");
+    let explosion: Handle<AudioSource> = asset_server.load("sounds/explosion1.ogg");
+    let sample = |source: &Handle<AudioSource>, speed: f32| Sample {
+        source: source.clone(),
+        speed,
+    };
+    // Effects without own recordings use pitched existing ones.
+    commands.insert_resource(SoundBank {
+        fire: vec![sample(&tank_fire, 1.)],
+        explosion: vec![sample(&explosion, 1.)],
+        impact: vec![sample(&explosion, 1.6)],
+        tank_landing: vec![sample(&explosion, 0.6)],
+        dirt_collapse: vec![sample(&explosion, 0.4)],
+        shield_hit: vec![sample(&tank_fire, 1.8)],
+        low_health_alarm: vec![],
+    });
+}
+
+/// Sound effect, which volume is controlled by `AudioSettings::sfx_volume`.
+#[derive(Component)]
+struct SoundEffect;
+
+/// Position of sound effect in the world. Sound is panned and attenuated
+/// according to the position relative to the camera view.
+#[derive(Debug, Clone, Copy)]
+struct SoundPosition {
+    translation: Vec3,
+    scale: SpatialScale,
+}
+
+/// Camera hears positional sounds.
+fn attach_listener_system(
+    mut commands: Commands,
+    cameras_query: Query<Entity, (With<MainCamera>, Without<SpatialListener>)>,
+) {
+    for entity in cameras_query.iter() {
+        commands
+            .entity(entity)
+            .insert(SpatialListener::new(EARS_GAP));
+    }
+}
+
+/// Camera view and the game field required to place sounds in the world.
+#[derive(SystemParam)]
+struct SoundListener<'w, 's> {
+    game_field: Option<Res<'w, GameField>>,
+    transforms_query: Query<'w, 's, &'static GlobalTransform>,
+    listener_query: Query<'w, 's, &'static OrthographicProjection, With<SpatialListener>>,
+}
+
+impl SoundListener<'_, '_> {
+    /// Returns position of sound emitted at given point of the game field,
+    /// `None` if there is no listener of positional sounds.
+    fn position(&self, point: Vec2) -> Option<SoundPosition> {
+        let projection = self.listener_query.get_single().ok()?;
+        let field_entity = self.game_field.as_ref()?.parent_entity;
+        let field_transform = self.transforms_query.get(field_entity).ok()?;
+        let view_width = projection.area.width();
+        if view_width <= 0. {
+            return None;
+        }
+        Some(SoundPosition {
+            translation: field_transform.transform_point(point.extend(0.)),
+            scale: SpatialScale::new_2d(EARS_GAP / view_width),
+        })
+    }
+}
+
+/// Plays random sample of sound effect once with volume of SFX channel
+/// multiplied by `volume` and with slightly changed pitch.
+fn play_sfx(
+    commands: &mut Commands,
+    audio: &AudioSettings,
+    samples: &[Sample],
+    volume: f32,
+    position: Option<SoundPosition>,
+) {
+    let mut rng = rand::thread_rng();
+    let Some(sample) = samples.choose(&mut rng) else {
+        return;
+    };
+    let speed = sample.speed * (1. + rng.gen_range(-PITCH_VARIATION..PITCH_VARIATION));
+    let mut settings = PlaybackSettings::DESPAWN
+        .with_volume(Volume::new(audio.sfx() * volume))
+        .with_speed(speed);
+    let mut sound = commands.spawn(SoundEffect);
+    if let Some(position) = position {
+        settings = settings
+            .with_spatial(true)
+            .with_spatial_scale(position.scale);
+        // Global transform is set explicitly, because the sound
+        // may be played before propagation of transforms.
+        sound.insert((
+            Transform::from_translation(position.translation),
+            GlobalTransform::from_translation(position.translation),
+        ));
+    }
+    sound.insert(AudioBundle {
+        source: sample.source.clone(),
+        settings,
+    });
+}
+
+fn toggle_mute_system(keyboard_input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        settings.audio.muted = !settings.audio.muted;
+        debug!("Sound muted: {}", settings.audio.muted);
+    }
+}
+
+fn update_sfx_volume_system(
+    settings: Res<Settings>,
+    sinks_query: Query<&AudioSink, With<SoundEffect>>,
+) {
+    let volume = settings.audio.sfx();
+    for sink in sinks_query.iter() {
+        sink.set_volume(volume);
+    }
+}
+
+fn tank_fire_sound_system(
+    mut commands: Commands,
+    sounds: Res<SoundBank>,
+    settings: Res<Settings>,
+    mut shot_events: EventReader<TankShotEvent>,
+    positions_query: Query<&Position>,
+    listener: SoundListener,
+) {
+    for event in shot_events.read() {
+        let position = positions_query
+            .get(event.tank_entity)
+            .ok()
+            .and_then(|tank_position| listener.position(tank_position.0));
+        play_sfx(&mut commands, &settings.audio, &sounds.fire, 1., position);
+    }
+}
+
+fn explosion_sound_system(
+    mut commands: Commands,
+    sounds: Res<SoundBank>,
+    settings: Res<Settings>,
+    new_explosions_query: Query<&Position, Added<Explosion>>,
+    listener: SoundListener,
+) {
+    for explosion_position in new_explosions_query.iter() {
+        let position = listener.position(explosion_position.0);
+        play_sfx(
+            &mut commands,
+            &settings.audio,
+            &sounds.explosion,
+            1.,
+            position,
+        );
+    }
+}
+
+/// Invincible tanks are protected by shield. Alarm sounds once
+/// health of the tank becomes low.
+fn tank_damaged_sound_system(
+    mut commands: Commands,
+    sounds: Res<SoundBank>,
+    settings: Res<Settings>,
+    mut damaged_events: EventReader<TankDamagedEvent>,
+    health_query: Query<&Health>,
+) {
+    for event in damaged_events.read() {
+        let Ok(health) = health_query.get(event.tank_entity) else {
+            continue;
+        };
+        if health.invincible {
+            play_sfx(&mut commands, &settings.audio, &sounds.shield_hit, 1., None);
+            continue;
+        }
+        play_sfx(&mut commands, &settings.audio, &sounds.impact, 1., None);
+        let previous_value = health.value.saturating_add(event.damage);
+        if health.value > 0 && health.value <= LOW_HEALTH && previous_value > LOW_HEALTH {
+            play_sfx(
+                &mut commands,
+                &settings.audio,
+                &sounds.low_health_alarm,
+                1.,
+                None,
+            );
+        }
+    }
+}
+
+/// Thud of falling tank is louder for greater impact speed.
+fn tank_landed_sound_system(
+    mut commands: Commands,
+    sounds: Res<SoundBank>,
+    settings: Res<Settings>,
+    mut landed_events: EventReader<TankLandedEvent>,
+) {
+    for event in landed_events.read() {
+        if event.impact_speed < SOFT_LANDING_SPEED {
+            continue;
+        }
+        let volume = (event.impact_speed / LOUD_LANDING_SPEED).min(1.);
+        play_sfx(
+            &mut commands,
+            &settings.audio,
+            &sounds.tank_landing,
+            volume,
+            None,
+        );
+    }
+}
+
+/// Rumble of dirt collapsing at the start of landscape subsidence.
+fn dirt_collapse_sound_system(
+    mut commands: Commands,
+    sounds: Res<SoundBank>,
+    settings: Res<Settings>,
+    game_field: Res<GameField>,
+    mut was_subsidence: Local<bool>,
+) {
+    let is_subsidence = game_field.landscape.is_subsidence();
+    if is_subsidence && !*was_subsidence {
+        play_sfx(
+            &mut commands,
+            &settings.audio,
+            &sounds.dirt_collapse,
+            1.,
+            None,
+        );
+    }
+    *was_subsidence = is_subsidence;
+}
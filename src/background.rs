@@ -0,0 +1,256 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_prototype_lyon::prelude::*;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::camera::MainCamera;
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::{setup_game_field, AppState};
+
+const SKY_TOP_COLOR: [u8; 3] = [28, 52, 110];
+const SKY_BOTTOM_COLOR: [u8; 3] = [130, 170, 215];
+/// Height (pixels) of the texture of sky gradient.
+const SKY_GRADIENT_HEIGHT: u32 = 64;
+/// Width of background layers relative to the width of game field,
+/// it leaves space for shifting of layers.
+const LAYER_WIDTH_SCALE: f32 = 2.;
+const MOUNTAIN_STEP: f32 = 16.;
+const CLOUDS_COUNT: usize = 6;
+/// Speed of clouds per unit of wind power.
+const CLOUD_SPEED: f32 = 4.;
+
+/// Sky, distant mountains and clouds behind the landscape.
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            setup_background.after(setup_game_field),
+        )
+        .add_systems(Update, (drift_clouds_system, parallax_system).chain());
+    }
+}
+
+/// Layer of background which moves slower than the game field
+/// while camera is moving.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ParallaxLayer {
+    /// Position of the layer while camera looks at the center of game field.
+    base: Vec2,
+    /// Part of the camera's movement which is followed by the layer:
+    /// 1 - layer is fixed relative to camera, 0 - relative to game field.
+    factor: f32,
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Cloud {
+    /// Relative speed of cloud, distant clouds are slower.
+    speed: f32,
+}
+
+fn sky_gradient() -> Image {
+    let mut data = Vec::with_capacity(SKY_GRADIENT_HEIGHT as usize * 4);
+    for row in 0..SKY_GRADIENT_HEIGHT {
+        let t = row as f32 / (SKY_GRADIENT_HEIGHT - 1) as f32;
+        for (top, bottom) in SKY_TOP_COLOR.into_iter().zip(SKY_BOTTOM_COLOR) {
+            data.push((top as f32 + (bottom as f32 - top as f32) * t).round() as u8);
+        }
+        data.push(255);
+    }
+    Image::new(
+        Extent3d {
+            width: 1,
+            height: SKY_GRADIENT_HEIGHT,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        Default::default(),
+    )
+}
+
+/// Returns points of the ridge of mountains over the given width.
+fn mountain_ridge(rng: &mut impl Rng, width: f32, base_height: f32, amplitude: f32) -> Vec<Vec2> {
+    let count = (width / MOUNTAIN_STEP).ceil() as usize;
+    let phases: [f32; 3] = [rng.gen(), rng.gen(), rng.gen()];
+    let mut points = Vec::with_capacity(count + 3);
+    points.push(Vec2::new(0., 0.));
+    for i in 0..=count {
+        let x = i as f32 * MOUNTAIN_STEP;
+        let t = x / width * std::f32::consts::TAU;
+        let wave = 0.5 * (t * 3. + phases[0] * 6.).sin()
+            + 0.3 * (t * 7. + phases[1] * 6.).sin()
+            + 0.2 * (t * 13. + phases[2] * 6.).sin();
+        points.push(Vec2::new(x, base_height + amplitude * wave));
+    }
+    points.push(Vec2::new(count as f32 * MOUNTAIN_STEP, 0.));
+    points
+}
+
+fn spawn_layer(
+    commands: &mut Commands,
+    parent: Entity,
+    bundle: impl Bundle,
+    base: Vec2,
+    factor: f32,
+) -> Entity {
+    let entity = commands
+        .spawn((bundle, ParallaxLayer { base, factor }))
+        .id();
+    commands.entity(parent).add_child(entity);
+    entity
+}
+
+fn shape_bundle(path: Path, base: Vec2, z: f32) -> ShapeBundle {
+    ShapeBundle {
+        path,
+        spatial: SpatialBundle::from_transform(Transform::from_translation(base.extend(z))),
+        ..default()
+    }
+}
+
+fn setup_background(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    game_rng: Res<GameRng>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    let Some(asset_server) = asset_server else {
+        return;
+    };
+    let parent = game_field.parent_entity;
+    let field_size = Vec2::new(game_field.width as f32, game_field.height as f32);
+    let layer_width = field_size.x * LAYER_WIDTH_SCALE;
+    let layers_left = (field_size.x - layer_width) / 2.;
+    let mut rng = SmallRng::seed_from_u64(game_rng.seed());
+
+    // Sky
+    let sky_base = field_size / 2.;
+    spawn_layer(
+        &mut commands,
+        parent,
+        SpriteBundle {
+            texture: asset_server.add(sky_gradient()),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(layer_width, field_size.y * LAYER_WIDTH_SCALE)),
+                ..default()
+            },
+            transform: Transform::from_translation(sky_base.extend(-3.)),
+            ..default()
+        },
+        sky_base,
+        1.,
+    );
+
+    // Distant mountains
+    let ridges = [
+        (0.45, 0.12, Color::rgb(0.45, 0.52, 0.65), -2.5, 0.7),
+        (0.3, 0.1, Color::rgb(0.32, 0.4, 0.5), -2., 0.5),
+    ];
+    for (height, amplitude, color, z, factor) in ridges {
+        let points = mountain_ridge(
+            &mut rng,
+            layer_width,
+            field_size.y * height,
+            field_size.y * amplitude,
+        );
+        let ridge = shapes::Polygon {
+            points,
+            closed: true,
+        };
+        let base = Vec2::new(layers_left, 0.);
+        spawn_layer(
+            &mut commands,
+            parent,
+            (
+                shape_bundle(GeometryBuilder::build_as(&ridge), base, z),
+                Fill::color(color),
+            ),
+            base,
+            factor,
+        );
+    }
+
+    // Clouds
+    for _ in 0..CLOUDS_COUNT {
+        let distance: f32 = rng.gen_range(0.3..1.);
+        let puffs = (0..rng.gen_range(3..6)).fold(ShapePath::new(), |path, i| {
+            let circle = shapes::Circle {
+                radius: rng.gen_range(12. ..24.) * distance,
+                center: Vec2::new(i as f32 * 18. * distance, rng.gen_range(-6. ..6.)),
+            };
+            path.add(&circle)
+        });
+        let base = Vec2::new(
+            rng.gen_range(layers_left..layers_left + layer_width),
+            field_size.y * rng.gen_range(0.65..0.95),
+        );
+        let entity = spawn_layer(
+            &mut commands,
+            parent,
+            (
+                shape_bundle(puffs.build(), base, -1.5 + 0.5 * distance),
+                Fill::color(Color::rgba(1., 1., 1., 0.3 + 0.5 * distance)),
+            ),
+            base,
+            0.6 - 0.4 * distance,
+        );
+        commands.entity(entity).insert(Cloud { speed: distance });
+    }
+}
+
+/// Clouds move with the wind and return from the opposite side
+/// of the layer when they leave it.
+fn drift_clouds_system(
+    time: Res<Time>,
+    game_field: Option<Res<GameField>>,
+    mut clouds_query: Query<(&Cloud, &mut ParallaxLayer)>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let field_width = game_field.width as f32;
+    let layer_width = field_width * LAYER_WIDTH_SCALE;
+    let layers_left = (field_width - layer_width) / 2.;
+    let delta = game_field.wind_power * CLOUD_SPEED * time.delta_seconds();
+    for (cloud, mut layer) in clouds_query.iter_mut() {
+        let x = layer.base.x + delta * cloud.speed;
+        layer.base.x = layers_left + (x - layers_left).rem_euclid(layer_width);
+    }
+}
+
+fn parallax_system(
+    game_field: Option<Res<GameField>>,
+    camera_query: Query<&Transform, With<MainCamera>>,
+    mut layers_query: Query<(&ParallaxLayer, &mut Transform), Without<MainCamera>>,
+) {
+    let (Some(game_field), Ok(camera_transform)) = (game_field, camera_query.get_single()) else {
+        return;
+    };
+    let field_center = Vec2::new(game_field.width as f32, game_field.height as f32) / 2.;
+    let camera_offset = camera_transform.translation.truncate() - field_center;
+    for (layer, mut transform) in layers_query.iter_mut() {
+        let position = layer.base + camera_offset * layer.factor;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mountain_ridge() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let points = mountain_ridge(&mut rng, 320., 100., 20.);
+        assert_eq!(points.first(), Some(&Vec2::ZERO));
+        assert_eq!(points.last(), Some(&Vec2::new(320., 0.)));
+        for point in &points[1..points.len() - 1] {
+            assert!((80. ..=120.).contains(&point.y));
+        }
+    }
+}
@@ -1,16 +1,19 @@
 use bevy::prelude::*;
-use std::time::Instant;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ballistics {
-    created: Instant,
+    /// Time (seconds) passed since start of motion or since the last rebound.
+    elapsed: f32,
     start_pos: Vec2,
     start_velocity: Vec2,
     acceleration: Vec2,
     cur_pos: Vec2,
     last_updated: f32,
     time_scale: f32,
-    rebound_efficiency: f32,
+    /// Coefficient of air resistance slowing down the start velocity.
+    drag: f32,
+    /// Body moves uniformly after reaching this speed.
+    max_speed: f32,
 }
 
 impl Ballistics {
@@ -20,14 +23,15 @@ impl Ballistics {
     {
         let start_pos = start_pos.into();
         Ballistics {
-            created: Instant::now(),
+            elapsed: 0.0,
             start_pos,
             start_velocity: start_velocity.into(),
             acceleration: acceleration.into(),
             cur_pos: start_pos,
             last_updated: 0.0,
             time_scale: 1.0,
-            rebound_efficiency: 1.0,
+            drag: 0.0,
+            max_speed: f32::INFINITY,
         }
     }
 
@@ -39,21 +43,95 @@ impl Ballistics {
         }
     }
 
-    pub fn rebound_efficiency(self, value: f32) -> Self {
+    pub fn drag(self, value: f32) -> Self {
         Self {
-            rebound_efficiency: value,
+            drag: value,
             ..self
         }
     }
 
+    /// Limits speed of motion, drag is not taken into account.
+    pub fn max_speed(self, value: f32) -> Self {
+        Self {
+            max_speed: value,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn acceleration(&self) -> Vec2 {
+        self.acceleration
+    }
+
+    /// Changes acceleration starting from the last updated position.
+    pub fn set_acceleration(&mut self, acceleration: Vec2) {
+        let (pos, velocity) = self.pos_and_velocity();
+        self.acceleration = acceleration;
+        self.restart(pos, velocity);
+    }
+
+    /// Continues motion from given position with given velocity.
+    /// Time which hasn't been passed yet is kept.
+    pub fn restart(&mut self, pos: Vec2, velocity: Vec2) {
+        self.start_pos = pos;
+        self.start_velocity = velocity;
+        self.cur_pos = pos;
+        self.elapsed = (self.elapsed - self.last_updated / self.time_scale).max(0.0);
+        self.last_updated = 0.0;
+    }
+
+    /// Advances time of motion by given count of seconds.
+    #[inline]
+    pub fn tick(&mut self, delta: f32) {
+        self.elapsed += delta;
+    }
+
+    /// Returns time when speed of motion reaches the max speed.
+    fn max_speed_time(&self) -> f32 {
+        if self.max_speed == f32::INFINITY {
+            return f32::INFINITY;
+        }
+        // Solve |start_velocity + 2 * acceleration * t| = max_speed
+        let a = 4.0 * self.acceleration.length_squared();
+        let b = 4.0 * self.start_velocity.dot(self.acceleration);
+        let c = self.start_velocity.length_squared() - self.max_speed * self.max_speed;
+        if c >= 0.0 {
+            0.0
+        } else if a == 0.0 {
+            f32::INFINITY
+        } else {
+            (-b + (b * b - 4.0 * a * c).sqrt()) / (2.0 * a)
+        }
+    }
+
     #[inline]
     fn velocity(&self, time: f32) -> Vec2 {
-        self.start_velocity + self.acceleration * time * 2.0
+        let time = time.min(self.max_speed_time());
+        let velocity =
+            self.start_velocity * (-self.drag * time).exp() + self.acceleration * time * 2.0;
+        velocity.clamp_length_max(self.max_speed)
     }
 
     #[inline]
     fn pos(&self, time: f32) -> Vec2 {
-        self.start_pos + (self.start_velocity + self.acceleration * time) * time
+        let max_speed_time = self.max_speed_time();
+        if time > max_speed_time {
+            return self.free_pos(max_speed_time)
+                + self.velocity(max_speed_time) * (time - max_speed_time);
+        }
+        self.free_pos(time)
+    }
+
+    /// Position of body which speed isn't limited.
+    #[inline]
+    fn free_pos(&self, time: f32) -> Vec2 {
+        // Distance passed with the start velocity decreasing by drag.
+        let velocity_time = if self.drag > 0.0 {
+            (1.0 - (-self.drag * time).exp()) / self.drag
+        } else {
+            time
+        };
+        self.start_pos + self.start_velocity * velocity_time + self.acceleration * time * time
     }
 
     #[inline]
@@ -80,9 +158,9 @@ impl Ballistics {
         }
 
         self.start_pos = pos;
-        self.start_velocity = velocity * self.rebound_efficiency;
+        self.start_velocity = velocity;
         self.cur_pos = pos;
-        self.created = Instant::now();
+        self.elapsed = 0.0;
         self.last_updated = 0.0;
     }
 
@@ -90,10 +168,9 @@ impl Ballistics {
         &mut self,
         end_time: Option<f32>,
         borders: Option<(i32, i32)>,
-    ) -> BallisticsPosIterator {
+    ) -> BallisticsPosIterator<'_> {
         let start_time = self.last_updated;
-        let end_time =
-            end_time.unwrap_or_else(|| self.created.elapsed().as_secs_f32()) * self.time_scale;
+        let end_time = end_time.unwrap_or(self.elapsed) * self.time_scale;
 
         let start_velocity = self.velocity(start_time);
         let end_velocity = self.velocity(end_time);
@@ -106,7 +183,10 @@ impl Ballistics {
 
         let time_period = end_time - start_time;
 
-        let time_step = if max_velocity == 0.0 {
+        let time_step = if time_period <= 0.0 {
+            // Time hasn't changed - the iterator is empty
+            1.0
+        } else if max_velocity == 0.0 {
             time_period
         } else {
             1.0 / (2.0 * max_velocity)
@@ -162,6 +242,7 @@ impl<'a> Iterator for BallisticsPosIterator<'a> {
                         self.ballistics.acceleration.x = 0.0;
                     }
                     self.end_time -= self.last_time;
+                    self.ballistics.elapsed = self.end_time / self.ballistics.time_scale;
                     self.last_time = 0.0;
                     next_time = 0.0;
                     rebound_on_prev_step = true;
@@ -230,6 +311,60 @@ mod tests {
         assert_eq!(pos_iterator.next(), Some((0, 1000)));
         assert_eq!(pos_iterator.next(), None);
         assert!((ballistics.last_updated - 10.0).abs() < f32::EPSILON);
-        assert!((ballistics.cur_pos.y - 1000.0) < f32::EPSILON);
+        assert!((ballistics.cur_pos.y - 1000.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_set_acceleration() {
+        let pos = [0., 0.];
+        let velocity = [0., 100.];
+        let acceleration = [0., -10.];
+        let mut ballistics = Ballistics::new(pos, velocity, acceleration);
+        ballistics.tick(2.0);
+        assert_eq!(ballistics.positions_iter(None, None).last(), Some((0, 160)));
+
+        ballistics.set_acceleration(Vec2::new(0., 10.));
+        let (pos, velocity) = ballistics.pos_and_velocity();
+        assert_eq!(pos, Vec2::new(0., 160.));
+        assert_eq!(velocity, Vec2::new(0., 60.));
+        ballistics.tick(1.0);
+        assert_eq!(ballistics.positions_iter(None, None).last(), Some((0, 230)));
+    }
+
+    #[test]
+    fn test_drag() {
+        let pos = [0., 0.];
+        let velocity = [100.0, 0.0];
+        let acceleration = [0., 0.];
+        let mut ballistics = Ballistics::new(pos, velocity, acceleration).drag(0.1);
+
+        let last_x = ballistics
+            .positions_iter(Some(10.0), None)
+            .last()
+            .map(|(x, _)| x);
+        assert!(last_x.is_some_and(|x| x > 600 && x < 700));
+        let (_, velocity) = ballistics.pos_and_velocity();
+        assert!((velocity.x - 100.0 * (-1.0_f32).exp()).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_max_speed() {
+        let pos = [0., 1000.];
+        let velocity = [0., 0.];
+        let acceleration = [0., -10.];
+        let mut ballistics = Ballistics::new(pos, velocity, acceleration).max_speed(40.);
+        // Speed reaches 40 in 2 seconds, after 40 passed pixels.
+        assert_eq!(
+            ballistics.positions_iter(Some(2.0), None).last(),
+            Some((0, 960))
+        );
+        let (_, velocity) = ballistics.pos_and_velocity();
+        assert_eq!(velocity, Vec2::new(0., -40.));
+        assert_eq!(
+            ballistics.positions_iter(Some(5.0), None).last(),
+            Some((0, 840))
+        );
+        let (_, velocity) = ballistics.pos_and_velocity();
+        assert_eq!(velocity, Vec2::new(0., -40.));
     }
 }
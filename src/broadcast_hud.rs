@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+
+use crate::game_field::GameField;
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::rules::GameRules;
+use crate::settings::{HudLayout, Settings};
+use crate::tank::{player_color, CurrentTank, Health, Tank, TankShotEvent};
+use crate::weapons::{TankWeapon, Weapons};
+
+const CARD_FONT_SIZE: f32 = 18.;
+const CARD_WIDTH: f32 = 190.;
+
+/// Alternative layout of HUD for casting and spectating: cards
+/// of all players docked at the top of the screen without aiming widgets.
+pub struct BroadcastHudPlugin;
+
+impl Plugin for BroadcastHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            setup_broadcast_panel
+                .after(setup_game_field)
+                .run_if(is_broadcast_hud),
+        )
+        .add_systems(
+            Update,
+            (
+                spawn_player_cards_system,
+                record_last_shot_system,
+                update_player_cards_system,
+            )
+                .chain()
+                .run_if(is_broadcast_hud),
+        );
+    }
+}
+
+pub fn is_broadcast_hud(settings: Res<Settings>) -> bool {
+    settings.hud_layout == HudLayout::Broadcast
+}
+
+#[derive(Component)]
+struct BroadcastPanel;
+
+/// Status of one player in the broadcast panel.
+#[derive(Component)]
+struct PlayerCard {
+    tank_entity: Entity,
+    player_number: u8,
+    /// Angle and power of the last shot of the player.
+    last_shot: Option<(f32, f32)>,
+}
+
+fn setup_broadcast_panel(mut commands: Commands, panel_query: Query<(), With<BroadcastPanel>>) {
+    if !panel_query.is_empty() {
+        return;
+    }
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                padding: UiRect::all(Val::Px(6.)),
+                justify_content: JustifyContent::SpaceEvenly,
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.6).into(),
+            ..default()
+        },
+        BroadcastPanel,
+    ));
+}
+
+/// Adds cards of tanks of the new round and removes cards of
+/// tanks of the previous one.
+fn spawn_player_cards_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    rules: Res<GameRules>,
+    new_tanks_query: Query<(Entity, &Tank), Added<Tank>>,
+    tanks_query: Query<(), With<Tank>>,
+    panel_query: Query<Entity, With<BroadcastPanel>>,
+    cards_query: Query<(Entity, &PlayerCard)>,
+) {
+    let (Some(game_field), Ok(panel)) = (game_field, panel_query.get_single()) else {
+        return;
+    };
+    if new_tanks_query.is_empty() {
+        return;
+    }
+    for (card_entity, card) in cards_query.iter() {
+        if tanks_query.get(card.tank_entity).is_err() {
+            commands.entity(card_entity).despawn_recursive();
+        }
+    }
+    let mut new_tanks: Vec<(Entity, &Tank)> = new_tanks_query.iter().collect();
+    new_tanks.sort_by_key(|(_, tank)| tank.player_number);
+    commands.entity(panel).with_children(|parent| {
+        for (tank_entity, tank) in new_tanks {
+            parent.spawn((
+                TextBundle {
+                    style: Style {
+                        // Cards of many players share width of the panel.
+                        width: Val::Percent(100. / game_field.players_count().max(1) as f32),
+                        max_width: Val::Px(CARD_WIDTH),
+                        ..default()
+                    },
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: game_field.font.clone(),
+                            font_size: CARD_FONT_SIZE,
+                            color: player_color(
+                                tank.player_number,
+                                game_field.players_count(),
+                                rules.teams.as_ref(),
+                            ),
+                        },
+                    ),
+                    ..default()
+                },
+                PlayerCard {
+                    tank_entity,
+                    player_number: tank.player_number,
+                    last_shot: None,
+                },
+            ));
+        }
+    });
+}
+
+fn record_last_shot_system(
+    mut shot_events: EventReader<TankShotEvent>,
+    tanks_query: Query<&Tank>,
+    mut cards_query: Query<&mut PlayerCard>,
+) {
+    for event in shot_events.read() {
+        let Ok(tank) = tanks_query.get(event.tank_entity) else {
+            continue;
+        };
+        for mut card in cards_query.iter_mut() {
+            if card.tank_entity == event.tank_entity {
+                card.last_shot = Some((tank.gun_angle_deg(), tank.power));
+            }
+        }
+    }
+}
+
+fn card_text(
+    card: &PlayerCard,
+    status: Option<(&Health, &TankWeapon, bool)>,
+    weapons: &Weapons,
+) -> String {
+    let Some((health, weapon, is_current)) = status else {
+        return format!("Player {}\nDestroyed", card.player_number);
+    };
+    let marker = if is_current { "> " } else { "" };
+    let selected = weapon
+        .selected()
+        .and_then(|index| weapons.0.get(index))
+        .map_or("Missile", |definition| definition.name.as_str());
+    let cooldowns: Vec<String> = weapons
+        .0
+        .iter()
+        .enumerate()
+        .filter_map(|(index, definition)| match weapon.cooldown(index) {
+            0 => None,
+            turns => Some(format!("{} ({})", definition.name, turns)),
+        })
+        .collect();
+    let mut text = format!(
+        "{}Player {}\nHealth: {}\nWeapon: {}",
+        marker, card.player_number, health.value, selected
+    );
+    if !cooldowns.is_empty() {
+        text += &format!("\nCooldown: {}", cooldowns.join(", "));
+    }
+    if let Some((angle, power)) = card.last_shot {
+        text += &format!("\nLast shot: {:.1}° / {:.1}", angle, power);
+    }
+    text
+}
+
+fn update_player_cards_system(
+    weapons: Res<Weapons>,
+    tanks_query: Query<(&Health, &TankWeapon, Has<CurrentTank>), With<Tank>>,
+    mut cards_query: Query<(&PlayerCard, &mut Text)>,
+) {
+    for (card, mut text) in cards_query.iter_mut() {
+        let status = tanks_query.get(card.tank_entity).ok();
+        let value = card_text(card, status, &weapons);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_text() {
+        let weapons = Weapons::default();
+        let card = PlayerCard {
+            tank_entity: Entity::from_raw(1),
+            player_number: 2,
+            last_shot: Some((-45., 60.)),
+        };
+        let health = Health {
+            value: 80,
+            shield: 0,
+            invincible: false,
+        };
+        let weapon = TankWeapon::default();
+        assert_eq!(
+            card_text(&card, Some((&health, &weapon, true)), &weapons),
+            "> Player 2\nHealth: 80\nWeapon: Missile\nLast shot: -45.0° / 60.0"
+        );
+        assert_eq!(card_text(&card, None, &weapons), "Player 2\nDestroyed");
+    }
+}
@@ -0,0 +1,529 @@
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::chat::is_chat_open;
+use crate::components::Position;
+use crate::explosion::Explosion;
+use crate::game_field::GameField;
+use crate::game_plugin::AppState;
+use crate::missile::Missile;
+use crate::net::SpectatorSession;
+use crate::settings::Settings;
+use crate::slow_motion::SlowMotion;
+use crate::status_panel::STATUS_PANEL_HEIGHT;
+use crate::tank::{CurrentTank, Tank};
+
+/// Speed of camera panning (pixels per second).
+const PAN_SPEED: f32 = 400.;
+/// Change of camera scale per second while zoom key is held.
+const ZOOM_SPEED: f32 = 1.5;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 2.;
+/// How fast camera reaches its target in follow presets.
+const FOLLOW_SHARPNESS: f32 = 5.;
+/// Exponential easing snaps the camera to its target within this distance.
+const SNAP_DISTANCE: f32 = 0.1;
+/// Scale of camera while it follows missiles and explosions.
+const ACTION_ZOOM: f32 = 0.6;
+/// Scale of camera during slow motion of the finishing explosion.
+const SLOW_MOTION_ZOOM: f32 = 0.35;
+
+pub struct GameCameraPlugin;
+
+impl Plugin for GameCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpectatorCamera>()
+            .init_resource::<CameraController>()
+            .add_systems(Startup, setup_camera_system)
+            .add_systems(
+                Update,
+                (
+                    fit_camera_to_field_system,
+                    enable_spectator_camera_system.run_if(resource_added::<SpectatorSession>),
+                    toggle_spectator_camera_system.run_if(not(is_chat_open)),
+                    drag_camera_system.run_if(resource_exists::<ButtonInput<MouseButton>>),
+                    (
+                        (camera_preset_system, free_camera_input_system).run_if(not(is_chat_open)),
+                        follow_camera_system,
+                    )
+                        .chain()
+                        .run_if(spectator_camera_enabled),
+                    camera_controller_system.run_if(not(spectator_camera_enabled)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Component)]
+pub struct MainCamera;
+
+/// What spectator camera is looking at.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CameraPreset {
+    /// Whole game field.
+    #[default]
+    Overview,
+    /// Camera is controlled by player.
+    Free,
+    FollowCurrentTank,
+    FollowMissile,
+    /// Tank of the player with given number.
+    FollowPlayer(u8),
+}
+
+/// State of spectator camera. While it is enabled the camera is controlled
+/// by its own keys (WASD, Q/E, mouse wheel, 1-4 for presets and 5
+/// for following of players one by one), which don't intersect
+/// with aiming keys. Dragging by the middle mouse button pans
+/// the camera too.
+#[derive(Debug, Clone, Resource)]
+pub struct SpectatorCamera {
+    pub enabled: bool,
+    pub preset: CameraPreset,
+    /// Position and scale of camera which shows the whole game field.
+    home_position: Vec2,
+    home_scale: f32,
+    field_size: Vec2,
+}
+
+impl Default for SpectatorCamera {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            preset: CameraPreset::default(),
+            home_position: Vec2::ZERO,
+            home_scale: 1.,
+            field_size: Vec2::ZERO,
+        }
+    }
+}
+
+impl SpectatorCamera {
+    /// Scale of camera which shows the whole game field.
+    #[inline]
+    pub fn home_scale(&self) -> f32 {
+        self.home_scale
+    }
+
+    /// Maximal scale of camera, it is enough to see the whole field.
+    #[inline]
+    pub fn max_scale(&self) -> f32 {
+        MAX_ZOOM.max(self.home_scale)
+    }
+
+    /// Returns position of camera which doesn't leave the game field.
+    fn clamp_to_field(&self, position: Vec2) -> Vec2 {
+        if self.field_size == Vec2::ZERO {
+            return position;
+        }
+        position.clamp(Vec2::ZERO, self.field_size)
+    }
+}
+
+/// Returns position and scale of camera which shows the whole game
+/// field in the window under the status panel.
+fn overview(field_size: Vec2, window_size: Vec2, panel_height: f32) -> (Vec2, f32) {
+    // Field is surrounded by border.
+    let field_size = field_size + 2.;
+    let visible_size = Vec2::new(window_size.x, window_size.y - panel_height);
+    let scale = (field_size / visible_size).max_element().max(1.);
+    // Bottom of the field is at the bottom of the window.
+    let position = Vec2::new(field_size.x / 2., window_size.y * scale / 2.);
+    (position, scale)
+}
+
+/// How camera moves to its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraEasing {
+    /// Constant speed (pixels per second).
+    Linear { speed: f32 },
+    /// Speed decreases while camera approaches its target.
+    Exponential { sharpness: f32 },
+}
+
+impl Default for CameraEasing {
+    fn default() -> Self {
+        Self::Exponential {
+            sharpness: FOLLOW_SHARPNESS,
+        }
+    }
+}
+
+impl CameraEasing {
+    /// Returns new position of camera after moving to the target
+    /// during `delta` seconds.
+    pub fn step(&self, position: Vec2, target: Vec2, delta: f32) -> Vec2 {
+        match *self {
+            Self::Linear { speed } => {
+                let offset = target - position;
+                let max_distance = speed * delta;
+                if offset.length() <= max_distance {
+                    target
+                } else {
+                    position + offset * (max_distance / offset.length())
+                }
+            }
+            Self::Exponential { sharpness } => {
+                let next = position.lerp(target, 1. - (-sharpness * delta).exp());
+                if next.distance(target) <= SNAP_DISTANCE {
+                    target
+                } else {
+                    next
+                }
+            }
+        }
+    }
+
+    /// Returns new scale of camera after zooming to the target scale.
+    fn step_scale(&self, scale: f32, target: f32, delta: f32) -> f32 {
+        let factor = match *self {
+            // Zooming of linear easing takes one second.
+            Self::Linear { .. } => delta.min(1.),
+            Self::Exponential { sharpness } => 1. - (-sharpness * delta).exp(),
+        };
+        scale + (target - scale) * factor
+    }
+}
+
+/// Follows the highest missile and then its explosion during the main
+/// action of turn, and returns to the view of the whole game field
+/// afterward. It is not active while spectator camera is enabled.
+#[derive(Debug, Clone, Resource)]
+pub struct CameraController {
+    pub enabled: bool,
+    pub easing: CameraEasing,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            easing: CameraEasing::default(),
+        }
+    }
+}
+
+fn spectator_camera_enabled(spectator_camera: Res<SpectatorCamera>) -> bool {
+    spectator_camera.enabled
+}
+
+fn setup_camera_system(
+    mut commands: Commands,
+    mut spectator_camera: ResMut<SpectatorCamera>,
+    primary_windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = primary_windows.get_single() else {
+        return;
+    };
+    let width = window.width();
+    let height = window.height();
+    spectator_camera.home_position = Vec2::new(width / 2., height / 2.);
+    let mut camera = Camera2dBundle::default();
+    camera.transform.translation = spectator_camera
+        .home_position
+        .extend(camera.transform.translation.z);
+    commands.spawn((camera, MainCamera));
+}
+
+/// Zooms out the home view of camera if the game field
+/// doesn't fit into the window.
+fn fit_camera_to_field_system(
+    game_field: Option<Res<GameField>>,
+    settings: Res<Settings>,
+    mut spectator_camera: ResMut<SpectatorCamera>,
+    primary_windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let (Some(game_field), Ok(window)) = (game_field, primary_windows.get_single()) else {
+        return;
+    };
+    let field_size = Vec2::new(game_field.width as f32, game_field.height as f32);
+    let (position, scale) = overview(
+        field_size,
+        Vec2::new(window.width(), window.height()),
+        STATUS_PANEL_HEIGHT * settings.ui_scale.get(),
+    );
+    if (spectator_camera.home_position, spectator_camera.home_scale) != (position, scale)
+        || spectator_camera.field_size != field_size
+    {
+        spectator_camera.home_position = position;
+        spectator_camera.home_scale = scale;
+        spectator_camera.field_size = field_size;
+    }
+}
+
+/// Spectators of network games always use spectator camera.
+fn enable_spectator_camera_system(mut spectator_camera: ResMut<SpectatorCamera>) {
+    spectator_camera.enabled = true;
+}
+
+fn toggle_spectator_camera_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut spectator_camera: ResMut<SpectatorCamera>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyF) {
+        return;
+    }
+    spectator_camera.enabled = !spectator_camera.enabled;
+    debug!("Spectator camera enabled: {}", spectator_camera.enabled);
+    if !spectator_camera.enabled {
+        // Return the camera to the fixed view of game field.
+        for (mut transform, mut projection) in camera_query.iter_mut() {
+            transform.translation.x = spectator_camera.home_position.x;
+            transform.translation.y = spectator_camera.home_position.y;
+            projection.scale = spectator_camera.home_scale;
+        }
+    }
+}
+
+/// Returns the player which is followed after the given one.
+fn next_followed_player(followed: Option<u8>, players: &[u8]) -> Option<u8> {
+    let followed = followed.unwrap_or_default();
+    players
+        .iter()
+        .copied()
+        .filter(|&player| player > followed)
+        .min()
+        .or_else(|| players.iter().copied().min())
+}
+
+fn camera_preset_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut spectator_camera: ResMut<SpectatorCamera>,
+    tanks_query: Query<&Tank>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Digit5) {
+        let followed = match spectator_camera.preset {
+            CameraPreset::FollowPlayer(player) => Some(player),
+            _ => None,
+        };
+        let players: Vec<u8> = tanks_query.iter().map(|tank| tank.player_number).collect();
+        if let Some(player) = next_followed_player(followed, &players) {
+            spectator_camera.preset = CameraPreset::FollowPlayer(player);
+        }
+    }
+    let presets = [
+        (KeyCode::Digit1, CameraPreset::Overview),
+        (KeyCode::Digit2, CameraPreset::FollowCurrentTank),
+        (KeyCode::Digit3, CameraPreset::FollowMissile),
+        (KeyCode::Digit4, CameraPreset::Free),
+    ];
+    for (key_code, preset) in presets {
+        if keyboard_input.just_pressed(key_code) {
+            spectator_camera.preset = preset;
+        }
+    }
+}
+
+fn free_camera_input_system(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut spectator_camera: ResMut<SpectatorCamera>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let delta_time = time.delta_seconds();
+
+    let mut direction = Vec2::ZERO;
+    for (key_code, key_direction) in [
+        (KeyCode::KeyW, Vec2::Y),
+        (KeyCode::KeyS, Vec2::NEG_Y),
+        (KeyCode::KeyA, Vec2::NEG_X),
+        (KeyCode::KeyD, Vec2::X),
+    ] {
+        if keyboard_input.pressed(key_code) {
+            direction += key_direction;
+        }
+    }
+
+    let mut zoom_factor = 1.;
+    if keyboard_input.pressed(KeyCode::KeyQ) {
+        zoom_factor *= 1. + ZOOM_SPEED * delta_time;
+    }
+    if keyboard_input.pressed(KeyCode::KeyE) {
+        zoom_factor /= 1. + ZOOM_SPEED * delta_time;
+    }
+    for event in mouse_wheel_events.read() {
+        zoom_factor *= 1. - event.y.clamp(-1., 1.) * 0.1;
+    }
+
+    if direction != Vec2::ZERO {
+        // Manual panning switches camera into free mode.
+        spectator_camera.preset = CameraPreset::Free;
+    }
+
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        projection.scale = zoomed_scale(projection.scale, zoom_factor, &spectator_camera);
+        let offset = direction.normalize_or_zero() * PAN_SPEED * projection.scale * delta_time;
+        let position = spectator_camera.clamp_to_field(transform.translation.truncate() + offset);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+/// Dragging by the middle mouse button pans the camera
+/// and switches it into free spectator mode.
+fn drag_camera_system(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut spectator_camera: ResMut<SpectatorCamera>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<MainCamera>>,
+) {
+    let motion: Vec2 = motion_events.read().map(|event| event.delta).sum();
+    if !mouse_input.pressed(MouseButton::Middle) || motion == Vec2::ZERO {
+        return;
+    }
+    spectator_camera.enabled = true;
+    spectator_camera.preset = CameraPreset::Free;
+    for (mut transform, projection) in camera_query.iter_mut() {
+        // Y axis of window points down.
+        let offset = Vec2::new(-motion.x, motion.y) * projection.scale;
+        let position = spectator_camera.clamp_to_field(transform.translation.truncate() + offset);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+/// Returns scale of camera projection multiplied by zoom factor
+/// within limits of zoom.
+pub(crate) fn zoomed_scale(
+    scale: f32,
+    zoom_factor: f32,
+    spectator_camera: &SpectatorCamera,
+) -> f32 {
+    (scale * zoom_factor).clamp(MIN_ZOOM, spectator_camera.max_scale())
+}
+
+fn follow_camera_system(
+    time: Res<Time>,
+    spectator_camera: Res<SpectatorCamera>,
+    current_tank_query: Query<&Position, With<CurrentTank>>,
+    tanks_query: Query<(&Tank, &Position)>,
+    missiles_query: Query<&Position, With<Missile>>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let target = match spectator_camera.preset {
+        CameraPreset::Free => None,
+        CameraPreset::Overview => Some(spectator_camera.home_position),
+        CameraPreset::FollowCurrentTank => current_tank_query.iter().next().map(|p| p.0),
+        CameraPreset::FollowMissile => missiles_query.iter().next().map(|p| p.0),
+        CameraPreset::FollowPlayer(player) => tanks_query
+            .iter()
+            .find(|(tank, _)| tank.player_number == player)
+            .map(|(_, p)| p.0),
+    };
+    let Some(target) = target else {
+        return;
+    };
+
+    let factor = 1. - (-FOLLOW_SHARPNESS * time.delta_seconds()).exp();
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        let position = transform.translation.truncate().lerp(target, factor);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        if spectator_camera.preset == CameraPreset::Overview {
+            let scale = spectator_camera.home_scale;
+            projection.scale += (scale - projection.scale) * factor;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn camera_controller_system(
+    time: Res<Time<Real>>,
+    state: Res<State<AppState>>,
+    controller: Res<CameraController>,
+    spectator_camera: Res<SpectatorCamera>,
+    slow_motion: Option<Res<SlowMotion>>,
+    missiles_query: Query<&Position, With<Missile>>,
+    explosions_query: Query<&Position, With<Explosion>>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    if !controller.enabled {
+        return;
+    }
+    let action_target = if *state.get() == AppState::MainAction {
+        missiles_query
+            .iter()
+            .map(|p| p.0)
+            .max_by(|a, b| a.y.total_cmp(&b.y))
+            .or_else(|| explosions_query.iter().next().map(|p| p.0))
+    } else {
+        None
+    };
+    let (target, target_scale) = match (slow_motion, action_target) {
+        (Some(slow_motion), _) => (slow_motion.focus, SLOW_MOTION_ZOOM),
+        (None, Some(target)) => (target, ACTION_ZOOM),
+        (None, None) => (spectator_camera.home_position, spectator_camera.home_scale),
+    };
+
+    // Camera isn't slowed down by slow motion.
+    let delta = time.delta_seconds();
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        let position = controller
+            .easing
+            .step(transform.translation.truncate(), target, delta);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        projection.scale = controller
+            .easing
+            .step_scale(projection.scale, target_scale, delta);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_easing() {
+        let linear = CameraEasing::Linear { speed: 100. };
+        let target = Vec2::new(300., 400.);
+        assert_eq!(linear.step(Vec2::ZERO, target, 1.), Vec2::new(60., 80.));
+        assert_eq!(linear.step(Vec2::ZERO, target, 10.), target);
+
+        let exponential = CameraEasing::default();
+        let mut position = Vec2::ZERO;
+        let mut prev_distance = position.distance(target);
+        for _ in 0..100 {
+            position = exponential.step(position, target, 0.1);
+            let distance = position.distance(target);
+            assert!(distance <= prev_distance);
+            prev_distance = distance;
+        }
+        assert_eq!(position, target);
+    }
+
+    #[test]
+    fn test_overview() {
+        // Field of the default window.
+        let window = Vec2::new(1024., 768.);
+        let (position, scale) = overview(Vec2::new(1022., 736.), window, 30.);
+        assert_eq!((position, scale), (Vec2::new(512., 384.), 1.));
+        // Wide field is zoomed out to fit its width.
+        let (position, scale) = overview(Vec2::new(4094., 1024.), window, 30.);
+        assert_eq!((position, scale), (Vec2::new(2048., 1536.), 4.));
+
+        let mut spectator_camera = SpectatorCamera::default();
+        assert_eq!(zoomed_scale(1.9, 2., &spectator_camera), MAX_ZOOM);
+        spectator_camera.home_scale = 4.;
+        assert_eq!(zoomed_scale(1.9, 2., &spectator_camera), 3.8);
+        spectator_camera.field_size = Vec2::new(4094., 1024.);
+        assert_eq!(
+            spectator_camera.clamp_to_field(Vec2::new(-10., 2000.)),
+            Vec2::new(0., 1024.)
+        );
+    }
+
+    #[test]
+    fn test_next_followed_player() {
+        let players = [4, 1, 3];
+        assert_eq!(next_followed_player(None, &players), Some(1));
+        assert_eq!(next_followed_player(Some(1), &players), Some(3));
+        assert_eq!(next_followed_player(Some(2), &players), Some(3));
+        assert_eq!(next_followed_player(Some(4), &players), Some(1));
+        assert_eq!(next_followed_player(Some(1), &[]), None);
+    }
+}
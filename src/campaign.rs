@@ -0,0 +1,347 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiDifficulty;
+use crate::announcements::AnnouncementEvent;
+use crate::environment::TerrainTheme;
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::launch::LaunchOptions;
+use crate::settings::Settings;
+use crate::storage;
+use crate::tank::{Tank, TankDestroyedEvent};
+use crate::turn::{RoundEndedEvent, TurnStartedEvent};
+use crate::weapons::{TankWeapon, Weapons};
+
+/// Directory with definitions of levels of the campaign.
+pub const LEVELS_DIR: &str = "assets/levels";
+/// File where progress of the campaign is saved.
+pub const PROGRESS_PATH: &str = "campaign_progress.ron";
+/// Number of the player who plays the campaign, tanks of
+/// other players are controlled by bots.
+const CAMPAIGN_PLAYER: u8 = 1;
+
+/// Plays levels of the campaign one by one, if `Campaign` resource exists.
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelState>()
+            .add_systems(
+                OnEnter(AppState::RoundSetup),
+                start_level_system
+                    .before(setup_game_field)
+                    .run_if(resource_exists::<Campaign>),
+            )
+            .add_systems(
+                Update,
+                (
+                    equip_level_weapons_system,
+                    check_level_result_system,
+                    next_level_system.run_if(in_state(AppState::RoundOver)),
+                )
+                    .run_if(resource_exists::<Campaign>),
+            );
+    }
+}
+
+/// Condition of winning of a level.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WinCondition {
+    /// Player's tank is the last survivor.
+    #[default]
+    DestroyAllEnemies,
+    /// Player's tank survives the given count of its turns.
+    SurviveTurns(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelDefinition {
+    pub name: String,
+    /// Seed of the landscape and other random values of the level.
+    pub seed: u64,
+    #[serde(default)]
+    pub theme: TerrainTheme,
+    /// Count of tanks controlled by bots.
+    pub enemies: u8,
+    #[serde(default)]
+    pub ai_difficulty: AiDifficulty,
+    /// Names of weapons which may be used at the level,
+    /// all weapons are available if it is `None`.
+    #[serde(default)]
+    pub weapons: Option<Vec<String>>,
+    #[serde(default)]
+    pub win_condition: WinCondition,
+}
+
+impl LevelDefinition {
+    pub fn from_ron(ron: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(ron)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    /// Count of levels which have been won.
+    pub completed_levels: usize,
+}
+
+/// Sequence of levels and progress of the player.
+#[derive(Debug, Clone, Resource)]
+pub struct Campaign {
+    levels: Vec<LevelDefinition>,
+    progress: CampaignProgress,
+    /// File where progress is saved after every won level.
+    progress_path: Option<PathBuf>,
+}
+
+impl Campaign {
+    pub fn new(levels: Vec<LevelDefinition>) -> Self {
+        Self {
+            levels,
+            progress: CampaignProgress::default(),
+            progress_path: None,
+        }
+    }
+
+    /// Loads levels from RON files of the directory in order of their names
+    /// and progress from the given file, if it exists.
+    pub fn load<P: AsRef<Path>>(levels_dir: P, progress_path: P) -> io::Result<Self> {
+        let mut paths: Vec<PathBuf> = storage::list_dir(levels_dir)?
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+            .collect();
+        paths.sort();
+        let levels = paths
+            .iter()
+            .map(|path| {
+                let ron = storage::read_to_string(path)?;
+                LevelDefinition::from_ron(&ron).map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{}: {}", path.display(), err),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let progress_path = progress_path.as_ref().to_path_buf();
+        let progress = match storage::read_to_string(&progress_path) {
+            Ok(ron) => ron::from_str(&ron)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => CampaignProgress::default(),
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            levels,
+            progress,
+            progress_path: Some(progress_path),
+        })
+    }
+
+    #[inline]
+    pub fn progress(&self) -> CampaignProgress {
+        self.progress
+    }
+
+    /// Returns number (starting from 1) and definition of the level
+    /// which is played now, `None` if the campaign is completed.
+    pub fn current_level(&self) -> Option<(usize, &LevelDefinition)> {
+        let index = self.progress.completed_levels;
+        self.levels.get(index).map(|level| (index + 1, level))
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.current_level().is_none()
+    }
+
+    /// Advances the campaign to the next level and saves progress.
+    pub fn complete_level(&mut self) -> io::Result<()> {
+        if self.is_completed() {
+            return Ok(());
+        }
+        self.progress.completed_levels += 1;
+        if let Some(path) = &self.progress_path {
+            let ron = ron::to_string(&self.progress)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            storage::write(path, ron)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LevelResult {
+    Victory,
+    Defeat,
+}
+
+/// State of the level which is played now.
+#[derive(Debug, Default, Resource)]
+struct LevelState {
+    player_turns: usize,
+    result: Option<LevelResult>,
+}
+
+/// Applies definition of the current level to settings of the round.
+fn start_level_system(
+    mut commands: Commands,
+    campaign: Res<Campaign>,
+    mut level_state: ResMut<LevelState>,
+    mut settings: ResMut<Settings>,
+    launch_options: Option<Res<LaunchOptions>>,
+    mut announcements: EventWriter<AnnouncementEvent>,
+) {
+    *level_state = LevelState::default();
+    let Some((number, level)) = campaign.current_level() else {
+        announcements.send(AnnouncementEvent {
+            text: "Campaign completed".to_string(),
+        });
+        return;
+    };
+    info!("Start level {} \"{}\"", number, level.name);
+    settings.seed = Some(level.seed);
+    commands.insert_resource(level.theme);
+    let mut options = launch_options
+        .map(|options| options.clone())
+        .unwrap_or_default();
+    options.players = level.enemies + 1;
+    options.bots = level.enemies;
+    options.ai_difficulty = level.ai_difficulty;
+    commands.insert_resource(options);
+    announcements.send(AnnouncementEvent {
+        text: format!("Level {}: {}", number, level.name),
+    });
+}
+
+/// Tanks may use only the weapons of the level.
+fn equip_level_weapons_system(
+    campaign: Res<Campaign>,
+    weapons: Res<Weapons>,
+    mut new_tanks_query: Query<&mut TankWeapon, Added<Tank>>,
+) {
+    let Some(names) = campaign
+        .current_level()
+        .and_then(|(_, level)| level.weapons.as_ref())
+    else {
+        return;
+    };
+    let ammo: HashMap<usize, u32> = weapons
+        .0
+        .iter()
+        .enumerate()
+        .filter(|(_, weapon)| names.contains(&weapon.name))
+        .map(|(index, _)| (index, u32::MAX))
+        .collect();
+    for mut weapon in new_tanks_query.iter_mut() {
+        weapon.set_ammo(ammo.clone());
+    }
+}
+
+fn check_level_result_system(
+    mut campaign: ResMut<Campaign>,
+    mut level_state: ResMut<LevelState>,
+    mut turn_started_events: EventReader<TurnStartedEvent>,
+    mut destroyed_events: EventReader<TankDestroyedEvent>,
+    mut round_ended_events: EventReader<RoundEndedEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut announcements: EventWriter<AnnouncementEvent>,
+) {
+    let Some(win_condition) = campaign
+        .current_level()
+        .map(|(_, level)| level.win_condition)
+    else {
+        return;
+    };
+    let mut result = None;
+    for event in destroyed_events.read() {
+        if event.player_number == CAMPAIGN_PLAYER {
+            result = Some(LevelResult::Defeat);
+        }
+    }
+    for event in round_ended_events.read() {
+        if result.is_none() && win_condition == WinCondition::DestroyAllEnemies {
+            result = Some(if event.winner == Some(CAMPAIGN_PLAYER) {
+                LevelResult::Victory
+            } else {
+                LevelResult::Defeat
+            });
+        }
+    }
+    for event in turn_started_events.read() {
+        if event.player_number != CAMPAIGN_PLAYER || event.extra_shot {
+            continue;
+        }
+        level_state.player_turns += 1;
+        if let WinCondition::SurviveTurns(turns) = win_condition {
+            if result.is_none() && level_state.player_turns > turns {
+                result = Some(LevelResult::Victory);
+                next_state.set(AppState::RoundOver);
+            }
+        }
+    }
+
+    let Some(result) = result.filter(|_| level_state.result.is_none()) else {
+        return;
+    };
+    level_state.result = Some(result);
+    let text = match result {
+        LevelResult::Victory => {
+            if let Err(err) = campaign.complete_level() {
+                warn!("Failed to save progress of campaign: {}", err);
+            }
+            "Level completed! Press Enter to continue"
+        }
+        LevelResult::Defeat => "Level failed. Press Enter to retry",
+    };
+    announcements.send(AnnouncementEvent {
+        text: text.to_string(),
+    });
+}
+
+/// Starts the next level or retries the failed one.
+fn next_level_system(
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.is_some_and(|input| input.just_pressed(KeyCode::Enter)) {
+        debug!("Switch to RoundSetup");
+        next_state.set(AppState::RoundSetup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_campaign_levels() {
+        let level = LevelDefinition::from_ron(
+            r#"(
+                name: "Survivor",
+                seed: 7,
+                enemies: 2,
+                ai_difficulty: Easy,
+                weapons: Some(["Missile"]),
+                win_condition: SurviveTurns(5),
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(level.theme, TerrainTheme::Temperate);
+        assert_eq!(level.win_condition, WinCondition::SurviveTurns(5));
+
+        // Levels of the game are valid.
+        let campaign = Campaign::load(LEVELS_DIR, "missing_progress.ron").unwrap();
+        assert_eq!(campaign.progress(), CampaignProgress::default());
+        assert!(campaign.current_level().is_some());
+
+        let mut campaign = Campaign::new(vec![level.clone()]);
+        assert_eq!(campaign.current_level(), Some((1, &level)));
+        campaign.complete_level().unwrap();
+        assert!(campaign.is_completed());
+        assert_eq!(campaign.progress().completed_levels, 1);
+    }
+}
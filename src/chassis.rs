@@ -0,0 +1,112 @@
+use crate::geometry::Ellipse;
+
+/// Size of the medium tank, which is equal to size of tank's textures.
+pub const MEDIUM_TANK_SIZE: f32 = 41.;
+const MEDIUM_GUN_LENGTH: f32 = 21.;
+/// Bounds of body of the medium tank: center, radius by X and by Y.
+const MEDIUM_BODY_BOUNDS: [((f32, f32), f32, f32); 4] = [
+    ((0., -5.5), 9.5, 9.),    // top bound
+    ((-9.5, -13.), 10., 6.5), // left bound
+    ((9.5, -13.), 10., 6.5),  // right bound
+    ((0., -13.), 19.5, 7.5),  // center bound
+];
+/// Bounds of gun of the medium tank in the coordinate system of the gun.
+const MEDIUM_GUN_BOUNDS: [((f32, f32), f32, f32); 2] = [((0., 14.), 2.5, 5.), ((0., 5.), 2., 8.)];
+
+/// Model of tank selected by player.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Chassis {
+    Light,
+    #[default]
+    Medium,
+    Heavy,
+}
+
+impl Chassis {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(Self::Light),
+            "medium" => Some(Self::Medium),
+            "heavy" => Some(Self::Heavy),
+            _ => None,
+        }
+    }
+
+    /// Scale of the tank relative to the medium one.
+    pub fn scale(&self) -> f32 {
+        match self {
+            Self::Light => 0.8,
+            Self::Medium => 1.,
+            Self::Heavy => 1.25,
+        }
+    }
+
+    /// Width and height of the tank.
+    #[inline]
+    pub fn size(&self) -> f32 {
+        MEDIUM_TANK_SIZE * self.scale()
+    }
+
+    pub fn spec(&self) -> ChassisSpec {
+        let scale = self.scale();
+        let health = match self {
+            Self::Light => 75,
+            Self::Medium => 100,
+            Self::Heavy => 130,
+        };
+        ChassisSpec {
+            size: self.size(),
+            gun_length: MEDIUM_GUN_LENGTH * scale,
+            health,
+            body_bounds: scaled_ellipses(&MEDIUM_BODY_BOUNDS, scale),
+            gun_bounds: scaled_ellipses(&MEDIUM_GUN_BOUNDS, scale),
+        }
+    }
+}
+
+fn scaled_ellipses(bounds: &[((f32, f32), f32, f32)], scale: f32) -> Vec<Ellipse> {
+    bounds
+        .iter()
+        .map(|&((x, y), a, b)| Ellipse::new((x * scale, y * scale), a * scale, b * scale))
+        .collect()
+}
+
+/// Dimensions and durability of a model of tank.
+#[derive(Debug, Clone)]
+pub struct ChassisSpec {
+    /// Width and height of the tank.
+    pub size: f32,
+    /// Distance from the center of tank to the end of its gun.
+    pub gun_length: f32,
+    pub health: u8,
+    /// Bounds of the tank's body relative to its center.
+    pub body_bounds: Vec<Ellipse>,
+    /// Bounds of the gun in the coordinate system of the gun.
+    pub gun_bounds: Vec<Ellipse>,
+}
+
+impl Default for ChassisSpec {
+    fn default() -> Self {
+        Chassis::default().spec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chassis_spec() {
+        let light = Chassis::Light.spec();
+        let medium = ChassisSpec::default();
+        let heavy = Chassis::Heavy.spec();
+        assert_eq!(medium.size, MEDIUM_TANK_SIZE);
+        assert_eq!(medium.gun_length, MEDIUM_GUN_LENGTH);
+        assert!(light.size < medium.size && medium.size < heavy.size);
+        assert!(light.gun_length < heavy.gun_length);
+        assert!(light.health < medium.health && medium.health < heavy.health);
+        assert_eq!(heavy.body_bounds.len(), MEDIUM_BODY_BOUNDS.len());
+        assert_eq!(Chassis::from_name("heavy"), Some(Chassis::Heavy));
+        assert_eq!(Chassis::from_name("huge"), None);
+    }
+}
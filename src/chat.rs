@@ -0,0 +1,410 @@
+//! Chat of players.
+//!
+//! Messages of the host and hot-seat players are added to the history
+//! at once. Clients send their messages to the host, which adds them
+//! to its history and relays them to all clients, including the sender.
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy::window::ReceivedCharacter;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiController;
+use crate::camera::SpectatorCamera;
+use crate::game_field::GameField;
+use crate::net::{NetMessage, NetMessageReceived, NetSlots, SendNetMessage, SpectatorSession};
+use crate::rules::GameRules;
+use crate::tank::{player_color, AimingTank, Tank};
+use crate::turn_timer::HostClock;
+
+const MAX_MESSAGE_LENGTH: usize = 200;
+const MAX_HISTORY: usize = 50;
+/// Time (seconds) during which a new message is shown while chat is closed.
+const SHOW_TIME: f64 = 10.;
+/// Count of last messages which are shown while chat is closed.
+const MAX_VISIBLE: usize = 5;
+/// Count of last messages which are shown while chat is open.
+const MAX_VISIBLE_OPEN: usize = 12;
+const FONT_SIZE: f32 = 18.;
+const SPECTATOR_COLOR: Color = Color::GRAY;
+/// Phrases of quick chat, bound to keys 1-4.
+pub const QUICK_PHRASES: [&str; 4] = ["Nice shot!", "Missed me!", "Oops...", "Good game!"];
+
+/// History of chat and networking of its messages.
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatLog>()
+            .add_event::<ChatMessageSent>()
+            .add_systems(Update, (send_chat_system, receive_chat_system));
+    }
+}
+
+/// Text entry, quick chat and panel with the history of chat.
+pub struct ChatOverlayPlugin;
+
+impl Plugin for ChatOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatInput>().add_systems(
+            Update,
+            (
+                (chat_input_system, quick_chat_system)
+                    .run_if(resource_exists::<ButtonInput<KeyCode>>),
+                update_chat_panel_system,
+            )
+                .chain()
+                .before(send_chat_system),
+        );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Number of player who has sent the message,
+    /// `None` for spectators.
+    pub player: Option<u8>,
+    pub text: String,
+}
+
+impl ChatMessage {
+    pub fn new(player: Option<u8>, text: &str) -> Self {
+        let text = text.trim().chars().take(MAX_MESSAGE_LENGTH).collect();
+        Self { player, text }
+    }
+}
+
+/// Message written by the local player.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessageSent(pub ChatMessage);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatEntry {
+    pub message: ChatMessage,
+    /// Elapsed time of the app when the message has been received (seconds).
+    pub time: f64,
+}
+
+#[derive(Debug, Default, Clone, Resource)]
+pub struct ChatLog {
+    entries: VecDeque<ChatEntry>,
+    /// Players whose messages are ignored.
+    muted: HashSet<u8>,
+}
+
+impl ChatLog {
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &ChatEntry> {
+        self.entries.iter()
+    }
+
+    /// Adds the message to the history if its sender isn't muted.
+    pub fn push(&mut self, message: ChatMessage, time: f64) {
+        if message.text.is_empty() || message.player.is_some_and(|p| self.is_muted(p)) {
+            return;
+        }
+        if self.entries.len() >= MAX_HISTORY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ChatEntry { message, time });
+    }
+
+    pub fn is_muted(&self, player: u8) -> bool {
+        self.muted.contains(&player)
+    }
+
+    /// Mutes or unmutes the player. Returns `true` if the player is muted.
+    pub fn toggle_mute(&mut self, player: u8) -> bool {
+        if self.muted.remove(&player) {
+            return false;
+        }
+        self.muted.insert(player);
+        true
+    }
+}
+
+/// Text which the local player is typing.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct ChatInput {
+    pub open: bool,
+    pub text: String,
+}
+
+/// Run condition of systems which must not react
+/// on keys while the player is typing.
+pub fn is_chat_open(chat_input: Option<Res<ChatInput>>) -> bool {
+    chat_input.is_some_and(|input| input.open)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChatCommand {
+    Say(String),
+    /// Toggles muting of the player.
+    Mute(u8),
+}
+
+fn parse_command(text: &str) -> Option<ChatCommand> {
+    let text = text.trim();
+    if let Some(args) = text.strip_prefix("/mute") {
+        return args.trim().parse().ok().map(ChatCommand::Mute);
+    }
+    if text.is_empty() {
+        return None;
+    }
+    Some(ChatCommand::Say(text.to_string()))
+}
+
+fn send_chat_system(
+    time: Res<Time>,
+    host_clock: Option<Res<HostClock>>,
+    mut chat_log: ResMut<ChatLog>,
+    mut sent_events: EventReader<ChatMessageSent>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    for ChatMessageSent(message) in sent_events.read() {
+        // Clients receive their own messages back from the host.
+        if host_clock.is_none() {
+            chat_log.push(message.clone(), time.elapsed_seconds_f64());
+        }
+        send_events.send(SendNetMessage(NetMessage::Chat(message.clone())));
+    }
+}
+
+fn receive_chat_system(
+    time: Res<Time>,
+    slots: Option<Res<NetSlots>>,
+    mut chat_log: ResMut<ChatLog>,
+    mut received_events: EventReader<NetMessageReceived>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    for NetMessageReceived(message) in received_events.read() {
+        let NetMessage::Chat(message) = message else {
+            continue;
+        };
+        let message = ChatMessage::new(message.player, &message.text);
+        if slots.is_some() {
+            send_events.send(SendNetMessage(NetMessage::Chat(message.clone())));
+        }
+        chat_log.push(message, time.elapsed_seconds_f64());
+    }
+}
+
+/// Returns number of player who writes messages on this machine.
+fn local_player(
+    spectator: Option<Res<SpectatorSession>>,
+    aiming_tank_query: &Query<(&Tank, Has<AiController>), With<AimingTank>>,
+) -> Option<u8> {
+    if spectator.is_some() {
+        return None;
+    }
+    aiming_tank_query
+        .iter()
+        .find(|(_, is_bot)| !is_bot)
+        .map(|(tank, _)| tank.player_number)
+}
+
+/// T opens chat, Enter sends the message and Escape closes chat.
+fn chat_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut chat_input: ResMut<ChatInput>,
+    mut chat_log: ResMut<ChatLog>,
+    spectator: Option<Res<SpectatorSession>>,
+    aiming_tank_query: Query<(&Tank, Has<AiController>), With<AimingTank>>,
+    mut sent_events: EventWriter<ChatMessageSent>,
+) {
+    if !chat_input.open {
+        characters.clear();
+        if keyboard_input.just_pressed(KeyCode::KeyT) {
+            chat_input.open = true;
+        }
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        *chat_input = ChatInput::default();
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        match parse_command(&chat_input.text) {
+            Some(ChatCommand::Say(text)) => {
+                let player = local_player(spectator, &aiming_tank_query);
+                sent_events.send(ChatMessageSent(ChatMessage::new(player, &text)));
+            }
+            Some(ChatCommand::Mute(player)) => {
+                let muted = chat_log.toggle_mute(player);
+                info!("Player {} is muted: {}", player, muted);
+            }
+            None => (),
+        }
+        *chat_input = ChatInput::default();
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        chat_input.text.pop();
+    }
+    for event in characters.read() {
+        let chars = event.char.chars().filter(|c| !c.is_control());
+        for c in chars {
+            if chat_input.text.chars().count() < MAX_MESSAGE_LENGTH {
+                chat_input.text.push(c);
+            }
+        }
+    }
+}
+
+/// Keys 1-4 send quick phrases. They select presets
+/// of spectator camera while it is enabled.
+fn quick_chat_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    chat_input: Res<ChatInput>,
+    spectator_camera: Option<Res<SpectatorCamera>>,
+    spectator: Option<Res<SpectatorSession>>,
+    aiming_tank_query: Query<(&Tank, Has<AiController>), With<AimingTank>>,
+    mut sent_events: EventWriter<ChatMessageSent>,
+) {
+    if chat_input.open || spectator_camera.is_some_and(|camera| camera.enabled) {
+        return;
+    }
+    let keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+    ];
+    let Some(phrase) = keys
+        .iter()
+        .position(|&key| keyboard_input.just_pressed(key))
+        .map(|i| QUICK_PHRASES[i])
+    else {
+        return;
+    };
+    let player = local_player(spectator, &aiming_tank_query);
+    sent_events.send(ChatMessageSent(ChatMessage::new(player, phrase)));
+}
+
+#[derive(Component)]
+struct ChatPanel;
+
+/// Returns lines of the chat panel with colors of their senders.
+fn visible_lines(
+    chat_log: &ChatLog,
+    chat_input: &ChatInput,
+    now: f64,
+    players_count: u8,
+    rules: &GameRules,
+) -> Vec<(String, Color)> {
+    let (max_visible, max_age) = if chat_input.open {
+        (MAX_VISIBLE_OPEN, f64::INFINITY)
+    } else {
+        (MAX_VISIBLE, SHOW_TIME)
+    };
+    let mut lines: Vec<(String, Color)> = chat_log
+        .entries()
+        .rev()
+        .take(max_visible)
+        .filter(|entry| now - entry.time < max_age)
+        .map(|entry| {
+            let message = &entry.message;
+            match message.player {
+                Some(player) => (
+                    format!("Player {}: {}", player, message.text),
+                    player_color(player, players_count, rules.teams.as_ref()),
+                ),
+                None => (format!("Spectator: {}", message.text), SPECTATOR_COLOR),
+            }
+        })
+        .collect();
+    lines.reverse();
+    if chat_input.open {
+        lines.push((format!("> {}_", chat_input.text), Color::WHITE));
+    }
+    lines
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_chat_panel_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    rules: Res<GameRules>,
+    game_field: Option<Res<GameField>>,
+    chat_log: Res<ChatLog>,
+    chat_input: Res<ChatInput>,
+    panel_query: Query<Entity, With<ChatPanel>>,
+    mut shown_lines: Local<Vec<(String, Color)>>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let lines = visible_lines(
+        &chat_log,
+        &chat_input,
+        time.elapsed_seconds_f64(),
+        game_field.players_count(),
+        &rules,
+    );
+    let panel = match panel_query.get_single() {
+        Ok(_) if lines == *shown_lines => return,
+        Ok(panel) => panel,
+        Err(_) => commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(10.),
+                        bottom: Val::Px(10.),
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ChatPanel,
+            ))
+            .id(),
+    };
+    commands.entity(panel).despawn_descendants();
+    commands.entity(panel).with_children(|parent| {
+        for (text, color) in lines.iter() {
+            parent.spawn(TextBundle::from_section(
+                text.clone(),
+                TextStyle {
+                    font: game_field.font.clone(),
+                    font_size: FONT_SIZE,
+                    color: *color,
+                },
+            ));
+        }
+    });
+    *shown_lines = lines;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_log() {
+        assert_eq!(
+            parse_command(" gg "),
+            Some(ChatCommand::Say("gg".to_string()))
+        );
+        assert_eq!(parse_command("/mute 3"), Some(ChatCommand::Mute(3)));
+        assert_eq!(parse_command("/mute all"), None);
+        assert_eq!(parse_command("  "), None);
+
+        let mut log = ChatLog::default();
+        assert!(log.toggle_mute(3));
+        log.push(ChatMessage::new(Some(3), "Missed me!"), 0.);
+        log.push(ChatMessage::new(Some(1), "Nice shot!"), 1.);
+        log.push(ChatMessage::new(None, "Hi"), 2.);
+        assert!(!log.toggle_mute(3));
+        log.push(ChatMessage::new(Some(3), "Oops..."), 3.);
+        let texts: Vec<&str> = log.entries().map(|e| e.message.text.as_str()).collect();
+        assert_eq!(texts, ["Nice shot!", "Hi", "Oops..."]);
+
+        let long_text = "a".repeat(MAX_MESSAGE_LENGTH + 10);
+        assert_eq!(
+            ChatMessage::new(None, &long_text).text.len(),
+            MAX_MESSAGE_LENGTH
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+
+use crate::input::PlayerAction;
+use crate::materials::HueOffsetMaterial;
+use crate::orbital_strike::TurnWithoutProjectile;
+use crate::scanner::Revealed;
+use crate::tank::{shoot_system, AimingTank, CurrentTank, Tank, TankSet, TankShotEvent};
+use crate::turn::TurnStartedEvent;
+use crate::weapons::{TankWeapon, WeaponKind, Weapons};
+
+/// Number of turns of the tank during which it stays cloaked.
+const CLOAK_TURNS: u32 = 2;
+/// Opacity of cloaked tank for its opponents.
+const CLOAK_ALPHA: f32 = 0.15;
+
+pub struct CloakPlugin;
+
+impl Plugin for CloakPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            activate_cloak_system
+                .after(shoot_system)
+                .in_set(TankSet::Aiming),
+        )
+        .add_systems(
+            Update,
+            (
+                cloak_turns_system,
+                uncloak_on_shot_system,
+                cloak_visibility_system,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Tank is semi-invisible for its opponents.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Cloaked {
+    /// Number of turns of the tank left before the cloak is off.
+    pub turns_left: u32,
+}
+
+impl Cloaked {
+    /// Cloak is switched off by a shot only after the turn
+    /// in which it has been activated.
+    #[inline]
+    fn is_armed(&self) -> bool {
+        self.turns_left < CLOAK_TURNS
+    }
+}
+
+/// Activates the cloak of the aiming tank. Activation takes the whole turn.
+fn activate_cloak_system(
+    mut commands: Commands,
+    weapons: Res<Weapons>,
+    mut actions: EventReader<PlayerAction>,
+    mut aiming_tanks: Query<(Entity, &Tank, &mut TankWeapon), With<AimingTank>>,
+    mut shot_events: EventWriter<TankShotEvent>,
+) {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    if !fire {
+        return;
+    }
+    for (tank_entity, tank, mut weapon) in aiming_tanks.iter_mut() {
+        if weapon.ready_weapon_kind(&weapons) != WeaponKind::Cloak {
+            continue;
+        }
+        weapon.fire(&weapons);
+        weapon.deselect();
+        info!("Player {} has activated cloak", tank.player_number);
+        commands.entity(tank_entity).insert(Cloaked {
+            turns_left: CLOAK_TURNS,
+        });
+        commands.insert_resource(TurnWithoutProjectile);
+        shot_events.send(TankShotEvent { tank_entity });
+    }
+}
+
+fn cloak_turns_system(
+    mut commands: Commands,
+    mut started_events: EventReader<TurnStartedEvent>,
+    mut cloaked_query: Query<&mut Cloaked>,
+) {
+    for event in started_events.read().filter(|event| !event.extra_shot) {
+        let Ok(mut cloaked) = cloaked_query.get_mut(event.tank_entity) else {
+            continue;
+        };
+        cloaked.turns_left = cloaked.turns_left.saturating_sub(1);
+        if cloaked.turns_left == 0 {
+            debug!("Cloak of player {} is off", event.player_number);
+            commands.entity(event.tank_entity).remove::<Cloaked>();
+        }
+    }
+}
+
+fn uncloak_on_shot_system(
+    mut commands: Commands,
+    mut shot_events: EventReader<TankShotEvent>,
+    cloaked_query: Query<&Cloaked>,
+) {
+    for event in shot_events.read() {
+        if cloaked_query
+            .get(event.tank_entity)
+            .is_ok_and(|cloaked| cloaked.is_armed())
+        {
+            commands.entity(event.tank_entity).remove::<Cloaked>();
+        }
+    }
+}
+
+/// Returns `true` if the tank may be seen by the player whose turn it is.
+pub fn is_visible_for(tank: &Tank, cloaked: bool, revealed: bool, player: Option<u8>) -> bool {
+    !cloaked || revealed || player == Some(tank.player_number)
+}
+
+/// Cloaked tank with all its children is almost transparent for opponents
+/// and fully visible during the turn of its owner.
+fn cloak_visibility_system(
+    current_tanks: Query<&Tank, With<CurrentTank>>,
+    tanks_query: Query<(Entity, &Tank, Has<Cloaked>, Has<Revealed>)>,
+    children_query: Query<&Children>,
+    mut materials: Option<ResMut<Assets<HueOffsetMaterial>>>,
+    materials_query: Query<&Handle<HueOffsetMaterial>>,
+    mut texts_query: Query<&mut Text>,
+) {
+    let current_player = current_tanks.iter().next().map(|tank| tank.player_number);
+    for (tank_entity, tank, cloaked, revealed) in tanks_query.iter() {
+        let alpha = if is_visible_for(tank, cloaked, revealed, current_player) {
+            1.
+        } else {
+            CLOAK_ALPHA
+        };
+        let children = children_query.get(tank_entity).into_iter().flatten();
+        for entity in std::iter::once(tank_entity).chain(children.copied()) {
+            if let (Some(materials), Ok(handle)) = (materials.as_mut(), materials_query.get(entity))
+            {
+                if materials.get(handle).is_some_and(|m| m.alpha != alpha) {
+                    if let Some(material) = materials.get_mut(handle) {
+                        material.alpha = alpha;
+                    }
+                }
+            }
+            if let Ok(mut text) = texts_query.get_mut(entity) {
+                for section in text.sections.iter_mut() {
+                    if section.style.color.a() != alpha {
+                        section.style.color.set_a(alpha);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chassis::ChassisSpec;
+
+    #[test]
+    fn test_is_visible_for() {
+        let tank = Tank::new(2, &ChassisSpec::default());
+        assert!(is_visible_for(&tank, false, false, Some(1)));
+        assert!(!is_visible_for(&tank, true, false, Some(1)));
+        assert!(!is_visible_for(&tank, true, false, None));
+        assert!(is_visible_for(&tank, true, false, Some(2)));
+        assert!(is_visible_for(&tank, true, true, Some(1)));
+    }
+}
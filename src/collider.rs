@@ -1,6 +1,479 @@
-use bevy::prelude::Vec2;
+use std::f32::consts::PI;
 
-pub trait Collider {
-    /// Returns `true` if given point locates inside of collider.
-    fn has_collision<P: Into<Vec2>>(&self, point: P) -> bool;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::components::Position;
+use crate::geometry::rect::MyRect;
+use crate::geometry::{Circle, Ellipse, Polygon};
+
+/// Size of cells of the grid used to find colliders near a point.
+const CELL_SIZE: f32 = 64.;
+
+pub struct CollidersPlugin;
+
+impl Plugin for CollidersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialQuery>()
+            .add_systems(Update, update_spatial_query_system);
+    }
+}
+
+/// Ellipses which are rotated together relative to their collider.
+#[derive(Debug, Clone)]
+pub struct EllipseSet {
+    pub ellipses: Vec<Ellipse>,
+    /// Counterclockwise rotation in radians.
+    pub rotation: f32,
+}
+
+#[derive(Debug, Clone)]
+pub enum ColliderShape {
+    /// Union of ellipse sets clipped by the square with given half size.
+    Ellipses {
+        half_size: f32,
+        sets: Vec<EllipseSet>,
+    },
+    Circle {
+        radius: f32,
+    },
+    Rect {
+        half_size: Vec2,
+    },
+    Polygon(Polygon),
+}
+
+/// Bounds of entity relative to its `Position`.
+#[derive(Debug, Clone, Component)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    /// Counterclockwise rotation in radians.
+    pub rotation: f32,
+}
+
+impl Collider {
+    pub fn ellipses(half_size: f32, sets: Vec<EllipseSet>) -> Self {
+        Self::from(ColliderShape::Ellipses { half_size, sets })
+    }
+
+    pub fn circle(radius: f32) -> Self {
+        Self::from(ColliderShape::Circle { radius })
+    }
+
+    pub fn rect(half_size: Vec2) -> Self {
+        Self::from(ColliderShape::Rect { half_size })
+    }
+
+    pub fn polygon(vertices: Vec<Vec2>) -> Self {
+        Self::from(ColliderShape::Polygon(Polygon::new(vertices)))
+    }
+
+    pub fn with_rotation(self, rotation: f32) -> Self {
+        Self { rotation, ..self }
+    }
+
+    /// Returns `true` if given point relative to the collider's position
+    /// locates inside of it.
+    pub fn contains(&self, local_point: Vec2) -> bool {
+        let point = Vec2::from_angle(-self.rotation).rotate(local_point);
+        match &self.shape {
+            ColliderShape::Ellipses { half_size, sets } => {
+                if point.abs().max_element() > *half_size {
+                    return false;
+                }
+                sets.iter().any(|set| {
+                    let set_point = Vec2::from_angle(-set.rotation).rotate(point);
+                    set.ellipses
+                        .iter()
+                        .any(|e| e.point_position(set_point) <= 0.)
+                })
+            }
+            ColliderShape::Circle { radius } => point.length_squared() <= radius * radius,
+            ColliderShape::Rect { half_size } => {
+                MyRect::from_center_size(Vec2::ZERO, *half_size * 2.).contains(point)
+            }
+            ColliderShape::Polygon(polygon) => polygon.contains(point),
+        }
+    }
+
+    /// Returns the first point of the segment relative to the collider's
+    /// position which locates inside of it, as fraction of the segment's
+    /// length from its start.
+    pub fn segment_entry(&self, start: Vec2, end: Vec2) -> Option<f32> {
+        let rotation = Vec2::from_angle(-self.rotation);
+        let (start, end) = (rotation.rotate(start), rotation.rotate(end));
+        match &self.shape {
+            ColliderShape::Ellipses { half_size, sets } => {
+                let square = MyRect::from_center_size(Vec2::ZERO, Vec2::splat(half_size * 2.));
+                let (enter, exit) = square.segment_interval(start, end)?;
+                sets.iter()
+                    .flat_map(|set| {
+                        let rotation = Vec2::from_angle(-set.rotation);
+                        let (start, end) = (rotation.rotate(start), rotation.rotate(end));
+                        set.ellipses
+                            .iter()
+                            .filter_map(move |e| e.segment_interval(start, end))
+                    })
+                    .filter_map(|(t0, t1)| {
+                        let t0 = t0.max(enter);
+                        (t0 <= t1.min(exit)).then_some(t0)
+                    })
+                    .min_by(f32::total_cmp)
+            }
+            ColliderShape::Circle { radius } => {
+                if start.length_squared() <= radius * radius {
+                    return Some(0.);
+                }
+                let length = start.distance(end);
+                Circle::new(Vec2::ZERO, *radius)
+                    .segment_intersection(start, end)
+                    .into_iter()
+                    .map(|point| start.distance(point) / length)
+                    .min_by(f32::total_cmp)
+            }
+            ColliderShape::Rect { half_size } => {
+                MyRect::from_center_size(Vec2::ZERO, *half_size * 2.)
+                    .segment_interval(start, end)
+                    .map(|(t0, _)| t0)
+            }
+            ColliderShape::Polygon(polygon) => polygon.segment_entry(start, end),
+        }
+    }
+
+    /// Returns axis-aligned rectangle which bounds the collider
+    /// placed in given position.
+    pub fn bounding_rect(&self, position: Vec2) -> MyRect {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (sin, cos) = (sin.abs(), cos.abs());
+        let half_size = match &self.shape {
+            ColliderShape::Ellipses { half_size, .. } => Vec2::splat(half_size * (cos + sin)),
+            ColliderShape::Circle { radius } => Vec2::splat(*radius),
+            ColliderShape::Rect { half_size } => Vec2::new(
+                half_size.x * cos + half_size.y * sin,
+                half_size.x * sin + half_size.y * cos,
+            ),
+            ColliderShape::Polygon(polygon) => {
+                let rotation = Vec2::from_angle(self.rotation);
+                let rotated: Vec<Vec2> = polygon
+                    .vertices()
+                    .iter()
+                    .map(|&vertex| rotation.rotate(vertex))
+                    .collect();
+                return Polygon::new(rotated).bounding_rect().translated(position);
+            }
+        };
+        MyRect::from_center_size(position, half_size * 2.)
+    }
+
+    /// Returns part of area of the collider placed in given position
+    /// which is covered by the circle. The circle is approximated by
+    /// the square of the same area, and clipping of ellipses by the
+    /// collider's square is ignored. Returns `None` for shapes which
+    /// fill their bounding rect.
+    pub fn covered_part(&self, position: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+        let center = Vec2::from_angle(-self.rotation).rotate(center - position);
+        let square_size = Vec2::splat(radius * PI.sqrt());
+        match &self.shape {
+            ColliderShape::Ellipses { sets, .. } => {
+                let mut area = 0.;
+                let mut covered_area = 0.;
+                for set in sets {
+                    let center = Vec2::from_angle(-set.rotation).rotate(center);
+                    let square = MyRect::from_center_size(center, square_size);
+                    for ellipse in set.ellipses.iter() {
+                        area += ellipse.area();
+                        if ellipse.point_position(center) > 0. {
+                            // Corners of the square are outside of the circle,
+                            // so the circle has to reach the border of ellipse.
+                            let border = ellipse.segment_intersection(center, ellipse.center);
+                            if !border.first().is_some_and(|p| p.distance(center) <= radius) {
+                                continue;
+                            }
+                        }
+                        covered_area += ellipse.area_of_rect_intersection(square);
+                    }
+                }
+                (area > 0.).then(|| (covered_area / area).min(1.))
+            }
+            ColliderShape::Polygon(polygon) => {
+                let area = polygon.area();
+                if area == 0. {
+                    return None;
+                }
+                if !polygon.contains(center) {
+                    // The circle has to reach the border of polygon
+                    // on the way to the center of its bounding rect.
+                    let target = Rect::from(polygon.bounding_rect()).center();
+                    let border = polygon.segment_intersection(center, target);
+                    if !border.first().is_some_and(|p| p.distance(center) <= radius) {
+                        return Some(0.);
+                    }
+                }
+                let square = MyRect::from_center_size(center, square_size);
+                Some((polygon.area_of_rect_intersection(square) / area).min(1.))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<ColliderShape> for Collider {
+    fn from(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            rotation: 0.,
+        }
+    }
+}
+
+/// Colliders of all entities with their positions in the current frame.
+/// Every collider is registered in all cells of grid which are overlapped
+/// by its bounding rect, so hit tests check only nearby colliders.
+#[derive(Resource, Default)]
+pub struct SpatialQuery {
+    entries: Vec<(Entity, Vec2, Collider)>,
+    cells: HashMap<IVec2, Vec<usize>>,
+}
+
+impl SpatialQuery {
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2, collider: Collider) {
+        let rect = collider.bounding_rect(position);
+        let min = cell(Vec2::new(rect.left, rect.bottom));
+        let max = cell(Vec2::new(rect.right, rect.top));
+        let index = self.entries.len();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+        self.entries.push((entity, position, collider));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Vec2, &Collider)> {
+        self.entries
+            .iter()
+            .map(|(entity, position, collider)| (*entity, *position, collider))
+    }
+
+    /// Returns entity which collider is the first one crossed by the segment
+    /// and fraction of the segment's length before the crossing.
+    pub fn segment_hit(&self, start: Vec2, end: Vec2) -> Option<(Entity, f32)> {
+        let (min, max) = (cell(start.min(end)), cell(start.max(end)));
+        let mut indices: Vec<usize> = (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .filter_map(|index| {
+                let (entity, position, collider) = &self.entries[index];
+                let t = collider.segment_entry(start - *position, end - *position)?;
+                Some((*entity, t))
+            })
+            // The first one of colliders crossed in the same point is taken.
+            .reduce(|first, hit| if hit.1 < first.1 { hit } else { first })
+    }
+}
+
+#[inline]
+fn cell(point: Vec2) -> IVec2 {
+    (point / CELL_SIZE).floor().as_ivec2()
+}
+
+pub fn update_spatial_query_system(
+    mut spatial_query: ResMut<SpatialQuery>,
+    colliders_query: Query<(Entity, &Position, &Collider)>,
+) {
+    spatial_query.clear();
+    for (entity, position, collider) in colliders_query.iter() {
+        spatial_query.insert(entity, position.0, collider.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use crate::chassis::ChassisSpec;
+    use crate::tank::Tank;
+
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let rect = Collider::rect(Vec2::new(10., 2.));
+        assert!(rect.contains(Vec2::new(9., 2.)));
+        assert!(!rect.contains(Vec2::new(1., 9.)));
+        let rect = rect.with_rotation(FRAC_PI_2);
+        assert!(rect.contains(Vec2::new(1., 9.)));
+        assert_eq!(rect.bounding_rect(Vec2::ZERO).top.round(), 10.);
+
+        let circle = Collider::circle(5.);
+        assert!(circle.contains(Vec2::new(3., 4.)));
+        assert!(!circle.contains(Vec2::new(4., 4.)));
+
+        let ellipses = Collider::ellipses(
+            10.,
+            vec![EllipseSet {
+                ellipses: vec![Ellipse::new((0., 5.), 2., 5.)],
+                rotation: -FRAC_PI_2,
+            }],
+        );
+        assert!(ellipses.contains(Vec2::new(9., 0.)));
+        assert!(!ellipses.contains(Vec2::new(0., 9.)));
+        // The point is clipped by the square of collider.
+        assert!(!ellipses.contains(Vec2::new(11., 0.)));
+
+        let triangle = Collider::polygon(vec![
+            Vec2::new(-5., 0.),
+            Vec2::new(5., 0.),
+            Vec2::new(0., 10.),
+        ])
+        .with_rotation(FRAC_PI_2);
+        assert!(triangle.contains(Vec2::new(-5., 1.)));
+        assert!(!triangle.contains(Vec2::new(5., 1.)));
+        assert_eq!(triangle.bounding_rect(Vec2::ZERO).left.round(), -10.);
+        let entry = triangle.segment_entry(Vec2::new(-5., 10.), Vec2::new(-5., -10.));
+        assert!((entry.unwrap() - 0.375).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_covered_part() {
+        let ellipses = Collider::ellipses(
+            10.,
+            vec![EllipseSet {
+                ellipses: vec![Ellipse::new(Vec2::ZERO, 10., 5.)],
+                rotation: 0.,
+            }],
+        )
+        .with_rotation(FRAC_PI_2);
+        let position = Vec2::new(100., 100.);
+        assert_eq!(ellipses.covered_part(position, position, 50.), Some(1.));
+        // The square approximating the explosion covers the upper half
+        // of the rotated ellipse.
+        let center = position + Vec2::new(0., 20. * PI.sqrt());
+        let part = ellipses.covered_part(position, center, 40.).unwrap();
+        assert!((part - 0.5).abs() < 1e-4);
+        // Only a corner of the square approximating the explosion
+        // reaches the ellipse.
+        let near = position + Vec2::new(6.5, 11.);
+        assert_eq!(ellipses.covered_part(position, near, 4.), Some(0.));
+
+        let square = Collider::polygon(vec![
+            Vec2::new(-10., -10.),
+            Vec2::new(10., -10.),
+            Vec2::new(10., 10.),
+            Vec2::new(-10., 10.),
+        ]);
+        assert_eq!(square.covered_part(position, position, 50.), Some(1.));
+        let left = position - Vec2::new(10. * PI.sqrt(), 0.);
+        let part = square.covered_part(position, left, 20.).unwrap();
+        assert!((part - 0.5).abs() < 1e-4);
+        let far = position + Vec2::new(0., 30.);
+        assert_eq!(square.covered_part(position, far, 10.), Some(0.));
+        assert_eq!(
+            Collider::circle(5.).covered_part(position, position, 10.),
+            None
+        );
+    }
+
+    #[test]
+    fn test_segment_hit() {
+        let mut spatial_query = SpatialQuery::default();
+        let (first, second) = (Entity::from_raw(1), Entity::from_raw(2));
+        // The rect lays over the border of cells.
+        spatial_query.insert(
+            first,
+            Vec2::new(CELL_SIZE, 10.),
+            Collider::rect(Vec2::splat(5.)),
+        );
+        spatial_query.insert(second, Vec2::new(-100., -100.), Collider::circle(20.));
+        assert_eq!(
+            spatial_query.segment_hit(Vec2::new(CELL_SIZE - 10., 10.), Vec2::new(CELL_SIZE, 10.)),
+            Some((first, 0.5))
+        );
+        assert_eq!(
+            spatial_query.segment_hit(Vec2::new(-140., -100.), Vec2::new(-100., -100.)),
+            Some((second, 0.5))
+        );
+        assert_eq!(
+            spatial_query.segment_hit(Vec2::new(-115., -115.), Vec2::new(-116., -116.)),
+            None
+        );
+
+        // Segment between neighbour pixels crosses a thin collider
+        // which doesn't contain any of these pixels.
+        let thin = Collider::ellipses(
+            5.,
+            vec![EllipseSet {
+                ellipses: vec![Ellipse::new(Vec2::ZERO, 0., 5.)],
+                rotation: 0.,
+            }],
+        );
+        assert!(!thin.contains(Vec2::new(-1., 1.)) && !thin.contains(Vec2::new(1., 0.)));
+        assert_eq!(
+            thin.segment_entry(Vec2::new(-1., 1.), Vec2::new(1., 0.)),
+            Some(0.5)
+        );
+    }
+
+    /// Run by `cargo test --release -- --ignored --nocapture bench_segment_hit`
+    #[test]
+    #[ignore]
+    fn bench_segment_hit() {
+        let collider = Tank::new(1, &ChassisSpec::default()).collider();
+        let mut spatial_query = SpatialQuery::default();
+        for i in 0..32 {
+            let position = Vec2::new(60. + i as f32 * 120., 300. + (i % 5) as f32 * 40.);
+            spatial_query.insert(Entity::from_raw(i), position, collider.clone());
+        }
+        // Paths of missiles of MIRV over the whole field.
+        let paths: Vec<Vec<Vec2>> = (0..8)
+            .map(|i| {
+                (0..4000)
+                    .map(|x| Vec2::new(x as f32, 200. + i as f32 * 30. + (x % 300) as f32))
+                    .collect()
+            })
+            .collect();
+
+        let started = std::time::Instant::now();
+        let brute_hits = paths
+            .iter()
+            .flat_map(|path| path.windows(2))
+            .filter(|segment| {
+                spatial_query.iter().any(|(_, position, collider)| {
+                    collider
+                        .segment_entry(segment[0] - position, segment[1] - position)
+                        .is_some()
+                })
+            })
+            .count();
+        let brute_time = started.elapsed();
+
+        let started = std::time::Instant::now();
+        let grid_hits = paths
+            .iter()
+            .flat_map(|path| path.windows(2))
+            .filter(|segment| spatial_query.segment_hit(segment[0], segment[1]).is_some())
+            .count();
+        let grid_time = started.elapsed();
+
+        assert_eq!(brute_hits, grid_hits);
+        println!(
+            "{} hits of {} segments: brute force takes {:?}, grid takes {:?}",
+            grid_hits,
+            paths.iter().map(|path| path.len() - 1).sum::<usize>(),
+            brute_time,
+            grid_time
+        );
+    }
 }
@@ -14,3 +14,7 @@ pub struct Opacity(pub f32);
 
 #[derive(Debug, Default, Clone, Copy, Component)]
 pub struct HueOffset(pub u16);
+
+/// Tank which has fired the missile or caused the explosion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct Owner(pub Entity);
@@ -0,0 +1,176 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::sprite::Mesh2dHandle;
+
+use crate::environment::DayPhase;
+use crate::game_field::GameField;
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::materials::DayNightMaterial;
+use crate::settings::Settings;
+use crate::turn::TurnStartedEvent;
+
+/// Number of turns in a full day.
+const DAY_TURNS: f32 = 30.;
+/// How fast displayed time of day reaches the time of the current turn.
+const TIME_SHARPNESS: f32 = 1.;
+const MAX_TINT_STRENGTH: f32 = 0.6;
+const DUSK_COLOR: Color = Color::rgb(0.8, 0.35, 0.15);
+const NIGHT_COLOR: Color = Color::rgb(0.02, 0.03, 0.15);
+/// Z-coordinate of the tint. Landscape, tanks and missiles are tinted,
+/// explosions and tracers are drawn above the tint.
+pub const TINT_Z: f32 = 1.8;
+/// Size of the tint relative to the size of game field,
+/// so it covers the background when camera moves.
+const TINT_SCALE: f32 = 4.;
+
+/// Optional day/night cycle which tints the game field over the course
+/// of a match. It is enabled by `Settings::day_night_cycle`.
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DayCycle>()
+            .add_systems(
+                OnEnter(AppState::RoundSetup),
+                setup_tint.after(setup_game_field).run_if(cycle_enabled),
+            )
+            .add_systems(
+                Update,
+                (advance_time_system, update_tint_system)
+                    .chain()
+                    .run_if(cycle_enabled),
+            );
+    }
+}
+
+/// Time of day: 0 - noon, 0.5 - midnight.
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct DayCycle {
+    /// Time of the current turn.
+    pub target_time: f32,
+    /// Displayed time which smoothly follows the target time.
+    pub time: f32,
+}
+
+impl DayCycle {
+    /// Darkness of the time of day from 0 (noon) to 1 (midnight).
+    pub fn darkness(&self) -> f32 {
+        darkness(self.time)
+    }
+}
+
+#[derive(Clone, Copy, Component)]
+struct DayNightTint;
+
+fn cycle_enabled(settings: Res<Settings>) -> bool {
+    settings.day_night_cycle
+}
+
+pub fn darkness(time: f32) -> f32 {
+    0.5 - 0.5 * (time * TAU).cos()
+}
+
+/// Returns color and strength of the tint for given time of day.
+/// The tint is reddish at dusk and dawn and dark blue at night.
+fn tint(time: f32) -> (Color, f32) {
+    let darkness = darkness(time);
+    let night_part = ((darkness - 0.5) * 2.).clamp(0., 1.);
+    let dusk = Vec4::from(DUSK_COLOR.as_rgba_f32());
+    let night = Vec4::from(NIGHT_COLOR.as_rgba_f32());
+    let color = dusk.lerp(night, night_part);
+    (
+        Color::rgb(color.x, color.y, color.z),
+        darkness * MAX_TINT_STRENGTH,
+    )
+}
+
+fn setup_tint(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<DayNightMaterial>>>,
+    day_cycle: Res<DayCycle>,
+) {
+    let (Some(mut meshes), Some(mut materials)) = (meshes, materials) else {
+        return;
+    };
+    let field_size = Vec2::new(game_field.width as f32, game_field.height as f32);
+    let (color, strength) = tint(day_cycle.time);
+    let tint_entity = commands
+        .spawn((
+            Mesh2dHandle(meshes.add(Rectangle::from_size(field_size * TINT_SCALE))),
+            materials.add(DayNightMaterial { color, strength }),
+            SpatialBundle::from_transform(Transform::from_translation(
+                (field_size / 2.).extend(TINT_Z),
+            )),
+            DayNightTint,
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(tint_entity);
+}
+
+fn advance_time_system(
+    time: Res<Time>,
+    mut started_events: EventReader<TurnStartedEvent>,
+    mut day_cycle: ResMut<DayCycle>,
+    mut day_phase: ResMut<DayPhase>,
+) {
+    for event in started_events.read() {
+        day_cycle.target_time = (event.turn_number as f32 / DAY_TURNS).fract();
+    }
+    // Time of day goes forward only.
+    let mut offset = (day_cycle.target_time - day_cycle.time).rem_euclid(1.);
+    if offset > 0.0001 {
+        offset *= 1. - (-TIME_SHARPNESS * time.delta_seconds()).exp();
+        day_cycle.time = (day_cycle.time + offset).fract();
+    }
+
+    let phase = if day_cycle.darkness() > 0.5 {
+        DayPhase::Night
+    } else {
+        DayPhase::Day
+    };
+    if *day_phase != phase {
+        *day_phase = phase;
+    }
+}
+
+fn update_tint_system(
+    day_cycle: Res<DayCycle>,
+    materials: Option<ResMut<Assets<DayNightMaterial>>>,
+    tints_query: Query<&Handle<DayNightMaterial>, With<DayNightTint>>,
+) {
+    let Some(mut materials) = materials else {
+        return;
+    };
+    if !day_cycle.is_changed() {
+        return;
+    }
+    let (color, strength) = tint(day_cycle.time);
+    for handle in tints_query.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = color;
+            material.strength = strength;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tint() {
+        let (_, noon_strength) = tint(0.);
+        assert_eq!(noon_strength, 0.);
+        let (dusk_color, dusk_strength) = tint(0.25);
+        assert!((dusk_strength - MAX_TINT_STRENGTH / 2.).abs() < 0.001);
+        assert!(dusk_color.r() > dusk_color.b());
+        let (night_color, night_strength) = tint(0.5);
+        assert!((night_strength - MAX_TINT_STRENGTH).abs() < 0.001);
+        assert!(night_color.b() > night_color.r());
+    }
+}
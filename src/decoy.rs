@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+
+use crate::chassis::Chassis;
+use crate::collider::Collider;
+use crate::components::{HueOffset, Position};
+use crate::explosion::ExplosionDamageEvent;
+use crate::game_field::GameField;
+use crate::game_plugin::AppState;
+use crate::input::PlayerAction;
+use crate::landscape::{Landscape, SubsidenceFinishedEvent};
+use crate::mines::settle_height;
+use crate::orbital_strike::TurnWithoutProjectile;
+use crate::rules::GameRules;
+use crate::tank::{
+    player_hue_offset, shoot_system, AimingTank, Health, Tank, TankSet, TankShotEvent,
+};
+use crate::weapons::{TankWeapon, WeaponKind, Weapons};
+
+/// Horizontal distance between the tank and its deployed decoy.
+const DECOY_DISTANCE: f32 = 60.;
+const DECOY_HEALTH: u8 = 20;
+
+pub struct DecoyPlugin;
+
+impl Plugin for DecoyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DecoyDestroyedEvent>()
+            .add_systems(
+                Update,
+                deploy_decoy_system
+                    .after(shoot_system)
+                    .in_set(TankSet::Aiming),
+            )
+            .add_systems(Update, (damage_decoys_system, settle_decoys_system))
+            .add_systems(OnEnter(AppState::RoundSetup), despawn_decoys_system);
+    }
+}
+
+/// Inflatable copy of the player's tank. It looks like a real tank
+/// for opponents and bots, but has low health and doesn't have a gun.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Decoy {
+    pub player_number: u8,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DecoyDestroyedEvent {
+    pub player_number: u8,
+    pub position: Vec2,
+}
+
+/// Decoy is a copy of the medium tank.
+fn decoy_size() -> Vec2 {
+    Vec2::splat(Chassis::default().size())
+}
+
+/// Returns position of the center of decoy which stands
+/// on the surface of landscape.
+fn decoy_position(landscape: &Landscape, x: f32) -> Vec2 {
+    let (width, height) = landscape.size();
+    let half_size = decoy_size() / 2.;
+    let x = x.clamp(half_size.x, width as f32 - half_size.x);
+    let y = settle_height(landscape, x as i32, height as i32 - 1);
+    Vec2::new(x, y as f32 + half_size.y)
+}
+
+/// Deploys decoy in front of the gun of aiming tank.
+/// Deployment takes the whole turn.
+fn deploy_decoy_system(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    weapons: Res<Weapons>,
+    rules: Res<GameRules>,
+    mut actions: EventReader<PlayerAction>,
+    mut aiming_tanks: Query<(Entity, &Tank, &Position, &mut TankWeapon), With<AimingTank>>,
+    mut shot_events: EventWriter<TankShotEvent>,
+) {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    if !fire {
+        return;
+    }
+    for (tank_entity, tank, &Position(tank_position), mut weapon) in aiming_tanks.iter_mut() {
+        if weapon.ready_weapon_kind(&weapons) != WeaponKind::Decoy {
+            continue;
+        }
+        weapon.fire(&weapons);
+        weapon.deselect();
+
+        let direction = if tank.gun_angle_rad().sin() < 0. {
+            -1.
+        } else {
+            1.
+        };
+        let position = decoy_position(
+            &game_field.landscape,
+            tank_position.x + direction * DECOY_DISTANCE,
+        );
+        info!(
+            "Player {} has deployed decoy at {:?}",
+            tank.player_number, position
+        );
+        let decoy_entity = commands
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_translation(position.extend(0.1))),
+                HueOffset(player_hue_offset(
+                    tank.player_number,
+                    game_field.players_count(),
+                    rules.teams.as_ref(),
+                )),
+                Position(position),
+                Collider::rect(decoy_size() / 2.),
+                Health {
+                    value: DECOY_HEALTH,
+                    shield: 0,
+                    invincible: false,
+                },
+                Decoy {
+                    player_number: tank.player_number,
+                },
+            ))
+            .id();
+        commands
+            .entity(game_field.parent_entity)
+            .add_child(decoy_entity);
+        commands.insert_resource(TurnWithoutProjectile);
+        shot_events.send(TankShotEvent { tank_entity });
+    }
+}
+
+fn damage_decoys_system(
+    mut commands: Commands,
+    mut damage_events: EventReader<ExplosionDamageEvent>,
+    mut decoys_query: Query<(Entity, &Decoy, &Position, &mut Health)>,
+    mut destroyed_events: EventWriter<DecoyDestroyedEvent>,
+) {
+    for event in damage_events.read() {
+        let Ok((entity, decoy, &Position(position), mut health)) =
+            decoys_query.get_mut(event.entity)
+        else {
+            continue;
+        };
+        if health.value > 0 && health.damage(event.damage) == 0 {
+            info!("Decoy of player {} has been destroyed", decoy.player_number);
+            commands.entity(entity).despawn_recursive();
+            destroyed_events.send(DecoyDestroyedEvent {
+                player_number: decoy.player_number,
+                position,
+            });
+        }
+    }
+}
+
+/// Decoys fall together with the landscape under them.
+fn settle_decoys_system(
+    game_field: Option<Res<GameField>>,
+    mut finished_events: EventReader<SubsidenceFinishedEvent>,
+    mut decoys_query: Query<&mut Position, With<Decoy>>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    if finished_events.read().count() == 0 {
+        return;
+    }
+    for mut position in decoys_query.iter_mut() {
+        position.0 = decoy_position(&game_field.landscape, position.0.x);
+    }
+}
+
+fn despawn_decoys_system(mut commands: Commands, decoys_query: Query<Entity, With<Decoy>>) {
+    for entity in decoys_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::landscape_buffer::LandscapeStorage;
+
+    #[test]
+    fn test_decoy_position() {
+        let landscape = Landscape::new(300, 200, 5, LandscapeStorage::default(), None).unwrap();
+        let half_size = decoy_size() / 2.;
+        for x in [0., 150., 1000.] {
+            let position = decoy_position(&landscape, x);
+            assert!(position.x >= half_size.x && position.x <= 300. - half_size.x);
+            let bottom = (position.y - half_size.y) as i32;
+            assert!(!landscape.is_not_empty(position.x as i32, bottom));
+            assert!(bottom == 0 || landscape.is_not_empty(position.x as i32, bottom - 1));
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Detection of desynchronization of network games and replays.
+//!
+//! Hash of the state of simulation is computed at start of every turn.
+//! Peers of network game send their hashes to each other and replays
+//! store hashes of recorded turns. `DesyncDetectedEvent` is emitted
+//! when hashes of the same turn don't match.
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::components::Position;
+use crate::game_field::{GameField, GameRng};
+use crate::landscape_buffer::{fnv1a, fnv1a_hash};
+use crate::net::{NetMessage, NetMessageReceived, NetSlots, SendNetMessage};
+use crate::replay::ReplayPlayback;
+use crate::tank::{Health, Tank};
+use crate::turn::TurnStartedEvent;
+use crate::turn_timer::HostClock;
+
+/// Count of hashes of the latest turns kept for comparison.
+const MAX_HASHES: usize = 16;
+
+pub struct DesyncPlugin;
+
+impl Plugin for DesyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StateHashLog>()
+            .add_event::<DesyncDetectedEvent>()
+            .add_systems(
+                Update,
+                (hash_state_system, receive_state_hash_system).chain(),
+            );
+    }
+}
+
+/// Hashes of parts of the state of simulation at start of the turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateHash {
+    pub turn_number: usize,
+    /// Checksum of pixels of landscape.
+    pub landscape: u64,
+    /// Hash of positions and health of tanks.
+    pub tanks: u64,
+    /// Hash of the next random value of the round.
+    pub rng: u64,
+}
+
+impl StateHash {
+    /// Returns names of parts of the state which differ.
+    pub fn differences(&self, other: &StateHash) -> Vec<&'static str> {
+        [
+            ("landscape", self.landscape == other.landscape),
+            ("tanks", self.tanks == other.tanks),
+            ("rng", self.rng == other.rng),
+        ]
+        .into_iter()
+        .filter(|&(_, is_equal)| !is_equal)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// Returns hash of tanks given as player number, position and health.
+fn hash_tanks(tanks: impl Iterator<Item = (u8, Vec2, u8)>) -> u64 {
+    let mut tanks: Vec<(u8, Vec2, u8)> = tanks.collect();
+    tanks.sort_by_key(|&(player, _, _)| player);
+    tanks
+        .iter()
+        .fold(fnv1a_hash(&[]), |hash, &(player, position, health)| {
+            let hash = fnv1a(hash, &[player, health]);
+            let hash = fnv1a(hash, &position.x.to_bits().to_le_bytes());
+            fnv1a(hash, &position.y.to_bits().to_le_bytes())
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DesyncSource {
+    /// Other peer of network game.
+    Peer,
+    Replay,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct DesyncDetectedEvent {
+    pub source: DesyncSource,
+    pub local: StateHash,
+    pub remote: StateHash,
+    /// Names of parts of the state which differ.
+    pub mismatched: Vec<&'static str>,
+}
+
+/// Hashes of the latest turns computed locally and received from peers.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct StateHashLog {
+    local: VecDeque<StateHash>,
+    remote: VecDeque<StateHash>,
+}
+
+impl StateHashLog {
+    /// Returns hash of the latest turn computed locally.
+    pub fn last(&self) -> Option<&StateHash> {
+        self.local.back()
+    }
+
+    /// Adds the local hash and returns the mismatched hash
+    /// of the same turn received from peer.
+    fn add_local(&mut self, hash: StateHash) -> Option<StateHash> {
+        push_limited(&mut self.local, hash);
+        mismatched(&mut self.remote, &hash)
+    }
+
+    /// Adds hash received from peer and returns the mismatched local hash
+    /// of the same turn. Hashes of turns which haven't started locally
+    /// yet are compared later.
+    fn add_remote(&mut self, hash: StateHash) -> Option<StateHash> {
+        match mismatched(&mut self.local, &hash) {
+            Some(local) => Some(local),
+            None if self.local.iter().any(|h| h.turn_number == hash.turn_number) => None,
+            None => {
+                push_limited(&mut self.remote, hash);
+                None
+            }
+        }
+    }
+}
+
+fn push_limited(hashes: &mut VecDeque<StateHash>, hash: StateHash) {
+    if hashes.len() >= MAX_HASHES {
+        hashes.pop_front();
+    }
+    hashes.push_back(hash);
+}
+
+/// Finds hash of the same turn and returns it if it differs.
+fn mismatched(hashes: &mut VecDeque<StateHash>, hash: &StateHash) -> Option<StateHash> {
+    let other = *hashes.iter().find(|h| h.turn_number == hash.turn_number)?;
+    (other != *hash).then_some(other)
+}
+
+fn report_desync(
+    source: DesyncSource,
+    local: StateHash,
+    remote: StateHash,
+    desync_events: &mut EventWriter<DesyncDetectedEvent>,
+) {
+    let mismatched = local.differences(&remote);
+    warn!(
+        "Desync with {:?} at turn {}: {} differ",
+        source,
+        local.turn_number,
+        mismatched.join(", ")
+    );
+    desync_events.send(DesyncDetectedEvent {
+        source,
+        local,
+        remote,
+        mismatched,
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn hash_state_system(
+    game_field: Option<Res<GameField>>,
+    game_rng: Option<Res<GameRng>>,
+    slots: Option<Res<NetSlots>>,
+    host_clock: Option<Res<HostClock>>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut log: ResMut<StateHashLog>,
+    tanks_query: Query<(&Tank, &Position, &Health)>,
+    mut turn_started_events: EventReader<TurnStartedEvent>,
+    mut send_events: EventWriter<SendNetMessage>,
+    mut desync_events: EventWriter<DesyncDetectedEvent>,
+) {
+    let (Some(game_field), Some(game_rng)) = (game_field, game_rng) else {
+        return;
+    };
+    // Extra shot continues the same turn.
+    for event in turn_started_events.read().filter(|e| !e.extra_shot) {
+        let hash =
+            StateHash {
+                turn_number: event.turn_number,
+                landscape: game_field.landscape.checksum(),
+                tanks: hash_tanks(tanks_query.iter().map(|(tank, position, health)| {
+                    (tank.player_number, position.0, health.value)
+                })),
+                rng: game_rng.clone().gen(),
+            };
+        if slots.is_some() || host_clock.is_some() {
+            send_events.send(SendNetMessage(NetMessage::StateHash(hash)));
+        }
+        if let Some(remote) = log.add_local(hash) {
+            report_desync(DesyncSource::Peer, hash, remote, &mut desync_events);
+        }
+        let recorded = playback
+            .as_ref()
+            .and_then(|playback| playback.next_turn())
+            .and_then(|turn| turn.state_hash);
+        if let Some(recorded) = recorded.filter(|recorded| *recorded != hash) {
+            report_desync(DesyncSource::Replay, hash, recorded, &mut desync_events);
+        }
+    }
+}
+
+fn receive_state_hash_system(
+    mut log: ResMut<StateHashLog>,
+    mut received_events: EventReader<NetMessageReceived>,
+    mut desync_events: EventWriter<DesyncDetectedEvent>,
+) {
+    for NetMessageReceived(message) in received_events.read() {
+        if let &NetMessage::StateHash(hash) = message {
+            if let Some(local) = log.add_remote(hash) {
+                report_desync(DesyncSource::Peer, local, hash, &mut desync_events);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_hash_log() {
+        let tanks = [(2, Vec2::new(10., 20.), 100), (1, Vec2::new(50., 20.), 90)];
+        let hash = StateHash {
+            turn_number: 1,
+            landscape: 10,
+            tanks: hash_tanks(tanks.into_iter()),
+            rng: 30,
+        };
+        assert_eq!(hash.tanks, hash_tanks(tanks.into_iter().rev()));
+        let moved_tank = (1, Vec2::new(50.5, 20.), 90);
+        let desynced = StateHash {
+            tanks: hash_tanks([tanks[0], moved_tank].into_iter()),
+            rng: 31,
+            ..hash
+        };
+        assert_eq!(hash.differences(&desynced), ["tanks", "rng"]);
+
+        let mut log = StateHashLog::default();
+        assert_eq!(log.add_local(hash), None);
+        assert_eq!(log.add_remote(hash), None);
+        // The peer is ahead.
+        let next_hash = StateHash {
+            turn_number: 2,
+            ..hash
+        };
+        assert_eq!(
+            log.add_remote(StateHash {
+                landscape: 11,
+                ..next_hash
+            }),
+            None
+        );
+        assert_eq!(
+            log.add_local(next_hash).map(|remote| remote.landscape),
+            Some(11)
+        );
+        assert_eq!(log.add_remote(desynced), Some(hash));
+        assert_eq!(log.last(), Some(&next_hash));
+    }
+}
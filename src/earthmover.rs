@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::components::Position;
+use crate::game_field::GameField;
+use crate::input::PlayerAction;
+use crate::landscape::Landscape;
+use crate::mines::settle_height;
+use crate::tank::{shoot_system, AimingTank, Tank, TankSet, TankShotEvent};
+use crate::weapons::{TankWeapon, WeaponKind, Weapons};
+
+pub struct EarthmoverPlugin;
+
+impl Plugin for EarthmoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            use_earthmover_system
+                .after(shoot_system)
+                .in_set(TankSet::Aiming),
+        );
+    }
+}
+
+/// Levels landscape in given radius around `center_x` to the `level`
+/// (y-coordinate of the top pixel of the ground). Landscape above
+/// the level is removed, holes under it are filled down to the ground.
+///
+/// Returns count of changed pixels.
+pub(crate) fn level_terrain(
+    landscape: &mut Landscape,
+    center_x: i32,
+    level: i32,
+    radius: i32,
+) -> usize {
+    let (width, height) = landscape.size();
+    let level = level.clamp(0, height as i32 - 1);
+    let left = (center_x - radius).max(0);
+    let right = (center_x + radius).min(width as i32);
+    if left >= right {
+        return 0;
+    }
+    let length = (right - left) as u16;
+    let mut changed = 0;
+    for y in level + 1..=(level + radius).min(height as i32 - 1) {
+        changed += landscape.clear_pixels_line((left, y), length);
+    }
+    for x in left..right {
+        let ground = settle_height(landscape, x, level + 1);
+        for y in ground..=level {
+            changed += landscape.fill_pixels_line((x, y), 1);
+        }
+    }
+    changed
+}
+
+/// Levels landscape around the aiming tank to the height of its bottom.
+/// It takes the whole turn, which is finished when tanks have landed
+/// after subsidence of the landscape.
+fn use_earthmover_system(
+    mut game_field: ResMut<GameField>,
+    weapons: Res<Weapons>,
+    mut actions: EventReader<PlayerAction>,
+    mut aiming_tanks: Query<(Entity, &Tank, &Position, &mut TankWeapon), With<AimingTank>>,
+    mut shot_events: EventWriter<TankShotEvent>,
+) {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    if !fire {
+        return;
+    }
+    for (tank_entity, tank, &Position(tank_position), mut weapon) in aiming_tanks.iter_mut() {
+        if weapon.ready_weapon_kind(&weapons) != WeaponKind::Earthmover {
+            continue;
+        }
+        let Some(radius) = weapon
+            .fire(&weapons)
+            .and_then(|index| weapons.0.get(index))
+            .map(|definition| definition.radius)
+        else {
+            continue;
+        };
+        weapon.deselect();
+
+        let level = (tank_position.y - tank.size().y / 2.).round() as i32 - 1;
+        let changed = level_terrain(
+            &mut game_field.landscape,
+            tank_position.x.round() as i32,
+            level,
+            radius as i32,
+        );
+        info!(
+            "Player {} has leveled {} pixels of landscape",
+            tank.player_number, changed
+        );
+        game_field.landscape.subsidence();
+        shot_events.send(TankShotEvent { tank_entity });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::landscape_buffer::LandscapeStorage;
+
+    #[test]
+    fn test_level_terrain() {
+        let mut landscape = Landscape::new(300, 200, 5, LandscapeStorage::default(), None).unwrap();
+        let (center_x, level, radius) = (150, 100, 20);
+        level_terrain(&mut landscape, center_x, level, radius);
+        for x in center_x - radius..center_x + radius {
+            assert!((0..=level).all(|y| landscape.is_not_empty(x, y)));
+            assert!(!landscape.is_not_empty(x, level + 1));
+        }
+
+        // Level is clamped by the size of landscape.
+        level_terrain(&mut landscape, 0, 1000, radius);
+        assert!(landscape.is_not_empty(0, 199));
+    }
+}
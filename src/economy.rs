@@ -0,0 +1,300 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::game_field::GameField;
+use crate::rules::{EconomyRules, GameRules, TeamRules};
+use crate::tank::{TankDamagedEvent, TankDestroyedEvent};
+use crate::turn::RoundEndedEvent;
+
+/// Money of every player at the start of the match.
+pub const START_MONEY: u32 = 10000;
+/// Money which is received by the winner of a round.
+pub const ROUND_PRIZE: u32 = 5000;
+/// Money which is received by a player for one point of damage
+/// of enemy tanks.
+pub const DAMAGE_REWARD: u32 = 20;
+/// Money which is received by a player for destroying an enemy tank.
+pub const KILL_REWARD: u32 = 2000;
+/// Money which is given by one loan taken in the shop.
+pub const LOAN_AMOUNT: u32 = 5000;
+
+pub struct EconomyPlugin;
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Finances>().add_systems(
+            Update,
+            (
+                pay_for_damage_system,
+                pay_for_kills_system,
+                pay_round_results_system,
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EconomyError {
+    NotEnoughMoney,
+    LoansDisabled,
+    DebtLimitExceeded,
+    MaxLevelReached,
+}
+
+impl fmt::Display for EconomyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEnoughMoney => write!(f, "not enough money"),
+            Self::LoansDisabled => write!(f, "loans are disabled by rules of the match"),
+            Self::DebtLimitExceeded => write!(f, "debt limit has been exceeded"),
+            Self::MaxLevelReached => write!(f, "upgrade has maximal level already"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerFinances {
+    pub money: u32,
+    /// Amount which must be repaid, including interest.
+    pub debt: u32,
+    /// Number of tanks of other players destroyed by the player.
+    pub points: u32,
+}
+
+impl Default for PlayerFinances {
+    fn default() -> Self {
+        Self {
+            money: START_MONEY,
+            debt: 0,
+            points: 0,
+        }
+    }
+}
+
+impl PlayerFinances {
+    pub fn buy(&mut self, price: u32) -> Result<(), EconomyError> {
+        self.money = self
+            .money
+            .checked_sub(price)
+            .ok_or(EconomyError::NotEnoughMoney)?;
+        Ok(())
+    }
+
+    /// Gives the player a loan, interest is added to the debt at once.
+    pub fn take_loan(&mut self, amount: u32, rules: &EconomyRules) -> Result<(), EconomyError> {
+        if !rules.bank {
+            return Err(EconomyError::LoansDisabled);
+        }
+        let interest = (amount as f32 * rules.loan_interest_rate).round() as u32;
+        let debt = self.debt.saturating_add(amount).saturating_add(interest);
+        if debt > rules.max_debt {
+            return Err(EconomyError::DebtLimitExceeded);
+        }
+        self.debt = debt;
+        self.money += amount;
+        Ok(())
+    }
+
+    /// Adds winnings to the money of player, debt is repaid first.
+    pub fn receive(&mut self, amount: u32) {
+        let repaid = amount.min(self.debt);
+        self.debt -= repaid;
+        self.money += amount - repaid;
+    }
+
+    /// Pays interest on savings if the player doesn't have debts.
+    pub fn pay_interest(&mut self, rules: &EconomyRules) {
+        if rules.bank && self.debt == 0 {
+            self.money += (self.money as f32 * rules.savings_interest_rate).round() as u32;
+        }
+    }
+}
+
+/// Finances of players which are kept across rounds.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct Finances(HashMap<u8, PlayerFinances>);
+
+impl Finances {
+    pub fn player(&self, player_number: u8) -> PlayerFinances {
+        self.0.get(&player_number).copied().unwrap_or_default()
+    }
+
+    pub fn player_mut(&mut self, player_number: u8) -> &mut PlayerFinances {
+        self.0.entry(player_number).or_default()
+    }
+
+    /// Returns the player who has more points than anyone else.
+    pub fn leader(&self) -> Option<u8> {
+        let max_points = self.0.values().map(|f| f.points).max().filter(|&p| p > 0)?;
+        let mut leaders = self.0.iter().filter(|(_, f)| f.points == max_points);
+        match (leaders.next(), leaders.next()) {
+            (Some((&player_number, _)), None) => Some(player_number),
+            _ => None,
+        }
+    }
+
+    /// Returns bounty for destroying the tank of the player.
+    /// Only the points leader has bounty, which grows with their points.
+    pub fn bounty(&self, player_number: u8, rules: &EconomyRules) -> Option<u32> {
+        let bounty_per_point = rules.bounty_per_point?;
+        if self.leader() != Some(player_number) {
+            return None;
+        }
+        Some(bounty_per_point.saturating_mul(self.player(player_number).points))
+    }
+
+    /// Returns sum of points of all players of the team.
+    pub fn team_points(&self, team: u8, teams: &TeamRules) -> u32 {
+        self.0
+            .iter()
+            .filter(|(&player_number, _)| teams.team_of(player_number) == team)
+            .map(|(_, f)| f.points)
+            .sum()
+    }
+}
+
+fn pay_for_damage_system(
+    rules: Res<GameRules>,
+    mut finances: ResMut<Finances>,
+    mut damaged_events: EventReader<TankDamagedEvent>,
+) {
+    for event in damaged_events.read() {
+        let Some(attacker) = event
+            .attacker
+            .filter(|&p| !rules.is_friendly(p, event.player_number))
+        else {
+            continue;
+        };
+        finances
+            .player_mut(attacker)
+            .receive(DAMAGE_REWARD * event.damage as u32);
+    }
+}
+
+fn pay_for_kills_system(
+    rules: Res<GameRules>,
+    mut finances: ResMut<Finances>,
+    mut destroyed_events: EventReader<TankDestroyedEvent>,
+) {
+    for event in destroyed_events.read() {
+        let victim = event.player_number;
+        if let Some(insurance) = rules.economy.insurance {
+            finances.player_mut(victim).receive(insurance);
+        }
+        let Some(killer) = event
+            .killer
+            .filter(|&killer| !rules.is_friendly(killer, victim))
+        else {
+            continue;
+        };
+        let bounty = finances.bounty(victim, &rules.economy);
+        let killer_finances = finances.player_mut(killer);
+        killer_finances.receive(KILL_REWARD);
+        if let Some(bounty) = bounty {
+            info!("Player {} has received bounty {}", killer, bounty);
+            killer_finances.receive(bounty);
+        }
+        killer_finances.points += 1;
+    }
+}
+
+fn pay_round_results_system(
+    rules: Res<GameRules>,
+    game_field: Option<Res<GameField>>,
+    mut finances: ResMut<Finances>,
+    mut round_ended_events: EventReader<RoundEndedEvent>,
+) {
+    for event in round_ended_events.read() {
+        // In team mode all players of the winning team receive the prize.
+        if let (Some(team), Some(teams), Some(game_field)) =
+            (event.winning_team, rules.teams, game_field.as_ref())
+        {
+            for &player_number in game_field.player_numbers.iter() {
+                if teams.team_of(player_number) == team {
+                    finances.player_mut(player_number).receive(ROUND_PRIZE);
+                }
+            }
+        } else if let Some(winner) = event.winner {
+            finances.player_mut(winner).receive(ROUND_PRIZE);
+        }
+        for player_finances in finances.0.values_mut() {
+            player_finances.pay_interest(&rules.economy);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loans() {
+        let mut rules = EconomyRules::default();
+        let mut finances = PlayerFinances::default();
+        assert_eq!(
+            finances.take_loan(1000, &rules),
+            Err(EconomyError::LoansDisabled)
+        );
+
+        rules.bank = true;
+        finances.take_loan(10000, &rules).unwrap();
+        assert_eq!(finances.money, START_MONEY + 10000);
+        assert_eq!(finances.debt, 12000);
+        assert_eq!(
+            finances.take_loan(10000, &rules),
+            Err(EconomyError::DebtLimitExceeded)
+        );
+
+        finances.buy(START_MONEY + 9000).unwrap();
+        assert_eq!(finances.buy(2000), Err(EconomyError::NotEnoughMoney));
+
+        // No interest on savings while the player has a debt.
+        finances.pay_interest(&rules);
+        assert_eq!(finances.money, 1000);
+
+        finances.receive(ROUND_PRIZE);
+        assert_eq!(finances.debt, 7000);
+        assert_eq!(finances.money, 1000);
+        finances.receive(10000);
+        assert_eq!(finances.debt, 0);
+        assert_eq!(finances.money, 4000);
+
+        finances.pay_interest(&rules);
+        assert_eq!(finances.money, 4200);
+    }
+
+    #[test]
+    fn test_bounty() {
+        let mut rules = EconomyRules::default();
+        let mut finances = Finances::default();
+        assert_eq!(finances.leader(), None);
+
+        finances.player_mut(1).points = 2;
+        finances.player_mut(2).points = 2;
+        assert_eq!(finances.leader(), None);
+
+        finances.player_mut(2).points = 3;
+        assert_eq!(finances.leader(), Some(2));
+        assert_eq!(finances.bounty(2, &rules), None);
+
+        rules.bounty_per_point = Some(500);
+        assert_eq!(finances.bounty(2, &rules), Some(1500));
+        assert_eq!(finances.bounty(1, &rules), None);
+
+        finances.player_mut(2).points = u32::MAX;
+        assert_eq!(finances.bounty(2, &rules), Some(u32::MAX));
+    }
+
+    #[test]
+    fn test_team_points() {
+        let teams = TeamRules::new(2);
+        let mut finances = Finances::default();
+        finances.player_mut(1).points = 2;
+        finances.player_mut(2).points = 1;
+        finances.player_mut(3).points = 3;
+        assert_eq!(finances.team_points(1, &teams), 5);
+        assert_eq!(finances.team_points(2, &teams), 1);
+    }
+}
@@ -0,0 +1,348 @@
+use std::io;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::MainCamera;
+use crate::game_field::GameField;
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::level_map::LevelMap;
+use crate::MAX_PLAYERS_COUNT;
+
+const DEFAULT_BRUSH_RADIUS: f32 = 40.;
+const MIN_BRUSH_RADIUS: f32 = 5.;
+const MAX_BRUSH_RADIUS: f32 = 200.;
+const BRUSH_RADIUS_STEP: f32 = 5.;
+/// Speed of raising and lowering of terrain in the center of brush
+/// (pixels per second).
+const BRUSH_SPEED: f32 = 120.;
+const HELP: &str = "LMB/RMB - raise/lower terrain, [ ] - brush size, \
+    P - add/remove spawn point, Ctrl+Z - undo, Ctrl+S - save, Ctrl+L - load, Enter - play";
+
+/// Editor of landscape and spawn points of tanks.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            switch_to_editor_system
+                .after(setup_game_field)
+                .run_if(is_editing),
+        )
+        .add_systems(
+            OnEnter(AppState::Editor),
+            (setup_editor_text, load_level_system).chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                brush_system,
+                spawn_points_system,
+                undo_system.run_if(ctrl_pressed_with(KeyCode::KeyZ)),
+                save_level_system,
+                load_level_system.run_if(ctrl_pressed_with(KeyCode::KeyL)),
+                play_level_system,
+                draw_editor_system,
+                update_editor_text_system,
+            )
+                .run_if(in_state(AppState::Editor)),
+        )
+        .add_systems(OnExit(AppState::Editor), despawn_editor_text);
+    }
+}
+
+/// Insert this resource into the app to edit the level
+/// instead of playing.
+#[derive(Debug, Resource)]
+pub struct EditorSession {
+    /// File of the edited level.
+    path: PathBuf,
+    brush_radius: f32,
+    spawn_points: Vec<f32>,
+    /// Lengths of log of landscape operations before every stroke of brush.
+    undo_marks: Vec<usize>,
+    /// Result of the last action of user.
+    message: String,
+}
+
+impl EditorSession {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            brush_radius: DEFAULT_BRUSH_RADIUS,
+            spawn_points: Vec::new(),
+            undo_marks: Vec::new(),
+            message: String::new(),
+        }
+    }
+
+    /// Removes the spawn point in the given distance from `x`
+    /// or adds a new one.
+    fn toggle_spawn_point(&mut self, x: f32, distance: f32) {
+        let nearest = self
+            .spawn_points
+            .iter()
+            .position(|&point| (point - x).abs() <= distance);
+        match nearest {
+            Some(index) => {
+                self.spawn_points.remove(index);
+            }
+            None if self.spawn_points.len() < MAX_PLAYERS_COUNT as usize => {
+                self.spawn_points.push(x);
+            }
+            None => self.message = "All spawn points have been placed".to_string(),
+        }
+    }
+}
+
+pub fn is_editing(session: Option<Res<EditorSession>>) -> bool {
+    session.is_some()
+}
+
+fn ctrl_pressed_with(key_code: KeyCode) -> impl Fn(Option<Res<ButtonInput<KeyCode>>>) -> bool {
+    move |keyboard_input: Option<Res<ButtonInput<KeyCode>>>| {
+        keyboard_input.is_some_and(|input| {
+            input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+                && input.just_pressed(key_code)
+        })
+    }
+}
+
+fn switch_to_editor_system(mut next_state: ResMut<NextState<AppState>>) {
+    debug!("Switch to Editor");
+    next_state.set(AppState::Editor);
+}
+
+/// Returns position of mouse cursor in coordinates of the game field.
+fn cursor_position(
+    windows_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let cursor = windows_query.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor)
+}
+
+/// Returns new height of the column under brush: the terrain
+/// rises or falls faster closer to the center of brush.
+fn brush_height(height: u16, distance: f32, radius: f32, delta: f32) -> u16 {
+    if distance >= radius {
+        return height;
+    }
+    let falloff = (1. - (distance / radius).powi(2)).sqrt();
+    let change = (BRUSH_SPEED * delta.abs() * falloff).ceil();
+    let height = height as f32 + change.copysign(delta);
+    height.max(0.) as u16
+}
+
+fn brush_system(
+    time: Res<Time>,
+    mouse_input: Option<Res<ButtonInput<MouseButton>>>,
+    mut session: ResMut<EditorSession>,
+    mut game_field: ResMut<GameField>,
+    windows_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let Some(mouse_input) = mouse_input else {
+        return;
+    };
+    let direction = match (
+        mouse_input.pressed(MouseButton::Left),
+        mouse_input.pressed(MouseButton::Right),
+    ) {
+        (true, false) => 1.,
+        (false, true) => -1.,
+        _ => return,
+    };
+    let Some(cursor) = cursor_position(&windows_query, &camera_query) else {
+        return;
+    };
+    let landscape = &mut game_field.landscape;
+    if mouse_input.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
+        session.undo_marks.push(landscape.ops().len());
+    }
+    let radius = session.brush_radius;
+    let (_, max_height) = landscape.size();
+    let delta = direction * time.delta_seconds();
+    for x in (cursor.x - radius) as i32..=(cursor.x + radius) as i32 {
+        let height = landscape.surface_height(x);
+        let new_height = brush_height(height, (x as f32 - cursor.x).abs(), radius, delta);
+        if new_height != height {
+            landscape.set_surface_height(x, new_height.min(max_height));
+        }
+    }
+}
+
+fn spawn_points_system(
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    mut session: ResMut<EditorSession>,
+    windows_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let Some(keyboard_input) = keyboard_input else {
+        return;
+    };
+    if keyboard_input.just_pressed(KeyCode::BracketLeft) {
+        session.brush_radius = (session.brush_radius - BRUSH_RADIUS_STEP).max(MIN_BRUSH_RADIUS);
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketRight) {
+        session.brush_radius = (session.brush_radius + BRUSH_RADIUS_STEP).min(MAX_BRUSH_RADIUS);
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        if let Some(cursor) = cursor_position(&windows_query, &camera_query) {
+            let distance = session.brush_radius;
+            session.toggle_spawn_point(cursor.x, distance);
+        }
+    }
+}
+
+fn save_level_system(
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    game_field: Res<GameField>,
+    mut session: ResMut<EditorSession>,
+) {
+    if !ctrl_pressed_with(KeyCode::KeyS)(keyboard_input) {
+        return;
+    }
+    let level = LevelMap::from_landscape(&game_field.landscape, session.spawn_points.clone());
+    session.message = match level.save(&session.path) {
+        Ok(()) => format!("Level has been saved to {}", session.path.display()),
+        Err(err) => format!("Failed to save level: {}", err),
+    };
+}
+
+/// Loads the edited level from its file, if the file exists.
+fn load_level_system(mut game_field: ResMut<GameField>, mut session: ResMut<EditorSession>) {
+    session.message = match LevelMap::load(&session.path) {
+        Ok(level) => {
+            let (width, height) = game_field.landscape.size();
+            let ops_count = game_field.landscape.ops().len();
+            session.undo_marks.push(ops_count);
+            game_field
+                .landscape
+                .set_surface_heights(&level.scaled_heights(width, height));
+            session.spawn_points = level.scaled_spawn_points(width);
+            format!("Level has been loaded from {}", session.path.display())
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return,
+        Err(err) => format!("Failed to load level: {}", err),
+    };
+}
+
+/// Reverts the last stroke of brush or loading of level.
+fn undo_system(mut game_field: ResMut<GameField>, mut session: ResMut<EditorSession>) {
+    session.message = match session.undo_marks.pop() {
+        Some(mark) if game_field.landscape.undo_to(mark) => "Undone".to_string(),
+        Some(_) => "Failed to undo".to_string(),
+        None => "Nothing to undo".to_string(),
+    };
+}
+
+/// Starts round on the edited level.
+fn play_level_system(
+    mut commands: Commands,
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    game_field: Res<GameField>,
+    session: Res<EditorSession>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keyboard_input.is_some_and(|input| input.just_pressed(KeyCode::Enter)) {
+        return;
+    }
+    let level = LevelMap::from_landscape(&game_field.landscape, session.spawn_points.clone());
+    commands.insert_resource(level);
+    commands.remove_resource::<EditorSession>();
+    debug!("Switch to RoundSetup");
+    next_state.set(AppState::RoundSetup);
+}
+
+fn draw_editor_system(
+    game_field: Res<GameField>,
+    session: Res<EditorSession>,
+    windows_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut gizmos: Gizmos,
+) {
+    if let Some(cursor) = cursor_position(&windows_query, &camera_query) {
+        gizmos.circle_2d(cursor, session.brush_radius, Color::WHITE);
+    }
+    for (i, &x) in session.spawn_points.iter().enumerate() {
+        let y = game_field.landscape.surface_height(x as i32) as f32;
+        let color = Color::hsl(i as f32 * 360. / MAX_PLAYERS_COUNT as f32, 0.7, 0.6);
+        gizmos.line_2d(Vec2::new(x, y), Vec2::new(x, y + 40.), color);
+        gizmos.circle_2d(Vec2::new(x, y + 40.), 5., color);
+    }
+}
+
+#[derive(Component)]
+struct EditorText;
+
+fn setup_editor_text(mut commands: Commands, game_field: Res<GameField>) {
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(5.),
+                left: Val::Px(10.),
+                ..default()
+            },
+            text: Text::from_section(
+                HELP,
+                TextStyle {
+                    font: game_field.font.clone(),
+                    font_size: 16.,
+                    color: Color::WHITE,
+                },
+            ),
+            ..default()
+        },
+        EditorText,
+    ));
+}
+
+fn update_editor_text_system(
+    session: Res<EditorSession>,
+    mut text_query: Query<&mut Text, With<EditorText>>,
+) {
+    if !session.is_changed() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "{}\nBrush: {}  Spawn points: {}/{}\n{}",
+            HELP,
+            session.brush_radius,
+            session.spawn_points.len(),
+            MAX_PLAYERS_COUNT,
+            session.message
+        );
+    }
+}
+
+fn despawn_editor_text(mut commands: Commands, text_query: Query<Entity, With<EditorText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_tools() {
+        assert_eq!(brush_height(100, 50., 40., 1.), 100);
+        assert!(brush_height(100, 0., 40., 0.1) > brush_height(100, 30., 40., 0.1));
+        assert!(brush_height(100, 0., 40., -0.1) < 100);
+        assert_eq!(brush_height(5, 0., 40., -1.), 0);
+
+        let mut session = EditorSession::new("level.ron".into());
+        session.toggle_spawn_point(100., 10.);
+        session.toggle_spawn_point(300., 10.);
+        assert_eq!(session.spawn_points, vec![100., 300.]);
+        session.toggle_spawn_point(105., 10.);
+        assert_eq!(session.spawn_points, vec![300.]);
+    }
+}
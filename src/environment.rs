@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Theme of terrain of the game field.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Resource, Serialize, Deserialize)]
+pub enum TerrainTheme {
+    #[default]
+    Temperate,
+    Arctic,
+    Desert,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Resource)]
+pub enum DayPhase {
+    #[default]
+    Day,
+    Night,
+}
+
+/// Coefficient of air resistance for missiles flying in the rain.
+const RAIN_DRAG: f32 = 0.02;
+
+/// Weather of the round.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Resource)]
+pub enum Weather {
+    #[default]
+    Clear,
+    /// Missiles are slowed down by drag.
+    Rain,
+    /// Snow is accumulated on top of the landscape every turn.
+    Snow,
+}
+
+impl Weather {
+    pub const ALL: [Weather; 3] = [Weather::Clear, Weather::Rain, Weather::Snow];
+
+    /// Coefficient of air resistance for missiles.
+    pub fn drag(self) -> f32 {
+        match self {
+            Weather::Rain => RAIN_DRAG,
+            _ => 0.,
+        }
+    }
+}
@@ -1,45 +1,91 @@
-use std::time::Instant;
-
 use bevy::prelude::*;
+use bevy::sprite::Mesh2dHandle;
 use bevy_prototype_lyon::prelude::*;
 
-use crate::components::{Opacity, Position, Scale};
+use crate::collider::{update_spatial_query_system, Collider, SpatialQuery};
+use crate::components::{Opacity, Owner, Position};
 use crate::game_field::GameField;
 use crate::geometry::rect::MyRect;
 use crate::geometry::Circle;
+use crate::materials::ExplosionMaterial;
 
 const SPEED: f32 = 150.0;
+/// Max radius of explosion of the standard missile.
+pub const STANDARD_RADIUS: f32 = 50.0;
+/// Size of mesh of explosion relative to its max radius,
+/// it leaves space for the shockwave.
+const MESH_SCALE: f32 = 1.5;
 
 pub struct ExplosionPlugin;
 
 impl Plugin for ExplosionPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ExplosionHitEvent>()
+            .add_event::<ExplosionDamageEvent>()
             .add_event::<ExplosionMaxRadiusEvent>()
             .add_event::<ExplosionsFinishedEvent>()
-            .add_systems(Update, update_explosion_system)
+            .add_systems(
+                Update,
+                (update_explosion_system, explosion_damage_system)
+                    .chain()
+                    .after(update_spatial_query_system),
+            )
             .add_systems(PostUpdate, update_explosion_alpha_system);
     }
 }
 
+/// Renders explosions by `ExplosionMaterial`.
+pub struct ExplosionVisualsPlugin;
+
+impl Plugin for ExplosionVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (
+                add_explosion_visuals_system,
+                update_explosion_material_system,
+            )
+                .chain(),
+        );
+    }
+}
+
 #[derive(Debug, Clone, Copy, Component)]
 pub struct Explosion {
-    created: Instant,
+    /// Time (seconds) passed since the explosion has started.
+    age: f32,
     max_radius: f32,
     pub cur_radius: f32,
     max_radius_passed: bool,
+    /// Damage of an entity which is completely covered by the explosion.
+    damage: u8,
 }
 
 #[derive(Event)]
 pub struct ExplosionHitEvent {
     pub explosion: Explosion,
     pub position: Vec2,
+    /// Tank which has caused the explosion.
+    pub owner: Option<Entity>,
+}
+
+/// Damage of entity with collider caused by explosion.
+#[derive(Event)]
+pub struct ExplosionDamageEvent {
+    pub entity: Entity,
+    /// Damage of the explosion multiplied by part of the entity's
+    /// bounding rect covered by it.
+    pub damage: u8,
+    /// Tank which has caused the explosion.
+    pub owner: Option<Entity>,
 }
 
 #[derive(Event)]
 pub struct ExplosionMaxRadiusEvent {
     pub position: Vec2,
     pub max_radius: f32,
+    /// Tank which has caused the explosion.
+    pub owner: Option<Entity>,
 }
 
 #[derive(Event)]
@@ -48,67 +94,130 @@ pub struct ExplosionsFinishedEvent;
 impl Explosion {
     pub fn new(max_radius: f32) -> Self {
         Explosion {
-            created: Instant::now(),
+            age: 0.0,
             max_radius,
             cur_radius: 0.0,
             max_radius_passed: false,
+            damage: 100,
+        }
+    }
+
+    pub fn with_damage(self, damage: u8) -> Self {
+        Self { damage, ..self }
+    }
+
+    #[inline]
+    pub fn max_radius(&self) -> f32 {
+        self.max_radius
+    }
+
+    /// Explosion which starts to grow after given delay (seconds).
+    pub fn delayed(max_radius: f32, delay: f32) -> Self {
+        Explosion {
+            age: -delay,
+            ..Self::new(max_radius)
         }
     }
 
+    /// Part of the explosion's lifetime which has passed. Explosion grows
+    /// up to its max radius and then fades out during the same time.
+    pub fn progress(&self) -> f32 {
+        (self.age * SPEED / (2.0 * self.max_radius)).clamp(0.0, 1.0)
+    }
+
     pub fn get_intersection_percents(&self, position: Vec2, bound: MyRect) -> u8 {
-        let bound_area = ((bound.right - bound.left) * (bound.top - bound.bottom)).abs();
+        let circle_rect = MyRect::from_center_size(position, Vec2::splat(2. * self.max_radius));
+        if !circle_rect.intersects(&bound) {
+            return 0;
+        }
+        let bound_area = bound.area().abs();
         if bound_area > 0.0 {
             let circle = Circle::new(position, self.max_radius);
             let intersection_area = circle.area_of_rect_intersection(bound);
             if intersection_area > 0.0 {
                 let percents = 100.0 * intersection_area / bound_area;
-                return percents.min(100.0).max(0.0) as u8;
+                return percents.clamp(0.0, 100.0) as u8;
             }
         }
         0
     }
+
+    /// Returns damage of the explosion for entity with given bounding rect.
+    pub fn get_damage(&self, position: Vec2, bound: MyRect) -> u8 {
+        self.damage_of_percents(self.get_intersection_percents(position, bound))
+    }
+
+    /// Returns damage of the explosion for entity with given collider.
+    /// Colliders which don't fill their bounding rect are damaged
+    /// by the covered part of their own area.
+    pub fn get_collider_damage(
+        &self,
+        position: Vec2,
+        collider: &Collider,
+        collider_position: Vec2,
+    ) -> u8 {
+        match collider.covered_part(collider_position, position, self.max_radius) {
+            Some(part) => self.damage_of_percents((100. * part) as u8),
+            None => self.get_damage(position, collider.bounding_rect(collider_position)),
+        }
+    }
+
+    fn damage_of_percents(&self, percents: u8) -> u8 {
+        (percents as u32 * self.damage as u32 / 100) as u8
+    }
 }
 
-pub fn spawn_explosion(commands: &mut Commands, game_field: &GameField, position: Vec2) {
+pub fn spawn_explosion(
+    commands: &mut Commands,
+    game_field: &GameField,
+    position: Vec2,
+    owner: Option<Owner>,
+) {
+    spawn_explosion_of(
+        commands,
+        game_field,
+        position,
+        Explosion::new(STANDARD_RADIUS),
+        owner,
+    );
+}
+
+pub fn spawn_explosion_of(
+    commands: &mut Commands,
+    game_field: &GameField,
+    position: Vec2,
+    explosion: Explosion,
+    owner: Option<Owner>,
+) {
     debug!("Spawn explosion");
-    let explosion = Explosion::new(50.0);
-    let scale = explosion.cur_radius / 1000.0;
-
-    let color = Color::rgba(242. / 255., 68. / 255., 15. / 255., 1.);
-    let explosion_circle = shapes::Circle {
-        radius: 1000.,
-        ..shapes::Circle::default()
-    };
-    let explosion_bundle = ShapeBundle {
-        path: GeometryBuilder::build_as(&explosion_circle),
-        spatial: SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
+    // Visual part of explosion is added by `ExplosionVisualsPlugin`.
+    let mut explosion_commands = commands.spawn((
+        SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
             position.x, position.y, 2.,
         ))),
-        ..default()
-    };
-
-    let explosion_entity = commands
-        .spawn((
-            explosion_bundle,
-            Fill::color(color),
-            explosion,
-            Position(position),
-            Scale(scale),
-            Opacity(1.),
-        ))
-        .id();
+        explosion,
+        Position(position),
+        Opacity(1.),
+    ));
+    if let Some(owner) = owner {
+        explosion_commands.insert(owner);
+    }
+    let explosion_entity = explosion_commands.id();
     commands
         .entity(game_field.parent_entity)
         .add_child(explosion_entity);
-    commands.spawn(AudioBundle {
-        source: game_field.explosion_sound.clone(),
-        ..Default::default()
-    });
 }
 
 pub fn update_explosion_system(
     mut commands: Commands,
-    mut explosions_query: Query<(&mut Explosion, &mut Scale, &Position, &mut Opacity, Entity)>,
+    time: Res<Time>,
+    mut explosions_query: Query<(
+        &mut Explosion,
+        &Position,
+        &mut Opacity,
+        Option<&Owner>,
+        Entity,
+    )>,
     mut hit_events: EventWriter<ExplosionHitEvent>,
     mut radius_events: EventWriter<ExplosionMaxRadiusEvent>,
     mut finish_events: EventWriter<ExplosionsFinishedEvent>,
@@ -116,14 +225,13 @@ pub fn update_explosion_system(
     let mut total_explosions: usize = 0;
     let mut remove_explosions: usize = 0;
 
-    for (mut explosion, mut scale, &Position(explosion_pos), mut opacity, entity) in
+    for (mut explosion, &Position(explosion_pos), mut opacity, owner, entity) in
         explosions_query.iter_mut()
     {
         total_explosions += 1;
-        let time = explosion.created.elapsed().as_secs_f32();
-        let radius = time * SPEED;
-        explosion.cur_radius = radius.min(explosion.max_radius);
-        scale.0 = explosion.cur_radius / 1000.;
+        explosion.age += time.delta_seconds();
+        let radius = explosion.age * SPEED;
+        explosion.cur_radius = radius.clamp(0.0, explosion.max_radius);
 
         let cur_opacity = if radius <= explosion.max_radius {
             1.0
@@ -138,6 +246,7 @@ pub fn update_explosion_system(
             radius_events.send(ExplosionMaxRadiusEvent {
                 position: explosion_pos,
                 max_radius: explosion.max_radius,
+                owner: owner.map(|owner| owner.0),
             });
             explosion.max_radius_passed = true;
         }
@@ -149,6 +258,7 @@ pub fn update_explosion_system(
             hit_events.send(ExplosionHitEvent {
                 explosion: *explosion,
                 position: explosion_pos,
+                owner: owner.map(|owner| owner.0),
             });
             debug!("Explosion removed");
         }
@@ -159,8 +269,96 @@ pub fn update_explosion_system(
     }
 }
 
+/// Damages all entities with colliders intersected by explosions.
+fn explosion_damage_system(
+    spatial_query: Res<SpatialQuery>,
+    mut hit_events: EventReader<ExplosionHitEvent>,
+    mut damage_events: EventWriter<ExplosionDamageEvent>,
+) {
+    for event in hit_events.read() {
+        for (entity, position, collider) in spatial_query.iter() {
+            let damage = event
+                .explosion
+                .get_collider_damage(event.position, collider, position);
+            if damage > 0 {
+                damage_events.send(ExplosionDamageEvent {
+                    entity,
+                    damage,
+                    owner: event.owner,
+                });
+            }
+        }
+    }
+}
+
+fn add_explosion_visuals_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ExplosionMaterial>>,
+    new_explosions_query: Query<(Entity, &Explosion), Added<Explosion>>,
+) {
+    for (entity, explosion) in new_explosions_query.iter() {
+        let size = 2.0 * MESH_SCALE * explosion.max_radius();
+        let material = materials.add(ExplosionMaterial {
+            color: Color::rgba(242. / 255., 68. / 255., 15. / 255., 1.),
+            radius: 0.0,
+            progress: 0.0,
+            opacity: 1.0,
+        });
+        commands.entity(entity).insert((
+            Mesh2dHandle(meshes.add(Rectangle::new(size, size))),
+            material,
+        ));
+    }
+}
+
+fn update_explosion_material_system(
+    mut materials: ResMut<Assets<ExplosionMaterial>>,
+    explosions_query: Query<(&Explosion, &Opacity, &Handle<ExplosionMaterial>)>,
+) {
+    for (explosion, opacity, handle) in explosions_query.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.radius = explosion.cur_radius / (MESH_SCALE * explosion.max_radius());
+            material.progress = explosion.progress();
+            material.opacity = opacity.0;
+        }
+    }
+}
+
 pub fn update_explosion_alpha_system(mut query: Query<(&Opacity, &mut Fill), Changed<Opacity>>) {
     for (opacity, mut fill) in query.iter_mut() {
         fill.color.set_a(opacity.0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explosion_progress() {
+        let mut explosion = Explosion::new(30.0);
+        assert_eq!(explosion.progress(), 0.0);
+        // Explosion reaches its max radius at the middle of its lifetime.
+        explosion.age = 30.0 / SPEED;
+        assert_eq!(explosion.progress(), 0.5);
+        explosion.age = 1000.0;
+        assert_eq!(explosion.progress(), 1.0);
+
+        let delayed = Explosion::delayed(30.0, 0.5);
+        assert_eq!(delayed.progress(), 0.0);
+    }
+
+    #[test]
+    fn test_explosion_damage() {
+        let position = Vec2::new(100., 100.);
+        let covered = MyRect::from_center_size(position, Vec2::splat(10.));
+        let half_covered =
+            MyRect::from_center_size(position + Vec2::new(50., 0.), Vec2::splat(10.));
+        let explosion = Explosion::new(50.);
+        assert_eq!(explosion.get_damage(position, covered), 100);
+        let explosion = explosion.with_damage(40);
+        assert_eq!(explosion.get_damage(position, covered), 40);
+        assert!(explosion.get_damage(position, half_covered) < 40);
+    }
+}
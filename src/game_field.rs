@@ -1,6 +1,7 @@
 use bevy::prelude::*;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
+use rand::{Rng, RngCore, SeedableRng};
 
 use crate::landscape::Landscape;
 
@@ -12,61 +13,87 @@ pub struct GameField {
     pub landscape: Landscape,
     pub wind_power: f32,
     pub player_numbers: Vec<u8>,
-    pub tanks: Vec<Option<Entity>>,
-    pub current_tank: Option<usize>,
-    pub number_of_iteration: usize,
     pub font: Handle<Font>,
     pub tank_texture: Handle<Image>,
     pub gun_texture: Handle<Image>,
-    pub tank_fire_sound: Handle<AudioSource>,
-    pub explosion_sound: Handle<AudioSource>,
+    pub plane_texture: Handle<Image>,
 }
 
 impl GameField {
-    pub fn start_round(&mut self, count_of_tanks: u8) {
+    /// Returns count of players of the round.
+    #[inline]
+    pub fn players_count(&self) -> u8 {
+        self.player_numbers.len() as u8
+    }
+
+    pub fn start_round(&mut self, count_of_tanks: u8, rng: &mut GameRng) {
         let mut player_numbers: Vec<u8> = (1..=count_of_tanks).collect();
-        player_numbers.shuffle(&mut rand::thread_rng());
-        self.tanks.clear();
+        player_numbers.shuffle(rng);
         self.player_numbers = player_numbers;
-        self.number_of_iteration = 0;
-        self.current_tank = None;
-        self.change_wind();
+        self.change_wind(rng);
     }
 
-    fn change_wind(&mut self) {
-        self.wind_power = (rand::thread_rng().gen_range(-10.0_f32..10.0_f32) * 10.0).round() / 10.0;
+    fn change_wind(&mut self, rng: &mut GameRng) {
+        self.wind_power = (rng.gen_range(-10.0_f32..10.0_f32) * 10.0).round() / 10.0;
     }
+}
+
+/// Random number generator of the round. All random values of the round
+/// (landscape, order of players, wind, etc.) are derived from its seed,
+/// so the round can be reproduced by the seed.
+#[derive(Debug, Clone, Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
 
-    pub fn switch_current_tank(&mut self) -> Option<Entity> {
-        if let Some(mut current_tank) = self.current_tank {
-            for _ in 0..self.tanks.len() {
-                current_tank += 1;
-                if current_tank >= self.tanks.len() {
-                    current_tank = 0;
-                }
-                if let Some(entity) = self.tanks[current_tank] {
-                    self.current_tank = Some(current_tank);
-                    return Some(entity);
-                }
-            }
-        } else if !self.tanks.is_empty() {
-            // The current tank is not selected yet.
-            // Select the first tank as current.
-            if let Some(entity) = self.tanks[0] {
-                self.current_tank = Some(0);
-                return Some(entity);
-            }
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
         }
-        None
     }
 
-    pub fn remove_tank_by_entity(&mut self, entity: Entity) {
-        if let Some(tank_entity) = self
-            .tanks
-            .iter_mut()
-            .find(|t| t.map(|e| e == entity).unwrap_or(false))
-        {
-            *tank_entity = None;
-        }
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_rng_is_reproducible() {
+        let mut rng = GameRng::new(42);
+        let values: Vec<u32> = (0..4).map(|_| rng.gen()).collect();
+        let mut same_rng = GameRng::new(rng.seed());
+        let same_values: Vec<u32> = (0..4).map(|_| same_rng.gen()).collect();
+        assert_eq!(values, same_values);
+        assert_ne!(GameRng::new(43).gen::<u32>(), values[0]);
     }
 }
@@ -1,16 +1,32 @@
 use std::f32::consts::PI;
 
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
 use bevy_prototype_lyon::prelude::*;
 
 use crate::components::{Angle, Position, Scale};
-use crate::game_field::GameField;
-use crate::input::InputWithRepeating;
+use crate::editor::is_editing;
+use crate::environment::{DayPhase, TerrainTheme, Weather};
+use crate::game_field::{GameField, GameRng};
+use crate::heightmap::{Heightmap, HeightmapPlugin};
+use crate::input::{PlayerAction, PlayerInputPlugin};
+use crate::level_map::{LevelMap, LevelMapPlugin};
+use crate::lobby::is_in_lobby;
+use crate::main_menu::is_in_main_menu;
+use crate::materials::MaterialsPlugin;
 use crate::missile;
-use crate::status_panel::setup_status_panel;
-use crate::tank::{setup_tanks, AimingTank, AllTanksPlacedEvent, CurrentTank, TankShotEvent};
-use crate::{explosion, landscape, status_panel, tank};
+use crate::replay::ReplayPlayback;
+use crate::rules::GameRules;
+use crate::settings::Settings;
+use crate::tank::{setup_tanks, AllTanksPlacedEvent};
+use crate::touch::TouchInputPlugin;
+use crate::{
+    ai, airstrike, announcements, anti_gravity, audio, background, broadcast_hud, camera, campaign,
+    chat, cloak, collider, day_night, decoy, desync, earthmover, economy, editor, explosion,
+    grappling_hook, hazards, idle_animation, jetpack, landscape, landscape_gpu, lobby, main_menu,
+    mines, minimap, net, obstacles, orbital_strike, particles, portal, range, replay, scanner,
+    shop, simulation, slow_motion, stats, status_panel, supply, tank, tank_labels, timeline,
+    trajectory_preview, turn, turn_order, turn_timer, weapons, weather,
+};
 
 #[derive(States, PartialEq, Eq, Debug, Clone, Hash, Default)]
 pub enum AppState {
@@ -19,38 +35,263 @@ pub enum AppState {
     TanksThrowing,
     Aiming,
     MainAction,
+    /// One or zero tanks remain.
+    RoundOver,
+    /// Players buy weapons and items before the next round.
+    Shop,
+    /// Landscape and spawn points of a level are edited.
+    Editor,
+    /// Players of network game gather before the first round.
+    Lobby,
+    /// Kind of match is chosen at launch of the game.
+    MainMenu,
 }
 
-pub struct TankWarGamePlugin;
+/// How the current tank is aimed during `AppState::Aiming`.
+#[derive(States, PartialEq, Eq, Debug, Clone, Copy, Hash, Default)]
+pub enum AimingMode {
+    /// By angle and power of the gun.
+    #[default]
+    Gun,
+    /// By moving of targeting reticle along the game field.
+    Reticle,
+}
+
+/// Main plugin of the game. Parts of the game which are not required
+/// for embedding or for running without window may be disabled:
+///
+/// ```no_run
+/// # use bevy_tank_war::TankWarGamePlugin;
+/// let plugin = TankWarGamePlugin::default()
+///     .with_ai(false)
+///     .with_audio(false)
+///     .with_ui(false);
+/// ```
+pub struct TankWarGamePlugin {
+    ai: bool,
+    audio: bool,
+    camera: bool,
+    input: bool,
+    ui: bool,
+    headless: Option<HeadlessField>,
+}
+
+impl Default for TankWarGamePlugin {
+    fn default() -> Self {
+        Self {
+            ai: true,
+            audio: true,
+            camera: true,
+            input: true,
+            ui: true,
+            headless: None,
+        }
+    }
+}
+
+/// Size of game field of the game running without window.
+#[derive(Debug, Clone, Copy, Resource)]
+pub(crate) struct HeadlessField {
+    width: u16,
+    height: u16,
+}
+
+impl TankWarGamePlugin {
+    /// Bots controlling tanks of absent players.
+    pub fn with_ai(self, enabled: bool) -> Self {
+        Self {
+            ai: enabled,
+            ..self
+        }
+    }
+
+    pub fn with_audio(self, enabled: bool) -> Self {
+        Self {
+            audio: enabled,
+            ..self
+        }
+    }
+
+    /// Camera of the game. Disable it if the app has its own camera.
+    pub fn with_camera(self, enabled: bool) -> Self {
+        Self {
+            camera: enabled,
+            ..self
+        }
+    }
+
+    /// Input from keyboard and gamepads.
+    pub fn with_input(self, enabled: bool) -> Self {
+        Self {
+            input: enabled,
+            ..self
+        }
+    }
+
+    /// Status panel, announcements overlay, labels of tanks,
+    /// trajectory preview and decorative animations.
+    pub fn with_ui(self, enabled: bool) -> Self {
+        Self {
+            ui: enabled,
+            ..self
+        }
+    }
+
+    /// Runs the game loop without rendering, audio and input from players,
+    /// so the plugin may be used together with `MinimalPlugins`.
+    /// Shots are issued by `ShootCommand` events.
+    pub fn headless(self, field_width: u16, field_height: u16) -> Self {
+        Self {
+            audio: false,
+            camera: false,
+            input: false,
+            ui: false,
+            headless: Some(HeadlessField {
+                width: field_width,
+                height: field_height,
+            }),
+            ..self
+        }
+    }
+}
 
 impl Plugin for TankWarGamePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<InputWithRepeating<KeyCode>>()
-            .init_state::<AppState>()
+        app.init_state::<AppState>()
+            .init_state::<AimingMode>()
             .add_systems(PostUpdate, (update_translation, update_scale, update_angle))
             .add_systems(PostUpdate, switch_to_aiming_system)
             .add_systems(
                 OnEnter(AppState::RoundSetup),
                 (
+                    despawn_previous_round_system,
                     setup_game_field,
-                    (setup_tanks, setup_status_panel),
-                    switch_to_tanks_throwing_system,
+                    setup_tanks
+                        .run_if(not(is_editing))
+                        .run_if(not(is_in_lobby))
+                        .run_if(not(is_in_main_menu)),
+                    switch_to_tanks_throwing_system
+                        .run_if(not(is_editing))
+                        .run_if(not(is_in_lobby))
+                        .run_if(not(is_in_main_menu)),
                 )
                     .chain(),
             )
-            .add_systems(OnEnter(AppState::Aiming), switch_current_tank_system)
-            .add_systems(
-                Update,
-                after_tank_shot_system.run_if(in_state(AppState::Aiming)),
-            )
+            .init_resource::<Settings>()
+            .init_resource::<GameRules>()
+            .init_resource::<TerrainTheme>()
+            .init_resource::<DayPhase>()
+            .init_resource::<Weather>()
+            .add_event::<PlayerAction>()
             .add_plugins((
-                ShapePlugin,
                 landscape::LandscapePlugin,
                 missile::MissilesPlugin,
                 tank::TanksPlugin,
                 explosion::ExplosionPlugin,
+                timeline::TimelinePlugin,
+                replay::ReplayPlugin,
+                simulation::SimulationPlugin,
+                net::NetPlugin,
+                turn::TurnPlugin,
+                turn_timer::TurnTimerPlugin,
+                announcements::AnnouncementsPlugin,
+                economy::EconomyPlugin,
+                stats::StatsPlugin,
+                shop::ShopPlugin,
+                campaign::CampaignPlugin,
+            ))
+            .add_plugins((
+                weapons::WeaponsPlugin,
+                orbital_strike::OrbitalStrikePlugin,
+                airstrike::AirstrikePlugin,
+                mines::MinesPlugin,
+                scanner::ScannerPlugin,
+                cloak::CloakPlugin,
+                decoy::DecoyPlugin,
+                grappling_hook::GrapplingHookPlugin,
+                jetpack::JetpackPlugin,
+                earthmover::EarthmoverPlugin,
+                weather::WeatherPlugin,
+                anti_gravity::AntiGravityPlugin,
+                portal::PortalPlugin,
+                range::TargetRangePlugin,
+            ))
+            .add_plugins((
+                collider::CollidersPlugin,
+                chat::ChatPlugin,
+                lobby::LobbyPlugin,
+                desync::DesyncPlugin,
+                obstacles::ObstaclesPlugin,
+                supply::SupplyPlugin,
+                hazards::HazardsPlugin,
+            ));
+
+        if let Some(headless) = self.headless {
+            app.insert_resource(headless);
+        } else {
+            app.add_plugins((
+                ShapePlugin,
+                MaterialsPlugin,
+                tank::TankVisualsPlugin,
+                LevelMapPlugin,
+                HeightmapPlugin,
+                landscape_gpu::LandscapeGpuPlugin,
+            ));
+        }
+        if self.ai {
+            app.add_plugins(ai::AiPlugin);
+        }
+        if self.audio {
+            app.add_plugins(audio::GameAudioPlugin);
+        }
+        if self.camera {
+            app.add_plugins((camera::GameCameraPlugin, slow_motion::SlowMotionPlugin));
+        }
+        if self.input {
+            app.add_plugins((PlayerInputPlugin, TouchInputPlugin));
+        }
+        if self.ui {
+            app.add_systems(Update, apply_ui_scale_system);
+            app.add_plugins((
                 status_panel::StatusPanelPlugin,
+                broadcast_hud::BroadcastHudPlugin,
+                announcements::AnnouncementsOverlayPlugin,
+                tank_labels::TankLabelsPlugin,
+                idle_animation::IdleAnimationPlugin,
+                particles::ParticlesPlugin,
+                explosion::ExplosionVisualsPlugin,
+                background::BackgroundPlugin,
+                day_night::DayNightPlugin,
+                trajectory_preview::TrajectoryPreviewPlugin,
+                stats::StatsOverlayPlugin,
+                shop::ShopScreenPlugin,
+                editor::EditorPlugin,
+                turn_order::TurnOrderPlugin,
+                chat::ChatOverlayPlugin,
+            ));
+            app.add_plugins((
+                lobby::LobbyScreenPlugin,
+                main_menu::MainMenuPlugin,
+                minimap::MinimapPlugin,
+                weapons::WeaponIconsPlugin,
             ));
+        }
+    }
+}
+
+/// Applies scale of user interface from settings to all UI nodes.
+fn apply_ui_scale_system(settings: Res<Settings>, ui_scale: Option<ResMut<UiScale>>) {
+    if let Some(mut ui_scale) = ui_scale.filter(|_| settings.is_changed()) {
+        ui_scale.0 = settings.ui_scale.get();
+    }
+}
+
+/// Removes entities of the game field of the previous round.
+fn despawn_previous_round_system(mut commands: Commands, game_field: Option<Res<GameField>>) {
+    if let Some(game_field) = game_field {
+        commands
+            .entity(game_field.parent_entity)
+            .despawn_recursive();
     }
 }
 
@@ -73,59 +314,35 @@ fn switch_to_aiming_system(
     }
 }
 
-fn switch_current_tank_system(
-    mut commands: Commands,
-    mut game_field: ResMut<GameField>,
-    cur_tank_query: Query<Entity, With<CurrentTank>>,
-) {
-    for cur_tank_entity in cur_tank_query.iter() {
-        commands.entity(cur_tank_entity).remove::<CurrentTank>();
-        commands.entity(cur_tank_entity).remove::<AimingTank>();
-    }
-
-    debug!("Switch current tank");
-    if let Some(new_current_entity) = game_field.switch_current_tank() {
-        commands
-            .entity(new_current_entity)
-            .insert(CurrentTank)
-            .insert(AimingTank);
-    } else {
-        // TODO: All tanks are dead
-    }
-}
-
-fn after_tank_shot_system(
-    mut commands: Commands,
-    mut shot_events: EventReader<TankShotEvent>,
-    mut next_state: ResMut<NextState<AppState>>,
-) {
-    let mut has_shoots = false;
-    for event in shot_events.read() {
-        if let Some(mut entity) = commands.get_entity(event.tank_entity) {
-            entity.remove::<AimingTank>();
-        }
-        has_shoots = true;
-    }
-    if has_shoots {
-        debug!("Switch to MainAction");
-        next_state.set(AppState::MainAction);
-    }
-}
-
+#[allow(clippy::too_many_arguments)]
 pub fn setup_game_field(
     mut commands: Commands,
-    mut textures: ResMut<Assets<Image>>,
-    asset_server: Res<AssetServer>,
-    primary_windows: Query<&Window, With<PrimaryWindow>>,
+    mut textures: Option<ResMut<Assets<Image>>>,
+    asset_server: Option<Res<AssetServer>>,
+    settings: Res<Settings>,
+    rules: Res<GameRules>,
+    playback: Option<Res<ReplayPlayback>>,
+    level_map: Option<Res<LevelMap>>,
+    heightmap: Option<Res<Heightmap>>,
+    headless: Option<Res<HeadlessField>>,
 ) {
-    let Ok(window) = primary_windows.get_single() else {
-        return;
+    let (mut field_width, mut field_height) = match headless {
+        Some(headless) => (headless.width, headless.height),
+        None => (settings.field_size.width, settings.field_size.height),
     };
-    let width = window.width();
-    let height = window.height() - 30.;
-
-    let field_width = (width - 2.) as u16;
-    let field_height = (height - 2.) as u16;
+    let mut seed: u64 = settings.seed.unwrap_or_else(rand::random);
+    if let Some(playback) = playback {
+        let replay = playback.replay();
+        if (replay.field_width, replay.field_height) != (field_width, field_height) {
+            warn!(
+                "Replay has been recorded for game field {}x{}",
+                replay.field_width, replay.field_height
+            );
+        }
+        field_width = replay.field_width;
+        field_height = replay.field_height;
+        seed = replay.seed;
+    }
 
     let parent_entity = commands
         .spawn(SpatialBundle {
@@ -137,7 +354,7 @@ pub fn setup_game_field(
 
     // Game Field border
     let border = shapes::Rectangle {
-        extents: Vec2::new(width - 1., height - 1.),
+        extents: Vec2::new(field_width as f32 + 1., field_height as f32 + 1.),
         origin: RectangleOrigin::BottomLeft,
     };
     let border_color = Color::rgb(1., 1., 1.);
@@ -153,8 +370,31 @@ pub fn setup_game_field(
         .set_parent(parent_entity);
 
     // Landscape
-    let game_landscape =
-        landscape::Landscape::new(field_width, field_height, &mut textures).unwrap();
+    let mut game_landscape = match (level_map, heightmap) {
+        (Some(level_map), _) => landscape::Landscape::from_level_map(
+            &level_map,
+            field_width,
+            field_height,
+            settings.landscape_storage,
+            textures.as_deref_mut(),
+        ),
+        (None, Some(heightmap)) => landscape::Landscape::from_heightmap(
+            &heightmap,
+            field_width,
+            field_height,
+            settings.landscape_storage,
+            textures.as_deref_mut(),
+        ),
+        (None, None) => landscape::Landscape::new(
+            field_width,
+            field_height,
+            seed,
+            settings.landscape_storage,
+            textures.as_deref_mut(),
+        ),
+    }
+    .unwrap();
+    game_landscape.set_angle_of_repose(rules.angle_of_repose);
     let position = Vec3::new(field_width as f32 / 2., field_height as f32 / 2., 0.);
     commands
         .spawn((
@@ -167,8 +407,9 @@ pub fn setup_game_field(
         ))
         .set_parent(parent_entity);
 
-    let tank_texture = asset_server.load("sprites/tank.png");
-    let gun_texture = asset_server.load("sprites/gun.png");
+    let tank_texture = load_asset(asset_server.as_deref(), "sprites/tank.png");
+    let gun_texture = load_asset(asset_server.as_deref(), "sprites/gun.png");
+    let plane_texture = load_asset(asset_server.as_deref(), "sprites/plane.png");
 
     // Game field
     let game_field = GameField {
@@ -178,16 +419,19 @@ pub fn setup_game_field(
         landscape: game_landscape,
         wind_power: 0.,
         player_numbers: vec![],
-        tanks: vec![],
-        current_tank: None,
-        number_of_iteration: 0,
-        font: asset_server.load("fonts/DejaVuSerif.ttf"),
+        font: load_asset(asset_server.as_deref(), "fonts/DejaVuSerif.ttf"),
         tank_texture,
         gun_texture,
-        tank_fire_sound: asset_server.load("sounds/tank_fire.ogg"),
-        explosion_sound: asset_server.load("sounds/explosion1.ogg"),
+        plane_texture,
     };
     commands.insert_resource(game_field);
+    commands.insert_resource(GameRng::new(seed));
+}
+
+/// Loads asset if the app has asset server, otherwise returns
+/// default handle (e.g. in headless mode).
+fn load_asset<A: Asset>(asset_server: Option<&AssetServer>, path: &'static str) -> Handle<A> {
+    asset_server.map(|s| s.load(path)).unwrap_or_default()
 }
 
 // fn set_texture_filtration(
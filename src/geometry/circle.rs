@@ -92,13 +92,14 @@ impl Circle {
             .collect()
     }
 
-    pub fn area_of_rect_intersection(&self, mut rect: MyRect) -> f32 {
-        rect.left -= self.center.x;
-        rect.right -= self.center.x;
-        rect.top -= self.center.y;
-        rect.bottom -= self.center.y;
-
-        self.area_of_normalized_rect_intersection(rect)
+    pub fn area_of_rect_intersection(&self, rect: MyRect) -> f32 {
+        // Parts of the rect outside of the circle's bounding square
+        // don't change the area.
+        let bounds = MyRect::from_center_size(Vec2::ZERO, Vec2::splat(2. * self.radius));
+        match rect.translated(-self.center).intersection(&bounds) {
+            Some(rect) => self.area_of_normalized_rect_intersection(rect),
+            None => 0.0,
+        }
     }
 
     fn area_of_normalized_rect_intersection(&self, mut rect: MyRect) -> f32 {
@@ -1,5 +1,10 @@
+use std::f32::consts::PI;
+
 use bevy::prelude::*;
 
+use crate::geometry::rect::MyRect;
+use crate::geometry::Circle;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Ellipse {
     pub center: Vec2,
@@ -32,6 +37,76 @@ impl Ellipse {
             point.x * point.x / self.a2 + point.y * point.y / self.b2 - 1.
         }
     }
+
+    #[inline]
+    pub fn area(&self) -> f32 {
+        PI * self.a * self.b
+    }
+
+    /// Returns part of the segment inside of the ellipse as fractions
+    /// of the segment's length from its start.
+    pub fn segment_interval(&self, start: Vec2, end: Vec2) -> Option<(f32, f32)> {
+        if self.a == 0. || self.b == 0. {
+            let rect = MyRect::from_center_size(self.center, Vec2::new(self.a, self.b) * 2.);
+            return rect.segment_interval(start, end);
+        }
+        // Solve the equation of intersection with the unit circle
+        // in coordinates scaled by radii of the ellipse.
+        let scale = Vec2::new(1. / self.a, 1. / self.b);
+        let start_scaled = (start - self.center) * scale;
+        let direction = (end - start) * scale;
+        let qa = direction.length_squared();
+        let qb = 2. * start_scaled.dot(direction);
+        let qc = start_scaled.length_squared() - 1.;
+        if qa == 0. {
+            return (qc <= 0.).then_some((0., 1.));
+        }
+        let discriminant = qb * qb - 4. * qa * qc;
+        if discriminant < 0. {
+            return None;
+        }
+        let discriminant_sqrt = discriminant.sqrt();
+        let t0 = ((-qb - discriminant_sqrt) / (2. * qa)).max(0.);
+        let t1 = ((-qb + discriminant_sqrt) / (2. * qa)).min(1.);
+        (t0 <= t1).then_some((t0, t1))
+    }
+
+    /// Returns points where the segment crosses the border of ellipse.
+    /// Ellipses with zero radius don't have intersections.
+    pub fn segment_intersection<P>(&self, point1: P, point2: P) -> Vec<Vec2>
+    where
+        P: Into<Vec2>,
+    {
+        if self.a == 0. || self.b == 0. {
+            return vec![];
+        }
+        // The ellipse is the circle with radius `b` stretched along X axis.
+        let stretch = Vec2::new(self.a / self.b, 1.);
+        let circle = Circle::new(Vec2::ZERO, self.b);
+        circle
+            .segment_intersection(
+                (point1.into() - self.center) / stretch,
+                (point2.into() - self.center) / stretch,
+            )
+            .into_iter()
+            .map(|point| point * stretch + self.center)
+            .collect()
+    }
+
+    pub fn area_of_rect_intersection(&self, rect: MyRect) -> f32 {
+        if self.a == 0. || self.b == 0. {
+            return 0.;
+        }
+        // Stretching along X axis changes areas in the same ratio.
+        let stretch = self.a / self.b;
+        let rect = rect.translated(-self.center);
+        let squeezed_rect = MyRect {
+            left: rect.left / stretch,
+            right: rect.right / stretch,
+            ..rect
+        };
+        Circle::new(Vec2::ZERO, self.b).area_of_rect_intersection(squeezed_rect) * stretch
+    }
 }
 
 #[cfg(test)]
@@ -78,4 +153,45 @@ mod tests {
         assert!(ellipse.point_position((11., -12.)) > 0.);
         assert!(ellipse.point_position((10., -11.)) > 0.);
     }
+
+    #[test]
+    fn test_segment_interval() {
+        let ellipse = Ellipse::new((10., 0.), 5., 2.);
+        let interval = ellipse.segment_interval(Vec2::new(0., 0.), Vec2::new(10., 0.));
+        assert_eq!(interval, Some((0.5, 1.)));
+        let interval = ellipse.segment_interval(Vec2::new(10., -4.), Vec2::new(10., 4.));
+        assert_eq!(interval, Some((0.25, 0.75)));
+        assert_eq!(
+            ellipse.segment_interval(Vec2::new(0., 3.), Vec2::new(20., 3.)),
+            None
+        );
+        // Segment crosses the ellipse with zero width.
+        let line = Ellipse::new((10., 0.), 0., 2.);
+        let interval = line.segment_interval(Vec2::new(9., 1.), Vec2::new(11., -1.));
+        assert_eq!(interval, Some((0.5, 0.5)));
+    }
+
+    #[test]
+    fn test_segment_intersection() {
+        let ellipse = Ellipse::new((10., 0.), 5., 2.);
+        let points = ellipse.segment_intersection((0., 0.), (20., 0.));
+        assert_eq!(points, vec![Vec2::new(5., 0.), Vec2::new(15., 0.)]);
+        let points = ellipse.segment_intersection((10., 0.), (10., 5.));
+        assert_eq!(points, vec![Vec2::new(10., 2.)]);
+        assert!(ellipse.segment_intersection((0., 3.), (20., 3.)).is_empty());
+    }
+
+    #[test]
+    fn test_area_of_rect_intersection() {
+        let ellipse = Ellipse::new((10., 0.), 5., 2.);
+        let huge_rect = MyRect::from_center_size(Vec2::ZERO, Vec2::splat(100.));
+        let area = ellipse.area_of_rect_intersection(huge_rect);
+        assert!((area - PI * 10.).abs() < 1e-4);
+        // The right half of ellipse.
+        let rect = MyRect::from_center_size(Vec2::new(20., 0.), Vec2::splat(20.));
+        let area = ellipse.area_of_rect_intersection(rect);
+        assert!((area - PI * 5.).abs() < 1e-4);
+        let far_rect = MyRect::from_center_size(Vec2::new(-20., 0.), Vec2::splat(10.));
+        assert_eq!(ellipse.area_of_rect_intersection(far_rect), 0.);
+    }
 }
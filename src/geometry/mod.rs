@@ -1,6 +1,8 @@
 pub use circle::Circle;
 pub use ellipse::Ellipse;
+pub use polygon::Polygon;
 
 pub mod circle;
 pub mod ellipse;
+pub mod polygon;
 pub mod rect;
@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::geometry::rect::MyRect;
+
+/// Simple polygon given by its vertices in any order of traversal.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    vertices: Vec<Vec2>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Vec2>) -> Self {
+        assert!(vertices.len() >= 3, "Polygon has less than 3 vertices");
+        Self { vertices }
+    }
+
+    #[inline]
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+
+    pub fn bounding_rect(&self) -> MyRect {
+        let (min, max) = self.vertices.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), &vertex| (min.min(vertex), max.max(vertex)),
+        );
+        MyRect::from(Rect::from_corners(min, max))
+    }
+
+    /// Returns `true` if the point locates inside of polygon
+    /// (even-odd rule).
+    pub fn contains(&self, point: Vec2) -> bool {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if (a.y > point.y) != (b.y > point.y) {
+                let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+                if point.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    /// Returns fractions of the segment's length from its start
+    /// where the segment crosses edges of polygon, in ascending order.
+    fn segment_crossings(&self, start: Vec2, end: Vec2) -> Vec<f32> {
+        let direction = end - start;
+        let mut crossings: Vec<f32> = self
+            .edges()
+            .filter_map(|(a, b)| {
+                let edge = b - a;
+                let denominator = direction.perp_dot(edge);
+                if denominator == 0. {
+                    return None;
+                }
+                let offset = a - start;
+                let t = offset.perp_dot(edge) / denominator;
+                let u = offset.perp_dot(direction) / denominator;
+                ((0. ..=1.).contains(&t) && (0. ..=1.).contains(&u)).then_some(t)
+            })
+            .collect();
+        crossings.sort_by(f32::total_cmp);
+        crossings
+    }
+
+    /// Returns points where the segment crosses edges of polygon,
+    /// starting from the nearest one to `point1`.
+    pub fn segment_intersection<P>(&self, point1: P, point2: P) -> Vec<Vec2>
+    where
+        P: Into<Vec2>,
+    {
+        let (start, end) = (point1.into(), point2.into());
+        self.segment_crossings(start, end)
+            .into_iter()
+            .map(|t| start.lerp(end, t))
+            .collect()
+    }
+
+    /// Returns the first point of the segment inside of polygon
+    /// as fraction of the segment's length from its start.
+    pub fn segment_entry(&self, start: Vec2, end: Vec2) -> Option<f32> {
+        if self.contains(start) {
+            return Some(0.);
+        }
+        self.segment_crossings(start, end).first().copied()
+    }
+
+    pub fn area(&self) -> f32 {
+        shoelace_area(&self.vertices)
+    }
+
+    /// Area of the part of polygon inside of the rect. The polygon
+    /// is clipped by sides of the rect (Sutherland–Hodgman). Clipped
+    /// concave polygon may get edges along sides of the rect,
+    /// but they don't add any area.
+    pub fn area_of_rect_intersection(&self, rect: MyRect) -> f32 {
+        let mut vertices = self.vertices.clone();
+        let sides: [(Vec2, f32); 4] = [
+            (Vec2::NEG_X, -rect.left),
+            (Vec2::X, rect.right),
+            (Vec2::NEG_Y, -rect.bottom),
+            (Vec2::Y, rect.top),
+        ];
+        for (normal, distance) in sides {
+            // Distance of point outside of the side is positive.
+            let outside = |point: Vec2| point.dot(normal) - distance;
+            let mut clipped = Vec::with_capacity(vertices.len() + 1);
+            for (i, &current) in vertices.iter().enumerate() {
+                let next = vertices[(i + 1) % vertices.len()];
+                let (current_outside, next_outside) = (outside(current), outside(next));
+                if current_outside <= 0. {
+                    clipped.push(current);
+                }
+                if (current_outside <= 0.) != (next_outside <= 0.) {
+                    let t = current_outside / (current_outside - next_outside);
+                    clipped.push(current.lerp(next, t));
+                }
+            }
+            if clipped.len() < 3 {
+                return 0.;
+            }
+            vertices = clipped;
+        }
+        shoelace_area(&vertices)
+    }
+}
+
+fn shoelace_area(vertices: &[Vec2]) -> f32 {
+    let doubled_area: f32 = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum();
+    doubled_area.abs() / 2.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Square 10x10 with a notch from the top to its center.
+    fn notched_square() -> Polygon {
+        Polygon::new(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(10., 0.),
+            Vec2::new(10., 10.),
+            Vec2::new(6., 10.),
+            Vec2::new(5., 5.),
+            Vec2::new(4., 10.),
+            Vec2::new(0., 10.),
+        ])
+    }
+
+    #[test]
+    fn test_contains() {
+        let polygon = notched_square();
+        assert!(polygon.contains(Vec2::new(2., 8.)));
+        assert!(polygon.contains(Vec2::new(5., 2.)));
+        assert!(!polygon.contains(Vec2::new(5., 8.)));
+        assert!(!polygon.contains(Vec2::new(12., 5.)));
+        assert_eq!(
+            Rect::from(polygon.bounding_rect()),
+            Rect::new(0., 0., 10., 10.)
+        );
+    }
+
+    #[test]
+    fn test_segment_intersection() {
+        let polygon = notched_square();
+        let points = polygon.segment_intersection((-5., 8.), (15., 8.));
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[0], Vec2::new(0., 8.));
+        assert_eq!(points[3], Vec2::new(10., 8.));
+        // The segment enters the polygon through the notch.
+        let entry = polygon.segment_entry(Vec2::new(5., 12.), Vec2::new(5., 2.));
+        assert!((entry.unwrap() - 0.7).abs() < 1e-6);
+        assert_eq!(
+            polygon.segment_entry(Vec2::new(5., 12.), Vec2::new(5., 8.)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_area_of_rect_intersection() {
+        let polygon = notched_square();
+        assert_eq!(polygon.area(), 95.);
+        let huge_rect = MyRect::from_center_size(Vec2::ZERO, Vec2::splat(100.));
+        assert_eq!(polygon.area_of_rect_intersection(huge_rect), 95.);
+        let bottom_half = MyRect::from_center_size(Vec2::new(5., 2.5), Vec2::new(10., 5.));
+        assert_eq!(polygon.area_of_rect_intersection(bottom_half), 50.);
+        let far_rect = MyRect::from_center_size(Vec2::new(50., 50.), Vec2::splat(10.));
+        assert_eq!(polygon.area_of_rect_intersection(far_rect), 0.);
+    }
+}
@@ -1,3 +1,5 @@
+use bevy::prelude::*;
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct MyRect {
     pub left: f32,
@@ -5,3 +7,145 @@ pub struct MyRect {
     pub top: f32,
     pub bottom: f32,
 }
+
+impl MyRect {
+    pub fn from_center_size(center: Vec2, size: Vec2) -> Self {
+        let half_size = size / 2.;
+        Self {
+            left: center.x - half_size.x,
+            right: center.x + half_size.x,
+            top: center.y + half_size.y,
+            bottom: center.y - half_size.y,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    #[inline]
+    pub fn height(&self) -> f32 {
+        self.top - self.bottom
+    }
+
+    #[inline]
+    pub fn area(&self) -> f32 {
+        self.width() * self.height()
+    }
+
+    /// Returns the rect moved by given offset.
+    pub fn translated(&self, offset: Vec2) -> Self {
+        Self {
+            left: self.left + offset.x,
+            right: self.right + offset.x,
+            top: self.top + offset.y,
+            bottom: self.bottom + offset.y,
+        }
+    }
+
+    /// Returns `true` if the point locates inside of the rect or on its border.
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= self.left
+            && point.x <= self.right
+            && point.y >= self.bottom
+            && point.y <= self.top
+    }
+
+    /// Returns `true` if rects have common points, including their borders.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.left <= other.right
+            && other.left <= self.right
+            && self.bottom <= other.top
+            && other.bottom <= self.top
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.intersects(other).then(|| Self {
+            left: self.left.max(other.left),
+            right: self.right.min(other.right),
+            top: self.top.min(other.top),
+            bottom: self.bottom.max(other.bottom),
+        })
+    }
+
+    /// Returns part of the segment inside of the rect as fractions
+    /// of the segment's length from its start.
+    pub fn segment_interval(&self, start: Vec2, end: Vec2) -> Option<(f32, f32)> {
+        let direction = end - start;
+        let mut interval = (0f32, 1f32);
+        for (start, direction, min, max) in [
+            (start.x, direction.x, self.left, self.right),
+            (start.y, direction.y, self.bottom, self.top),
+        ] {
+            if direction == 0. {
+                if start < min || start > max {
+                    return None;
+                }
+                continue;
+            }
+            let (t0, t1) = ((min - start) / direction, (max - start) / direction);
+            interval.0 = interval.0.max(t0.min(t1));
+            interval.1 = interval.1.min(t0.max(t1));
+        }
+        (interval.0 <= interval.1).then_some(interval)
+    }
+}
+
+impl From<Rect> for MyRect {
+    fn from(rect: Rect) -> Self {
+        Self {
+            left: rect.min.x,
+            right: rect.max.x,
+            top: rect.max.y,
+            bottom: rect.min.y,
+        }
+    }
+}
+
+impl From<MyRect> for Rect {
+    fn from(rect: MyRect) -> Self {
+        Rect::new(rect.left, rect.bottom, rect.right, rect.top)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_utilities() {
+        let rect = MyRect::from_center_size(Vec2::new(5., 5.), Vec2::splat(10.));
+        assert_eq!(rect.area(), 100.);
+        assert!(rect.contains(Vec2::new(10., 0.)));
+        assert!(!rect.contains(Vec2::new(10.5, 5.)));
+
+        let other = rect.translated(Vec2::new(8., 6.));
+        assert!(rect.intersects(&other));
+        let intersection = rect.intersection(&other).unwrap();
+        assert_eq!(Rect::from(intersection), Rect::new(8., 6., 10., 10.));
+
+        let far = rect.translated(Vec2::new(20., 0.));
+        assert!(!rect.intersects(&far));
+        assert!(rect.intersection(&far).is_none());
+        let bevy_rect = Rect::new(-1., -2., 3., 4.);
+        assert_eq!(Rect::from(MyRect::from(bevy_rect)), bevy_rect);
+    }
+
+    #[test]
+    fn test_segment_interval() {
+        let rect = MyRect {
+            left: 0.,
+            right: 10.,
+            top: 10.,
+            bottom: 0.,
+        };
+        let interval = rect.segment_interval(Vec2::new(-10., 5.), Vec2::new(10., 5.));
+        assert_eq!(interval, Some((0.5, 1.)));
+        let interval = rect.segment_interval(Vec2::new(5., 5.), Vec2::new(5., 20.));
+        assert_eq!(interval, Some((0., 1. / 3.)));
+        // Segment passes by the corner.
+        let interval = rect.segment_interval(Vec2::new(-1., 10.), Vec2::new(2., 13.));
+        assert_eq!(interval, None);
+    }
+}
@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::components::{Owner, Position};
+use crate::game_field::GameField;
+use crate::game_plugin::AppState;
+use crate::landscape::Landscape;
+use crate::missile::kill_missile;
+use crate::tank::{AllTanksPlacedEvent, Tank};
+
+/// Max distance between the tank and its flying hook.
+const HOOK_RANGE: f32 = 200.;
+/// Part of the rope length which is left after the tank has been pulled.
+const PULLED_ROPE_PART: f32 = 0.6;
+/// Angular speed of swinging tank (radians per second).
+const SWING_SPEED: f32 = 2.;
+const MIN_SWING_DURATION: f32 = 0.5;
+/// Distance between the border of tank's texture and its body.
+const BODY_INSET: f32 = 4.;
+
+pub struct GrapplingHookPlugin;
+
+impl Plugin for GrapplingHookPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HookLandedEvent>()
+            .add_systems(
+                Update,
+                (hook_range_system, start_swing_system, swing_system).chain(),
+            )
+            .add_systems(OnEnter(AppState::RoundSetup), despawn_ropes_system);
+    }
+}
+
+/// Missile which is a hook of grappling hook.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct GrapplingHook;
+
+/// Hook has stopped its flight.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HookLandedEvent {
+    pub tank_entity: Entity,
+    pub position: Vec2,
+}
+
+/// Tank swings on the rope and is pulled up to the anchor of its hook.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct HookSwing {
+    anchor: Vec2,
+    length: f32,
+    start_angle: f32,
+    duration: f32,
+    time: f32,
+    rope_entity: Entity,
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct HookRope;
+
+impl HookSwing {
+    fn new(anchor: Vec2, tank_position: Vec2, rope_entity: Entity) -> Self {
+        let offset = tank_position - anchor;
+        // Angle between the rope and the vertical line under the anchor.
+        let start_angle = offset.x.atan2(-offset.y);
+        Self {
+            anchor,
+            length: offset.length(),
+            start_angle,
+            duration: (2. * start_angle.abs() / SWING_SPEED).max(MIN_SWING_DURATION),
+            time: 0.,
+            rope_entity,
+        }
+    }
+
+    /// Returns position of tank at given part of the swing. Tank swings
+    /// to the opposite side of the anchor while the rope is pulled in.
+    fn position(&self, part: f32) -> Vec2 {
+        let angle = self.start_angle * (1. - 2. * part);
+        let length = self.length * (1. - (1. - PULLED_ROPE_PART) * part);
+        self.anchor + Vec2::new(angle.sin(), -angle.cos()) * length
+    }
+}
+
+/// Returns `true` if the body of tank with given center intersects the landscape.
+pub(crate) fn body_hits_landscape(landscape: &Landscape, tank: &Tank, center: Vec2) -> bool {
+    let half_size = tank.size() / 2. - BODY_INSET;
+    [
+        Vec2::new(0., -half_size.y),
+        Vec2::new(-half_size.x, -half_size.y),
+        Vec2::new(half_size.x, -half_size.y),
+        Vec2::new(-half_size.x, 0.),
+        Vec2::new(half_size.x, 0.),
+        Vec2::new(0., half_size.y),
+    ]
+    .into_iter()
+    .map(|offset| center + offset)
+    .any(|point| landscape.is_not_empty(point.x as i32, point.y as i32))
+}
+
+/// Hook drops down when its rope is over.
+fn hook_range_system(
+    mut commands: Commands,
+    hooks_query: Query<(Entity, &Position, &Owner), With<GrapplingHook>>,
+    tanks_query: Query<&Position, With<Tank>>,
+) {
+    for (entity, &Position(position), owner) in hooks_query.iter() {
+        let Ok(tank_position) = tanks_query.get(owner.0) else {
+            continue;
+        };
+        if tank_position.0.distance(position) > HOOK_RANGE {
+            debug!("Rope of grappling hook is over");
+            kill_missile(&mut commands, entity, position.x as i32, position.y as i32);
+        }
+    }
+}
+
+/// Starts swing of the tank if its hook anchors in the landscape above
+/// the tank, otherwise finishes the turn.
+fn start_swing_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    mut landed_events: EventReader<HookLandedEvent>,
+    tanks_query: Query<&Position, With<Tank>>,
+    mut placed_events: EventWriter<AllTanksPlacedEvent>,
+) {
+    let Some(game_field) = game_field else {
+        landed_events.clear();
+        return;
+    };
+    for event in landed_events.read() {
+        let Ok(&Position(tank_position)) = tanks_query.get(event.tank_entity) else {
+            placed_events.send(AllTanksPlacedEvent);
+            continue;
+        };
+        let (x, y) = (event.position.x as i32, event.position.y as i32);
+        let anchored =
+            game_field.landscape.is_not_empty(x, y) && event.position.y > tank_position.y;
+        if !anchored {
+            debug!("Grappling hook hasn't anchored");
+            placed_events.send(AllTanksPlacedEvent);
+            continue;
+        }
+        info!("Grappling hook has anchored at {:?}", event.position);
+        let rope_entity = commands
+            .spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shapes::Line(tank_position, event.position)),
+                    spatial: SpatialBundle::from_transform(Transform::from_xyz(0., 0., 0.5)),
+                    ..default()
+                },
+                Stroke::new(Color::rgb(0.6, 0.5, 0.4), 1.),
+                HookRope,
+            ))
+            .id();
+        commands
+            .entity(game_field.parent_entity)
+            .add_child(rope_entity);
+        commands.entity(event.tank_entity).insert(HookSwing::new(
+            event.position,
+            tank_position,
+            rope_entity,
+        ));
+    }
+}
+
+/// Moves swinging tanks along their arcs. Tank is released when the swing
+/// is finished or the tank has hit the landscape, and then it falls down.
+fn swing_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_field: Option<Res<GameField>>,
+    mut swings_query: Query<(Entity, &Tank, &mut HookSwing, &mut Position)>,
+    mut ropes_query: Query<&mut Path, With<HookRope>>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    for (entity, tank, mut swing, mut position) in swings_query.iter_mut() {
+        swing.time += time.delta_seconds();
+        let part = (swing.time / swing.duration).min(1.);
+        let new_position = swing.position(part);
+        let hit = body_hits_landscape(&game_field.landscape, tank, new_position);
+        if !hit {
+            position.0 = new_position;
+        }
+        if let Ok(mut path) = ropes_query.get_mut(swing.rope_entity) {
+            *path = GeometryBuilder::build_as(&shapes::Line(position.0, swing.anchor));
+        }
+        if hit || part >= 1. {
+            debug!("Tank of player {} released the rope", tank.player_number);
+            commands.entity(swing.rope_entity).despawn_recursive();
+            commands
+                .entity(entity)
+                .remove::<HookSwing>()
+                .insert(tank.throw_down(position.0));
+        }
+    }
+}
+
+fn despawn_ropes_system(mut commands: Commands, ropes_query: Query<Entity, With<HookRope>>) {
+    for entity in ropes_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swing_position() {
+        let anchor = Vec2::new(100., 200.);
+        let start = Vec2::new(60., 170.);
+        let swing = HookSwing::new(anchor, start, Entity::PLACEHOLDER);
+        assert!(swing.position(0.).distance(start) < 0.001);
+        // Tank swings to the opposite side of the anchor and rises.
+        let end = swing.position(1.);
+        assert!(end.x > anchor.x);
+        assert!(end.y > start.y && end.y < anchor.y);
+        assert!((end.distance(anchor) - 50. * PULLED_ROPE_PART).abs() < 0.001);
+    }
+}
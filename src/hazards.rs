@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::Rng;
+
+use crate::airstrike::airstrike;
+use crate::components::Owner;
+use crate::game_field::{GameField, GameRng};
+use crate::input::PlayerAction;
+use crate::missile::{spawn_missile, Missile};
+use crate::rules::GameRules;
+use crate::tank::{shoot_system, AimingTank, TankSet, TankShotEvent};
+use crate::turn::TurnEndedEvent;
+use crate::weapons::{TankWeapon, WeaponDefinition, WeaponKind, Weapons};
+use crate::G;
+
+const METEORS_COUNT: usize = 8;
+/// Maximal deviation of meteors from vertical fall (degrees).
+const METEOR_SPREAD: f32 = 30.;
+const MIN_METEOR_POWER: f32 = 20.;
+const MAX_METEOR_POWER: f32 = 40.;
+/// Chance of random hazard at the end of every turn.
+const HAZARD_CHANCE: f64 = 0.15;
+
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HazardEvent>()
+            .add_systems(
+                Update,
+                call_meteor_shower_system
+                    .after(shoot_system)
+                    .in_set(TankSet::Aiming),
+            )
+            .add_systems(Update, random_hazards_system);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HazardKind {
+    MeteorShower,
+    Airstrike,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HazardEvent {
+    pub kind: HazardKind,
+    /// Tank which has called the hazard, `None` for random hazards.
+    pub owner: Option<Entity>,
+}
+
+/// Returns start x-coordinates, angles and powers of meteors.
+fn meteor_launches(width: f32, rng: &mut impl Rng) -> Vec<(f32, f32, f32)> {
+    (0..METEORS_COUNT)
+        .map(|_| {
+            let x = rng.gen_range(0. ..width).round();
+            let angle = 180. + rng.gen_range(-METEOR_SPREAD..=METEOR_SPREAD);
+            let power = rng.gen_range(MIN_METEOR_POWER..=MAX_METEOR_POWER);
+            (x, angle, power)
+        })
+        .collect()
+}
+
+/// Meteors fall from the top of game field onto random points,
+/// their paths are bent by wind like paths of missiles.
+pub fn meteor_shower(
+    commands: &mut Commands,
+    game_field: &GameField,
+    rng: &mut impl Rng,
+    weapon: Option<&WeaponDefinition>,
+    owner: Option<Owner>,
+) {
+    let top = game_field.height as f32 - 1.;
+    let acceleration = Vec2::new(game_field.wind_power, -G);
+    for (x, angle, power) in meteor_launches(game_field.width as f32, rng) {
+        let missile = Missile::new(Vec2::new(x, top), angle, power, acceleration);
+        let meteor_entity = spawn_missile(commands, game_field, missile, weapon, owner);
+        commands
+            .entity(meteor_entity)
+            .insert(Fill::color(Color::rgb(1., 0.55, 0.1)));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn call_meteor_shower_system(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    mut game_rng: ResMut<GameRng>,
+    weapons: Res<Weapons>,
+    mut actions: EventReader<PlayerAction>,
+    aiming_tanks: Query<(Entity, &TankWeapon), With<AimingTank>>,
+    mut shot_events: EventWriter<TankShotEvent>,
+    mut hazard_events: EventWriter<HazardEvent>,
+) {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    if !fire {
+        return;
+    }
+    for (tank_entity, weapon) in aiming_tanks.iter() {
+        if weapon.ready_weapon_kind(&weapons) != WeaponKind::MeteorShower {
+            continue;
+        }
+        info!("Meteor shower has been called");
+        meteor_shower(
+            &mut commands,
+            &game_field,
+            &mut *game_rng,
+            weapons.of_kind(WeaponKind::MeteorShower),
+            Some(Owner(tank_entity)),
+        );
+        hazard_events.send(HazardEvent {
+            kind: HazardKind::MeteorShower,
+            owner: Some(tank_entity),
+        });
+        shot_events.send(TankShotEvent { tank_entity });
+    }
+}
+
+/// Random hazards start at the end of turn, so their projectiles
+/// fly together with the shot of the turn.
+fn random_hazards_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    game_rng: Option<ResMut<GameRng>>,
+    rules: Res<GameRules>,
+    weapons: Res<Weapons>,
+    mut ended_events: EventReader<TurnEndedEvent>,
+    mut hazard_events: EventWriter<HazardEvent>,
+) {
+    let (Some(game_field), Some(mut game_rng)) = (game_field, game_rng) else {
+        ended_events.clear();
+        return;
+    };
+    if !rules.hazards {
+        ended_events.clear();
+        return;
+    }
+    for _ in ended_events.read() {
+        if !game_rng.gen_bool(HAZARD_CHANCE) {
+            continue;
+        }
+        let kind = if game_rng.gen_bool(0.5) {
+            let width = game_field.width as f32;
+            let target_x = game_rng.gen_range(0. ..width).round();
+            let direction = if game_rng.gen_bool(0.5) { 1. } else { -1. };
+            info!("Random airstrike to x={}", target_x);
+            let weapon = weapons.of_kind(WeaponKind::Airstrike);
+            airstrike(
+                &mut commands,
+                &game_field,
+                target_x,
+                direction,
+                weapon,
+                None,
+            );
+            HazardKind::Airstrike
+        } else {
+            info!("Random meteor shower");
+            let weapon = weapons.of_kind(WeaponKind::MeteorShower);
+            meteor_shower(&mut commands, &game_field, &mut *game_rng, weapon, None);
+            HazardKind::MeteorShower
+        };
+        hazard_events.send(HazardEvent { kind, owner: None });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_meteor_launches() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let launches = meteor_launches(500., &mut rng);
+        assert_eq!(launches.len(), METEORS_COUNT);
+        for (x, angle, power) in launches {
+            assert!((0. ..=500.).contains(&x));
+            // Meteors always fly downwards.
+            let velocity_y = (angle * std::f32::consts::PI / 180.).cos() * power;
+            assert!(velocity_y < 0.);
+        }
+    }
+}
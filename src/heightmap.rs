@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::game_plugin::AppState;
+use crate::launch::LaunchOptions;
+
+/// Brightness of pixel of heightmap which is the ground in threshold mode.
+const GROUND_THRESHOLD: u8 = 128;
+
+/// Loads hand-drawn grayscale PNG maps.
+pub struct HeightmapPlugin;
+
+impl Plugin for HeightmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_launch_heightmap_system)
+            .add_systems(
+                Update,
+                apply_loaded_heightmap_system.run_if(resource_exists::<HeightmapHandle>),
+            );
+    }
+}
+
+/// How brightness of pixels of heightmap is turned into landscape.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HeightmapMode {
+    /// Bright pixels are the ground and dark ones are the sky,
+    /// so maps may have caves and overhangs.
+    #[default]
+    Threshold,
+    /// Average brightness of every column of pixels is height
+    /// of the landscape in the column.
+    Columns,
+}
+
+impl HeightmapMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "threshold" => Some(Self::Threshold),
+            "columns" => Some(Self::Columns),
+            _ => None,
+        }
+    }
+}
+
+/// Returns `true` if the path is a path of PNG heightmap.
+pub fn is_heightmap_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "png")
+}
+
+/// Grayscale image which is stretched to the game field to make
+/// its landscape. If this resource exists, the round is played on it
+/// instead of generated landscape.
+#[derive(Debug, Clone, PartialEq, Eq, Resource)]
+pub struct Heightmap {
+    width: u32,
+    height: u32,
+    /// Brightness of pixels row by row from top to bottom.
+    luminance: Vec<u8>,
+    pub mode: HeightmapMode,
+}
+
+impl Heightmap {
+    pub fn new(
+        width: u32,
+        height: u32,
+        luminance: Vec<u8>,
+        mode: HeightmapMode,
+    ) -> Result<Self, String> {
+        if width.min(height) == 0 || luminance.len() != (width * height) as usize {
+            return Err(format!(
+                "Heightmap {}x{} has {} pixels",
+                width,
+                height,
+                luminance.len()
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            luminance,
+            mode,
+        })
+    }
+
+    /// Takes brightness of pixels from the first channel of the image.
+    pub fn from_image(image: &Image, mode: HeightmapMode) -> Result<Self, String> {
+        let size = image.size();
+        let pixels_count = (size.x * size.y) as usize;
+        let pixel_size = image.data.len().checked_div(pixels_count).unwrap_or(0);
+        if pixel_size == 0 {
+            return Err("Heightmap image is empty".to_string());
+        }
+        let luminance = image
+            .data
+            .chunks_exact(pixel_size)
+            .map(|pixel| pixel[0])
+            .collect();
+        Self::new(size.x, size.y, luminance, mode)
+    }
+
+    /// Returns brightness of the pixel which is stretched to the point
+    /// of the field, `row` is counted from top to bottom.
+    fn sample(&self, x: usize, row: usize, field_width: u16, field_height: u16) -> u8 {
+        let src_x = x * self.width as usize / field_width as usize;
+        let src_y = row * self.height as usize / field_height as usize;
+        let src_x = src_x.min(self.width as usize - 1);
+        let src_y = src_y.min(self.height as usize - 1);
+        self.luminance[src_y * self.width as usize + src_x]
+    }
+
+    /// Returns `true` if the pixel of field is the ground in threshold mode,
+    /// `row` is counted from top to bottom.
+    pub fn is_ground(&self, x: usize, row: usize, field_width: u16, field_height: u16) -> bool {
+        self.sample(x, row, field_width, field_height) >= GROUND_THRESHOLD
+    }
+
+    /// Returns height of the column of field in columns mode.
+    pub fn column_height(&self, x: usize, field_width: u16, field_height: u16) -> u16 {
+        let src_x = (x * self.width as usize / field_width as usize).min(self.width as usize - 1);
+        let sum: u32 = (0..self.height as usize)
+            .map(|y| self.luminance[y * self.width as usize + src_x] as u32)
+            .sum();
+        let brightness = sum as f32 / (self.height as f32 * 255.);
+        (brightness * field_height as f32).round() as u16
+    }
+}
+
+/// Handle of PNG image which is turned into `Heightmap` after loading.
+#[derive(Debug, Clone, Resource)]
+pub struct HeightmapHandle {
+    pub image: Handle<Image>,
+    pub mode: HeightmapMode,
+}
+
+fn load_launch_heightmap_system(
+    mut commands: Commands,
+    asset_server: Option<Res<AssetServer>>,
+    launch_options: Option<Res<LaunchOptions>>,
+) {
+    let (Some(asset_server), Some(options)) = (asset_server, launch_options) else {
+        return;
+    };
+    let Some(path) = options.level.clone().filter(|path| is_heightmap_path(path)) else {
+        return;
+    };
+    commands.insert_resource(HeightmapHandle {
+        image: asset_server.load(path),
+        mode: options.heightmap_mode,
+    });
+}
+
+/// Restarts the round when the heightmap image is loaded or modified.
+fn apply_loaded_heightmap_system(
+    mut commands: Commands,
+    handle: Res<HeightmapHandle>,
+    images: Res<Assets<Image>>,
+    mut asset_events: EventReader<AssetEvent<Image>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != handle.image.id() {
+            continue;
+        }
+        let Some(image) = images.get(*id) else {
+            continue;
+        };
+        match Heightmap::from_image(image, handle.mode) {
+            Ok(heightmap) => {
+                info!("Heightmap {:?} has been loaded", handle.image.path());
+                commands.insert_resource(heightmap);
+                next_state.set(AppState::RoundSetup);
+            }
+            Err(err) => warn!("Failed to load heightmap: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::landscape::Landscape;
+    use crate::landscape_buffer::LandscapeStorage;
+
+    #[test]
+    fn test_heightmap_landscape() {
+        // 2x2 image: sky at the top, ground at the bottom,
+        // the left column is brighter.
+        let luminance = vec![255, 0, 255, 255];
+        assert!(Heightmap::new(2, 3, luminance.clone(), HeightmapMode::Threshold).is_err());
+
+        let heightmap = Heightmap::new(2, 2, luminance.clone(), HeightmapMode::Threshold).unwrap();
+        let landscape =
+            Landscape::from_heightmap(&heightmap, 100, 50, LandscapeStorage::default(), None)
+                .unwrap();
+        assert_eq!(landscape.surface_height(10), 50);
+        assert_eq!(landscape.surface_height(90), 25);
+
+        let heightmap = Heightmap::new(2, 2, luminance, HeightmapMode::Columns).unwrap();
+        let landscape =
+            Landscape::from_heightmap(&heightmap, 100, 50, LandscapeStorage::default(), None)
+                .unwrap();
+        assert_eq!(landscape.surface_height(10), 50);
+        assert_eq!(landscape.surface_height(90), 25);
+        assert!(is_heightmap_path(Path::new("maps/island.png")));
+    }
+}
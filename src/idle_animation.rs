@@ -0,0 +1,231 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::components::{Angle, Position, Scale};
+use crate::game_field::GameField;
+use crate::tank::{Tank, TankThrowing};
+
+/// Amplitude of antenna sway of standing tank (degrees).
+const ANTENNA_IDLE_SWAY: f32 = 4.;
+/// Amplitude of antenna sway of moving tank (degrees).
+const ANTENNA_MOVING_SWAY: f32 = 15.;
+/// Count of antenna sways per second.
+const ANTENNA_SWAY_FREQUENCY: f32 = 0.8;
+const ANTENNA_LENGTH: f32 = 12.;
+/// Position of antenna base relative to the center of tank.
+const ANTENNA_OFFSET: Vec2 = Vec2::new(-8., -2.);
+/// Interval between exhaust puffs of standing tank (seconds).
+const EXHAUST_IDLE_INTERVAL: f32 = 1.2;
+/// Interval between exhaust puffs of moving tank (seconds).
+const EXHAUST_MOVING_INTERVAL: f32 = 0.15;
+const EXHAUST_PUFF_LIFETIME: f32 = 1.5;
+/// Size of puff on the screen before it grows.
+const EXHAUST_PUFF_SIZE: f32 = 4.;
+/// Count of frames of puff in its sprite sheet, puff fades out
+/// frame by frame.
+const EXHAUST_PUFF_FRAMES: usize = 6;
+/// Size of one frame in the sprite sheet (pixels).
+const EXHAUST_PUFF_FRAME_SIZE: f32 = 8.;
+/// Position of exhaust pipe relative to the center of tank.
+const EXHAUST_OFFSET: Vec2 = Vec2::new(-18., -12.);
+
+pub struct IdleAnimationPlugin;
+
+impl Plugin for IdleAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_exhaust_puff_sheet_system)
+            .add_systems(
+                Update,
+                (
+                    setup_engine_system,
+                    antenna_sway_system,
+                    exhaust_emitter_system,
+                    exhaust_puff_system,
+                ),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Component)]
+pub struct Antenna {
+    phase: f32,
+}
+
+#[derive(Clone, Copy, Component)]
+pub struct ExhaustEmitter {
+    /// Time left before the next puff (seconds).
+    next_puff: f32,
+}
+
+#[derive(Clone, Copy, Component)]
+pub struct ExhaustPuff {
+    age: f32,
+    velocity: Vec2,
+}
+
+/// Texture atlas with frames of exhaust puff.
+#[derive(Resource)]
+struct ExhaustPuffSheet {
+    texture: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+fn load_exhaust_puff_sheet_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let layout = TextureAtlasLayout::from_grid(
+        Vec2::splat(EXHAUST_PUFF_FRAME_SIZE),
+        EXHAUST_PUFF_FRAMES,
+        1,
+        None,
+        None,
+    );
+    commands.insert_resource(ExhaustPuffSheet {
+        texture: asset_server.load("sprites/exhaust_puff.png"),
+        layout: layouts.add(layout),
+    });
+}
+
+/// Returns index of frame of the puff with given age.
+fn exhaust_puff_frame(age: f32) -> usize {
+    let frame = (age / EXHAUST_PUFF_LIFETIME * EXHAUST_PUFF_FRAMES as f32) as usize;
+    frame.min(EXHAUST_PUFF_FRAMES - 1)
+}
+
+fn setup_engine_system(mut commands: Commands, new_tanks_query: Query<Entity, Added<Tank>>) {
+    for tank_entity in new_tanks_query.iter() {
+        let antenna_line = shapes::Line(Vec2::ZERO, Vec2::new(0., ANTENNA_LENGTH));
+        let antenna_entity = commands
+            .spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&antenna_line),
+                    spatial: SpatialBundle::from_transform(Transform::from_translation(
+                        ANTENNA_OFFSET.extend(-0.05),
+                    )),
+                    ..default()
+                },
+                Stroke::new(Color::rgb(0.25, 0.25, 0.25), 1.),
+                Antenna {
+                    phase: tank_entity.index() as f32,
+                },
+                Angle(0.),
+            ))
+            .id();
+        commands
+            .entity(tank_entity)
+            .insert(ExhaustEmitter {
+                next_puff: EXHAUST_IDLE_INTERVAL,
+            })
+            .add_child(antenna_entity);
+    }
+}
+
+fn antenna_sway_system(
+    time: Res<Time>,
+    mut antenna_query: Query<(&Antenna, &Parent, &mut Angle)>,
+    moving_tanks_query: Query<(), With<TankThrowing>>,
+) {
+    let elapsed = time.elapsed_seconds();
+    for (antenna, parent, mut angle) in antenna_query.iter_mut() {
+        let amplitude = if moving_tanks_query.contains(parent.get()) {
+            ANTENNA_MOVING_SWAY
+        } else {
+            ANTENNA_IDLE_SWAY
+        };
+        angle.0 = amplitude * (elapsed * ANTENNA_SWAY_FREQUENCY * TAU + antenna.phase).sin();
+    }
+}
+
+fn exhaust_emitter_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_field: Res<GameField>,
+    sheet: Res<ExhaustPuffSheet>,
+    mut emitters_query: Query<(&mut ExhaustEmitter, &Position, Has<TankThrowing>)>,
+) {
+    let delta = time.delta_seconds();
+    for (mut emitter, &Position(tank_position), is_moving) in emitters_query.iter_mut() {
+        emitter.next_puff -= delta;
+        if emitter.next_puff > 0. {
+            continue;
+        }
+        emitter.next_puff = if is_moving {
+            EXHAUST_MOVING_INTERVAL
+        } else {
+            EXHAUST_IDLE_INTERVAL
+        };
+        spawn_exhaust_puff(
+            &mut commands,
+            &game_field,
+            &sheet,
+            tank_position + EXHAUST_OFFSET,
+        );
+    }
+}
+
+fn spawn_exhaust_puff(
+    commands: &mut Commands,
+    game_field: &GameField,
+    sheet: &ExhaustPuffSheet,
+    position: Vec2,
+) {
+    let puff_bundle = SpriteSheetBundle {
+        sprite: Sprite {
+            custom_size: Some(Vec2::splat(EXHAUST_PUFF_SIZE)),
+            ..default()
+        },
+        atlas: TextureAtlas {
+            layout: sheet.layout.clone(),
+            index: 0,
+        },
+        texture: sheet.texture.clone(),
+        transform: Transform::from_translation(position.extend(0.5)),
+        ..default()
+    };
+    let puff_entity = commands
+        .spawn((
+            puff_bundle,
+            ExhaustPuff {
+                age: 0.,
+                velocity: Vec2::new(-6., 10.),
+            },
+            Position(position),
+            Scale(1.),
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(puff_entity);
+}
+
+fn exhaust_puff_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut puffs_query: Query<(
+        Entity,
+        &mut ExhaustPuff,
+        &mut Position,
+        &mut Scale,
+        &mut TextureAtlas,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut puff, mut position, mut scale, mut atlas) in puffs_query.iter_mut() {
+        puff.age += delta;
+        if puff.age >= EXHAUST_PUFF_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let life_part = puff.age / EXHAUST_PUFF_LIFETIME;
+        position.0 += puff.velocity * delta;
+        scale.0 = 1. + 1.5 * life_part;
+        let frame = exhaust_puff_frame(puff.age);
+        if atlas.index != frame {
+            atlas.index = frame;
+        }
+    }
+}
@@ -1,45 +1,278 @@
 use std::hash::Hash;
-use std::ops::Add;
-use std::time::{Duration, Instant};
 
+use bevy::input::InputSystem;
 use bevy::prelude::*;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, Instant};
+
+use crate::ai::AiController;
+use crate::chat::is_chat_open;
+use crate::net::SpectatorSession;
+use crate::replay::ReplayPlayback;
+use crate::settings::{InputRepeatSettings, Settings};
+use crate::tank::AimingTank;
+
+/// Deflection of stick after which it is treated as pressed button.
+const STICK_THRESHOLD: f32 = 0.5;
+/// Step of aiming actions while Shift is held.
+const FINE_AIM_STEP: f32 = 0.1;
+/// Step of aiming actions while Ctrl is held.
+const COARSE_AIM_STEP: f32 = 5.0;
+/// Limit of repeats of one action during one frame.
+const MAX_REPEATS_PER_UPDATE: u32 = 10;
+
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .init_resource::<InputWithRepeating<AimAction>>()
+            .add_event::<PlayerAction>()
+            .add_systems(
+                PreUpdate,
+                (
+                    apply_repeat_settings_system.run_if(resource_changed::<Settings>),
+                    player_actions_system
+                        .run_if(not(resource_exists::<ReplayPlayback>))
+                        .run_if(not(resource_exists::<SpectatorSession>))
+                        .run_if(not(is_chat_open))
+                        .run_if(human_is_aiming),
+                )
+                    .chain()
+                    .after(InputSystem),
+            );
+    }
+}
+
+/// Aiming actions which are repeated while corresponding button is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AimAction {
+    RotateLeft,
+    RotateRight,
+    PowerUp,
+    PowerDown,
+}
+
+impl AimAction {
+    const ALL: [AimAction; 4] = [
+        AimAction::RotateLeft,
+        AimAction::RotateRight,
+        AimAction::PowerUp,
+        AimAction::PowerDown,
+    ];
+}
+
+/// Action of player produced by keyboard or gamepad.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum PlayerAction {
+    RotateGun(f32),
+    ChangePower(f32),
+    Fire,
+    /// Selects the next weapon of the tank.
+    NextWeapon,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RepeatState {
+    pressed_since: Instant,
+    next_tick: Instant,
+}
 
 #[derive(Debug, Clone, Resource)]
 pub struct InputWithRepeating<T: Eq + Hash> {
-    next_tick: HashMap<T, Instant>,
+    states: HashMap<T, RepeatState>,
+    timings: InputRepeatSettings,
 }
 
 impl<T: Eq + Hash> Default for InputWithRepeating<T> {
     fn default() -> Self {
         Self {
-            next_tick: Default::default(),
+            states: Default::default(),
+            timings: Default::default(),
         }
     }
 }
 
-impl<T> InputWithRepeating<T>
-where
-    T: Copy + Eq + Hash + Send + Sync + 'static,
-{
-    pub fn pressed(&mut self, input: &ButtonInput<T>, key_code: T) -> bool {
-        if input.pressed(key_code) {
-            let now = Instant::now();
-            if let Some(next_tick) = self.next_tick.get_mut(&key_code) {
-                if *next_tick <= now {
-                    *next_tick = now.add(Duration::from_millis(25));
-                    true
-                } else {
-                    false
-                }
-            } else {
-                self.next_tick
-                    .insert(key_code, now.add(Duration::from_millis(500)));
-                true
+impl<T: Copy + Eq + Hash> InputWithRepeating<T> {
+    pub fn set_timings(&mut self, timings: InputRepeatSettings) {
+        self.timings = timings;
+    }
+
+    /// Returns how many times the action of given key must be applied.
+    /// It is once when key is just pressed, and then repeatedly, with
+    /// shrinking interval, while key is held.
+    pub fn update(&mut self, key: T, is_pressed: bool) -> u32 {
+        self.update_at(key, is_pressed, Instant::now())
+    }
+
+    fn update_at(&mut self, key: T, is_pressed: bool, now: Instant) -> u32 {
+        if !is_pressed {
+            self.states.remove(&key);
+            return 0;
+        }
+
+        let Some(state) = self.states.get_mut(&key) else {
+            self.states.insert(
+                key,
+                RepeatState {
+                    pressed_since: now,
+                    next_tick: now + self.timings.delay,
+                },
+            );
+            return 1;
+        };
+
+        let mut repeats = 0;
+        while state.next_tick <= now {
+            if repeats == MAX_REPEATS_PER_UPDATE {
+                state.next_tick = now + self.timings.interval;
+                break;
             }
-        } else {
-            self.next_tick.remove(&key_code);
-            false
+            repeats += 1;
+            let held_time = state.next_tick - state.pressed_since;
+            state.next_tick += if held_time >= self.timings.fast_after {
+                self.timings.fast_interval
+            } else {
+                self.timings.interval
+            };
         }
+        repeats
+    }
+}
+
+fn apply_repeat_settings_system(
+    settings: Res<Settings>,
+    mut repeated_input: ResMut<InputWithRepeating<AimAction>>,
+) {
+    repeated_input.set_timings(settings.input_repeat);
+}
+
+/// Players can't control tanks of bots.
+pub(crate) fn human_is_aiming(
+    bots_query: Query<(), (With<AimingTank>, With<AiController>)>,
+) -> bool {
+    bots_query.is_empty()
+}
+
+fn player_actions_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut repeated_input: ResMut<InputWithRepeating<AimAction>>,
+    mut actions: EventWriter<PlayerAction>,
+) {
+    let step = aim_step(&keyboard_input);
+    for aim_action in AimAction::ALL {
+        let (key_code, button_type, axis_type, direction) = match aim_action {
+            AimAction::RotateLeft => (
+                KeyCode::ArrowLeft,
+                GamepadButtonType::DPadLeft,
+                GamepadAxisType::LeftStickX,
+                -1.,
+            ),
+            AimAction::RotateRight => (
+                KeyCode::ArrowRight,
+                GamepadButtonType::DPadRight,
+                GamepadAxisType::LeftStickX,
+                1.,
+            ),
+            AimAction::PowerUp => (
+                KeyCode::ArrowUp,
+                GamepadButtonType::DPadUp,
+                GamepadAxisType::LeftStickY,
+                1.,
+            ),
+            AimAction::PowerDown => (
+                KeyCode::ArrowDown,
+                GamepadButtonType::DPadDown,
+                GamepadAxisType::LeftStickY,
+                -1.,
+            ),
+        };
+
+        let is_pressed = keyboard_input.pressed(key_code)
+            || gamepads.iter().any(|gamepad| {
+                let stick_value = gamepad_axes
+                    .get(GamepadAxis::new(gamepad, axis_type))
+                    .unwrap_or_default();
+                gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type))
+                    || stick_value * direction > STICK_THRESHOLD
+            });
+
+        let repeats = repeated_input.update(aim_action, is_pressed);
+        if repeats > 0 {
+            let delta = step * repeats as f32;
+            actions.send(match aim_action {
+                AimAction::RotateLeft => PlayerAction::RotateGun(-delta),
+                AimAction::RotateRight => PlayerAction::RotateGun(delta),
+                AimAction::PowerUp => PlayerAction::ChangePower(delta),
+                AimAction::PowerDown => PlayerAction::ChangePower(-delta),
+            });
+        }
+    }
+
+    let fire_pressed = keyboard_input.just_pressed(KeyCode::Space)
+        || gamepads.iter().any(|gamepad| {
+            [
+                GamepadButtonType::RightTrigger2,
+                GamepadButtonType::RightTrigger,
+            ]
+            .into_iter()
+            .any(|button_type| {
+                gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type))
+            })
+        });
+    if fire_pressed {
+        actions.send(PlayerAction::Fire);
+    }
+
+    let next_weapon_pressed = keyboard_input.just_pressed(KeyCode::Tab)
+        || gamepads.iter().any(|gamepad| {
+            gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::North))
+        });
+    if next_weapon_pressed {
+        actions.send(PlayerAction::NextWeapon);
+    }
+}
+
+/// Returns step of aiming actions depending on pressed modifier keys.
+fn aim_step(keyboard_input: &ButtonInput<KeyCode>) -> f32 {
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        FINE_AIM_STEP
+    } else if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        COARSE_AIM_STEP
+    } else {
+        1.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_repeat_acceleration() {
+        let mut input: InputWithRepeating<u8> = Default::default();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        assert_eq!(input.update_at(1, true, at(0)), 1);
+        assert_eq!(input.update_at(1, true, at(499)), 0);
+        assert_eq!(input.update_at(1, true, at(500)), 1);
+        // 25ms interval before the key is held for 2 seconds
+        assert_eq!(input.update_at(1, true, at(600)), 4);
+        input.update_at(1, true, at(2000));
+        assert_eq!(input.update_at(1, true, at(2025)), 1);
+        // 5ms interval after that
+        assert_eq!(input.update_at(1, true, at(2055)), 6);
+        // Too many repeats are limited
+        assert_eq!(input.update_at(1, true, at(5000)), MAX_REPEATS_PER_UPDATE);
+
+        // Releasing of the key resets timings
+        assert_eq!(input.update_at(1, false, at(5001)), 0);
+        assert_eq!(input.update_at(1, true, at(5002)), 1);
+        assert_eq!(input.update_at(1, true, at(5100)), 0);
     }
 }
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+
+use crate::components::Position;
+use crate::game_field::GameField;
+use crate::grappling_hook::body_hits_landscape;
+use crate::input::PlayerAction;
+use crate::shop::{FuelCans, FUEL_CAN_TIME};
+use crate::tank::{shoot_system, AimingTank, Tank, TankSet, TankShotEvent};
+use crate::upgrades::TankUpgrades;
+use crate::weapons::{TankWeapon, WeaponKind, Weapons};
+use crate::G;
+
+/// Time (seconds) of thrust with full power of the gun.
+const MAX_FUEL: f32 = 1.5;
+/// Speed of the tank at the start of flight.
+const START_SPEED: f32 = 40.;
+/// Thrust acceleration in the direction of the gun.
+const THRUST: f32 = 3. * G;
+const TIME_SCALE: f32 = 3.;
+
+pub struct JetpackPlugin;
+
+impl Plugin for JetpackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            launch_jetpack_system
+                .after(shoot_system)
+                .in_set(TankSet::Aiming),
+        )
+        .add_systems(Update, jetpack_flight_system);
+    }
+}
+
+/// Tank flies on the jetpack in the direction of its gun. It falls down
+/// as usual after the fuel has been burnt out and the tank has reached
+/// the top of its flight.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct JetpackFlight {
+    velocity: Vec2,
+    direction: Vec2,
+    /// Time (seconds) of thrust left.
+    fuel: f32,
+}
+
+impl JetpackFlight {
+    fn new(direction: Vec2, fuel: f32) -> Self {
+        Self {
+            velocity: direction * START_SPEED,
+            direction,
+            fuel,
+        }
+    }
+
+    /// Returns velocity of the tank after `delta` seconds of flight.
+    fn accelerate(&mut self, delta: f32) -> Vec2 {
+        let thrust_time = self.fuel.min(delta);
+        self.fuel -= thrust_time;
+        self.velocity += self.direction * THRUST * thrust_time - Vec2::Y * G * delta;
+        self.velocity
+    }
+
+    #[inline]
+    fn is_finished(&self) -> bool {
+        self.fuel <= 0. && self.velocity.y <= 0.
+    }
+}
+
+/// Jetpack takes the whole turn of the tank. Direction of flight
+/// is set by the gun's angle and amount of fuel by the gun's power.
+#[allow(clippy::type_complexity)]
+fn launch_jetpack_system(
+    mut commands: Commands,
+    weapons: Res<Weapons>,
+    mut actions: EventReader<PlayerAction>,
+    mut aiming_tanks: Query<
+        (
+            Entity,
+            &Tank,
+            &TankUpgrades,
+            &mut TankWeapon,
+            Option<&mut FuelCans>,
+        ),
+        With<AimingTank>,
+    >,
+    mut shot_events: EventWriter<TankShotEvent>,
+) {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    if !fire {
+        return;
+    }
+    for (tank_entity, tank, upgrades, mut weapon, fuel_cans) in aiming_tanks.iter_mut() {
+        if weapon.ready_weapon_kind(&weapons) != WeaponKind::Jetpack {
+            continue;
+        }
+        weapon.fire(&weapons);
+        weapon.deselect();
+        let angle = tank.gun_angle_rad();
+        let direction = Vec2::new(angle.sin(), angle.cos());
+        let mut fuel = MAX_FUEL * upgrades.fuel_scale() * tank.power / 100.;
+        if let Some(mut fuel_cans) = fuel_cans.filter(|fuel_cans| fuel_cans.0 > 0) {
+            fuel_cans.0 -= 1;
+            fuel += FUEL_CAN_TIME;
+        }
+        info!(
+            "Player {} has launched jetpack with {:.2}s of fuel",
+            tank.player_number, fuel
+        );
+        commands
+            .entity(tank_entity)
+            .insert(JetpackFlight::new(direction, fuel));
+        shot_events.send(TankShotEvent { tank_entity });
+    }
+}
+
+fn jetpack_flight_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_field: Option<Res<GameField>>,
+    mut flights_query: Query<(Entity, &Tank, &mut JetpackFlight, &mut Position)>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let delta = time.delta_seconds() * TIME_SCALE;
+    for (entity, tank, mut flight, mut position) in flights_query.iter_mut() {
+        let half_size = tank.size() / 2.;
+        let max_x = game_field.width as f32 - half_size.x;
+        let max_y = game_field.height as f32 - half_size.y;
+        let velocity = flight.accelerate(delta);
+        // Move the tank pixel by pixel to stop it in front of landscape.
+        let offset = velocity * delta;
+        let steps = offset.abs().max_element().ceil().max(1.);
+        let step = offset / steps;
+        for _ in 0..steps as usize {
+            let mut new_position = position.0 + step;
+            new_position.x = new_position.x.clamp(half_size.x, max_x);
+            new_position.y = new_position.y.min(max_y);
+            if body_hits_landscape(&game_field.landscape, tank, new_position) {
+                flight.velocity = Vec2::ZERO;
+                break;
+            }
+            position.0 = new_position;
+        }
+        if flight.is_finished() {
+            debug!("Jetpack of player {} is off", tank.player_number);
+            commands
+                .entity(entity)
+                .remove::<JetpackFlight>()
+                .insert(tank.throw_down(position.0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jetpack_flight() {
+        let mut flight = JetpackFlight::new(Vec2::Y, 1.);
+        let mut height = 0.;
+        let mut max_height: f32 = 0.;
+        let delta = 0.01;
+        while !flight.is_finished() {
+            height += flight.accelerate(delta).y * delta;
+            max_height = max_height.max(height);
+        }
+        assert!(flight.fuel <= 0.);
+        // Tank is lifted up during thrust and after it by inertia.
+        assert!(max_height > START_SPEED);
+        assert!((height - max_height).abs() < 1.);
+    }
+}
@@ -1,24 +1,42 @@
-use std::time::Instant;
+use std::sync::atomic::{AtomicU16, Ordering};
 
+use bevy::math::URect;
 use bevy::prelude::*;
 use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use itertools::Itertools;
 use noise::{self, Fbm, MultiFractal, NoiseFn, Seedable};
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
 use crate::explosion::{ExplosionMaxRadiusEvent, ExplosionsFinishedEvent};
 use crate::game_field::GameField;
+use crate::heightmap::{Heightmap, HeightmapMode};
+use crate::landscape_buffer::{LandscapeBuffer, LandscapeStorage, TerrainMaterial};
+use crate::level_map::LevelMap;
 use crate::missile;
 use crate::missile::kill_missile;
 use crate::G;
 
 const TIME_SCALE: f32 = 3.0;
+/// Depth of rock under the surface as part of height of the field.
+const ROCK_DEPTH: f64 = 0.3;
+/// Depth of sand which covers hills.
+const SAND_DEPTH: usize = 12;
+/// Sand covers parts of the surface where noise is greater than it.
+const SAND_THRESHOLD: f64 = 0.2;
+/// Marks column which height hasn't been cached.
+const UNKNOWN_HEIGHT: u16 = u16::MAX;
+/// Count of columns at both sides of the column used to compute
+/// normal of the surface.
+const NORMAL_SPAN: i32 = 3;
 
 pub struct LandscapePlugin;
 
 impl Plugin for LandscapePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SubsidenceFinishedEvent>()
+            .add_event::<TerrainDestroyedEvent>()
             .add_systems(
                 Update,
                 (
@@ -29,7 +47,11 @@ impl Plugin for LandscapePlugin {
             )
             .add_systems(
                 PostUpdate,
-                (update_landscape_system, update_landscape_texture_system).chain(),
+                (
+                    update_landscape_system,
+                    update_landscape_texture_system.run_if(resource_exists::<Assets<Image>>),
+                )
+                    .chain(),
             );
     }
 }
@@ -37,64 +59,184 @@ impl Plugin for LandscapePlugin {
 #[derive(Event)]
 pub struct SubsidenceFinishedEvent;
 
+/// Pixels of landscape have been destroyed by explosion.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TerrainDestroyedEvent {
+    /// Tank which has caused the explosion.
+    pub owner: Option<Entity>,
+    pub pixels: usize,
+}
+
+/// Operation which has changed pixels of the landscape. Subsidence isn't
+/// logged as an operation, so applying the same operations to the same
+/// landscape gives the same pixels only if no subsidence has happened
+/// in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LandscapeOp {
+    DestroyCircle {
+        x: i32,
+        y: i32,
+        radius: i32,
+    },
+    ClearLine {
+        x: i32,
+        y: i32,
+        length: u16,
+    },
+    FillLine {
+        x: i32,
+        y: i32,
+        length: u16,
+    },
+    /// Previous height of the column is kept to undo the operation.
+    SetSurfaceHeight {
+        x: i32,
+        height: u16,
+        prev_height: u16,
+    },
+}
+
 #[derive(Debug)]
 pub struct Landscape {
     width: u16,
     height: u16,
-    buffer: Vec<u8>,
+    buffer: LandscapeBuffer,
     texture_handle: Handle<Image>,
     noise: Fbm,
     amplitude: f64,
     pub dx: f64,
-    changed: bool,
-    subsidence_started: Option<Instant>,
+    // Region of pixels changed since the last update of texture.
+    // Coordinates of rows are counted from top to bottom, `max` is exclusive.
+    dirty_rect: Option<URect>,
+    // Time (seconds) passed since start of subsidence.
+    subsidence_time: Option<f32>,
     // Last position of virtual pixel of landscape on the way of it falling.
     // Used for calculate speed of fall.
     subsidence_last_pos: u32,
     // "Skip" and "take" used for optimize process of landscape subsidence.
     subsidence_skip: usize,
     subsidence_take: usize,
+    // Operations applied since the landscape has been generated.
+    ops: Vec<LandscapeOp>,
+    // Minimal height of slope under which pixels slide sideways while subsidence.
+    slide_drop: Option<usize>,
+    // Steps of subsidence made since the last call of `take_drop_steps()`.
+    drop_steps: u32,
+    // Cached heights of the surface of columns, they are reset
+    // when pixels of columns change.
+    surface_heights: Vec<AtomicU16>,
 }
 
 #[derive(Component)]
 pub struct LandscapeSprite;
 
 impl Landscape {
-    pub fn new(width: u16, height: u16, textures: &mut Assets<Image>) -> Result<Self, String> {
+    /// Creates landscape which keeps its pixels in given kind of storage.
+    /// The same `seed` always gives the same landscape.
+    pub fn new(
+        width: u16,
+        height: u16,
+        seed: u64,
+        storage: LandscapeStorage,
+        textures: Option<&mut Assets<Image>>,
+    ) -> Result<Self, String> {
         if width.min(height) == 0 {
             return Err("'width' and 'height' must be greater than 0".into());
         }
 
         let stride = width as usize;
         let res_size = stride * height as usize;
-        let texture = Image::new(
-            Extent3d {
-                width: width as u32,
-                height: height as u32,
-                depth_or_array_layers: 1,
-            },
-            TextureDimension::D2,
-            vec![0u8; res_size * 4],
-            TextureFormat::Rgba8UnormSrgb,
-            Default::default(),
-        );
-
-        let mut rng = rand::thread_rng();
+        // The game running without rendering has no textures
+        let texture_handle = textures
+            .map(|textures| {
+                textures.add(Image::new(
+                    Extent3d {
+                        width: width as u32,
+                        height: height as u32,
+                        depth_or_array_layers: 1,
+                    },
+                    TextureDimension::D2,
+                    vec![0u8; res_size * 4],
+                    TextureFormat::Rgba8UnormSrgb,
+                    Default::default(),
+                ))
+            })
+            .unwrap_or_default();
+
+        let mut rng = StdRng::seed_from_u64(seed);
         let mut landscape = Self {
             width,
             height,
-            buffer: vec![0; res_size],
-            texture_handle: textures.add(texture),
+            buffer: LandscapeBuffer::new(stride, height as usize, storage),
+            texture_handle,
             amplitude: f64::from(height) / 2.,
             dx: rng.gen_range(0.0..width as f64 / 2.),
             noise: Self::create_noise(width, rng.gen()),
-            changed: true,
-            subsidence_started: None,
+            dirty_rect: None,
+            subsidence_time: None,
             subsidence_last_pos: 0,
             subsidence_skip: 0,
             subsidence_take: stride,
+            ops: Vec::new(),
+            slide_drop: None,
+            drop_steps: 0,
+            surface_heights: (0..width).map(|_| AtomicU16::new(UNKNOWN_HEIGHT)).collect(),
         };
         landscape.generate();
+        debug!(
+            "Landscape {}x{} ({:?}) takes {} bytes",
+            width,
+            height,
+            storage,
+            landscape.buffer.memory_size()
+        );
+        Ok(landscape)
+    }
+
+    /// Creates landscape from the hand-made level
+    /// stretched to the given size.
+    pub fn from_level_map(
+        level: &LevelMap,
+        width: u16,
+        height: u16,
+        storage: LandscapeStorage,
+        textures: Option<&mut Assets<Image>>,
+    ) -> Result<Self, String> {
+        let mut landscape = Self::new(width, height, 0, storage, textures)?;
+        landscape.buffer.clear_materials();
+        landscape.set_surface_heights(&level.scaled_heights(width, height));
+        landscape.ops.clear();
+        Ok(landscape)
+    }
+
+    /// Creates landscape from the heightmap stretched to the given size.
+    pub fn from_heightmap(
+        heightmap: &Heightmap,
+        width: u16,
+        height: u16,
+        storage: LandscapeStorage,
+        textures: Option<&mut Assets<Image>>,
+    ) -> Result<Self, String> {
+        let mut landscape = Self::new(width, height, 0, storage, textures)?;
+        landscape.buffer.clear_materials();
+        match heightmap.mode {
+            HeightmapMode::Threshold => {
+                for x in 0..width as usize {
+                    for row in 0..height as usize {
+                        let is_ground = heightmap.is_ground(x, row, width, height);
+                        landscape.buffer.set(x, row, is_ground);
+                    }
+                }
+                landscape.mark_dirty(URect::new(0, 0, width as u32, height as u32));
+            }
+            HeightmapMode::Columns => {
+                let heights: Vec<u16> = (0..width as usize)
+                    .map(|x| heightmap.column_height(x, width, height))
+                    .collect();
+                landscape.set_surface_heights(&heights);
+                landscape.ops.clear();
+            }
+        }
         Ok(landscape)
     }
 
@@ -110,22 +252,56 @@ impl Landscape {
         self.texture_handle.clone()
     }
 
-    pub fn set_seed(&mut self, seed: u32) {
-        self.noise = Self::create_noise(self.width, seed);
+    #[inline]
+    pub fn changed(&self) -> bool {
+        self.dirty_rect.is_some()
     }
 
-    pub fn seed(&self) -> u32 {
-        self.noise.seed()
+    /// Adds given region (`max` is exclusive) into the region of changed pixels.
+    fn mark_dirty(&mut self, rect: URect) {
+        let columns = rect.min.x as usize..(rect.max.x as usize).min(self.width as usize);
+        for height in &mut self.surface_heights[columns] {
+            *height.get_mut() = UNKNOWN_HEIGHT;
+        }
+        self.dirty_rect = Some(match self.dirty_rect {
+            Some(dirty_rect) => dirty_rect.union(rect),
+            None => rect,
+        });
     }
 
-    #[inline]
-    pub fn changed(&self) -> bool {
-        self.changed
+    /// Returns region of pixels changed since the previous call
+    /// of this method.
+    pub fn take_dirty_rect(&mut self) -> Option<URect> {
+        self.dirty_rect.take()
+    }
+
+    /// Returns checksum of pixels of the landscape.
+    pub fn checksum(&self) -> u64 {
+        self.buffer.checksum()
     }
 
+    /// Returns color of pixel in the texture of landscape.
     #[inline]
-    pub fn set_changed(&mut self) {
-        self.changed = true;
+    fn pixel_color(&self, x: usize, row: usize) -> u32 {
+        if self.buffer.get(x, row) {
+            self.buffer.material(x, row).color()
+        } else {
+            0
+        }
+    }
+
+    /// Returns colors of all pixels row by row from the top one.
+    pub fn pixel_colors(&self) -> Vec<u32> {
+        (0..self.height as usize)
+            .flat_map(|row| (0..self.width as usize).map(move |x| (x, row)))
+            .map(|(x, row)| self.pixel_color(x, row))
+            .collect()
+    }
+
+    /// Returns count of steps of subsidence made since the previous
+    /// call of this method. Pixels fall by one row every step.
+    pub fn take_drop_steps(&mut self) -> u32 {
+        std::mem::take(&mut self.drop_steps)
     }
 
     #[inline]
@@ -133,63 +309,235 @@ impl Landscape {
         (self.width, self.height)
     }
 
+    /// Pixels slide sideways while subsidence until slopes are not steeper
+    /// than given angle (degrees). Only integer slopes may be made of
+    /// pixels, so angles below 45 degrees act as 45 degrees.
+    /// Pixels only fall down if the angle is `None`.
+    pub fn set_angle_of_repose(&mut self, angle: Option<f32>) {
+        self.slide_drop = angle.map(|angle| {
+            let max_slope = angle.clamp(45., 89.).to_radians().tan();
+            // Rounding keeps integer slopes like 45 degrees exact.
+            let max_slope = (max_slope * 1000.).round() / 1000.;
+            max_slope.floor() as usize + 1
+        });
+    }
+
+    /// Returns operations applied since the landscape has been generated.
+    #[inline]
+    pub fn ops(&self) -> &[LandscapeOp] {
+        &self.ops
+    }
+
+    /// Applies the operation received from peer or loaded from file.
+    /// Returns count of changed pixels, or change of height for columns.
+    pub fn apply(&mut self, op: LandscapeOp) -> usize {
+        match op {
+            LandscapeOp::DestroyCircle { x, y, radius } => {
+                self.destroy_circle(Vec2::new(x as f32, y as f32), radius)
+            }
+            LandscapeOp::ClearLine { x, y, length } => self.clear_pixels_line((x, y), length),
+            LandscapeOp::FillLine { x, y, length } => self.fill_pixels_line((x, y), length),
+            LandscapeOp::SetSurfaceHeight { x, height, .. } => {
+                let prev_height = self.surface_height(x);
+                self.set_surface_height(x, height);
+                (height as i32 - prev_height as i32).unsigned_abs() as usize
+            }
+        }
+    }
+
+    /// Reverts operations applied after the first `len` operations.
+    /// Only changes of height of columns can be reverted, so reverting
+    /// stops at other operations. Returns `true` if all operations
+    /// have been reverted.
+    pub fn undo_to(&mut self, len: usize) -> bool {
+        while self.ops.len() > len {
+            let Some(&LandscapeOp::SetSurfaceHeight { x, prev_height, .. }) = self.ops.last()
+            else {
+                return false;
+            };
+            self.ops.pop();
+            self.fill_column(x, prev_height);
+        }
+        true
+    }
+
     pub fn generate(&mut self) {
-        let stride = self.width as usize;
+        let height = self.height as usize;
         let y_center: f64 = f64::from(self.height) / 2.;
+        // Materials are taken from other, not correlated, lines of noise.
+        let (rock_line, sand_line) = (f64::from(self.width), 2. * f64::from(self.width));
+        self.buffer.clear_materials();
 
         for x in 0..self.width {
             let sx = f64::from(x) + self.dx;
             let value = self.noise.get([sx, 0.]) * self.amplitude;
-            let y = (y_center + value).round().max(0.) as usize;
-            let y = y.min(self.height as usize);
-            let index = y * stride + (x as usize);
+            // Index of the top filled row in this column
+            let top_row = (y_center + value).round().max(0.) as usize;
+            let top_row = top_row.min(height);
+            for row in 0..height {
+                self.buffer.set(x as usize, row, row >= top_row);
+            }
 
-            if y > 0 {
+            let rock_depth = ROCK_DEPTH + 0.1 * self.noise.get([sx, rock_line]);
+            let rock_row = top_row + (rock_depth * height as f64).max(0.) as usize;
+            for row in rock_row.min(height)..height {
                 self.buffer
-                    .iter_mut()
-                    .skip(x as usize)
-                    .step_by(stride)
-                    .take(y)
-                    .for_each(|v| *v = 0);
+                    .set_material(x as usize, row, TerrainMaterial::Rock);
+            }
+            if self.noise.get([sx, sand_line]) > SAND_THRESHOLD {
+                for row in top_row..(top_row + SAND_DEPTH).min(rock_row).min(height) {
+                    self.buffer
+                        .set_material(x as usize, row, TerrainMaterial::Sand);
+                }
             }
-
-            self.buffer
-                .iter_mut()
-                .skip(index)
-                .step_by(stride)
-                .for_each(|v| *v = 1);
         }
+        self.mark_dirty(URect::new(0, 0, self.width as u32, self.height as u32));
+        self.ops.clear();
     }
 
     #[inline]
-    fn index(&self, x: i32, y: i32) -> usize {
+    fn row(&self, y: i32) -> usize {
         // Point (0, 0) located in left bottom corner
-        ((self.height as i32 - y - 1) * self.width as i32 + x) as usize
+        (self.height as i32 - y - 1) as usize
     }
 
-    /// Get mutable slice with row of pixels given length
-    pub fn get_pixels_line_mut(&mut self, point: (i32, i32), length: u16) -> Option<&mut [u8]> {
+    #[inline]
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+    }
+
+    /// Returns count of empty pixels in the row of pixels given length.
+    pub fn count_empty_pixels_in_line(&self, point: (i32, i32), length: u16) -> Option<usize> {
         let (x, y) = point;
-        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 || length == 0 {
+        if !self.contains(x, y) || length == 0 {
             return None;
         }
-        let index = self.index(x, y);
-        let length = (self.width as i32 - x).min(length as i32) as usize;
-        Some(&mut self.buffer[index..index + length])
+        Some(
+            self.buffer
+                .count_empty(x as usize, self.row(y), length as usize),
+        )
+    }
+
+    /// Clears the row of pixels given length.
+    /// Returns count of pixels that were not empty.
+    pub fn clear_pixels_line(&mut self, point: (i32, i32), length: u16) -> usize {
+        let cleared = self.clear_line(point, length);
+        if cleared > 0 {
+            let (x, y) = point;
+            self.ops.push(LandscapeOp::ClearLine { x, y, length });
+        }
+        cleared
+    }
+
+    fn clear_line(&mut self, point: (i32, i32), length: u16) -> usize {
+        let (x, y) = point;
+        if !self.contains(x, y) || length == 0 {
+            return 0;
+        }
+        let row = self.row(y);
+        let cleared = self.buffer.clear(x as usize, row, length as usize);
+        if cleared > 0 {
+            let right = (x as u32 + length as u32).min(self.width as u32);
+            self.mark_dirty(URect::new(x as u32, row as u32, right, row as u32 + 1));
+        }
+        cleared
+    }
+
+    /// Fills the row of pixels given length.
+    /// Returns count of pixels that were empty.
+    pub fn fill_pixels_line(&mut self, point: (i32, i32), length: u16) -> usize {
+        let (x, y) = point;
+        if !self.contains(x, y) || length == 0 {
+            return 0;
+        }
+        let row = self.row(y);
+        let filled = self.buffer.fill(x as usize, row, length as usize);
+        if filled > 0 {
+            let right = (x as u32 + length as u32).min(self.width as u32);
+            self.mark_dirty(URect::new(x as u32, row as u32, right, row as u32 + 1));
+            self.ops.push(LandscapeOp::FillLine { x, y, length });
+        }
+        filled
     }
 
     pub fn is_not_empty(&self, x: i32, y: i32) -> bool {
-        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
-            return false;
+        self.contains(x, y) && self.buffer.get(x as usize, self.row(y))
+    }
+
+    /// Returns the first filled pixel on the line from one point
+    /// to another, both ends are included.
+    pub fn raycast(&self, from: Vec2, to: Vec2) -> Option<IVec2> {
+        let (from, to) = (from.as_ivec2(), to.as_ivec2());
+        line_drawing::Bresenham::new((from.x, from.y), (to.x, to.y))
+            .find(|&(x, y)| self.is_not_empty(x, y))
+            .map(IVec2::from)
+    }
+
+    /// Returns count of rows from the bottom to the top filled pixel
+    /// of the column (inclusive).
+    pub fn surface_height(&self, x: i32) -> u16 {
+        if !self.contains(x, 0) {
+            return 0;
+        }
+        let cached = &self.surface_heights[x as usize];
+        let height = cached.load(Ordering::Relaxed);
+        if height != UNKNOWN_HEIGHT {
+            return height;
+        }
+        let height = (0..self.height as usize)
+            .find(|&row| self.buffer.get(x as usize, row))
+            .map_or(0, |row| self.height - row as u16);
+        cached.store(height, Ordering::Relaxed);
+        height
+    }
+
+    /// Returns unit normal of the surface of landscape at the column,
+    /// it is computed from heights of neighbouring columns.
+    pub fn surface_normal(&self, x: i32) -> Vec2 {
+        let max_x = self.width as i32 - 1;
+        let (left, right) = ((x - NORMAL_SPAN).max(0), (x + NORMAL_SPAN).min(max_x));
+        if left >= right {
+            return Vec2::Y;
+        }
+        let rise = self.surface_height(right) as f32 - self.surface_height(left) as f32;
+        Vec2::new(-rise, (right - left) as f32).normalize()
+    }
+
+    /// Fills the column of pixels up to the given height
+    /// and clears pixels above it.
+    pub fn set_surface_height(&mut self, x: i32, height: u16) {
+        if !self.contains(x, 0) {
+            return;
+        }
+        let prev_height = self.surface_height(x);
+        self.fill_column(x, height);
+        self.ops.push(LandscapeOp::SetSurfaceHeight {
+            x,
+            height,
+            prev_height,
+        });
+    }
+
+    fn fill_column(&mut self, x: i32, height: u16) {
+        let top_row = self.height.saturating_sub(height) as usize;
+        for row in 0..self.height as usize {
+            self.buffer.set(x as usize, row, row >= top_row);
+        }
+        let x = x as u32;
+        self.mark_dirty(URect::new(x, 0, x + 1, self.height as u32));
+    }
+
+    /// Replaces the landscape by the given heights of columns.
+    pub fn set_surface_heights(&mut self, heights: &[u16]) {
+        for (x, &height) in heights.iter().enumerate().take(self.width as usize) {
+            self.set_surface_height(x as i32, height);
         }
-        let index = self.index(x, y);
-        self.buffer[index] > 0
     }
 
     pub fn subsidence(&mut self) {
-        if self.subsidence_started.is_none() {
+        if self.subsidence_time.is_none() {
             debug!("Start subsidence");
-            self.subsidence_started = Some(Instant::now());
+            self.subsidence_time = Some(0.);
         }
         self.subsidence_last_pos = 0;
         self.subsidence_skip = 0;
@@ -197,72 +545,106 @@ impl Landscape {
     }
 
     pub fn is_subsidence(&self) -> bool {
-        self.subsidence_started.is_some()
+        self.subsidence_time.is_some()
     }
 
+    /// Advances subsidence by given count of seconds.
     /// Returns `true` if current subsidence has finished.
-    pub fn update(&mut self) -> bool {
-        if let Some(subsidence_started) = self.subsidence_started {
-            let time = subsidence_started.elapsed().as_secs_f32();
+    pub fn update(&mut self, delta: f32) -> bool {
+        if let Some(time) = self.subsidence_time.as_mut() {
+            *time += delta;
+            let time = *time;
             let subsidence_cur_pos = (G * time * time * TIME_SCALE).round() as u32;
             let delta = subsidence_cur_pos - self.subsidence_last_pos;
             self.subsidence_last_pos = subsidence_cur_pos;
-            let stride = self.width as usize;
 
             for _ in 0..delta {
-                let mut changed = false;
-                let mut cur_row_index = stride * self.height as usize;
-                let mut left_changed_pos: usize = self.subsidence_take;
-                let mut right_changed_pos = 0;
-
-                for _ in 1..self.height {
-                    cur_row_index -= stride;
-                    let (top_rows, current_row) = self.buffer.split_at_mut(cur_row_index);
-                    let (_, top_row) = top_rows.split_at_mut(cur_row_index - stride);
-                    let pixels_for_change = top_row
-                        .iter_mut()
-                        .zip(current_row)
-                        .skip(self.subsidence_skip)
-                        .take(self.subsidence_take)
-                        .enumerate()
-                        .filter(|(_, (&mut top_pixel, &mut cur_pixel))| {
-                            cur_pixel == 0 && top_pixel != 0
-                        });
-                    let min_max = pixels_for_change
-                        .map(|(i, (top_pixel, cur_pixel))| {
-                            *cur_pixel = *top_pixel;
-                            *top_pixel = 0;
-                            i
-                        })
-                        .minmax();
-
-                    if let Some((min, max)) = min_max.into_option() {
-                        changed = true;
-                        left_changed_pos = left_changed_pos.min(min);
-                        right_changed_pos = right_changed_pos.max(max);
-                    };
+                let (skip, take) = (self.subsidence_skip, self.subsidence_take);
+                // Columns are dropped independently, but pixels sliding sideways
+                // move between columns, so they are processed after dropping.
+                let mut changed = self.buffer.drop_rows(skip, take);
+                for cur_row in (1..self.height as usize).rev() {
+                    let slide_min_max = self.buffer.slide_pixels(
+                        cur_row,
+                        skip.saturating_sub(1),
+                        skip.saturating_add(take + 1),
+                        self.slide_drop,
+                    );
+                    if let Some((left, right)) = slide_min_max {
+                        let rect = URect::new(
+                            left as u32,
+                            cur_row as u32 - 1,
+                            right as u32 + 1,
+                            cur_row as u32 + 1,
+                        );
+                        changed = Some(changed.map_or(rect, |changed| changed.union(rect)));
+                    }
                 }
 
-                self.subsidence_skip += left_changed_pos;
-                self.subsidence_take = right_changed_pos + 1;
-
-                if changed {
-                    self.changed = true;
-                } else {
+                self.drop_steps += 1;
+                let Some(changed) = changed else {
                     debug!("Subsidence has end");
-                    self.subsidence_started = None;
+                    self.subsidence_time = None;
                     return true;
-                }
+                };
+                self.mark_dirty(changed);
+                self.subsidence_skip = changed.min.x as usize;
+                self.subsidence_take = changed.width() as usize;
             }
         }
 
         false
     }
 
-    pub fn destroy_circle(&mut self, position: Vec2, radius: i32) {
-        let mut landscape_changed = false;
-        let circle =
-            line_drawing::BresenhamCircle::new(position.x as i32, position.y as i32, radius - 1);
+    /// Clears pixels of the row which don't resist the explosion
+    /// with given center and radius. Returns count of cleared pixels.
+    fn clear_line_by_blast(
+        &mut self,
+        point: (i32, i32),
+        length: u16,
+        center: Vec2,
+        radius: f32,
+    ) -> usize {
+        if !self.buffer.has_materials() {
+            return self.clear_line(point, length);
+        }
+        let (x, y) = point;
+        if !self.contains(x, y) || length == 0 {
+            return 0;
+        }
+        let row = self.row(y);
+        let right = (x + length as i32).min(self.width as i32);
+        let mut cleared = 0;
+        for px in x..right {
+            if !self.buffer.get(px as usize, row) {
+                continue;
+            }
+            let resistance = self.buffer.material(px as usize, row).blast_resistance();
+            let distance = Vec2::new(px as f32, y as f32).distance(center);
+            if resistance > 0. && distance > radius * (1. - resistance) {
+                continue;
+            }
+            self.buffer.set(px as usize, row, false);
+            cleared += 1;
+        }
+        if cleared > 0 {
+            self.mark_dirty(URect::new(
+                x as u32,
+                row as u32,
+                right as u32,
+                row as u32 + 1,
+            ));
+        }
+        cleared
+    }
+
+    /// Returns count of destroyed pixels.
+    pub fn destroy_circle(&mut self, position: Vec2, radius: i32) -> usize {
+        let (center_x, center_y) = (position.x as i32, position.y as i32);
+        // Logged operation keeps integer center, so it is used everywhere.
+        let center = Vec2::new(center_x as f32, center_y as f32);
+        let circle = line_drawing::BresenhamCircle::new(center_x, center_y, radius - 1);
+        let mut destroyed = 0;
         for points_iter in &circle.chunks(4) {
             let points: Vec<(i32, i32)> = points_iter.step_by(2).collect();
             if points.len() != 2 {
@@ -276,36 +658,27 @@ impl Landscape {
                 continue;
             }
             for &y in [y1, y2].iter() {
-                if let Some(pixels) = self.get_pixels_line_mut((x, y), len) {
-                    let changed_count: u32 = pixels
-                        .iter_mut()
-                        .map(|c| {
-                            if *c == 0 {
-                                0
-                            } else {
-                                *c = 0;
-                                1
-                            }
-                        })
-                        .sum();
-                    if changed_count > 0 {
-                        landscape_changed = true;
-                    }
-                }
+                destroyed += self.clear_line_by_blast((x, y), len, center, radius as f32);
             }
         }
-        if landscape_changed {
-            self.set_changed();
+        if destroyed > 0 {
+            self.ops.push(LandscapeOp::DestroyCircle {
+                x: center_x,
+                y: center_y,
+                radius,
+            });
         }
+        destroyed
     }
 }
 
 pub fn update_landscape_system(
+    time: Res<Time>,
     mut game_field: ResMut<GameField>,
     mut finished_event: EventWriter<SubsidenceFinishedEvent>,
 ) {
     let landscape = &mut game_field.landscape;
-    if landscape.update() {
+    if landscape.update(time.delta_seconds()) {
         finished_event.send(SubsidenceFinishedEvent);
     }
 }
@@ -315,50 +688,23 @@ pub fn update_landscape_texture_system(
     mut game_field: ResMut<GameField>,
 ) {
     let landscape = &mut game_field.landscape;
-    if landscape.changed() {
-        if let Some(texture) = textures.get_mut(&landscape.texture_handle) {
-            let buf = unsafe { texture.data.align_to_mut::<u32>().1 };
-            for (&v, d) in landscape.buffer.iter().zip(buf) {
-                *d = if v == 0 { 0 } else { 0xff_40_71_9c } // 0xff_cf_bd_00
-            }
-            landscape.changed = false;
-        }
+    if !landscape.changed() {
+        return;
     }
-}
-
-pub fn scroll_landscape(
-    time: Res<Time>,
-    keyboard_input: ResMut<ButtonInput<KeyCode>>,
-    mut game_field: ResMut<GameField>,
-) {
-    let landscape = &mut game_field.landscape;
-
-    const SPEED: f64 = 1.;
-    let mut changed = false;
-
-    if keyboard_input.pressed(KeyCode::ArrowLeft) {
-        landscape.dx -= SPEED;
-        changed = true;
-    }
-    if keyboard_input.pressed(KeyCode::ArrowRight) {
-        landscape.dx += SPEED;
-        changed = true;
-    }
-
-    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
-        let seed = landscape.seed().wrapping_add(1);
-        landscape.set_seed(seed);
-        changed = true;
-    }
-    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
-        let seed = landscape.seed().wrapping_sub(1);
-        landscape.set_seed(seed);
-        changed = true;
-    }
-
-    if changed {
-        landscape.generate();
-        landscape.set_changed();
+    let Some(texture) = textures.get_mut(&landscape.texture_handle) else {
+        return;
+    };
+    let Some(rect) = landscape.take_dirty_rect() else {
+        return;
+    };
+    // Only changed region of texture is updated
+    let stride = landscape.width as usize;
+    let buf = unsafe { texture.data.align_to_mut::<u32>().1 };
+    for row in rect.min.y as usize..rect.max.y as usize {
+        let row_start = row * stride;
+        for x in rect.min.x as usize..rect.max.x as usize {
+            buf[row_start + x] = landscape.pixel_color(x, row);
+        }
     }
 }
 
@@ -382,10 +728,17 @@ pub fn check_missile_collides_with_landscape_system(
 fn destroy_by_explosion_system(
     mut game_field: ResMut<GameField>,
     mut radius_events: EventReader<ExplosionMaxRadiusEvent>,
+    mut destroyed_events: EventWriter<TerrainDestroyedEvent>,
 ) {
     let landscape = &mut game_field.landscape;
     for event in radius_events.read() {
-        landscape.destroy_circle(event.position, event.max_radius as i32)
+        let pixels = landscape.destroy_circle(event.position, event.max_radius as i32);
+        if pixels > 0 {
+            destroyed_events.send(TerrainDestroyedEvent {
+                owner: event.owner,
+                pixels,
+            });
+        }
     }
 }
 
@@ -397,3 +750,144 @@ fn run_subsidence_after_explosions_system(
         game_field.landscape.subsidence();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_surface_normal() {
+        let mut landscape = Landscape::new(100, 100, 3, LandscapeStorage::default(), None).unwrap();
+        let heights: Vec<u16> = (0..100).map(|x| if x < 50 { 40 } else { x - 10 }).collect();
+        landscape.set_surface_heights(&heights);
+        assert_eq!(landscape.surface_normal(20), Vec2::Y);
+        assert_eq!(landscape.surface_normal(-5), Vec2::Y);
+        // Slope of 45 degrees rising to the right.
+        let normal = landscape.surface_normal(70);
+        assert!(normal.abs_diff_eq(Vec2::new(-1., 1.).normalize(), 1e-6));
+
+        // Cached height changes together with pixels.
+        assert_eq!(landscape.surface_height(20), 40);
+        landscape.destroy_circle(Vec2::new(20., 40.), 10);
+        assert_eq!(landscape.surface_height(20), 31);
+        assert!(landscape.surface_normal(15).x > 0.);
+    }
+
+    #[test]
+    fn test_raycast() {
+        let mut landscape = Landscape::new(100, 100, 3, LandscapeStorage::default(), None).unwrap();
+        landscape.set_surface_heights(&[50; 100]);
+        let sky = Vec2::new(10., 90.);
+        assert_eq!(
+            landscape.raycast(sky, Vec2::new(10., 0.)),
+            Some(IVec2::new(10, 49))
+        );
+        assert_eq!(
+            landscape.raycast(Vec2::new(90., 90.), sky),
+            None,
+            "Line above the surface"
+        );
+        // The ray goes from its start.
+        assert_eq!(
+            landscape.raycast(Vec2::new(0., 0.), sky),
+            Some(IVec2::new(0, 0))
+        );
+        // Points outside of the field are empty.
+        assert_eq!(
+            landscape.raycast(Vec2::new(-20., 60.), Vec2::new(20., 40.)),
+            Some(IVec2::new(2, 49))
+        );
+    }
+
+    #[test]
+    fn test_ops_log() {
+        let new_landscape =
+            || Landscape::new(200, 100, 3, LandscapeStorage::default(), None).unwrap();
+        let mut landscape = new_landscape();
+        assert!(landscape.destroy_circle(Vec2::new(100., 50.), 20) > 0);
+        landscape.fill_pixels_line((10, 90), 30);
+        assert!(landscape.ops().len() >= 2);
+
+        let mut replica = new_landscape();
+        for &op in landscape.ops() {
+            replica.apply(op);
+        }
+        assert_eq!(replica.checksum(), landscape.checksum());
+
+        let checksum = landscape.checksum();
+        let mark = landscape.ops().len();
+        landscape.set_surface_height(50, 10);
+        landscape.set_surface_height(51, 99);
+        assert_ne!(landscape.checksum(), checksum);
+        assert!(landscape.undo_to(mark));
+        assert_eq!(landscape.checksum(), checksum);
+        // Destruction can't be reverted.
+        assert!(!landscape.undo_to(0));
+    }
+
+    #[test]
+    fn test_rock_resists_explosions() {
+        let mut landscape = Landscape::new(100, 100, 3, LandscapeStorage::default(), None).unwrap();
+        landscape.set_surface_heights(&[100; 100]);
+        let dirt_destroyed = landscape.destroy_circle(Vec2::new(30., 50.), 20);
+        for x in 50..100 {
+            for row in 0..100 {
+                landscape.buffer.set_material(x, row, TerrainMaterial::Rock);
+            }
+        }
+        let rock_destroyed = landscape.destroy_circle(Vec2::new(75., 50.), 20);
+        assert!(rock_destroyed > 0);
+        assert!(rock_destroyed < dirt_destroyed / 2);
+    }
+
+    #[test]
+    fn test_angle_of_repose() {
+        let mut heights = [5; 60];
+        heights[30] = 40;
+        let pixels: u16 = heights.iter().sum();
+        let subside = |angle: Option<f32>| {
+            let mut landscape =
+                Landscape::new(60, 60, 3, LandscapeStorage::default(), None).unwrap();
+            landscape.buffer.clear_materials();
+            landscape.set_surface_heights(&heights);
+            landscape.set_angle_of_repose(angle);
+            landscape.subsidence();
+            while !landscape.update(0.05) {}
+            (0..60)
+                .map(|x| landscape.surface_height(x))
+                .collect::<Vec<_>>()
+        };
+        // Columns only fall down, so the tower stays.
+        assert_eq!(subside(None), heights);
+
+        let slope = subside(Some(45.));
+        assert_eq!(slope.iter().sum::<u16>(), pixels);
+        assert!(slope.windows(2).all(|w| w[0].abs_diff(w[1]) <= 1));
+        let steep_slope = subside(Some(65.));
+        assert_eq!(steep_slope.iter().sum::<u16>(), pixels);
+        assert!(steep_slope.windows(2).all(|w| w[0].abs_diff(w[1]) <= 2));
+        assert!(steep_slope[30] > slope[30]);
+    }
+
+    /// Run by `cargo test --release -- --ignored --nocapture bench_subsidence`
+    #[test]
+    #[ignore]
+    fn bench_subsidence() {
+        for storage in [LandscapeStorage::Bytes, LandscapeStorage::Bits] {
+            let mut landscape = Landscape::new(4096, 2160, 1, storage, None).unwrap();
+            for x in (100..4000).step_by(150) {
+                let y = landscape.surface_height(x) as f32 - 40.;
+                landscape.destroy_circle(Vec2::new(x as f32, y), 80);
+            }
+            let started = std::time::Instant::now();
+            landscape.subsidence();
+            while !landscape.update(0.01) {}
+            println!(
+                "{:?}: {} bytes, subsidence takes {:?}",
+                storage,
+                landscape.buffer.memory_size(),
+                started.elapsed()
+            );
+        }
+    }
+}
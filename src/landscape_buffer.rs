@@ -0,0 +1,654 @@
+use bevy::math::{URect, UVec2};
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+
+/// Kind of storage of landscape pixels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LandscapeStorage {
+    /// One byte per pixel.
+    Bytes,
+    /// One bit per pixel. Takes 8 times less memory than `Bytes`
+    /// and processes 64 pixels of row at once.
+    #[default]
+    Bits,
+}
+
+/// Material of filled pixel of landscape.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum TerrainMaterial {
+    #[default]
+    Dirt = 0,
+    /// Resists explosions, only pixels close to the center
+    /// of explosion are destroyed.
+    Rock = 1,
+    /// Flows sideways while subsidence.
+    Sand = 2,
+}
+
+impl TerrainMaterial {
+    fn from_id(id: u8) -> Self {
+        match id {
+            1 => Self::Rock,
+            2 => Self::Sand,
+            _ => Self::Dirt,
+        }
+    }
+
+    /// Color of pixel in RGBA format with reversed order of bytes.
+    pub fn color(self) -> u32 {
+        match self {
+            Self::Dirt => 0xff_40_71_9c,
+            Self::Rock => 0xff_6e_6e_74,
+            Self::Sand => 0xff_80_c4_dc,
+        }
+    }
+
+    /// Part of radius of explosion in which pixels stay intact.
+    pub fn blast_resistance(self) -> f32 {
+        match self {
+            Self::Rock => 0.5,
+            _ => 0.,
+        }
+    }
+}
+
+const WORD_BITS: usize = u64::BITS as usize;
+/// Minimal width of band of columns which are dropped in parallel.
+const MIN_BAND_WIDTH: usize = 512;
+/// Sand slides from slopes steeper than 45 degrees.
+const SAND_DROP: usize = 2;
+
+#[derive(Debug, Clone)]
+enum BufferData {
+    Bytes(Vec<u8>),
+    /// Every row is aligned to whole count of words.
+    Bits {
+        words: Vec<u64>,
+        row_words: usize,
+    },
+}
+
+/// Buffer with pixels of landscape. Each pixel is either filled or empty.
+/// Rows are counted from top to bottom.
+#[derive(Debug, Clone)]
+pub struct LandscapeBuffer {
+    width: usize,
+    height: usize,
+    data: BufferData,
+    /// Ids of materials of pixels. It is allocated only when
+    /// a material other than dirt is used.
+    materials: Option<Vec<u8>>,
+}
+
+impl LandscapeBuffer {
+    pub fn new(width: usize, height: usize, storage: LandscapeStorage) -> Self {
+        let data = match storage {
+            LandscapeStorage::Bytes => BufferData::Bytes(vec![0; width * height]),
+            LandscapeStorage::Bits => {
+                let row_words = width.div_ceil(WORD_BITS);
+                BufferData::Bits {
+                    words: vec![0; row_words * height],
+                    row_words,
+                }
+            }
+        };
+        Self {
+            width,
+            height,
+            data,
+            materials: None,
+        }
+    }
+
+    /// Size of memory occupied by pixels in bytes.
+    pub fn memory_size(&self) -> usize {
+        let materials_size = self.materials.as_ref().map_or(0, |m| m.len());
+        materials_size
+            + match &self.data {
+                BufferData::Bytes(bytes) => bytes.len(),
+                BufferData::Bits { words, .. } => words.len() * std::mem::size_of::<u64>(),
+            }
+    }
+
+    #[inline]
+    pub fn has_materials(&self) -> bool {
+        self.materials.is_some()
+    }
+
+    #[inline]
+    pub fn material(&self, x: usize, row: usize) -> TerrainMaterial {
+        self.materials
+            .as_ref()
+            .map_or(TerrainMaterial::Dirt, |materials| {
+                TerrainMaterial::from_id(materials[row * self.width + x])
+            })
+    }
+
+    pub fn set_material(&mut self, x: usize, row: usize, material: TerrainMaterial) {
+        if self.materials.is_none() && material == TerrainMaterial::Dirt {
+            return;
+        }
+        let materials = self
+            .materials
+            .get_or_insert_with(|| vec![0; self.width * self.height]);
+        materials[row * self.width + x] = material as u8;
+    }
+
+    /// Turns all pixels into dirt.
+    pub fn clear_materials(&mut self) {
+        self.materials = None;
+    }
+
+    #[inline]
+    pub fn get(&self, x: usize, row: usize) -> bool {
+        match &self.data {
+            BufferData::Bytes(bytes) => bytes[row * self.width + x] != 0,
+            BufferData::Bits { words, row_words } => {
+                words[row * row_words + x / WORD_BITS] & (1 << (x % WORD_BITS)) != 0
+            }
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, row: usize, value: bool) {
+        match &mut self.data {
+            BufferData::Bytes(bytes) => bytes[row * self.width + x] = value as u8,
+            BufferData::Bits { words, row_words } => {
+                let word = &mut words[row * *row_words + x / WORD_BITS];
+                let mask = 1 << (x % WORD_BITS);
+                if value {
+                    *word |= mask;
+                } else {
+                    *word &= !mask;
+                }
+            }
+        }
+    }
+
+    /// Returns words of the row if pixels are stored as bits.
+    /// Bits beyond the width of buffer are always empty.
+    #[inline]
+    pub fn row_words(&self, row: usize) -> Option<&[u64]> {
+        match &self.data {
+            BufferData::Bytes(_) => None,
+            BufferData::Bits { words, row_words } => {
+                Some(&words[row * row_words..(row + 1) * row_words])
+            }
+        }
+    }
+
+    /// Returns checksum of pixels which doesn't depend on kind of storage.
+    pub fn checksum(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        for row in 0..self.height {
+            if let Some(materials) = &self.materials {
+                let row_start = row * self.width;
+                hash = fnv1a(hash, &materials[row_start..row_start + self.width]);
+            }
+            if let Some(words) = self.row_words(row) {
+                hash = words
+                    .iter()
+                    .fold(hash, |hash, word| fnv1a(hash, &word.to_le_bytes()));
+                continue;
+            }
+            for word_start in (0..self.width).step_by(WORD_BITS) {
+                let word_end = (word_start + WORD_BITS).min(self.width);
+                let word = (word_start..word_end)
+                    .filter(|&x| self.get(x, row))
+                    .fold(0u64, |word, x| word | 1 << (x - word_start));
+                hash = fnv1a(hash, &word.to_le_bytes());
+            }
+        }
+        hash
+    }
+
+    /// Returns count of empty pixels in the part of row.
+    pub fn count_empty(&self, x: usize, row: usize, length: usize) -> usize {
+        let end = (x + length).min(self.width);
+        match &self.data {
+            BufferData::Bytes(bytes) => {
+                let start = row * self.width;
+                bytecount::count(&bytes[start + x..start + end], 0)
+            }
+            BufferData::Bits { words, row_words } => {
+                let row_start = row * row_words;
+                let filled: usize = word_masks(x, end)
+                    .map(|(i, mask)| (words[row_start + i] & mask).count_ones() as usize)
+                    .sum();
+                end - x - filled
+            }
+        }
+    }
+
+    /// Clears the part of row and returns count of cleared pixels.
+    pub fn clear(&mut self, x: usize, row: usize, length: usize) -> usize {
+        let end = (x + length).min(self.width);
+        match &mut self.data {
+            BufferData::Bytes(bytes) => {
+                let start = row * self.width;
+                bytes[start + x..start + end]
+                    .iter_mut()
+                    .map(|c| std::mem::take(c).min(1) as usize)
+                    .sum()
+            }
+            BufferData::Bits { words, row_words } => {
+                let row_start = row * *row_words;
+                word_masks(x, end)
+                    .map(|(i, mask)| {
+                        let word = &mut words[row_start + i];
+                        let cleared = (*word & mask).count_ones() as usize;
+                        *word &= !mask;
+                        cleared
+                    })
+                    .sum()
+            }
+        }
+    }
+
+    /// Fills the part of row by dirt and returns count of pixels
+    /// which were empty.
+    pub fn fill(&mut self, x: usize, row: usize, length: usize) -> usize {
+        let end = (x + length).min(self.width);
+        if let Some(materials) = self.materials.as_mut() {
+            let start = row * self.width;
+            materials[start + x..start + end].fill(TerrainMaterial::Dirt as u8);
+        }
+        match &mut self.data {
+            BufferData::Bytes(bytes) => {
+                let start = row * self.width;
+                bytes[start + x..start + end]
+                    .iter_mut()
+                    .map(|c| (std::mem::replace(c, 1) == 0) as usize)
+                    .sum()
+            }
+            BufferData::Bits { words, row_words } => {
+                let row_start = row * *row_words;
+                word_masks(x, end)
+                    .map(|(i, mask)| {
+                        let word = &mut words[row_start + i];
+                        let filled = (!*word & mask).count_ones() as usize;
+                        *word |= mask;
+                        filled
+                    })
+                    .sum()
+            }
+        }
+    }
+
+    /// Moves filled pixels of every row into empty pixels of the row
+    /// below it, rows are processed from bottom to top. Only pixels
+    /// in range `skip..skip + take` are processed. Wide ranges are split
+    /// into bands of columns which are processed in parallel.
+    ///
+    /// Returns region of changed pixels (`max` is exclusive).
+    pub fn drop_rows(&mut self, skip: usize, take: usize) -> Option<URect> {
+        let threads = ComputeTaskPool::get_or_init(TaskPool::default).thread_num();
+        let bands = (take.min(self.width) / MIN_BAND_WIDTH).clamp(1, threads.max(1));
+        self.drop_rows_in_bands(skip, take, bands)
+    }
+
+    fn drop_rows_in_bands(&mut self, skip: usize, take: usize, bands: usize) -> Option<URect> {
+        let end = skip.saturating_add(take).min(self.width);
+        if skip >= end || self.height < 2 {
+            return None;
+        }
+        // Bands of bits are aligned to whole words, so different bands
+        // never share a word.
+        let align = match self.data {
+            BufferData::Bytes(_) => 1,
+            BufferData::Bits { .. } => WORD_BITS,
+        };
+        let first = skip / align * align;
+        let band_width = (end - first).div_ceil(bands).next_multiple_of(align);
+        let mut bounds: Vec<usize> = (first..end).step_by(band_width).collect();
+        bounds.push(end);
+        // The first column of band and range of processed columns relative to it.
+        let ranges: Vec<(usize, usize, usize)> = bounds
+            .windows(2)
+            .map(|b| (b[0], skip.max(b[0]) - b[0], b[1] - b[0]))
+            .collect();
+
+        let width = self.width;
+        let materials: Vec<Option<Vec<&mut [u8]>>> = match self.materials.as_mut() {
+            Some(materials) => split_into_bands(materials, width, &bounds)
+                .into_iter()
+                .map(Some)
+                .collect(),
+            None => ranges.iter().map(|_| None).collect(),
+        };
+        let regions = match &mut self.data {
+            BufferData::Bytes(bytes) => {
+                let tasks = split_into_bands(bytes, width, &bounds)
+                    .into_iter()
+                    .zip(materials)
+                    .zip(ranges)
+                    .map(|((mut rows, mut materials), (start, from, to))| {
+                        move || {
+                            drop_bytes_band(&mut rows, materials.as_deref_mut(), from, to)
+                                .map(|rect| offset_rect(rect, start))
+                        }
+                    });
+                run_in_parallel(tasks.collect())
+            }
+            BufferData::Bits { words, row_words } => {
+                let word_bounds: Vec<usize> =
+                    bounds.iter().map(|b| b.div_ceil(WORD_BITS)).collect();
+                let tasks = split_into_bands(words, *row_words, &word_bounds)
+                    .into_iter()
+                    .zip(materials)
+                    .zip(ranges)
+                    .map(|((mut rows, mut materials), (start, from, to))| {
+                        move || {
+                            drop_bits_band(&mut rows, materials.as_deref_mut(), from, to)
+                                .map(|rect| offset_rect(rect, start))
+                        }
+                    });
+                run_in_parallel(tasks.collect())
+            }
+        };
+        regions.into_iter().flatten().reduce(|a, b| a.union(b))
+    }
+
+    /// Moves pixels of the row above given one, which can't fall down,
+    /// into empty neighbour pixels of given row. A pixel slides if the
+    /// neighbour column is lower by at least `min_drop` pixels, sand
+    /// slides if it is lower by 2 pixels. Only pixels in range
+    /// `start..end` are processed.
+    ///
+    /// Returns min and max columns of changed pixels.
+    pub fn slide_pixels(
+        &mut self,
+        row: usize,
+        start: usize,
+        end: usize,
+        min_drop: Option<usize>,
+    ) -> Option<(usize, usize)> {
+        debug_assert!(row > 0 && row < self.height);
+        if min_drop.is_none() && self.materials.is_none() {
+            return None;
+        }
+        let end = end.min(self.width);
+        let mut min_max: Option<(usize, usize)> = None;
+        for x in start..end {
+            if !self.get(x, row - 1) || !self.get(x, row) {
+                continue;
+            }
+            let drop = match (self.material(x, row - 1), min_drop) {
+                (TerrainMaterial::Sand, drop) => drop.map_or(SAND_DROP, |d| d.min(SAND_DROP)),
+                (_, Some(drop)) => drop,
+                (_, None) => continue,
+            };
+            let is_lower = |to: usize| {
+                to < self.width
+                    && row - 1 + drop <= self.height
+                    && (row - 1..row - 1 + drop).all(|r| !self.get(to, r))
+            };
+            // Sides are alternated between rows to keep slopes symmetric.
+            let sides = if row.is_multiple_of(2) {
+                [x.wrapping_sub(1), x + 1]
+            } else {
+                [x + 1, x.wrapping_sub(1)]
+            };
+            let Some(to) = sides.into_iter().find(|&to| is_lower(to)) else {
+                continue;
+            };
+            let material = self.material(x, row - 1);
+            self.set(x, row - 1, false);
+            self.set(to, row, true);
+            self.set_material(to, row, material);
+            let (left, right) = (x.min(to), x.max(to));
+            min_max =
+                Some(min_max.map_or((left, right), |(min, max)| (min.min(left), max.max(right))));
+        }
+        min_max
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continues FNV-1a hash with given bytes.
+pub fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Returns FNV-1a hash of bytes.
+pub fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    fnv1a(FNV_OFFSET_BASIS, bytes)
+}
+
+/// Splits rows of data into bands by given bounds of columns.
+/// Returns rows of every band.
+fn split_into_bands<'a, T>(
+    data: &'a mut [T],
+    row_len: usize,
+    bounds: &[usize],
+) -> Vec<Vec<&'a mut [T]>> {
+    let mut bands: Vec<Vec<&mut [T]>> = bounds.windows(2).map(|_| Vec::new()).collect();
+    let (first, last) = (bounds[0], bounds[bounds.len() - 1]);
+    for row in data.chunks_mut(row_len) {
+        let mut rest = &mut row[first..last];
+        for (band, b) in bands.iter_mut().zip(bounds.windows(2)) {
+            let (part, tail) = std::mem::take(&mut rest).split_at_mut(b[1] - b[0]);
+            band.push(part);
+            rest = tail;
+        }
+    }
+    bands
+}
+
+fn run_in_parallel<T: Send + 'static>(tasks: Vec<impl FnOnce() -> T + Send>) -> Vec<T> {
+    if tasks.len() < 2 {
+        return tasks.into_iter().map(|task| task()).collect();
+    }
+    ComputeTaskPool::get_or_init(TaskPool::default).scope(|scope| {
+        for task in tasks {
+            scope.spawn(async move { task() });
+        }
+    })
+}
+
+fn offset_rect(rect: URect, x: usize) -> URect {
+    let offset = UVec2::new(x as u32, 0);
+    URect::from_corners(rect.min + offset, rect.max + offset)
+}
+
+/// Returns region of pixels changed by moving of pixel
+/// from the row above given one.
+fn moved_rect(row: usize, min: usize, max: usize) -> URect {
+    URect::new(min as u32, row as u32 - 1, max as u32 + 1, row as u32 + 1)
+}
+
+fn move_material(materials: &mut [&mut [u8]], row: usize, x: usize) {
+    let (top_rows, rows) = materials.split_at_mut(row);
+    rows[0][x] = top_rows[row - 1][x];
+}
+
+/// Drops pixels of band of bytes in range `from..to` of its columns.
+fn drop_bytes_band(
+    rows: &mut [&mut [u8]],
+    mut materials: Option<&mut [&mut [u8]]>,
+    from: usize,
+    to: usize,
+) -> Option<URect> {
+    let mut region: Option<URect> = None;
+    for row in (1..rows.len()).rev() {
+        let (top_rows, current_rows) = rows.split_at_mut(row);
+        let (top_row, current_row) = (&mut *top_rows[row - 1], &mut *current_rows[0]);
+        let mut min_max: Option<(usize, usize)> = None;
+        for x in from..to {
+            if current_row[x] == 0 && top_row[x] != 0 {
+                current_row[x] = std::mem::take(&mut top_row[x]);
+                if let Some(materials) = materials.as_deref_mut() {
+                    move_material(materials, row, x);
+                }
+                min_max = Some(min_max.map_or((x, x), |(min, _)| (min, x)));
+            }
+        }
+        if let Some((min, max)) = min_max {
+            let rect = moved_rect(row, min, max);
+            region = Some(region.map_or(rect, |region| region.union(rect)));
+        }
+    }
+    region
+}
+
+/// Drops pixels of band of words in range `from..to` of its bits.
+fn drop_bits_band(
+    rows: &mut [&mut [u64]],
+    mut materials: Option<&mut [&mut [u8]]>,
+    from: usize,
+    to: usize,
+) -> Option<URect> {
+    let mut region: Option<URect> = None;
+    for row in (1..rows.len()).rev() {
+        let (top_rows, current_rows) = rows.split_at_mut(row);
+        let (top_row, current_row) = (&mut *top_rows[row - 1], &mut *current_rows[0]);
+        let mut min_max: Option<(usize, usize)> = None;
+        for (i, mask) in word_masks(from, to) {
+            let moved = top_row[i] & !current_row[i] & mask;
+            if moved == 0 {
+                continue;
+            }
+            current_row[i] |= moved;
+            top_row[i] &= !moved;
+            if let Some(materials) = materials.as_deref_mut() {
+                let mut moved_bits = moved;
+                while moved_bits != 0 {
+                    let x = i * WORD_BITS + moved_bits.trailing_zeros() as usize;
+                    move_material(materials, row, x);
+                    moved_bits &= moved_bits - 1;
+                }
+            }
+            let first = i * WORD_BITS + moved.trailing_zeros() as usize;
+            let last = i * WORD_BITS + (WORD_BITS - 1 - moved.leading_zeros() as usize);
+            min_max = Some(min_max.map_or((first, last), |(min, _)| (min, last)));
+        }
+        if let Some((min, max)) = min_max {
+            let rect = moved_rect(row, min, max);
+            region = Some(region.map_or(rect, |region| region.union(rect)));
+        }
+    }
+    region
+}
+
+/// Returns iterator with indexes of words and bit masks
+/// which cover bits in range `start..end`.
+fn word_masks(start: usize, end: usize) -> impl Iterator<Item = (usize, u64)> {
+    let first_word = start / WORD_BITS;
+    let last_word = end.div_ceil(WORD_BITS);
+    (first_word..last_word).map(move |i| {
+        let word_start = i * WORD_BITS;
+        let from = start.max(word_start) - word_start;
+        let to = end.min(word_start + WORD_BITS) - word_start;
+        let mask = if to - from == WORD_BITS {
+            u64::MAX
+        } else {
+            ((1u64 << (to - from)) - 1) << from
+        };
+        (i, mask)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixels(buffer: &LandscapeBuffer) -> impl Iterator<Item = bool> + '_ {
+        (0..buffer.height).flat_map(move |row| (0..buffer.width).map(move |x| buffer.get(x, row)))
+    }
+
+    fn fill_pattern(buffer: &mut LandscapeBuffer) {
+        for row in 0..buffer.height {
+            for x in 0..buffer.width {
+                buffer.set(x, row, (x * 7 + row * 13) % 5 < 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_storages_are_equivalent() {
+        let (width, height) = (150, 20);
+        let mut bytes = LandscapeBuffer::new(width, height, LandscapeStorage::Bytes);
+        let mut bits = LandscapeBuffer::new(width, height, LandscapeStorage::Bits);
+        fill_pattern(&mut bytes);
+        fill_pattern(&mut bits);
+        assert!(pixels(&bytes).eq(pixels(&bits)));
+        assert_eq!(bytes.checksum(), bits.checksum());
+
+        for (x, row, length) in [(0, 0, 150), (3, 5, 70), (60, 7, 10), (140, 9, 100)] {
+            assert_eq!(
+                bytes.count_empty(x, row, length),
+                bits.count_empty(x, row, length)
+            );
+        }
+        assert_eq!(bytes.clear(10, 3, 100), bits.clear(10, 3, 100));
+        assert!(pixels(&bytes).eq(pixels(&bits)));
+        assert_eq!(bytes.fill(5, 3, 70), bits.fill(5, 3, 70));
+        assert_eq!(bytes.fill(60, 8, 100), bits.fill(60, 8, 100));
+        assert!(pixels(&bytes).eq(pixels(&bits)));
+
+        for (skip, take) in [(0, 150), (5, 100), (63, 2), (100, 1000)] {
+            assert_eq!(
+                bytes.drop_rows(skip, take),
+                bits.drop_rows(skip, take),
+                "skip={skip}, take={take}"
+            );
+            assert!(pixels(&bytes).eq(pixels(&bits)));
+        }
+    }
+
+    #[test]
+    fn test_parallel_drop_is_equal_to_serial() {
+        for storage in [LandscapeStorage::Bytes, LandscapeStorage::Bits] {
+            let mut serial = LandscapeBuffer::new(1500, 40, storage);
+            fill_pattern(&mut serial);
+            for x in (0..1500).step_by(3) {
+                serial.set_material(x, 5, TerrainMaterial::Rock);
+            }
+            let mut parallel = serial.clone();
+            for (skip, take) in [(0, 1500), (70, 1000), (300, 5000), (0, 1500)] {
+                assert_eq!(
+                    serial.drop_rows_in_bands(skip, take, 1),
+                    parallel.drop_rows_in_bands(skip, take, 4),
+                    "storage={storage:?}, skip={skip}, take={take}"
+                );
+                assert!(pixels(&serial).eq(pixels(&parallel)));
+                assert_eq!(serial.checksum(), parallel.checksum());
+            }
+        }
+    }
+
+    #[test]
+    fn test_materials() {
+        for storage in [LandscapeStorage::Bytes, LandscapeStorage::Bits] {
+            let mut buffer = LandscapeBuffer::new(10, 4, storage);
+            assert!(!buffer.has_materials());
+            buffer.set(3, 2, true);
+            buffer.set_material(3, 2, TerrainMaterial::Sand);
+            buffer.set(3, 3, true);
+            buffer.set(7, 0, true);
+            buffer.set_material(7, 0, TerrainMaterial::Rock);
+            assert!(buffer.has_materials());
+
+            // Rock falls down, sand flows sideways from the dirt.
+            assert_eq!(buffer.drop_rows(0, 10), Some(URect::new(7, 0, 8, 2)));
+            assert_eq!(buffer.material(7, 1), TerrainMaterial::Rock);
+            assert_eq!(buffer.slide_pixels(3, 0, 10, None), Some((3, 4)));
+            assert!(!buffer.get(3, 2) && buffer.get(4, 3));
+            assert_eq!(buffer.material(4, 3), TerrainMaterial::Sand);
+            assert_eq!(buffer.slide_pixels(3, 0, 10, None), None);
+
+            buffer.fill(0, 1, 10);
+            assert_eq!(buffer.material(7, 1), TerrainMaterial::Dirt);
+        }
+    }
+
+    #[test]
+    fn test_bits_take_less_memory() {
+        let bytes = LandscapeBuffer::new(8192, 100, LandscapeStorage::Bytes);
+        let bits = LandscapeBuffer::new(8192, 100, LandscapeStorage::Bits);
+        assert_eq!(bytes.memory_size(), 8 * bits.memory_size());
+    }
+}
@@ -0,0 +1,458 @@
+//! Simulation of landscape by compute shaders for large maps.
+//!
+//! Landscape on CPU stays the authoritative one: collisions, network
+//! game, replays and desync detection depend on it. Instead of uploading
+//! of changed pixels into the texture every frame, explosions and steps
+//! of subsidence are repeated by compute shaders on a copy of pixels
+//! kept on GPU, which is copied into the texture of landscape.
+//! GPU doesn't slide pixels down slopes and draws circles of explosions
+//! a bit differently, so pixels from CPU are uploaded again when
+//! subsidence has finished and after other changes of landscape.
+use std::borrow::Cow;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{self, RenderGraph, RenderLabel};
+use bevy::render::render_resource::binding_types::{
+    storage_buffer_read_only_sized, storage_buffer_sized, uniform_buffer_sized,
+};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderContext, RenderDevice, RenderQueue};
+use bevy::render::{Render, RenderApp, RenderSet};
+
+use crate::game_field::GameField;
+use crate::landscape::{
+    update_landscape_system, update_landscape_texture_system, LandscapeOp, SubsidenceFinishedEvent,
+};
+use crate::landscape_buffer::TerrainMaterial;
+use crate::settings::Settings;
+
+const SHADER_PATH: &str = "shaders/landscape_simulation.wgsl";
+/// Rows of buffer copied into texture must be aligned to 256 bytes.
+const STRIDE_ALIGN: u32 = 256 / 4;
+
+/// Enabled by `Settings::gpu_landscape`, so settings must be
+/// inserted before adding of the plugin.
+pub struct LandscapeGpuPlugin;
+
+impl Plugin for LandscapeGpuPlugin {
+    fn build(&self, app: &mut App) {
+        let enabled = app
+            .world
+            .get_resource::<Settings>()
+            .is_some_and(|settings| settings.gpu_landscape);
+        if !enabled {
+            return;
+        }
+        app.init_resource::<GpuLandscape>()
+            .add_plugins(ExtractResourcePlugin::<GpuLandscape>::default())
+            .add_systems(
+                PostUpdate,
+                queue_gpu_landscape_system
+                    .after(update_landscape_system)
+                    .before(update_landscape_texture_system),
+            );
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            warn!("Landscape can't be simulated on GPU without rendering");
+            return;
+        };
+        render_app.init_resource::<LandscapePixels>().add_systems(
+            Render,
+            prepare_landscape_bind_group_system.in_set(RenderSet::PrepareBindGroups),
+        );
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node(LandscapeSimulationLabel, LandscapeSimulationNode);
+        render_graph.add_node_edge(
+            LandscapeSimulationLabel,
+            bevy::render::graph::CameraDriverLabel,
+        );
+    }
+
+    fn finish(&self, app: &mut App) {
+        if !app.world.contains_resource::<GpuLandscape>() {
+            return;
+        }
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<LandscapePipeline>();
+        }
+    }
+}
+
+/// Changes of landscape made in the current frame.
+#[derive(Debug, Default, Clone, Resource, ExtractResource)]
+pub struct GpuLandscape {
+    image: AssetId<Image>,
+    width: u32,
+    height: u32,
+    /// Colors of all pixels which replace pixels on GPU.
+    upload: Option<Arc<Vec<u32>>>,
+    /// Circles of explosions: x, row and radius.
+    circles: Vec<IVec4>,
+    /// Steps of subsidence.
+    steps: u32,
+}
+
+/// State of landscape which has been sent to GPU.
+#[derive(Default)]
+struct SyncedLandscape {
+    image: AssetId<Image>,
+    ops_len: usize,
+}
+
+/// Returns circles of explosions of the operations applied after the first
+/// `synced_len` ones, or `None` if GPU can't repeat these operations.
+fn gpu_circles(ops: &[LandscapeOp], synced_len: usize, height: u16) -> Option<Vec<IVec4>> {
+    ops.get(synced_len..)?
+        .iter()
+        .map(|op| match *op {
+            LandscapeOp::DestroyCircle { x, y, radius } => {
+                Some(IVec4::new(x, height as i32 - y - 1, radius, 0))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn queue_gpu_landscape_system(
+    game_field: Option<ResMut<GameField>>,
+    mut gpu_landscape: ResMut<GpuLandscape>,
+    mut synced: Local<SyncedLandscape>,
+    mut finished_events: EventReader<SubsidenceFinishedEvent>,
+) {
+    let subsidence_finished = finished_events.read().count() > 0;
+    let Some(mut game_field) = game_field else {
+        *gpu_landscape = GpuLandscape::default();
+        return;
+    };
+    let landscape = &mut game_field.landscape;
+    // Texture is updated by GPU.
+    landscape.take_dirty_rect();
+    let steps = landscape.take_drop_steps();
+    let (width, height) = landscape.size();
+    let image = landscape.texture_handle().id();
+    let circles = gpu_circles(landscape.ops(), synced.ops_len, height)
+        .filter(|_| image == synced.image && !subsidence_finished);
+    *synced = SyncedLandscape {
+        image,
+        ops_len: landscape.ops().len(),
+    };
+    *gpu_landscape = match circles {
+        Some(circles) => GpuLandscape {
+            image,
+            width: width as u32,
+            height: height as u32,
+            upload: None,
+            circles,
+            steps,
+        },
+        None => GpuLandscape {
+            image,
+            width: width as u32,
+            height: height as u32,
+            upload: Some(Arc::new(landscape.pixel_colors())),
+            circles: Vec::new(),
+            steps: 0,
+        },
+    };
+}
+
+/// Uniform of shaders, its layout matches `Params` of the shader.
+#[derive(Debug, Clone, Copy)]
+struct LandscapeParams {
+    width: u32,
+    height: u32,
+    stride: u32,
+    steps: u32,
+    circles_count: u32,
+    rock_color: u32,
+    rock_resistance: f32,
+}
+
+impl LandscapeParams {
+    /// Size of uniform padded to 16 bytes.
+    const SIZE: u64 = 32;
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = [
+            self.width,
+            self.height,
+            self.stride,
+            self.steps,
+            self.circles_count,
+            self.rock_color,
+            self.rock_resistance.to_bits(),
+        ]
+        .iter()
+        .flat_map(|value| value.to_ne_bytes())
+        .collect();
+        bytes.resize(Self::SIZE as usize, 0);
+        bytes
+    }
+}
+
+#[derive(Resource)]
+struct LandscapePipeline {
+    layout: BindGroupLayout,
+    destroy: CachedComputePipelineId,
+    subside: CachedComputePipelineId,
+}
+
+impl FromWorld for LandscapePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "landscape_simulation_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    uniform_buffer_sized(false, NonZeroU64::new(LandscapeParams::SIZE)),
+                    storage_buffer_sized(false, None),
+                    storage_buffer_read_only_sized(false, None),
+                ),
+            ),
+        );
+        let shader = world.resource::<AssetServer>().load(SHADER_PATH);
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let queue_pipeline = |entry_point: &'static str| {
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label: Some(format!("landscape_{}_pipeline", entry_point).into()),
+                layout: vec![layout.clone()],
+                push_constant_ranges: Vec::new(),
+                shader: shader.clone(),
+                shader_defs: Vec::new(),
+                entry_point: Cow::from(entry_point),
+            })
+        };
+        let destroy = queue_pipeline("destroy");
+        let subside = queue_pipeline("subside");
+        Self {
+            layout,
+            destroy,
+            subside,
+        }
+    }
+}
+
+/// Pixels of landscape on GPU, they live between frames.
+#[derive(Default, Resource)]
+struct LandscapePixels {
+    buffer: Option<Buffer>,
+    image: AssetId<Image>,
+    /// Pixels waiting until texture of landscape is ready.
+    pending_upload: Option<Arc<Vec<u32>>>,
+}
+
+/// Work of GPU in the current frame.
+#[derive(Resource)]
+struct LandscapeBindGroup {
+    bind_group: BindGroup,
+    pixels: Buffer,
+    image: AssetId<Image>,
+    width: u32,
+    height: u32,
+    circles_count: u32,
+    steps: u32,
+}
+
+/// Returns count of pixels in a row of buffer which may be copied into texture.
+fn padded_stride(width: u32) -> u32 {
+    width.div_ceil(STRIDE_ALIGN) * STRIDE_ALIGN
+}
+
+fn prepare_landscape_bind_group_system(
+    mut commands: Commands,
+    gpu_landscape: Option<Res<GpuLandscape>>,
+    pipeline: Res<LandscapePipeline>,
+    mut pixels: ResMut<LandscapePixels>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    commands.remove_resource::<LandscapeBindGroup>();
+    let Some(gpu_landscape) = gpu_landscape.filter(|landscape| landscape.width > 0) else {
+        return;
+    };
+    let (width, height) = (gpu_landscape.width, gpu_landscape.height);
+    let stride = padded_stride(width);
+    if gpu_landscape.upload.is_some() {
+        pixels.pending_upload.clone_from(&gpu_landscape.upload);
+    }
+    if images.get(gpu_landscape.image).is_none() {
+        return;
+    }
+    if let Some(colors) = pixels.pending_upload.take() {
+        let mut contents = vec![0u8; (stride * height) as usize * 4];
+        for (row, colors) in colors.chunks_exact(width as usize).enumerate() {
+            let row_start = row * stride as usize * 4;
+            for (x, color) in colors.iter().enumerate() {
+                let start = row_start + x * 4;
+                contents[start..start + 4].copy_from_slice(&color.to_ne_bytes());
+            }
+        }
+        let buffer = match pixels.buffer.take() {
+            Some(buffer) if buffer.size() == contents.len() as u64 => {
+                render_queue.write_buffer(&buffer, 0, &contents);
+                buffer
+            }
+            _ => render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("landscape_pixels_buffer"),
+                contents: &contents,
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            }),
+        };
+        pixels.buffer = Some(buffer);
+        pixels.image = gpu_landscape.image;
+    } else if gpu_landscape.circles.is_empty() && gpu_landscape.steps == 0 {
+        return;
+    }
+    let Some(pixels_buffer) = pixels
+        .buffer
+        .clone()
+        .filter(|_| pixels.image == gpu_landscape.image)
+    else {
+        return;
+    };
+
+    let rock = TerrainMaterial::Rock;
+    let params = LandscapeParams {
+        width,
+        height,
+        stride,
+        steps: gpu_landscape.steps,
+        circles_count: gpu_landscape.circles.len() as u32,
+        rock_color: rock.color(),
+        rock_resistance: rock.blast_resistance(),
+    };
+    let params = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("landscape_params_buffer"),
+        contents: &params.to_bytes(),
+        usage: BufferUsages::UNIFORM,
+    });
+    // Empty storage buffer can't be bound.
+    let circles: Vec<u8> = gpu_landscape
+        .circles
+        .iter()
+        .chain(gpu_landscape.circles.is_empty().then_some(&IVec4::ZERO))
+        .flat_map(|circle| circle.to_array())
+        .flat_map(|value| value.to_ne_bytes())
+        .collect();
+    let circles = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("landscape_circles_buffer"),
+        contents: &circles,
+        usage: BufferUsages::STORAGE,
+    });
+    let bind_group = render_device.create_bind_group(
+        "landscape_simulation_bind_group",
+        &pipeline.layout,
+        &BindGroupEntries::sequential((
+            params.as_entire_binding(),
+            pixels_buffer.as_entire_binding(),
+            circles.as_entire_binding(),
+        )),
+    );
+    commands.insert_resource(LandscapeBindGroup {
+        bind_group,
+        pixels: pixels_buffer,
+        image: gpu_landscape.image,
+        width,
+        height,
+        circles_count: gpu_landscape.circles.len() as u32,
+        steps: gpu_landscape.steps,
+    });
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct LandscapeSimulationLabel;
+
+struct LandscapeSimulationNode;
+
+impl render_graph::Node for LandscapeSimulationNode {
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let Some(work) = world.get_resource::<LandscapeBindGroup>() else {
+            return Ok(());
+        };
+        let Some(image) = world.resource::<RenderAssets<Image>>().get(work.image) else {
+            return Ok(());
+        };
+        let pipeline = world.resource::<LandscapePipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let destroy = pipeline_cache.get_compute_pipeline(pipeline.destroy);
+        let subside = pipeline_cache.get_compute_pipeline(pipeline.subside);
+        {
+            let mut pass = render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor::default());
+            pass.set_bind_group(0, &work.bind_group, &[]);
+            // Pixels are only copied into texture while shaders are compiled.
+            if let Some(destroy) = destroy.filter(|_| work.circles_count > 0) {
+                pass.set_pipeline(destroy);
+                pass.dispatch_workgroups(work.width.div_ceil(8), work.height.div_ceil(8), 1);
+            }
+            if let Some(subside) = subside.filter(|_| work.steps > 0) {
+                pass.set_pipeline(subside);
+                pass.dispatch_workgroups(work.width.div_ceil(64), 1, 1);
+            }
+        }
+        render_context.command_encoder().copy_buffer_to_texture(
+            ImageCopyBuffer {
+                buffer: &work.pixels,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_stride(work.width) * 4),
+                    rows_per_image: None,
+                },
+            },
+            image.texture.as_image_copy(),
+            Extent3d {
+                width: work.width,
+                height: work.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpu_circles() {
+        let ops = [
+            LandscapeOp::DestroyCircle {
+                x: 10,
+                y: 20,
+                radius: 5,
+            },
+            LandscapeOp::DestroyCircle {
+                x: 30,
+                y: 0,
+                radius: 8,
+            },
+            LandscapeOp::FillLine {
+                x: 0,
+                y: 0,
+                length: 3,
+            },
+        ];
+        assert_eq!(
+            gpu_circles(&ops[..2], 0, 100),
+            Some(vec![IVec4::new(10, 79, 5, 0), IVec4::new(30, 99, 8, 0)])
+        );
+        assert_eq!(gpu_circles(&ops[..2], 2, 100), Some(vec![]));
+        // Filled pixels and reverted operations are uploaded from CPU.
+        assert_eq!(gpu_circles(&ops, 1, 100), None);
+        assert_eq!(gpu_circles(&ops[..1], 2, 100), None);
+
+        assert_eq!(padded_stride(64), 64);
+        assert_eq!(padded_stride(1000), 1024);
+    }
+}
@@ -0,0 +1,466 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::ai::AiDifficulty;
+use crate::chassis::Chassis;
+use crate::environment::TerrainTheme;
+use crate::heightmap::HeightmapMode;
+use crate::rules::{EconomyRules, GameMode, GameRules, TeamRules, MIN_TEAMS_COUNT};
+use crate::settings::{FieldSize, HudLayout, Settings, UiScaleFactor, MAX_UI_SCALE, MIN_UI_SCALE};
+use crate::{DEFAULT_PLAYERS_COUNT, MAX_PLAYERS_COUNT};
+
+pub const USAGE: &str = "Usage: bevy_tank_war [--players <2-8>] [--ai <count>] [--seed <number>] \
+    [--map temperate|arctic|desert] [--rules standard|practice|range|blitz|chaos|shop] \
+    [--campaign] [--fullscreen] [--chassis <light|medium|heavy,...>] [--teams <count>] \
+    [--friendly-fire] [--broadcast] [--record <replay file>] [--replay <replay file>] \
+    [--level <level asset or PNG heightmap>] [--heightmap threshold|columns] \
+    [--editor <level file>] [--ui-scale <0.75-2>] [--repose <45-89>] \
+    [--gpu-landscape] [--field <width>x<height>] [--interception] \
+    [--obstacles <count>] [--supply <turns>] [--hazards]";
+
+/// Configuration of the match given at launch of the game,
+/// so it starts without clicking through menus.
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct LaunchOptions {
+    /// Count of tanks in the round.
+    pub players: u8,
+    /// Count of tanks controlled by bots. Bots play for players
+    /// with the greatest numbers.
+    pub bots: u8,
+    pub ai_difficulty: AiDifficulty,
+    pub seed: Option<u64>,
+    pub map: Option<TerrainTheme>,
+    /// Chassis of tanks in order of players' numbers, medium tanks
+    /// are used for the rest of players.
+    pub chassis: Vec<Chassis>,
+    /// Name of preset of rules.
+    pub rules: Option<String>,
+    /// Count of teams in team mode.
+    pub teams: Option<u8>,
+    pub friendly_fire: bool,
+    /// Angle of repose of landscape (degrees).
+    pub angle_of_repose: Option<f32>,
+    /// Missiles of different tanks detonate each other.
+    pub interception: bool,
+    /// Count of obstacles placed on the field.
+    pub obstacles: Option<u8>,
+    /// Number of turns between supply drops.
+    pub supply_drop_turns: Option<usize>,
+    /// Random meteor showers and airstrikes.
+    pub hazards: bool,
+    /// Play levels of the campaign instead of a free match.
+    pub campaign: bool,
+    pub fullscreen: bool,
+    pub broadcast: bool,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    /// Path of asset of hand-made level to play on.
+    pub level: Option<PathBuf>,
+    /// How PNG heightmap given as the level is turned into landscape.
+    pub heightmap_mode: HeightmapMode,
+    /// File of level to edit in the level editor.
+    pub editor: Option<PathBuf>,
+    pub ui_scale: Option<f32>,
+    pub gpu_landscape: bool,
+    /// Size of game field, it may be larger than window.
+    pub field_size: Option<FieldSize>,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            players: DEFAULT_PLAYERS_COUNT,
+            bots: 0,
+            ai_difficulty: AiDifficulty::default(),
+            seed: None,
+            map: None,
+            chassis: Vec::new(),
+            rules: None,
+            teams: None,
+            friendly_fire: false,
+            angle_of_repose: None,
+            interception: false,
+            obstacles: None,
+            supply_drop_turns: None,
+            hazards: false,
+            campaign: false,
+            fullscreen: false,
+            broadcast: false,
+            record: None,
+            replay: None,
+            level: None,
+            heightmap_mode: HeightmapMode::default(),
+            editor: None,
+            ui_scale: None,
+            gpu_landscape: false,
+            field_size: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchError {
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue { option: String, value: String },
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownOption(option) => write!(f, "Unknown option: {}", option),
+            Self::MissingValue(option) => write!(f, "Value of option {} is missing", option),
+            Self::InvalidValue { option, value } => {
+                write!(f, "Invalid value of option {}: {}", option, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LaunchError {}
+
+impl LaunchOptions {
+    /// Parses command-line arguments without the name of program.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, LaunchError> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(option) = args.next() {
+            match option.as_str() {
+                "--campaign" => options.campaign = true,
+                "--fullscreen" => options.fullscreen = true,
+                "--broadcast" => options.broadcast = true,
+                "--friendly-fire" => options.friendly_fire = true,
+                "--gpu-landscape" => options.gpu_landscape = true,
+                "--interception" => options.interception = true,
+                "--hazards" => options.hazards = true,
+                "--players" | "--ai" | "--seed" | "--map" | "--chassis" | "--rules" | "--teams"
+                | "--record" | "--replay" | "--level" | "--heightmap" | "--editor"
+                | "--ui-scale" | "--repose" | "--field" | "--obstacles" | "--supply" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| LaunchError::MissingValue(option.clone()))?;
+                    options.set(&option, value)?;
+                }
+                _ => return Err(LaunchError::UnknownOption(option)),
+            }
+        }
+        if options.bots > options.players {
+            return Err(LaunchError::InvalidValue {
+                option: "--ai".to_string(),
+                value: options.bots.to_string(),
+            });
+        }
+        if let Some(teams) = options.teams.filter(|&teams| teams > options.players) {
+            return Err(LaunchError::InvalidValue {
+                option: "--teams".to_string(),
+                value: teams.to_string(),
+            });
+        }
+        Ok(options)
+    }
+
+    fn set(&mut self, option: &str, value: String) -> Result<(), LaunchError> {
+        let invalid = || LaunchError::InvalidValue {
+            option: option.to_string(),
+            value: value.clone(),
+        };
+        match option {
+            "--players" => {
+                self.players = value
+                    .parse()
+                    .ok()
+                    .filter(|count| (2..=MAX_PLAYERS_COUNT).contains(count))
+                    .ok_or_else(invalid)?;
+            }
+            "--ai" => self.bots = value.parse().map_err(|_| invalid())?,
+            "--seed" => self.seed = Some(value.parse().map_err(|_| invalid())?),
+            "--map" => self.map = Some(terrain_theme(&value).ok_or_else(invalid)?),
+            "--chassis" => {
+                self.chassis = value
+                    .split(',')
+                    .map(Chassis::from_name)
+                    .collect::<Option<_>>()
+                    .ok_or_else(invalid)?;
+            }
+            "--rules" => {
+                rules_preset(&value).ok_or_else(invalid)?;
+                self.rules = Some(value);
+            }
+            "--teams" => {
+                self.teams = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|&count| count >= MIN_TEAMS_COUNT)
+                        .ok_or_else(invalid)?,
+                );
+            }
+            "--record" => self.record = Some(value.into()),
+            "--replay" => self.replay = Some(value.into()),
+            "--level" => self.level = Some(value.into()),
+            "--heightmap" => {
+                self.heightmap_mode = HeightmapMode::from_name(&value).ok_or_else(invalid)?;
+            }
+            "--editor" => self.editor = Some(value.into()),
+            "--ui-scale" => {
+                self.ui_scale = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|scale| (MIN_UI_SCALE..=MAX_UI_SCALE).contains(scale))
+                        .ok_or_else(invalid)?,
+                );
+            }
+            "--repose" => {
+                self.angle_of_repose = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|angle| (45. ..90.).contains(angle))
+                        .ok_or_else(invalid)?,
+                );
+            }
+            "--obstacles" => self.obstacles = Some(value.parse().map_err(|_| invalid())?),
+            "--supply" => {
+                self.supply_drop_turns = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|&turns| turns > 0)
+                        .ok_or_else(invalid)?,
+                );
+            }
+            "--field" => self.field_size = Some(FieldSize::parse(&value).ok_or_else(invalid)?),
+            _ => return Err(LaunchError::UnknownOption(option.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn chassis(&self, player_number: u8) -> Chassis {
+        let index = (player_number as usize).wrapping_sub(1);
+        self.chassis.get(index).copied().unwrap_or_default()
+    }
+
+    pub fn is_bot(&self, player_number: u8) -> bool {
+        player_number + self.bots > self.players
+    }
+
+    pub fn apply_to_settings(&self, settings: &mut Settings) {
+        if self.seed.is_some() {
+            settings.seed = self.seed;
+        }
+        if self.record.is_some() {
+            settings.replay_path.clone_from(&self.record);
+        }
+        if self.broadcast {
+            settings.hud_layout = HudLayout::Broadcast;
+        }
+        if let Some(scale) = self.ui_scale {
+            settings.ui_scale = UiScaleFactor::new(scale);
+        }
+        settings.gpu_landscape |= self.gpu_landscape;
+        if let Some(field_size) = self.field_size {
+            settings.field_size = field_size;
+        }
+    }
+
+    /// Main menu is shown if options don't choose the kind of match.
+    pub fn shows_main_menu(&self) -> bool {
+        self.game_rules().is_none()
+            && !self.campaign
+            && self.replay.is_none()
+            && self.level.is_none()
+            && self.editor.is_none()
+    }
+
+    /// Rules of the match selected by `--rules`, `--teams`,
+    /// `--repose`, `--interception`, `--obstacles`, `--supply`
+    /// and `--hazards` options.
+    pub fn game_rules(&self) -> Option<GameRules> {
+        let mut rules = match self.rules.as_deref() {
+            Some(name) => rules_preset(name)?,
+            None if self.teams.is_some()
+                || self.angle_of_repose.is_some()
+                || self.interception
+                || self.obstacles.is_some()
+                || self.supply_drop_turns.is_some()
+                || self.hazards =>
+            {
+                GameRules::default()
+            }
+            None => return None,
+        };
+        if self.angle_of_repose.is_some() {
+            rules.angle_of_repose = self.angle_of_repose;
+        }
+        rules.missile_interception |= self.interception;
+        rules.hazards |= self.hazards;
+        if let Some(count) = self.obstacles {
+            rules.obstacles = count;
+        }
+        if self.supply_drop_turns.is_some() {
+            rules.supply_drop_turns = self.supply_drop_turns;
+        }
+        if let Some(count) = self.teams {
+            rules.teams = Some(TeamRules {
+                count,
+                friendly_fire: self.friendly_fire,
+            });
+        }
+        Some(rules)
+    }
+}
+
+fn terrain_theme(name: &str) -> Option<TerrainTheme> {
+    match name {
+        "temperate" => Some(TerrainTheme::Temperate),
+        "arctic" => Some(TerrainTheme::Arctic),
+        "desert" => Some(TerrainTheme::Desert),
+        _ => None,
+    }
+}
+
+pub(crate) fn rules_preset(name: &str) -> Option<GameRules> {
+    match name {
+        "standard" => Some(GameRules::default()),
+        "practice" => Some(GameRules {
+            mode: GameMode::Practice,
+            ..default()
+        }),
+        "range" => Some(GameRules {
+            mode: GameMode::TargetRange,
+            ..default()
+        }),
+        "blitz" => Some(GameRules::blitz()),
+        "chaos" => Some(GameRules::chaos()),
+        "shop" => Some(GameRules {
+            economy: EconomyRules {
+                shop: true,
+                ..default()
+            },
+            ..default()
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<LaunchOptions, LaunchError> {
+        LaunchOptions::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_launch_options() {
+        let options =
+            parse("--players 3 --ai 2 --seed 42 --map desert --rules chaos --fullscreen").unwrap();
+        assert_eq!(options.players, 3);
+        assert_eq!(options.bots, 2);
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.map, Some(TerrainTheme::Desert));
+        assert_eq!(options.rules.as_deref(), Some("chaos"));
+        assert!(options.fullscreen);
+        assert!(!options.is_bot(1));
+        assert!(options.is_bot(2));
+        assert!(options.is_bot(3));
+
+        let options = parse("--chassis light,heavy").unwrap();
+        assert_eq!(options.chassis(1), Chassis::Light);
+        assert_eq!(options.chassis(2), Chassis::Heavy);
+        assert_eq!(options.chassis(3), Chassis::Medium);
+        assert_eq!(
+            parse("--chassis light,huge"),
+            Err(LaunchError::InvalidValue {
+                option: "--chassis".to_string(),
+                value: "light,huge".to_string()
+            })
+        );
+
+        let options = parse("--level maps/island.png --heightmap columns").unwrap();
+        assert_eq!(options.heightmap_mode, HeightmapMode::Columns);
+
+        let options = parse("--teams 2 --friendly-fire").unwrap();
+        let teams = options.game_rules().unwrap().teams.unwrap();
+        assert_eq!(teams.count, 2);
+        assert!(teams.friendly_fire);
+        let rules = parse("--rules chaos --repose 60").unwrap().game_rules();
+        assert_eq!(rules.unwrap().angle_of_repose, Some(60.));
+        assert!(parse("--repose 30").is_err());
+        let rules = parse("--interception").unwrap().game_rules();
+        assert!(rules.unwrap().missile_interception);
+        let rules = parse("--obstacles 4").unwrap().game_rules();
+        assert_eq!(rules.unwrap().obstacles, 4);
+        assert!(parse("--obstacles many").is_err());
+        let rules = parse("--supply 3").unwrap().game_rules();
+        assert_eq!(rules.unwrap().supply_drop_turns, Some(3));
+        assert!(parse("--supply 0").is_err());
+        let rules = parse("--rules chaos --hazards").unwrap().game_rules();
+        assert!(rules.unwrap().hazards);
+        assert_eq!(
+            parse("--players 3 --teams 4"),
+            Err(LaunchError::InvalidValue {
+                option: "--teams".to_string(),
+                value: "4".to_string()
+            })
+        );
+
+        assert_eq!(parse("").unwrap(), LaunchOptions::default());
+        assert!(parse("").unwrap().game_rules().is_none());
+        assert!(parse("").unwrap().shows_main_menu());
+        assert!(!parse("--rules range").unwrap().shows_main_menu());
+        assert!(!parse("--campaign").unwrap().shows_main_menu());
+        assert_eq!(parse("--players 8").unwrap().players, 8);
+        let mut settings = Settings::default();
+        parse("--ui-scale 1.5 --gpu-landscape")
+            .unwrap()
+            .apply_to_settings(&mut settings);
+        assert_eq!(settings.ui_scale.get(), 1.5);
+        assert!(settings.gpu_landscape);
+        parse("--field 4096x1024")
+            .unwrap()
+            .apply_to_settings(&mut settings);
+        assert_eq!(
+            settings.field_size,
+            FieldSize {
+                width: 4096,
+                height: 1024
+            }
+        );
+        assert!(parse("--field 4096").is_err());
+        assert!(parse("--field 100000x1024").is_err());
+        assert!(parse("--ui-scale 3").is_err());
+        assert_eq!(
+            parse("--players 9"),
+            Err(LaunchError::InvalidValue {
+                option: "--players".to_string(),
+                value: "9".to_string()
+            })
+        );
+        assert_eq!(
+            parse("--players 2 --ai 3"),
+            Err(LaunchError::InvalidValue {
+                option: "--ai".to_string(),
+                value: "3".to_string()
+            })
+        );
+        assert_eq!(
+            parse("--seed"),
+            Err(LaunchError::MissingValue("--seed".to_string()))
+        );
+        assert_eq!(
+            parse("--rules fast"),
+            Err(LaunchError::InvalidValue {
+                option: "--rules".to_string(),
+                value: "fast".to_string()
+            })
+        );
+        assert_eq!(
+            parse("--cheats"),
+            Err(LaunchError::UnknownOption("--cheats".to_string()))
+        );
+    }
+}
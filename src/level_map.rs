@@ -0,0 +1,215 @@
+use std::io;
+use std::path::Path;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, BoxedFuture, LoadContext};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game_plugin::AppState;
+use crate::heightmap::is_heightmap_path;
+use crate::landscape::Landscape;
+use crate::launch::LaunchOptions;
+use crate::storage;
+
+/// Loads level maps from `*.level.ron` assets.
+pub struct LevelMapPlugin;
+
+impl Plugin for LevelMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LevelMap>()
+            .register_asset_loader(LevelMapLoader)
+            .add_systems(Startup, load_launch_level_system)
+            .add_systems(
+                Update,
+                apply_loaded_level_system.run_if(resource_exists::<LevelHandle>),
+            );
+    }
+}
+
+/// Hand-made landscape and spawn points of tanks. If this resource
+/// exists, the round is played on it instead of generated landscape.
+#[derive(Debug, Clone, PartialEq, Asset, TypePath, Resource, Serialize, Deserialize)]
+pub struct LevelMap {
+    pub width: u16,
+    pub height: u16,
+    /// Height of the surface of landscape in every column of pixels.
+    pub heights: Vec<u16>,
+    /// X coordinates of spawn points of tanks in order of their placement.
+    #[serde(default)]
+    pub spawn_points: Vec<f32>,
+}
+
+impl LevelMap {
+    pub fn from_landscape(landscape: &Landscape, spawn_points: Vec<f32>) -> Self {
+        let (width, height) = landscape.size();
+        Self {
+            width,
+            height,
+            heights: (0..width as i32)
+                .map(|x| landscape.surface_height(x))
+                .collect(),
+            spawn_points,
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    pub fn from_ron(ron: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(ron)
+    }
+
+    /// Parses content of a level asset.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let level: Self = ron::de::from_bytes(bytes).map_err(|err| err.to_string())?;
+        if level.heights.len() != level.width as usize {
+            return Err(format!(
+                "Level has {} heights of columns instead of {}",
+                level.heights.len(),
+                level.width
+            ));
+        }
+        Ok(level)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let ron = self
+            .to_ron()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        storage::write(path, ron)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = storage::read(path)?;
+        Self::from_bytes(&bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Returns heights of columns of the map stretched to the game field
+    /// of the given size.
+    pub fn scaled_heights(&self, width: u16, height: u16) -> Vec<u16> {
+        if self.heights.is_empty() || self.height == 0 {
+            return vec![0; width as usize];
+        }
+        (0..width as usize)
+            .map(|x| {
+                let src_x = (x * self.heights.len() / width as usize).min(self.heights.len() - 1);
+                let scaled = self.heights[src_x] as u32 * height as u32 / self.height as u32;
+                scaled.min(height as u32) as u16
+            })
+            .collect()
+    }
+
+    /// Returns spawn points stretched to the game field of the given width.
+    pub fn scaled_spawn_points(&self, width: u16) -> Vec<f32> {
+        let scale = width as f32 / self.width.max(1) as f32;
+        self.spawn_points.iter().map(|&x| x * scale).collect()
+    }
+}
+
+#[derive(Default)]
+pub struct LevelMapLoader;
+
+impl AssetLoader for LevelMapLoader {
+    type Asset = LevelMap;
+    type Settings = ();
+    type Error = String;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(|err| err.to_string())?;
+            LevelMap::from_bytes(&bytes)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// Handle of the level asset which the round is played on.
+#[derive(Debug, Clone, Resource)]
+pub struct LevelHandle(pub Handle<LevelMap>);
+
+fn load_launch_level_system(
+    mut commands: Commands,
+    asset_server: Option<Res<AssetServer>>,
+    launch_options: Option<Res<LaunchOptions>>,
+) {
+    let (Some(asset_server), Some(path)) = (
+        asset_server,
+        launch_options
+            .and_then(|options| options.level.clone())
+            .filter(|path| !is_heightmap_path(path)),
+    ) else {
+        return;
+    };
+    commands.insert_resource(LevelHandle(asset_server.load(path)));
+}
+
+/// Restarts the round when the level asset is loaded or modified.
+fn apply_loaded_level_system(
+    mut commands: Commands,
+    level_handle: Res<LevelHandle>,
+    levels: Res<Assets<LevelMap>>,
+    mut asset_events: EventReader<AssetEvent<LevelMap>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != level_handle.0.id() {
+            continue;
+        }
+        if let Some(level) = levels.get(*id) {
+            info!("Level {:?} has been loaded", level_handle.0.path());
+            commands.insert_resource(level.clone());
+            next_state.set(AppState::RoundSetup);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::landscape_buffer::LandscapeStorage;
+
+    #[test]
+    fn test_level_map_round_trip() {
+        let mut landscape = Landscape::new(100, 50, 1, LandscapeStorage::default(), None).unwrap();
+        landscape.set_surface_height(10, 40);
+        let map = LevelMap::from_landscape(&landscape, vec![10., 90.]);
+        assert_eq!(map.heights[10], 40);
+
+        let loaded = LevelMap::from_bytes(map.to_ron().unwrap().as_bytes()).unwrap();
+        assert_eq!(loaded, map);
+
+        let other =
+            Landscape::from_level_map(&loaded, 100, 50, LandscapeStorage::default(), None).unwrap();
+        assert_eq!(LevelMap::from_landscape(&other, vec![10., 90.]), map);
+
+        let mut broken = map.clone();
+        broken.heights.pop();
+        assert!(LevelMap::from_bytes(broken.to_ron().unwrap().as_bytes()).is_err());
+
+        let heights = map.scaled_heights(200, 100);
+        assert_eq!(heights.len(), 200);
+        assert_eq!(heights[20], 80);
+        assert_eq!(map.scaled_spawn_points(200), vec![20., 180.]);
+
+        let asset = LevelMap::load("assets/maps/twin_peaks.level.ron").unwrap();
+        assert_eq!(asset.heights.len(), asset.width as usize);
+    }
+}
@@ -1,18 +1,143 @@
-pub use game_plugin::TankWarGamePlugin;
+pub use ai::{AiController, AiDifficulty};
+pub use announcements::AnnouncementEvent;
+pub use camera::{CameraController, CameraEasing, CameraPreset, MainCamera, SpectatorCamera};
+pub use campaign::{
+    Campaign, CampaignProgress, LevelDefinition, WinCondition, LEVELS_DIR, PROGRESS_PATH,
+};
+pub use chassis::{Chassis, ChassisSpec};
+pub use chat::{ChatEntry, ChatInput, ChatLog, ChatMessage, ChatMessageSent, QUICK_PHRASES};
+pub use cloak::Cloaked;
+pub use day_night::DayCycle;
+pub use decoy::{Decoy, DecoyDestroyedEvent};
+pub use desync::{DesyncDetectedEvent, DesyncSource, StateHash, StateHashLog};
+pub use economy::{
+    EconomyError, Finances, PlayerFinances, DAMAGE_REWARD, KILL_REWARD, ROUND_PRIZE, START_MONEY,
+};
+pub use editor::EditorSession;
+pub use environment::{DayPhase, TerrainTheme, Weather};
+pub use game_field::GameRng;
+pub use game_plugin::{AimingMode, TankWarGamePlugin};
+pub use hazards::{HazardEvent, HazardKind};
+pub use heightmap::{Heightmap, HeightmapHandle, HeightmapMode};
+pub use landscape::{LandscapeOp, TerrainDestroyedEvent};
+pub use landscape_buffer::{LandscapeStorage, TerrainMaterial};
+pub use launch::{LaunchError, LaunchOptions, USAGE};
+pub use level_map::{LevelHandle, LevelMap};
+pub use lobby::{LobbyPlayer, LobbyRequest, LobbySession, LobbyState, LOBBY_RULES_PRESETS};
+pub use main_menu::{MainMenu, MAIN_MENU_ENTRIES};
 pub use materials::*;
+pub use mines::MineDetonatedEvent;
+pub use net::{
+    answer_hello, check_compatibility, Capabilities, ClientId, ClientSession, HandshakeError,
+    Hello, KickClientEvent, NetMessage, NetMessageFrom, NetMessageReceived, NetSlots,
+    PlayerConnectedEvent, PlayerDisconnectedEvent, ReconnectRequestEvent, SendNetMessage,
+    SendNetMessageTo, SessionRole, SessionToken, SlotState, SpectatorConnectedEvent,
+    SpectatorDisconnectedEvent, SpectatorSession, StateSnapshot, TankSnapshot,
+    DEFAULT_RECONNECT_TURNS, HOST_CLIENT_ID, PROTOCOL_VERSION,
+};
+pub use obstacles::{Obstacle, ObstacleDestroyedEvent, ObstacleKind};
+pub use placeholder_icon::{initials, placeholder_icon};
+pub use range::{RangeScore, TargetHitEvent};
+pub use replay::{Replay, ReplayPlayback, ReplayTurn, REPLAY_FORMAT_VERSION};
+pub use rules::{
+    validate_shot, EconomyRules, GameMode, GameRules, Shot, ShotViolation, SpawnLayout, SpawnRules,
+    TeamRules, BLITZ_TIME_BANK, MAX_GUN_ANGLE, MAX_GUN_POWER, MIN_GUN_ANGLE,
+};
+pub use scanner::ScanEvent;
+pub use settings::{
+    AudioSettings, FieldSize, HudLayout, InputRepeatSettings, Settings, UiScaleFactor,
+    MAX_FIELD_SIZE, MAX_UI_SCALE, MIN_FIELD_SIZE, MIN_UI_SCALE,
+};
+pub use shop::{Inventories, PlayerInventory, ShopItem};
+pub use simulation::{
+    ShootCommand, ShotRejectedEvent, Simulation, TankStatus, SIMULATION_FRAME_TIME,
+};
+pub use stats::{MatchStats, PlayerStats};
+pub use status_panel::StatusPanelSet;
+pub use supply::{SupplyCrate, SupplyDropEvent};
+pub use tank::{TankDamagedEvent, TankDestroyedEvent, TankLandedEvent, TankShotEvent};
+pub use teams::Team;
+pub use timeline::{EventTimeline, TimelineEvent, TimelineEventKind, TIMELINE_FORMAT_VERSION};
+pub use turn::{RoundEndedEvent, SuddenDeathEvent, TurnEndedEvent, TurnManager, TurnStartedEvent};
+pub use turn_timer::{host_time, HostClock, MatchClocks, TurnTimer};
+pub use upgrades::{TankUpgrades, Upgrade};
+pub use weapons::{TankWeapon, WeaponDefinition, WeaponKind, Weapons};
 
+mod ai;
+mod airstrike;
+mod announcements;
+mod anti_gravity;
+mod audio;
+mod background;
 mod ballistics;
+mod broadcast_hud;
+mod camera;
+mod campaign;
+mod chassis;
+mod chat;
+mod cloak;
 mod collider;
 mod components;
+mod day_night;
+mod decoy;
+mod desync;
+mod earthmover;
+mod economy;
+mod editor;
+mod environment;
 mod explosion;
 mod game_field;
 mod game_plugin;
 mod geometry;
+mod grappling_hook;
+mod hazards;
+mod heightmap;
+mod idle_animation;
 mod input;
+mod jetpack;
 mod landscape;
+mod landscape_buffer;
+mod landscape_gpu;
+mod launch;
+mod level_map;
+mod lobby;
+mod main_menu;
 mod materials;
+mod mines;
+mod minimap;
 mod missile;
+mod net;
+mod obstacles;
+mod orbital_strike;
+mod particles;
+mod placeholder_icon;
+mod portal;
+mod range;
+mod replay;
+mod rules;
+mod scanner;
+mod settings;
+mod shop;
+mod simulation;
+mod slow_motion;
+mod spawn;
+mod stats;
 mod status_panel;
+mod storage;
+mod supply;
 mod tank;
+mod tank_labels;
+mod teams;
+mod timeline;
+mod touch;
+mod trajectory_preview;
+mod turn;
+mod turn_order;
+mod turn_timer;
+mod upgrades;
+mod weapons;
+mod weather;
 pub const G: f32 = 9.80665;
-pub const MAX_PLAYERS_COUNT: u8 = 5;
+pub const MAX_PLAYERS_COUNT: u8 = 8;
+/// Count of players if it isn't given at launch.
+pub const DEFAULT_PLAYERS_COUNT: u8 = 5;
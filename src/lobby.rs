@@ -0,0 +1,520 @@
+//! Lobby of network game where players gather before the first round.
+//!
+//! The host keeps `LobbyState` and sends it to all clients after every
+//! change. Clients send `LobbyRequest` messages to change their names,
+//! colors and readiness. The round starts when all players are ready,
+//! slots of players who haven't joined or have left are given to bots.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::chat::is_chat_open;
+use crate::game_field::GameField;
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::launch::{rules_preset, LaunchOptions};
+use crate::net::{
+    ClientId, NetMessage, NetMessageFrom, NetMessageReceived, NetSlots, PlayerConnectedEvent,
+    PlayerDisconnectedEvent, SendNetMessage, SendNetMessageTo, SlotState, HOST_CLIENT_ID,
+};
+use crate::rules::GameRules;
+use crate::tank::player_color;
+
+/// Presets of rules which the host may choose in the lobby.
+pub const LOBBY_RULES_PRESETS: [&str; 4] = ["standard", "blitz", "chaos", "shop"];
+const MAX_NAME_LENGTH: usize = 16;
+const FONT_SIZE: f32 = 20.;
+const FREE_SLOT_COLOR: Color = Color::GRAY;
+
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            switch_to_lobby_system
+                .after(setup_game_field)
+                .run_if(is_in_lobby),
+        )
+        .add_systems(
+            Update,
+            (
+                lobby_input_system.run_if(not(is_chat_open)),
+                host_lobby_system.run_if(is_lobby_host),
+                client_lobby_system.run_if(not(is_lobby_host)),
+                start_round_system,
+            )
+                .chain()
+                .run_if(in_state(AppState::Lobby)),
+        );
+    }
+}
+
+/// List of players and rules shown in the lobby.
+pub struct LobbyScreenPlugin;
+
+impl Plugin for LobbyScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Lobby), setup_lobby_screen)
+            .add_systems(
+                Update,
+                update_lobby_screen_system
+                    .after(start_round_system)
+                    .run_if(in_state(AppState::Lobby)),
+            )
+            .add_systems(OnExit(AppState::Lobby), despawn_lobby_screen);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LobbyPlayer {
+    /// Number of player, it also defines color of the player's tank.
+    pub player: u8,
+    pub client_id: ClientId,
+    pub name: String,
+    pub ready: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LobbyState {
+    pub players_count: u8,
+    pub players: Vec<LobbyPlayer>,
+    /// Name of preset of rules chosen by the host.
+    pub rules_preset: String,
+    pub started: bool,
+}
+
+/// Request of client to the host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LobbyRequest {
+    SetName(String),
+    /// Takes the free slot of player with the given number,
+    /// so the tank of the client has color of this player.
+    PickPlayer(u8),
+    SetReady(bool),
+}
+
+impl LobbyState {
+    pub fn new(players_count: u8) -> Self {
+        Self {
+            players_count,
+            players: Vec::new(),
+            rules_preset: LOBBY_RULES_PRESETS[0].to_string(),
+            started: false,
+        }
+    }
+
+    pub fn get(&self, client_id: ClientId) -> Option<&LobbyPlayer> {
+        self.players.iter().find(|p| p.client_id == client_id)
+    }
+
+    fn get_mut(&mut self, client_id: ClientId) -> Option<&mut LobbyPlayer> {
+        self.players.iter_mut().find(|p| p.client_id == client_id)
+    }
+
+    pub fn is_free(&self, player: u8) -> bool {
+        (1..=self.players_count).contains(&player)
+            && self.players.iter().all(|p| p.player != player)
+    }
+
+    /// Returns the first free slot after the given one.
+    pub fn next_free_player(&self, player: u8) -> Option<u8> {
+        (1..self.players_count)
+            .map(|i| (player + i - 1) % self.players_count + 1)
+            .find(|&p| self.is_free(p))
+    }
+
+    /// Adds the client into the slot of player, if it is free.
+    pub fn add_player(&mut self, player: u8, client_id: ClientId) {
+        if self.is_free(player) && self.get(client_id).is_none() {
+            self.players.push(LobbyPlayer {
+                player,
+                client_id,
+                name: format!("Player {}", player),
+                ready: false,
+            });
+            self.players.sort_by_key(|p| p.player);
+        }
+    }
+
+    pub fn remove_client(&mut self, client_id: ClientId) {
+        self.players.retain(|p| p.client_id != client_id);
+    }
+
+    /// Applies request of the client. Returns `false` if the request
+    /// has been rejected.
+    pub fn apply(&mut self, client_id: ClientId, request: &LobbyRequest) -> bool {
+        if self.started {
+            return false;
+        }
+        let is_free = |player| self.is_free(player);
+        let can_pick = match *request {
+            LobbyRequest::PickPlayer(player) => is_free(player),
+            _ => true,
+        };
+        let Some(lobby_player) = self.get_mut(client_id) else {
+            return false;
+        };
+        match request {
+            LobbyRequest::SetName(name) => {
+                let name: String = name.trim().chars().take(MAX_NAME_LENGTH).collect();
+                if name.is_empty() {
+                    return false;
+                }
+                lobby_player.name = name;
+            }
+            &LobbyRequest::PickPlayer(player) => {
+                if !can_pick {
+                    return false;
+                }
+                lobby_player.player = player;
+                self.players.sort_by_key(|p| p.player);
+            }
+            &LobbyRequest::SetReady(ready) => lobby_player.ready = ready,
+        }
+        true
+    }
+
+    pub fn all_ready(&self) -> bool {
+        !self.players.is_empty() && self.players.iter().all(|p| p.ready)
+    }
+
+    pub fn rules(&self) -> GameRules {
+        rules_preset(&self.rules_preset).unwrap_or_default()
+    }
+}
+
+/// Insert this resource into the app to gather players
+/// in the lobby before the first round of network game.
+#[derive(Debug, Clone, Resource)]
+pub struct LobbySession {
+    pub state: LobbyState,
+    /// Client of this app, `HOST_CLIENT_ID` on the host.
+    pub client_id: Option<ClientId>,
+    /// Name of the player of this app.
+    pub name: String,
+}
+
+impl LobbySession {
+    /// Lobby on the host, the host takes the slot of the first player.
+    pub fn host(players_count: u8, name: &str) -> Self {
+        let mut state = LobbyState::new(players_count);
+        state.add_player(1, HOST_CLIENT_ID);
+        state.apply(HOST_CLIENT_ID, &LobbyRequest::SetName(name.to_string()));
+        Self {
+            state,
+            client_id: Some(HOST_CLIENT_ID),
+            name: name.to_string(),
+        }
+    }
+
+    /// Lobby on the client. Its state is received from the host.
+    pub fn client(name: &str) -> Self {
+        Self {
+            state: LobbyState::new(0),
+            client_id: None,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.client_id == Some(HOST_CLIENT_ID)
+    }
+}
+
+pub fn is_in_lobby(session: Option<Res<LobbySession>>) -> bool {
+    session.is_some()
+}
+
+fn is_lobby_host(session: Option<Res<LobbySession>>) -> bool {
+    session.is_some_and(|session| session.is_host())
+}
+
+fn switch_to_lobby_system(mut next_state: ResMut<NextState<AppState>>) {
+    debug!("Switch to Lobby");
+    next_state.set(AppState::Lobby);
+}
+
+/// C picks the next free color, Space toggles readiness
+/// and R on the host chooses the next preset of rules.
+fn lobby_input_system(
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    mut session: ResMut<LobbySession>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    let (Some(keyboard_input), Some(client_id)) = (keyboard_input, session.client_id) else {
+        return;
+    };
+    let Some(player) = session.state.get(client_id).cloned() else {
+        return;
+    };
+    let mut requests = Vec::new();
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        if let Some(next_player) = session.state.next_free_player(player.player) {
+            requests.push(LobbyRequest::PickPlayer(next_player));
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        requests.push(LobbyRequest::SetReady(!player.ready));
+    }
+    if session.is_host() {
+        if keyboard_input.just_pressed(KeyCode::KeyR) {
+            let state = &mut session.state;
+            let i = LOBBY_RULES_PRESETS
+                .iter()
+                .position(|&name| name == state.rules_preset)
+                .map_or(0, |i| (i + 1) % LOBBY_RULES_PRESETS.len());
+            state.rules_preset = LOBBY_RULES_PRESETS[i].to_string();
+        }
+        for request in requests {
+            session.state.apply(client_id, &request);
+        }
+    } else {
+        for request in requests {
+            send_events.send(SendNetMessage(NetMessage::LobbyRequest(request)));
+        }
+    }
+}
+
+/// Applies requests of clients and sends them the state of lobby
+/// after its changes.
+#[allow(clippy::too_many_arguments)]
+fn host_lobby_system(
+    mut session: ResMut<LobbySession>,
+    slots: Option<ResMut<NetSlots>>,
+    mut connected_events: EventReader<PlayerConnectedEvent>,
+    mut disconnected_events: EventReader<PlayerDisconnectedEvent>,
+    mut received_events: EventReader<NetMessageFrom>,
+    mut sent_state: Local<Option<LobbyState>>,
+    mut send_events: EventWriter<SendNetMessageTo>,
+) {
+    let state = &mut session.state;
+    for event in connected_events.read() {
+        state.add_player(event.player, event.client_id);
+    }
+    for event in disconnected_events.read() {
+        if let Some(client_id) = state
+            .players
+            .iter()
+            .find(|p| p.player == event.player)
+            .map(|p| p.client_id)
+        {
+            state.remove_client(client_id);
+        }
+    }
+    for NetMessageFrom { client_id, message } in received_events.read() {
+        if let NetMessage::LobbyRequest(request) = message {
+            if !state.apply(*client_id, request) {
+                debug!("Request {:?} of client {} is rejected", request, client_id);
+            }
+        }
+    }
+
+    if sent_state.as_ref() == Some(&session.state) {
+        return;
+    }
+    let state = &session.state;
+    if let Some(mut slots) = slots {
+        // Slots follow colors picked by players.
+        for player in 1..=state.players_count {
+            let slot = match state.players.iter().find(|p| p.player == player) {
+                Some(p) => SlotState::Human(p.client_id),
+                None => SlotState::Empty,
+            };
+            if slots.get(player) != Some(slot) {
+                slots.set(player, slot);
+            }
+        }
+        for &client_id in slots.spectators() {
+            send_events.send(SendNetMessageTo {
+                client_id,
+                message: NetMessage::LobbyUpdate {
+                    lobby: state.clone(),
+                    client_id,
+                },
+            });
+        }
+    }
+    for p in state
+        .players
+        .iter()
+        .filter(|p| p.client_id != HOST_CLIENT_ID)
+    {
+        send_events.send(SendNetMessageTo {
+            client_id: p.client_id,
+            message: NetMessage::LobbyUpdate {
+                lobby: state.clone(),
+                client_id: p.client_id,
+            },
+        });
+    }
+    *sent_state = Some(state.clone());
+}
+
+fn client_lobby_system(
+    mut session: ResMut<LobbySession>,
+    mut received_events: EventReader<NetMessageReceived>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    for NetMessageReceived(message) in received_events.read() {
+        if let NetMessage::LobbyUpdate { lobby, client_id } = message {
+            session.state = lobby.clone();
+            session.client_id = Some(*client_id);
+        }
+    }
+    let Some(client_id) = session.client_id else {
+        return;
+    };
+    // The host gives default names to new players.
+    let has_other_name = session
+        .state
+        .get(client_id)
+        .is_some_and(|p| p.name != session.name);
+    if has_other_name && session.is_changed() {
+        send_events.send(SendNetMessage(NetMessage::LobbyRequest(
+            LobbyRequest::SetName(session.name.clone()),
+        )));
+    }
+}
+
+fn start_round_system(
+    mut commands: Commands,
+    mut session: ResMut<LobbySession>,
+    slots: Option<ResMut<NetSlots>>,
+    launch_options: Option<ResMut<LaunchOptions>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if session.is_host() && !session.state.started && session.state.all_ready() {
+        session.state.started = true;
+        // Clients are notified by the next update of lobby.
+        return;
+    }
+    if !session.state.started {
+        return;
+    }
+    let state = &session.state;
+    if let Some(mut slots) = slots {
+        slots.fill_with_bots = true;
+    }
+    match launch_options {
+        Some(mut options) => {
+            options.players = state.players_count;
+            options.bots = 0;
+        }
+        None => commands.insert_resource(LaunchOptions {
+            players: state.players_count,
+            ..default()
+        }),
+    }
+    commands.insert_resource(state.rules());
+    commands.remove_resource::<LobbySession>();
+    debug!("Switch to RoundSetup");
+    next_state.set(AppState::RoundSetup);
+}
+
+#[derive(Component)]
+struct LobbyScreen;
+
+fn setup_lobby_screen(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(80.),
+                left: Val::Px(120.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            text: Text::default(),
+            background_color: Color::rgba(0., 0., 0., 0.8).into(),
+            ..default()
+        },
+        LobbyScreen,
+    ));
+}
+
+fn update_lobby_screen_system(
+    session: Option<Res<LobbySession>>,
+    game_field: Res<GameField>,
+    mut text_query: Query<&mut Text, With<LobbyScreen>>,
+) {
+    let Some(session) = session.filter(|session| session.is_changed()) else {
+        return;
+    };
+    let state = &session.state;
+    let rules = state.rules();
+    let style = |color| TextStyle {
+        font: game_field.font.clone(),
+        font_size: FONT_SIZE,
+        color,
+    };
+    let mut sections = vec![TextSection::new(
+        format!("Lobby. Rules: {}\n\n", state.rules_preset),
+        style(Color::WHITE),
+    )];
+    for player in 1..=state.players_count {
+        let section = match state.players.iter().find(|p| p.player == player) {
+            Some(p) => TextSection::new(
+                format!(
+                    "{}. {}{}\n",
+                    player,
+                    p.name,
+                    if p.ready { " - ready" } else { "" }
+                ),
+                style(player_color(
+                    player,
+                    state.players_count,
+                    rules.teams.as_ref(),
+                )),
+            ),
+            None => TextSection::new(format!("{}. Bot\n", player), style(FREE_SLOT_COLOR)),
+        };
+        sections.push(section);
+    }
+    let mut help = "\nC - change color, Space - ready".to_string();
+    if session.is_host() {
+        help += ", R - change rules";
+    }
+    sections.push(TextSection::new(help, style(Color::WHITE)));
+    for mut text in text_query.iter_mut() {
+        text.sections = sections.clone();
+    }
+}
+
+fn despawn_lobby_screen(mut commands: Commands, screens_query: Query<Entity, With<LobbyScreen>>) {
+    for entity in screens_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lobby_state() {
+        let mut session = LobbySession::host(3, "Host");
+        let state = &mut session.state;
+        state.add_player(3, 10);
+        // The slot is taken.
+        state.add_player(3, 11);
+        assert_eq!(state.players.len(), 2);
+        assert_eq!(state.get(HOST_CLIENT_ID).unwrap().name, "Host");
+
+        assert!(state.apply(10, &LobbyRequest::SetName(" Rookie ".into())));
+        assert_eq!(state.get(10).unwrap().name, "Rookie");
+        assert!(!state.apply(10, &LobbyRequest::PickPlayer(1)));
+        assert_eq!(state.next_free_player(3), Some(2));
+        assert_eq!(state.next_free_player(1), Some(2));
+        assert!(state.apply(10, &LobbyRequest::PickPlayer(2)));
+        assert_eq!(state.next_free_player(1), Some(3));
+        assert!(!state.apply(11, &LobbyRequest::SetReady(true)));
+
+        assert!(!state.all_ready());
+        assert!(state.apply(10, &LobbyRequest::SetReady(true)));
+        assert!(state.apply(HOST_CLIENT_ID, &LobbyRequest::SetReady(true)));
+        assert!(state.all_ready());
+        state.remove_client(10);
+        assert!(state.is_free(2));
+        assert!(state.all_ready());
+    }
+}
@@ -1,14 +1,61 @@
 use bevy::prelude::*;
-use bevy::sprite::MaterialMesh2dBundle;
-use bevy::window::{PresentMode, PrimaryWindow};
+use bevy::window::{PresentMode, WindowMode};
 
 //use bevy::diagnostic::LogDiagnosticsPlugin;
-use bevy_tank_war::{GlowMaterial, HueOffsetMaterial, MaterialsPlugin, TankWarGamePlugin};
+use bevy_tank_war::{
+    Campaign, EditorSession, LaunchOptions, MainMenu, Replay, ReplayPlayback, Settings,
+    TankWarGamePlugin, LEVELS_DIR, PROGRESS_PATH, USAGE,
+};
 
 fn main() {
     // env_logger::init();
 
-    App::new()
+    let options = match LaunchOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut settings = Settings::default();
+    options.apply_to_settings(&mut settings);
+
+    let mut app = App::new();
+    if let Some(path) = &options.replay {
+        match Replay::load(path) {
+            Ok(replay) => {
+                app.insert_resource(ReplayPlayback::new(replay));
+            }
+            Err(err) => eprintln!("Failed to load replay {}: {}", path.display(), err),
+        }
+    }
+    if let Some(path) = &options.editor {
+        app.insert_resource(EditorSession::new(path.clone()));
+    }
+    if options.campaign {
+        match Campaign::load(LEVELS_DIR, PROGRESS_PATH) {
+            Ok(campaign) => {
+                app.insert_resource(campaign);
+            }
+            Err(err) => eprintln!("Failed to load campaign: {}", err),
+        }
+    }
+    if options.shows_main_menu() {
+        app.init_resource::<MainMenu>();
+    }
+    if let Some(rules) = options.game_rules() {
+        app.insert_resource(rules);
+    }
+    if let Some(theme) = options.map {
+        app.insert_resource(theme);
+    }
+    let mode = if options.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+    app.insert_resource(settings)
+        .insert_resource(options)
         // .insert_resource(Msaa { samples: 4 })
         .add_plugins(
             DefaultPlugins
@@ -18,23 +65,19 @@ fn main() {
                         resolution: (1024., 768.).into(),
                         present_mode: PresentMode::AutoNoVsync,
                         resizable: false,
+                        mode,
+                        // Canvas of the page in the browser build.
+                        canvas: Some("#bevy".to_string()),
                         ..default()
                     }),
                     ..default()
                 })
                 .set(AssetPlugin {
                     // Tell the asset server to watch for asset changes on disk:
-                    watch_for_changes_override: Some(true),
+                    watch_for_changes_override: Some(cfg!(not(target_arch = "wasm32"))),
                     ..default()
                 }),
         )
-        .add_systems(
-            Startup,
-            (
-                setup_camera,
-                //setup_mesh
-            ),
-        )
         // // Adds frame time diagnostics
         // .add_plugin(FrameTimeDiagnosticsPlugin::default())
         // Adds a system that prints diagnostics to the console
@@ -42,50 +85,6 @@ fn main() {
         //     debug: true,
         //     ..Default::default()
         // })
-        .add_plugins((
-            TankWarGamePlugin,
-            //MaterialsPlugin
-        ))
+        .add_plugins(TankWarGamePlugin::default())
         .run();
 }
-
-fn setup_camera(mut commands: Commands, primary_windows: Query<&Window, With<PrimaryWindow>>) {
-    let Ok(window) = primary_windows.get_single() else {
-        return;
-    };
-    let width = window.width();
-    let height = window.height();
-    let mut camera = Camera2dBundle::default();
-    camera.transform.translation =
-        Vec3::new(width / 2., height / 2., camera.transform.translation.z);
-    commands.spawn(camera);
-}
-
-pub fn setup_mesh(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut glow_materials: ResMut<Assets<GlowMaterial>>,
-    mut hue_materials: ResMut<Assets<HueOffsetMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-) {
-    commands.spawn(MaterialMesh2dBundle {
-        mesh: meshes.add(Rectangle::new(41., 41.)).into(),
-        transform: Transform::from_translation(Vec3::new(100.5, 600.5, 0.)),
-        material: glow_materials.add(GlowMaterial {
-            color: Color::WHITE,
-            intensity: 2.0,
-            texture: asset_server.load("sprites/tank.png"),
-        }),
-        ..Default::default()
-    });
-
-    commands.spawn(MaterialMesh2dBundle {
-        mesh: meshes.add(Rectangle::new(41., 41.)).into(),
-        transform: Transform::from_translation(Vec3::new(200.5, 600.5, 0.)),
-        material: hue_materials.add(HueOffsetMaterial {
-            offset: 0.5,
-            texture: asset_server.load("sprites/tank.png"),
-        }),
-        ..Default::default()
-    });
-}
@@ -0,0 +1,201 @@
+//! Main menu is shown at launch of the game without options which
+//! choose the kind of match. Every entry of the menu starts a match
+//! with its preset of rules.
+use bevy::prelude::*;
+
+use crate::game_field::GameField;
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::launch::rules_preset;
+
+const FONT_SIZE: f32 = 24.;
+const ENTRY_COLOR: Color = Color::NONE;
+const SELECTED_ENTRY_COLOR: Color = Color::rgba(1., 1., 1., 0.15);
+
+/// Names of presets of rules with titles of their menu entries.
+pub const MAIN_MENU_ENTRIES: [(&str, &str); 6] = [
+    ("standard", "Standard match"),
+    ("practice", "Practice"),
+    ("range", "Target range"),
+    ("blitz", "Blitz"),
+    ("chaos", "Chaos"),
+    ("shop", "Match with shop"),
+];
+
+/// Resource which makes the game show the main menu
+/// instead of starting the first round.
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct MainMenu {
+    /// Index of selected entry.
+    pub selected: usize,
+}
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            switch_to_main_menu_system
+                .after(setup_game_field)
+                .run_if(is_in_main_menu),
+        )
+        .add_systems(OnEnter(AppState::MainMenu), setup_main_menu_screen)
+        .add_systems(
+            Update,
+            (main_menu_input_system, update_main_menu_screen_system)
+                .chain()
+                .run_if(in_state(AppState::MainMenu)),
+        )
+        .add_systems(OnExit(AppState::MainMenu), despawn_main_menu_screen);
+    }
+}
+
+pub fn is_in_main_menu(menu: Option<Res<MainMenu>>) -> bool {
+    menu.is_some()
+}
+
+fn switch_to_main_menu_system(mut next_state: ResMut<NextState<AppState>>) {
+    debug!("Switch to MainMenu");
+    next_state.set(AppState::MainMenu);
+}
+
+#[derive(Component)]
+struct MainMenuScreen;
+
+#[derive(Component)]
+struct MainMenuEntry(usize);
+
+fn setup_main_menu_screen(mut commands: Commands, game_field: Res<GameField>) {
+    let text_style = TextStyle {
+        font: game_field.font.clone(),
+        font_size: FONT_SIZE,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(80.),
+                    left: Val::Px(120.),
+                    padding: UiRect::all(Val::Px(10.)),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(5.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.8).into(),
+                ..default()
+            },
+            MainMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Tank War", text_style.clone()));
+            for (index, (_, title)) in MAIN_MENU_ENTRIES.iter().enumerate() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(5.)),
+                                ..default()
+                            },
+                            background_color: ENTRY_COLOR.into(),
+                            ..default()
+                        },
+                        MainMenuEntry(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(*title, text_style.clone()));
+                    });
+            }
+            parent.spawn(TextBundle::from_section(
+                "Up/Down - select, Enter - start",
+                TextStyle {
+                    font_size: FONT_SIZE * 0.75,
+                    ..text_style.clone()
+                },
+            ));
+        });
+}
+
+/// Up and Down select an entry, Enter or click on the entry
+/// starts the match with its rules.
+fn main_menu_input_system(
+    mut commands: Commands,
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    mut menu: ResMut<MainMenu>,
+    entries_query: Query<(&Interaction, &MainMenuEntry), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut start = false;
+    if let Some(keyboard_input) = keyboard_input {
+        let count = MAIN_MENU_ENTRIES.len();
+        if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+            menu.selected = (menu.selected + count - 1) % count;
+        }
+        if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+            menu.selected = (menu.selected + 1) % count;
+        }
+        start = keyboard_input.just_pressed(KeyCode::Enter);
+    }
+    for (&interaction, entry) in entries_query.iter() {
+        match interaction {
+            Interaction::Hovered => menu.selected = entry.0,
+            Interaction::Pressed => {
+                menu.selected = entry.0;
+                start = true;
+            }
+            Interaction::None => {}
+        }
+    }
+    if !start {
+        return;
+    }
+    let (preset, _) = MAIN_MENU_ENTRIES[menu.selected];
+    info!("Start match with {} rules", preset);
+    commands.insert_resource(rules_preset(preset).unwrap_or_default());
+    commands.remove_resource::<MainMenu>();
+    debug!("Switch to RoundSetup");
+    next_state.set(AppState::RoundSetup);
+}
+
+fn update_main_menu_screen_system(
+    menu: Option<Res<MainMenu>>,
+    mut entries_query: Query<(&MainMenuEntry, &mut BackgroundColor)>,
+) {
+    let Some(menu) = menu.filter(|menu| menu.is_changed()) else {
+        return;
+    };
+    for (entry, mut background) in entries_query.iter_mut() {
+        background.0 = if entry.0 == menu.selected {
+            SELECTED_ENTRY_COLOR
+        } else {
+            ENTRY_COLOR
+        };
+    }
+}
+
+fn despawn_main_menu_screen(
+    mut commands: Commands,
+    screens_query: Query<Entity, With<MainMenuScreen>>,
+) {
+    for entity in screens_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GameMode;
+
+    #[test]
+    fn test_main_menu_entries() {
+        for (preset, _) in MAIN_MENU_ENTRIES {
+            assert!(rules_preset(preset).is_some(), "{}", preset);
+        }
+        let range = MAIN_MENU_ENTRIES.iter().find_map(|&(preset, _)| {
+            rules_preset(preset).filter(|r| r.mode == GameMode::TargetRange)
+        });
+        assert!(range.is_some());
+    }
+}
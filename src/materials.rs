@@ -9,6 +9,8 @@ impl Plugin for MaterialsPlugin {
         app.add_plugins((
             Material2dPlugin::<GlowMaterial>::default(),
             Material2dPlugin::<HueOffsetMaterial>::default(),
+            Material2dPlugin::<ExplosionMaterial>::default(),
+            Material2dPlugin::<DayNightMaterial>::default(),
         ));
     }
 }
@@ -39,6 +41,8 @@ pub struct HueOffsetMaterial {
     #[texture(1)]
     #[sampler(2)]
     pub texture: Handle<Image>,
+    #[uniform(3)]
+    pub alpha: f32,
 }
 
 // All functions on `Material2d` have default impls. You only need to implement the
@@ -48,3 +52,40 @@ impl Material2d for HueOffsetMaterial {
         "shaders/hue_material.wgsl".into()
     }
 }
+
+/// Animated explosion: fireball with hot ring on its edge
+/// and shockwave running ahead of it.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct ExplosionMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    /// Radius of fireball relative to the half size of mesh.
+    #[uniform(1)]
+    pub radius: f32,
+    /// Part of the explosion's lifetime which has passed.
+    #[uniform(2)]
+    pub progress: f32,
+    #[uniform(3)]
+    pub opacity: f32,
+}
+
+impl Material2d for ExplosionMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/explosion_material.wgsl".into()
+    }
+}
+
+/// Color grading of the game field according to the time of day.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct DayNightMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    #[uniform(1)]
+    pub strength: f32,
+}
+
+impl Material2d for DayNightMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/day_night_material.wgsl".into()
+    }
+}
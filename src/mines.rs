@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::ai::AiController;
+use crate::components::{Owner, Position};
+use crate::explosion::{spawn_explosion, Explosion};
+use crate::game_field::GameField;
+use crate::game_plugin::AppState;
+use crate::landscape::{Landscape, SubsidenceFinishedEvent};
+use crate::missile::Missile;
+use crate::net::{ClientSession, NetSlots, SpectatorSession, HOST_CLIENT_ID};
+use crate::scanner::Revealed;
+use crate::tank::{AllTanksPlacedEvent, CurrentTank, Tank, TankThrowing};
+
+/// Distance between a mine and the center of thrown tank
+/// at which the mine detonates.
+const TRIGGER_RADIUS: f32 = 25.;
+const MINE_RADIUS: f32 = 3.;
+
+pub struct MinesPlugin;
+
+impl Plugin for MinesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MineDetonatedEvent>()
+            .add_systems(
+                Update,
+                (
+                    finish_mine_laying_system,
+                    settle_mines_system,
+                    detonate_mines_system,
+                    mines_visibility_system,
+                ),
+            )
+            .add_systems(OnEnter(AppState::RoundSetup), despawn_mines_system);
+    }
+}
+
+/// Missile which becomes a mine when it lands.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct MineLayer;
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Mine {
+    /// Number of player who has laid the mine.
+    pub player_number: Option<u8>,
+    /// Mine is visible for all players.
+    pub revealed: bool,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MineDetonatedEvent {
+    pub position: Vec2,
+    pub tank_entity: Entity,
+}
+
+/// Marks that a mine has been laid during the current frame.
+#[derive(Debug, Clone, Copy, Component)]
+struct NewMine;
+
+pub fn spawn_mine(commands: &mut Commands, game_field: &GameField, position: Vec2, owner: Owner) {
+    debug!("Lay mine at {:?}", position);
+    let mine_circle = shapes::Circle {
+        radius: MINE_RADIUS,
+        ..default()
+    };
+    let mine_entity = commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&mine_circle),
+                spatial: SpatialBundle {
+                    transform: Transform::from_translation(position.extend(1.)),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                ..default()
+            },
+            Fill::color(Color::rgb(0.8, 0.1, 0.1)),
+            Position(position),
+            owner,
+            Mine {
+                player_number: None,
+                revealed: false,
+            },
+            NewMine,
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(mine_entity);
+}
+
+/// Remembers owners of new mines and finishes the turn if nothing else
+/// is flying or exploding, because laid mine doesn't explode.
+fn finish_mine_laying_system(
+    mut commands: Commands,
+    mut new_mines: Query<(Entity, &mut Mine, &Owner), With<NewMine>>,
+    tanks: Query<&Tank>,
+    missiles: Query<(), With<Missile>>,
+    explosions: Query<(), With<Explosion>>,
+    mut placed_events: EventWriter<AllTanksPlacedEvent>,
+) {
+    if new_mines.is_empty() {
+        return;
+    }
+    for (entity, mut mine, owner) in new_mines.iter_mut() {
+        mine.player_number = tanks.get(owner.0).ok().map(|tank| tank.player_number);
+        commands.entity(entity).remove::<NewMine>();
+    }
+    if missiles.is_empty() && explosions.is_empty() {
+        placed_events.send(AllTanksPlacedEvent);
+    }
+}
+
+/// Returns height at which a mine lays on the surface of landscape.
+pub(crate) fn settle_height(landscape: &Landscape, x: i32, y: i32) -> i32 {
+    if y <= 0 {
+        return y;
+    }
+    let from = Vec2::new(x as f32, (y - 1) as f32);
+    landscape
+        .raycast(from, Vec2::new(x as f32, 0.))
+        .map_or(0, |point| point.y + 1)
+}
+
+/// Mines fall together with the landscape under them.
+fn settle_mines_system(
+    game_field: Option<Res<GameField>>,
+    mut finished_events: EventReader<SubsidenceFinishedEvent>,
+    mut mines: Query<&mut Position, With<Mine>>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    if finished_events.read().count() == 0 {
+        return;
+    }
+    for mut position in mines.iter_mut() {
+        let (x, y) = (position.0.x as i32, position.0.y as i32);
+        position.0.y = settle_height(&game_field.landscape, x, y) as f32;
+    }
+}
+
+/// Mines are detonated by thrown tanks which have really moved
+/// since the previous frame. All tanks are thrown after subsidence
+/// of landscape, but most of them stay in place.
+fn detonate_mines_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    mines: Query<(Entity, &Position, &Owner), With<Mine>>,
+    tanks: Query<(Entity, &Position, Has<TankThrowing>), With<Tank>>,
+    mut previous_positions: Local<HashMap<Entity, Vec2>>,
+    mut detonated_events: EventWriter<MineDetonatedEvent>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let moved_tanks: Vec<(Entity, Vec2)> = tanks
+        .iter()
+        .filter(|&(entity, position, is_thrown)| {
+            is_thrown
+                && previous_positions
+                    .get(&entity)
+                    .is_some_and(|&previous| previous != position.0)
+        })
+        .map(|(entity, position, _)| (entity, position.0))
+        .collect();
+    *previous_positions = tanks
+        .iter()
+        .map(|(entity, position, _)| (entity, position.0))
+        .collect();
+
+    for (mine_entity, &Position(mine_position), &owner) in mines.iter() {
+        let triggered_by = moved_tanks
+            .iter()
+            .find(|(_, tank_position)| tank_position.distance(mine_position) <= TRIGGER_RADIUS);
+        if let Some(&(tank_entity, _)) = triggered_by {
+            info!("Mine at {:?} has been detonated", mine_position);
+            commands.entity(mine_entity).despawn_recursive();
+            spawn_explosion(&mut commands, &game_field, mine_position, Some(owner));
+            detonated_events.send(MineDetonatedEvent {
+                position: mine_position,
+                tank_entity,
+            });
+        }
+    }
+}
+
+/// Returns number of player who looks at the screen of this machine.
+/// It is the player of client or host in network game, and the current
+/// human player in local game. Spectators see only revealed mines.
+fn local_player(
+    spectator: Option<Res<SpectatorSession>>,
+    client_session: Option<Res<ClientSession>>,
+    slots: Option<Res<NetSlots>>,
+    current_tanks: &Query<(&Tank, Has<AiController>), With<CurrentTank>>,
+) -> Option<u8> {
+    if spectator.is_some() {
+        return None;
+    }
+    if let Some(session) = client_session {
+        return Some(session.player);
+    }
+    if let Some(slots) = slots {
+        return slots.player_of(HOST_CLIENT_ID);
+    }
+    current_tanks
+        .iter()
+        .find(|(_, is_bot)| !is_bot)
+        .map(|(tank, _)| tank.player_number)
+}
+
+/// Mines are hidden from opponents of their owner unless they
+/// have been revealed or scanned.
+fn mines_visibility_system(
+    spectator: Option<Res<SpectatorSession>>,
+    client_session: Option<Res<ClientSession>>,
+    slots: Option<Res<NetSlots>>,
+    current_tanks: Query<(&Tank, Has<AiController>), With<CurrentTank>>,
+    mut mines: Query<(&Mine, &mut Visibility, Has<Revealed>)>,
+) {
+    let player = local_player(spectator, client_session, slots, &current_tanks);
+    for (mine, mut visibility, scanned) in mines.iter_mut() {
+        let visible = mine.revealed
+            || scanned
+            || (mine.player_number.is_some() && mine.player_number == player);
+        let new_visibility = if visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+    }
+}
+
+fn despawn_mines_system(mut commands: Commands, mines: Query<Entity, With<Mine>>) {
+    for entity in mines.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::landscape_buffer::LandscapeStorage;
+
+    #[test]
+    fn test_settle_height() {
+        let landscape = Landscape::new(200, 100, 3, LandscapeStorage::default(), None).unwrap();
+        for x in [10, 100, 190] {
+            let y = settle_height(&landscape, x, 99);
+            assert!(!landscape.is_not_empty(x, y));
+            assert!(y == 0 || landscape.is_not_empty(x, y - 1));
+        }
+    }
+}
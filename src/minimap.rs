@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+
+use crate::camera::{MainCamera, SpectatorCamera};
+use crate::components::Position;
+use crate::game_field::GameField;
+use crate::rules::GameRules;
+use crate::tank::{player_color, Tank};
+
+const MINIMAP_WIDTH: f32 = 240.;
+const MARKER_SIZE: f32 = 6.;
+const BACKGROUND_COLOR: Color = Color::rgba(0., 0., 0., 0.5);
+const FRAME_COLOR: Color = Color::WHITE;
+
+/// Small view of the whole game field with tanks and the region
+/// visible by camera. It is shown while the field doesn't fit
+/// into the window.
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (
+                setup_minimap_system,
+                (update_minimap_view_system, update_minimap_markers_system),
+            )
+                .chain()
+                .run_if(resource_exists::<SpectatorCamera>),
+        );
+    }
+}
+
+#[derive(Component)]
+struct Minimap {
+    /// Texture of landscape shown by the minimap.
+    image: AssetId<Image>,
+    size: Vec2,
+    field_size: Vec2,
+}
+
+/// Frame of the region visible by camera.
+#[derive(Component)]
+struct MinimapView;
+
+#[derive(Component)]
+struct MinimapMarker(Entity);
+
+/// Returns position on the minimap (from its top left corner)
+/// of the point of game field.
+fn minimap_point(point: Vec2, field_size: Vec2, size: Vec2) -> Vec2 {
+    let point = point.clamp(Vec2::ZERO, field_size) / field_size;
+    Vec2::new(point.x, 1. - point.y) * size
+}
+
+/// Spawns minimap for landscape of the current round.
+fn setup_minimap_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    minimap_query: Query<(Entity, &Minimap)>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let texture = game_field.landscape.texture_handle();
+    if let Ok((entity, minimap)) = minimap_query.get_single() {
+        if minimap.image == texture.id() {
+            return;
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+    let field_size = Vec2::new(game_field.width as f32, game_field.height as f32);
+    let size = Vec2::new(
+        MINIMAP_WIDTH,
+        (MINIMAP_WIDTH * field_size.y / field_size.x).round(),
+    );
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(10.),
+                    bottom: Val::Px(10.),
+                    width: Val::Px(size.x),
+                    height: Val::Px(size.y),
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Minimap {
+                image: texture.id(),
+                size,
+                field_size,
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                image: UiImage::new(texture.clone()),
+                ..default()
+            });
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        border: UiRect::all(Val::Px(1.)),
+                        ..default()
+                    },
+                    border_color: FRAME_COLOR.into(),
+                    ..default()
+                },
+                MinimapView,
+            ));
+        });
+}
+
+/// Shows minimap while camera doesn't see the whole field
+/// and moves the frame of visible region.
+fn update_minimap_view_system(
+    spectator_camera: Res<SpectatorCamera>,
+    mut minimap_query: Query<(&Minimap, &mut Visibility)>,
+    mut view_query: Query<&mut Style, With<MinimapView>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+) {
+    let Ok((minimap, mut visibility)) = minimap_query.get_single_mut() else {
+        return;
+    };
+    let new_visibility = if spectator_camera.home_scale() > 1. {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != new_visibility {
+        *visibility = new_visibility;
+    }
+    let (Ok(mut style), Ok((transform, projection))) =
+        (view_query.get_single_mut(), camera_query.get_single())
+    else {
+        return;
+    };
+    let center = transform.translation.truncate();
+    let top_left = minimap_point(
+        center + Vec2::new(projection.area.min.x, projection.area.max.y),
+        minimap.field_size,
+        minimap.size,
+    );
+    let bottom_right = minimap_point(
+        center + Vec2::new(projection.area.max.x, projection.area.min.y),
+        minimap.field_size,
+        minimap.size,
+    );
+    style.left = Val::Px(top_left.x);
+    style.top = Val::Px(top_left.y);
+    style.width = Val::Px(bottom_right.x - top_left.x);
+    style.height = Val::Px(bottom_right.y - top_left.y);
+}
+
+/// Keeps one marker for every tank.
+fn update_minimap_markers_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    rules: Res<GameRules>,
+    minimap_query: Query<(Entity, &Minimap)>,
+    tanks_query: Query<(Entity, &Tank, &Position)>,
+    mut markers_query: Query<(Entity, &MinimapMarker, &mut Style)>,
+) {
+    let (Some(game_field), Ok((minimap_entity, minimap))) =
+        (game_field, minimap_query.get_single())
+    else {
+        return;
+    };
+    let marker_position = |position: Vec2| {
+        minimap_point(position, minimap.field_size, minimap.size) - MARKER_SIZE / 2.
+    };
+    let mut marked = Vec::new();
+    for (marker_entity, &MinimapMarker(tank_entity), mut style) in markers_query.iter_mut() {
+        let Ok((_, _, position)) = tanks_query.get(tank_entity) else {
+            commands.entity(marker_entity).despawn_recursive();
+            continue;
+        };
+        let point = marker_position(position.0);
+        style.left = Val::Px(point.x);
+        style.top = Val::Px(point.y);
+        marked.push(tank_entity);
+    }
+    let players_count = game_field.players_count();
+    for (tank_entity, tank, position) in tanks_query.iter() {
+        if marked.contains(&tank_entity) {
+            continue;
+        }
+        let point = marker_position(position.0);
+        let color = player_color(tank.player_number, players_count, rules.teams.as_ref());
+        let marker = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(point.x),
+                        top: Val::Px(point.y),
+                        width: Val::Px(MARKER_SIZE),
+                        height: Val::Px(MARKER_SIZE),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                MinimapMarker(tank_entity),
+            ))
+            .id();
+        commands.entity(minimap_entity).add_child(marker);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimap_point() {
+        let field_size = Vec2::new(4000., 1000.);
+        let size = Vec2::new(240., 60.);
+        assert_eq!(
+            minimap_point(Vec2::ZERO, field_size, size),
+            Vec2::new(0., 60.)
+        );
+        assert_eq!(
+            minimap_point(Vec2::new(1000., 500.), field_size, size),
+            Vec2::new(60., 30.)
+        );
+        // Points outside of the field are kept on the edge of minimap.
+        assert_eq!(
+            minimap_point(Vec2::new(5000., 2000.), field_size, size),
+            Vec2::new(240., 0.)
+        );
+    }
+}
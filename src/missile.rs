@@ -3,20 +3,38 @@ use std::f32::consts::PI;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 
+use crate::anti_gravity::{spawn_field, AntiGravityCharge};
 use crate::ballistics::Ballistics;
-use crate::components::Position;
-use crate::explosion::spawn_explosion;
+use crate::collider::{update_spatial_query_system, Collider, SpatialQuery};
+use crate::components::{Owner, Position};
+use crate::explosion::{spawn_explosion_of, Explosion, STANDARD_RADIUS};
 use crate::game_field::GameField;
-use crate::tank::Tank;
+use crate::grappling_hook::{GrapplingHook, HookLandedEvent};
+use crate::mines::{spawn_mine, MineLayer};
+use crate::portal::{map_velocity, spawn_portal, Portal, PortalCharge};
+use crate::rules::GameRules;
+use crate::weapons::WeaponDefinition;
 
 const TIME_SCALE: f32 = 3.0;
+/// Distance between paths of missiles at which they intercept each other.
+const INTERCEPTION_RADIUS: f32 = 3.;
 
 pub struct MissilesPlugin;
 
 impl Plugin for MissilesPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<MissileMovedEvent>()
-            .add_systems(Update, missile_moving_system2)
+            .add_event::<InterceptionEvent>()
+            .add_systems(
+                Update,
+                (
+                    missile_moving_system2,
+                    missile_interception_system,
+                    check_missile_collisions_system,
+                )
+                    .chain()
+                    .after(update_spatial_query_system),
+            )
             .add_systems(PostUpdate, despawn_dead_missiles);
     }
 }
@@ -24,11 +42,15 @@ impl Plugin for MissilesPlugin {
 #[derive(Event)]
 pub struct MissileMovedEvent {
     pub missile: Entity,
+    /// Pixel of missile before the move.
+    pub start: (i32, i32),
     pub path: Vec<(i32, i32)>,
 }
 
-pub trait HasCollision {
-    fn has_collision(&self, entity_position: Vec2, point: Vec2) -> bool;
+/// Two missiles of different tanks have detonated each other.
+#[derive(Event)]
+pub struct InterceptionEvent {
+    pub owners: [Owner; 2],
 }
 
 #[derive(Debug, Default, Clone, Copy, Component)]
@@ -37,6 +59,10 @@ struct DeadPosition {
     y: i32,
 }
 
+/// Explosion which is spawned where the missile has hit.
+#[derive(Debug, Clone, Copy, Component)]
+struct Warhead(Explosion);
+
 #[derive(Debug, Clone, Copy, Component)]
 pub struct Missile {
     ballistics: Ballistics,
@@ -52,15 +78,63 @@ impl Missile {
         }
     }
 
+    /// Missile is slowed down by air resistance.
+    pub fn with_drag(self, drag: f32) -> Missile {
+        Missile {
+            ballistics: self.ballistics.drag(drag),
+        }
+    }
+
     #[inline]
     pub fn cur_pos(&self) -> Vec2 {
         self.ballistics.cur_pos()
     }
 
-    pub fn update<F>(&mut self, borders: (i32, i32), mut has_collision: F) -> Option<Vec2>
+    #[inline]
+    pub fn acceleration(&self) -> Vec2 {
+        self.ballistics.acceleration()
+    }
+
+    pub fn set_acceleration(&mut self, acceleration: Vec2) {
+        self.ballistics.set_acceleration(acceleration);
+    }
+
+    #[inline]
+    pub fn velocity(&self) -> Vec2 {
+        self.ballistics.pos_and_velocity().1
+    }
+
+    /// Moves the missile to given position and continues its flight
+    /// with given velocity.
+    pub fn teleport(&mut self, position: Vec2, velocity: Vec2) {
+        self.ballistics.restart(position, velocity);
+    }
+
+    /// Returns points of missile's path during given flight time
+    /// without moving the missile itself.
+    pub fn predict_path(
+        &self,
+        duration: f32,
+        borders: (i32, i32),
+        max_points: usize,
+    ) -> Vec<(i32, i32)> {
+        let mut ballistics = self.ballistics;
+        ballistics
+            .positions_iter(Some(duration), Some(borders))
+            .take(max_points)
+            .collect()
+    }
+
+    pub fn update<F>(
+        &mut self,
+        delta: f32,
+        borders: (i32, i32),
+        mut has_collision: F,
+    ) -> Option<Vec2>
     where
         F: FnMut(i32, i32) -> bool,
     {
+        self.ballistics.tick(delta);
         for (x, y) in self.ballistics.positions_iter(None, Some(borders)) {
             if has_collision(x, y) || y <= 0 {
                 return Some(Vec2::new(x as f32, y as f32));
@@ -71,7 +145,14 @@ impl Missile {
     }
 }
 
-pub fn spawn_missile(commands: &mut Commands, game_field: &GameField, missile: Missile) {
+/// Spawns missile of given weapon, `None` means the standard missile.
+pub fn spawn_missile(
+    commands: &mut Commands,
+    game_field: &GameField,
+    missile: Missile,
+    weapon: Option<&WeaponDefinition>,
+    owner: Option<Owner>,
+) -> Entity {
     let position = missile.cur_pos();
     let missile_color = Color::rgb(1., 1., 1.);
     let missile_circle = shapes::Circle {
@@ -85,97 +166,219 @@ pub fn spawn_missile(commands: &mut Commands, game_field: &GameField, missile: M
         ))),
         ..Default::default()
     };
-    let missile_entity = commands
-        .spawn((
-            missile_bundle,
-            Fill::color(missile_color),
-            missile,
-            Position(position),
-        ))
-        .id();
+    let mut missile_commands = commands.spawn((
+        missile_bundle,
+        Fill::color(missile_color),
+        missile,
+        Warhead(weapon.map_or(Explosion::new(STANDARD_RADIUS), WeaponDefinition::explosion)),
+        Position(position),
+    ));
+    if let Some(owner) = owner {
+        missile_commands.insert(owner);
+    }
+    let missile_entity = missile_commands.id();
     commands
         .entity(game_field.parent_entity)
         .add_child(missile_entity);
-}
-
-pub fn missile_moving_system(
-    mut commands: Commands,
-    game_field: Res<GameField>,
-    tank_position_query: Query<(&Tank, &Position), Without<Missile>>,
-    mut missile_query: Query<(Entity, &mut Missile, &mut Position)>,
-) {
-    let landscape = &game_field.landscape;
-    let size = landscape.size();
-    let borders = (size.0 as i32, size.1 as i32);
-    for (missile_entity, mut missile, mut missile_position) in missile_query.iter_mut() {
-        let is_hit = missile
-            .update(borders, |x, y| {
-                landscape.is_not_empty(x, y)
-                    || tank_position_query.iter().any(|(tank, position)| {
-                        tank.has_collision(position.0, (x as f32, y as f32))
-                    })
-            })
-            .is_some();
-        let current_position = missile.cur_pos();
-        missile_position.0 = current_position;
-
-        if is_hit {
-            kill_missile(
-                &mut commands,
-                missile_entity,
-                current_position.x as i32,
-                current_position.y as i32,
-            );
-            // commands.entity(missile_entity).despawn();
-            // spawn_explosion(&mut commands, &game_field, current_position);
-        }
-    }
+    missile_entity
 }
 
 pub fn missile_moving_system2(
+    time: Res<Time>,
     game_field: Res<GameField>,
     mut ev_missile_moved: EventWriter<MissileMovedEvent>,
     mut missile_query: Query<(Entity, &mut Missile, &mut Position)>,
+    portals_query: Query<&Portal>,
 ) {
     let landscape = &game_field.landscape;
     let size = landscape.size();
     let borders = (size.0 as i32, size.1 as i32);
+    // Pairs of entry and exit portals
+    let portals: Vec<(&Portal, &Portal)> = portals_query
+        .iter()
+        .filter_map(|entry| {
+            let exit = portals_query.get(entry.linked?).ok()?;
+            Some((entry, exit))
+        })
+        .collect();
 
     for (missile_entity, mut missile, mut missile_position) in missile_query.iter_mut() {
+        let start = missile_position.0.floor().as_ivec2();
         let mut path: Vec<(i32, i32)> = Vec::new();
-        missile.update(borders, |x, y| {
+        let mut entered_portals = None;
+        missile.update(time.delta_seconds(), borders, |x, y| {
             path.push((x, y));
-            false
+            if portals.is_empty() || landscape.is_not_empty(x, y) {
+                return false;
+            }
+            let point = Vec2::new(x as f32, y as f32);
+            entered_portals = portals.iter().find(|(entry, _)| entry.contains(point));
+            entered_portals.is_some()
         });
+        // Path of the missile is continued from the exit portal
+        // during the next update.
+        if let Some((entry, exit)) = entered_portals {
+            let velocity = map_velocity(missile.velocity(), entry.normal, exit.normal);
+            missile.teleport(exit.exit_position(), velocity);
+        }
         let current_position = missile.cur_pos();
         missile_position.0 = current_position;
 
         if !path.is_empty() {
             ev_missile_moved.send(MissileMovedEvent {
                 missile: missile_entity,
+                start: (start.x, start.y),
                 path,
             });
         }
     }
 }
 
+/// Returns the first points of given paths which are closer
+/// than interception radius to each other.
+fn meeting_points(path: &[(i32, i32)], other_path: &[(i32, i32)]) -> Option<[(i32, i32); 2]> {
+    let collider = Collider::circle(INTERCEPTION_RADIUS);
+    path.iter().find_map(|&(x, y)| {
+        let point = Vec2::new(x as f32, y as f32);
+        other_path
+            .iter()
+            .find(|&&(ox, oy)| collider.contains(Vec2::new(ox as f32, oy as f32) - point))
+            .map(|&other| [(x, y), other])
+    })
+}
+
+/// Detonates missiles of different tanks which paths have met
+/// during the current update, if interception is enabled by rules.
+fn missile_interception_system(
+    mut commands: Commands,
+    rules: Res<GameRules>,
+    mut moved_events: EventReader<MissileMovedEvent>,
+    owners_query: Query<&Owner, With<Missile>>,
+    mut interception_events: EventWriter<InterceptionEvent>,
+) {
+    if !rules.missile_interception {
+        moved_events.clear();
+        return;
+    }
+    let moves: Vec<(&MissileMovedEvent, Owner)> = moved_events
+        .read()
+        .filter_map(|event| Some((event, *owners_query.get(event.missile).ok()?)))
+        .collect();
+    let mut intercepted: Vec<Entity> = Vec::new();
+    for (i, &(event, owner)) in moves.iter().enumerate() {
+        for &(other_event, other_owner) in moves[i + 1..].iter() {
+            if owner == other_owner
+                || intercepted.contains(&event.missile)
+                || intercepted.contains(&other_event.missile)
+            {
+                continue;
+            }
+            let Some([point, other_point]) = meeting_points(&event.path, &other_event.path) else {
+                continue;
+            };
+            debug!("Missiles have intercepted each other in point {:?}", point);
+            kill_missile(&mut commands, event.missile, point.0, point.1);
+            kill_missile(
+                &mut commands,
+                other_event.missile,
+                other_point.0,
+                other_point.1,
+            );
+            intercepted.extend([event.missile, other_event.missile]);
+            interception_events.send(InterceptionEvent {
+                owners: [owner, other_owner],
+            });
+        }
+    }
+}
+
+/// Stops missiles in the first point where segments between pixels
+/// of their paths cross any collider, so thin colliders can't be
+/// passed through between pixels.
+fn check_missile_collisions_system(
+    mut commands: Commands,
+    spatial_query: Res<SpatialQuery>,
+    mut moved_events: EventReader<MissileMovedEvent>,
+) {
+    for event in moved_events.read() {
+        let mut start = Vec2::new(event.start.0 as f32, event.start.1 as f32);
+        for &(x, y) in event.path.iter() {
+            let end = Vec2::new(x as f32, y as f32);
+            if let Some((entity, t)) = spatial_query.segment_hit(start, end) {
+                let point = start.lerp(end, t).round().as_ivec2();
+                debug!("Missile hit {:?} in point {:?}", entity, point);
+                kill_missile(&mut commands, event.missile, point.x, point.y);
+                break;
+            }
+            start = end;
+        }
+    }
+}
+
 pub fn kill_missile(commands: &mut Commands, entity_id: Entity, x: i32, y: i32) {
     if let Some(mut entity) = commands.get_entity(entity_id) {
         entity.try_insert(DeadPosition { x, y });
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn despawn_dead_missiles(
     mut commands: Commands,
     game_field: Res<GameField>,
-    query: Query<(Entity, &DeadPosition), With<Missile>>,
+    query: Query<
+        (
+            Entity,
+            &DeadPosition,
+            &Warhead,
+            Option<&Owner>,
+            Has<MineLayer>,
+            Has<GrapplingHook>,
+            Has<PortalCharge>,
+            Option<&AntiGravityCharge>,
+        ),
+        With<Missile>,
+    >,
+    mut hook_events: EventWriter<HookLandedEvent>,
 ) {
-    for (entity, dead_pos) in query.iter() {
+    for (entity, dead_pos, &Warhead(explosion), owner, is_mine, is_hook, is_portal, charge) in
+        query.iter()
+    {
         commands.entity(entity).despawn_recursive();
-        spawn_explosion(
-            &mut commands,
-            &game_field,
-            Vec2::new(dead_pos.x as f32, dead_pos.y as f32),
-        );
+        let position = Vec2::new(dead_pos.x as f32, dead_pos.y as f32);
+        if let Some(&charge) = charge {
+            spawn_field(&mut commands, &game_field, position, charge);
+            continue;
+        }
+        match owner {
+            Some(&owner) if is_mine => spawn_mine(&mut commands, &game_field, position, owner),
+            Some(&owner) if is_portal => spawn_portal(&mut commands, &game_field, position, owner),
+            Some(&owner) if is_hook => {
+                hook_events.send(HookLandedEvent {
+                    tank_entity: owner.0,
+                    position,
+                });
+            }
+            _ => spawn_explosion_of(
+                &mut commands,
+                &game_field,
+                position,
+                explosion,
+                owner.copied(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meeting_points() {
+        let path = [(0, 0), (2, 2), (4, 4), (6, 6), (8, 8)];
+        let crossing = [(10, 0), (8, 2), (6, 4), (4, 6)];
+        assert_eq!(meeting_points(&path, &crossing), Some([(4, 4), (6, 4)]));
+        let parallel = [(0, 10), (2, 12), (4, 14)];
+        assert_eq!(meeting_points(&path, &parallel), None);
     }
 }
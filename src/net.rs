@@ -0,0 +1,951 @@
+//! Transport-agnostic messages of network protocol.
+//!
+//! Each side of connection sends `NetMessage::Hello` first. The host checks
+//! compatibility of the client with `check_compatibility()` and answers
+//! by `HelloAccepted` or `HelloRejected` with the reason readable by player.
+//! Messages are encoded as JSON, so new optional fields may be added
+//! without bumping of `PROTOCOL_VERSION`.
+//!
+//! Transport layer sends `SendNetMessage` events to other side of connection
+//! and emits `NetMessageReceived` events for incoming messages. The host
+//! also gets `NetMessageFrom` events which tell the sender of messages.
+//!
+//! The host keeps state of player slots in `NetSlots` resource and
+//! may fill empty slots with bots.
+//!
+//! Every connected player gets a session token. Tank of the disconnected
+//! player is controlled by a bot for `NetSlots::reconnect_turns` turns
+//! of the player. Within this time the client may reconnect with
+//! `Hello::reconnect()` and get its tank back with the current state
+//! of the game. After that the bot keeps the tank till the end of match.
+//!
+//! Clients which join with `SessionRole::Spectator` don't take slots and
+//! can't act. The host sends them `StateSnapshot` messages, and the
+//! spectator applies them instead of playing the game.
+//!
+//! Shots of clients are validated by the host with `validate_shot()`.
+//! Shots out of turn are rejected, clients which send shots impossible
+//! by rules of the game are kicked and their tanks are given to bots.
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiController;
+use crate::chat::ChatMessage;
+use crate::components::Position;
+use crate::desync::StateHash;
+use crate::game_field::GameField;
+use crate::landscape::Landscape;
+use crate::lobby::{LobbyRequest, LobbyState};
+use crate::rules::{validate_shot, GameRules, Shot};
+use crate::simulation::ShootCommand;
+use crate::tank::{AimingTank, Health, Tank};
+use crate::turn::TurnStartedEvent;
+use crate::weapons::{TankWeapon, Weapons};
+
+/// Interval between state snapshots sent to spectators (seconds).
+const SNAPSHOT_INTERVAL: f64 = 0.1;
+/// Count of turns of disconnected player during which the player
+/// may reconnect.
+pub const DEFAULT_RECONNECT_TURNS: usize = 3;
+
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NetMessageReceived>()
+            .add_event::<SendNetMessage>()
+            .add_event::<PlayerConnectedEvent>()
+            .add_event::<PlayerDisconnectedEvent>()
+            .add_event::<SpectatorConnectedEvent>()
+            .add_event::<SpectatorDisconnectedEvent>()
+            .add_event::<SendNetMessageTo>()
+            .add_event::<NetMessageFrom>()
+            .add_event::<ReconnectRequestEvent>()
+            .add_event::<KickClientEvent>()
+            .add_systems(
+                PreUpdate,
+                (
+                    update_slots_system,
+                    reconnect_system,
+                    fill_slots_with_bots_system,
+                )
+                    .chain()
+                    .run_if(resource_exists::<NetSlots>),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        expire_disconnected_slots_system,
+                        remote_shots_system,
+                        sync_bot_tanks_system,
+                        send_snapshots_system,
+                    )
+                        .chain()
+                        .run_if(resource_exists::<NetSlots>),
+                    // The host is the authority, state sent by clients is ignored.
+                    (store_session_token_system, apply_snapshot_system)
+                        .run_if(not(resource_exists::<NetSlots>)),
+                ),
+            );
+    }
+}
+
+/// Version of network protocol. It must be increased on every
+/// incompatible change of messages.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Features and content which must be the same on both sides of connection.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Names of mods in the form "name@version", sorted by name.
+    #[serde(default)]
+    pub mods: Vec<String>,
+    /// Result of `GameRules::stable_hash()`.
+    pub rules_hash: u64,
+}
+
+impl Capabilities {
+    pub fn new(rules: &GameRules, mut mods: Vec<String>) -> Self {
+        mods.sort();
+        Self {
+            mods,
+            rules_hash: rules.stable_hash(),
+        }
+    }
+}
+
+/// Role of the client in the game.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionRole {
+    #[default]
+    Player,
+    /// Client only watches the game.
+    Spectator,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hello {
+    pub protocol_version: u32,
+    /// Version of the game, for messages only.
+    pub game_version: String,
+    pub capabilities: Capabilities,
+    #[serde(default)]
+    pub role: SessionRole,
+    /// Token of the previous session of player who reconnects.
+    #[serde(default)]
+    pub session_token: Option<SessionToken>,
+}
+
+impl Hello {
+    pub fn new(capabilities: Capabilities) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            capabilities,
+            role: SessionRole::Player,
+            session_token: None,
+        }
+    }
+
+    /// Hello of player who reconnects to take back their tank.
+    pub fn reconnect(capabilities: Capabilities, session_token: SessionToken) -> Self {
+        Self {
+            session_token: Some(session_token),
+            ..Self::new(capabilities)
+        }
+    }
+
+    pub fn spectator(capabilities: Capabilities) -> Self {
+        Self {
+            role: SessionRole::Spectator,
+            ..Self::new(capabilities)
+        }
+    }
+}
+
+/// State of one tank in `StateSnapshot`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TankSnapshot {
+    pub player: u8,
+    pub position: [f32; 2],
+    pub gun_angle: f32,
+    pub power: f32,
+    pub health: u8,
+}
+
+/// State of the game which the host sends to spectators
+/// and reconnected players.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// Surviving tanks, others have been destroyed.
+    pub tanks: Vec<TankSnapshot>,
+    /// Heights of columns of landscape. They are sent only
+    /// after changes of landscape and to new spectators.
+    #[serde(default)]
+    pub surface_heights: Option<Vec<u16>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NetMessage {
+    Hello(Hello),
+    HelloAccepted,
+    HelloRejected {
+        reason: String,
+    },
+    /// Request of client to measure offset of its clock from the host clock.
+    TimeSyncRequest {
+        client_time: f64,
+    },
+    TimeSyncResponse {
+        client_time: f64,
+        host_time: f64,
+    },
+    /// Turn must be done before `deadline` by the host clock.
+    TurnTimerStarted {
+        turn_number: usize,
+        deadline: f64,
+    },
+    /// Time of the turn is over, the host has made the shot instead of player.
+    TurnTimedOut {
+        turn_number: usize,
+    },
+    /// Clock of the player in blitz match has started at `host_time`
+    /// with `remaining` seconds in their time bank.
+    MatchClockStarted {
+        player: u8,
+        remaining: f64,
+        host_time: f64,
+    },
+    MatchClockStopped {
+        player: u8,
+        remaining: f64,
+    },
+    /// Time bank of the player is empty, the player has lost.
+    PlayerForfeited {
+        player: u8,
+    },
+    StateSnapshot(StateSnapshot),
+    Chat(ChatMessage),
+    /// State of lobby sent by the host to the client with given id.
+    LobbyUpdate {
+        lobby: LobbyState,
+        client_id: ClientId,
+    },
+    LobbyRequest(LobbyRequest),
+    /// Token which the client must send to reconnect as the player.
+    SessionToken {
+        player: u8,
+        token: SessionToken,
+    },
+    /// Hash of the game state at start of the turn.
+    StateHash(StateHash),
+    /// Shot of the client's player.
+    Shot(Shot),
+    /// The host hasn't accepted the shot of the client.
+    ShotRejected {
+        reason: String,
+    },
+    /// The host closes connection with the client.
+    Kicked {
+        reason: String,
+    },
+}
+
+impl NetMessage {
+    pub fn encode(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(self)
+    }
+
+    pub fn decode(data: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(data)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeError {
+    IncompatibleVersion {
+        local_version: String,
+        remote_version: String,
+    },
+    IncompatibleMods {
+        /// Mods of host absent on client.
+        missing: Vec<String>,
+        /// Mods of client absent on host.
+        extra: Vec<String>,
+    },
+    RulesMismatch,
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IncompatibleVersion {
+                local_version,
+                remote_version,
+            } => write!(
+                f,
+                "Incompatible version of the game: {} (required {})",
+                remote_version, local_version
+            ),
+            Self::IncompatibleMods { missing, extra } => {
+                write!(f, "Incompatible mods.")?;
+                if !missing.is_empty() {
+                    write!(f, " Missing: {}.", missing.join(", "))?;
+                }
+                if !extra.is_empty() {
+                    write!(f, " Not installed on the host: {}.", extra.join(", "))?;
+                }
+                Ok(())
+            }
+            Self::RulesMismatch => write!(f, "Rules of the game are different from the host"),
+        }
+    }
+}
+
+/// Message received from other side of connection.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct NetMessageReceived(pub NetMessage);
+
+/// Message which must be sent to other side of connection
+/// (to all clients if it is sent by the host).
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct SendNetMessage(pub NetMessage);
+
+/// Message received by the host from the client.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct NetMessageFrom {
+    pub client_id: ClientId,
+    pub message: NetMessage,
+}
+
+/// Message which must be sent to one client only.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct SendNetMessageTo {
+    pub client_id: ClientId,
+    pub message: NetMessage,
+}
+
+pub type ClientId = u64;
+
+/// Id of the player of the host itself.
+pub const HOST_CLIENT_ID: ClientId = 0;
+
+pub type SessionToken = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotState {
+    Empty,
+    Human(ClientId),
+    /// Connection with the client has been lost.
+    Disconnected(ClientId),
+    Bot,
+}
+
+/// Player slots of the game on the host.
+#[derive(Debug, Clone, Resource)]
+pub struct NetSlots {
+    /// State of slot of player with number `i + 1`.
+    slots: Vec<SlotState>,
+    /// Give empty slots to bots.
+    pub fill_with_bots: bool,
+    /// Count of turns of disconnected player during which the player
+    /// may reconnect.
+    pub reconnect_turns: usize,
+    spectators: Vec<ClientId>,
+    /// Session tokens of players.
+    tokens: HashMap<u8, SessionToken>,
+    /// Count of turns of disconnected players played by bots.
+    missed_turns: HashMap<u8, usize>,
+}
+
+impl NetSlots {
+    pub fn new(players_count: u8, fill_with_bots: bool) -> Self {
+        Self {
+            slots: vec![SlotState::Empty; players_count as usize],
+            fill_with_bots,
+            reconnect_turns: DEFAULT_RECONNECT_TURNS,
+            spectators: vec![],
+            tokens: HashMap::default(),
+            missed_turns: HashMap::default(),
+        }
+    }
+
+    pub fn get(&self, player: u8) -> Option<SlotState> {
+        self.slots.get((player as usize).checked_sub(1)?).copied()
+    }
+
+    pub fn set(&mut self, player: u8, state: SlotState) {
+        if let Some(slot) = (player as usize)
+            .checked_sub(1)
+            .and_then(|i| self.slots.get_mut(i))
+        {
+            *slot = state;
+        }
+    }
+
+    pub fn is_bot(&self, player: u8) -> bool {
+        self.get(player) == Some(SlotState::Bot)
+    }
+
+    /// Returns `true` if the player's tank is controlled by a bot,
+    /// including tanks of disconnected players.
+    pub fn is_ai_controlled(&self, player: u8) -> bool {
+        matches!(
+            self.get(player),
+            Some(SlotState::Bot | SlotState::Disconnected(_))
+        )
+    }
+
+    /// Gives a new session token to the player.
+    pub fn issue_token(&mut self, player: u8, token: SessionToken) {
+        self.tokens.insert(player, token);
+    }
+
+    /// Gives the slot of disconnected player with the token to the client.
+    /// Returns number of the player.
+    pub fn reconnect(&mut self, token: SessionToken, client_id: ClientId) -> Option<u8> {
+        let (&player, _) = self.tokens.iter().find(|(_, &t)| t == token)?;
+        if !matches!(self.get(player), Some(SlotState::Disconnected(_))) {
+            return None;
+        }
+        self.set(player, SlotState::Human(client_id));
+        self.missed_turns.remove(&player);
+        Some(player)
+    }
+
+    /// Counts the turn of the player if the player is disconnected.
+    /// The slot is given to a bot after `reconnect_turns` turns.
+    pub fn count_missed_turn(&mut self, player: u8) {
+        if !matches!(self.get(player), Some(SlotState::Disconnected(_))) {
+            return;
+        }
+        let missed_turns = self.missed_turns.entry(player).or_default();
+        *missed_turns += 1;
+        if *missed_turns > self.reconnect_turns {
+            info!("Player {} hasn't reconnected, bot takes the slot", player);
+            self.missed_turns.remove(&player);
+            self.tokens.remove(&player);
+            self.set(player, SlotState::Bot);
+        }
+    }
+
+    /// Gives the slot of the player to a bot for the rest of the match.
+    pub fn kick(&mut self, player: u8) {
+        self.tokens.remove(&player);
+        self.missed_turns.remove(&player);
+        self.set(player, SlotState::Bot);
+    }
+
+    /// Returns number of player controlled by the client.
+    /// Messages with actions of clients without players,
+    /// e.g. spectators, must be ignored.
+    pub fn player_of(&self, client_id: ClientId) -> Option<u8> {
+        let i = self
+            .slots
+            .iter()
+            .position(|&slot| slot == SlotState::Human(client_id))?;
+        Some(i as u8 + 1)
+    }
+
+    pub fn spectators(&self) -> &[ClientId] {
+        &self.spectators
+    }
+
+    pub fn add_spectator(&mut self, client_id: ClientId) {
+        if !self.spectators.contains(&client_id) {
+            self.spectators.push(client_id);
+        }
+    }
+
+    pub fn remove_spectator(&mut self, client_id: ClientId) {
+        self.spectators.retain(|&id| id != client_id);
+    }
+}
+
+/// Session of player on the client, it is required to reconnect.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct ClientSession {
+    pub player: u8,
+    pub token: SessionToken,
+}
+
+/// Resource of the client which has joined the game as spectator.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct SpectatorSession;
+
+/// Client has passed the handshake and took the slot of player.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerConnectedEvent {
+    pub player: u8,
+    pub client_id: ClientId,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerDisconnectedEvent {
+    pub player: u8,
+}
+
+/// Client has sent `Hello` with session token of a disconnected player.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ReconnectRequestEvent {
+    pub client_id: ClientId,
+    pub token: SessionToken,
+}
+
+/// Client has passed the handshake as spectator.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SpectatorConnectedEvent {
+    pub client_id: ClientId,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SpectatorDisconnectedEvent {
+    pub client_id: ClientId,
+}
+
+/// Transport layer must close connection with the client.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct KickClientEvent {
+    pub client_id: ClientId,
+}
+
+fn update_slots_system(
+    mut slots: ResMut<NetSlots>,
+    mut connected_events: EventReader<PlayerConnectedEvent>,
+    mut disconnected_events: EventReader<PlayerDisconnectedEvent>,
+    mut spectator_connected_events: EventReader<SpectatorConnectedEvent>,
+    mut spectator_disconnected_events: EventReader<SpectatorDisconnectedEvent>,
+    mut send_events: EventWriter<SendNetMessageTo>,
+) {
+    for event in connected_events.read() {
+        slots.set(event.player, SlotState::Human(event.client_id));
+        let token = rand::random();
+        slots.issue_token(event.player, token);
+        send_events.send(SendNetMessageTo {
+            client_id: event.client_id,
+            message: NetMessage::SessionToken {
+                player: event.player,
+                token,
+            },
+        });
+    }
+    for event in disconnected_events.read() {
+        if let Some(SlotState::Human(client_id)) = slots.get(event.player) {
+            info!("Player {} has been disconnected", event.player);
+            slots.set(event.player, SlotState::Disconnected(client_id));
+        }
+    }
+    for event in spectator_connected_events.read() {
+        slots.add_spectator(event.client_id);
+    }
+    for event in spectator_disconnected_events.read() {
+        slots.remove_spectator(event.client_id);
+    }
+}
+
+/// Returns the tank to the reconnected player and sends them
+/// the current state of the game.
+fn reconnect_system(
+    mut slots: ResMut<NetSlots>,
+    game_field: Option<Res<GameField>>,
+    tanks_query: Query<(&Tank, &Position, &Health)>,
+    mut reconnect_events: EventReader<ReconnectRequestEvent>,
+    mut send_events: EventWriter<SendNetMessageTo>,
+) {
+    for &ReconnectRequestEvent { client_id, token } in reconnect_events.read() {
+        let message = match slots.reconnect(token, client_id) {
+            Some(player) => {
+                info!("Player {} has reconnected", player);
+                NetMessage::StateSnapshot(StateSnapshot {
+                    tanks: tank_snapshots(&tanks_query),
+                    surface_heights: game_field
+                        .as_ref()
+                        .map(|game_field| surface_heights(&game_field.landscape)),
+                })
+            }
+            None => NetMessage::HelloRejected {
+                reason: "Session has expired".to_string(),
+            },
+        };
+        send_events.send(SendNetMessageTo { client_id, message });
+    }
+}
+
+fn fill_slots_with_bots_system(mut slots: ResMut<NetSlots>) {
+    if !slots.fill_with_bots {
+        return;
+    }
+    for slot in slots.slots.iter_mut() {
+        if *slot == SlotState::Empty {
+            *slot = SlotState::Bot;
+        }
+    }
+}
+
+fn expire_disconnected_slots_system(
+    mut slots: ResMut<NetSlots>,
+    mut turn_started_events: EventReader<TurnStartedEvent>,
+) {
+    for event in turn_started_events.read() {
+        slots.count_missed_turn(event.player_number);
+    }
+}
+
+fn store_session_token_system(
+    mut commands: Commands,
+    mut received_events: EventReader<NetMessageReceived>,
+) {
+    for NetMessageReceived(message) in received_events.read() {
+        if let &NetMessage::SessionToken { player, token } = message {
+            commands.insert_resource(ClientSession { player, token });
+        }
+    }
+}
+
+/// Validates shots of clients before they are made by the host.
+#[allow(clippy::too_many_arguments)]
+fn remote_shots_system(
+    mut slots: ResMut<NetSlots>,
+    weapons: Res<Weapons>,
+    mut received_events: EventReader<NetMessageFrom>,
+    aiming_tanks: Query<(&Tank, &TankWeapon), With<AimingTank>>,
+    mut shoot_commands: EventWriter<ShootCommand>,
+    mut send_events: EventWriter<SendNetMessageTo>,
+    mut kick_events: EventWriter<KickClientEvent>,
+) {
+    let aiming_tank = aiming_tanks.get_single().ok();
+    let current_player = aiming_tank.map(|(tank, _)| tank.player_number);
+    let inventory = aiming_tank.map(|(_, tank_weapon)| (tank_weapon, weapons.as_ref()));
+    for NetMessageFrom { client_id, message } in received_events.read() {
+        let (client_id, &NetMessage::Shot(shot)) = (*client_id, message) else {
+            continue;
+        };
+        // Spectators can't act.
+        let Some(player) = slots.player_of(client_id) else {
+            continue;
+        };
+        let result = if shot.player == player {
+            validate_shot(&shot, current_player, inventory)
+                .map_err(|violation| (violation.is_cheating(), violation.to_string()))
+        } else {
+            // Honest client never sends shots for other players.
+            Err((true, "shot for tank of other player".to_string()))
+        };
+        match result {
+            Ok(()) => {
+                shoot_commands.send(ShootCommand(shot));
+            }
+            Err((false, reason)) => {
+                debug!("Shot of player {} is rejected: {}", player, reason);
+                send_events.send(SendNetMessageTo {
+                    client_id,
+                    message: NetMessage::ShotRejected { reason },
+                });
+            }
+            Err((true, reason)) => {
+                warn!("Player {} is kicked: {}", player, reason);
+                slots.kick(player);
+                send_events.send(SendNetMessageTo {
+                    client_id,
+                    message: NetMessage::Kicked { reason },
+                });
+                kick_events.send(KickClientEvent { client_id });
+            }
+        }
+    }
+}
+
+/// Gives tanks of players to bots and back according to state of slots.
+/// Bot takes over the tank as it is, including its health and aim.
+fn sync_bot_tanks_system(
+    mut commands: Commands,
+    slots: Res<NetSlots>,
+    tanks_query: Query<(Entity, &Tank, Has<AiController>)>,
+) {
+    for (entity, tank, has_ai) in tanks_query.iter() {
+        let is_bot = slots.is_ai_controlled(tank.player_number);
+        if is_bot && !has_ai {
+            debug!("Bot takes tank of player {}", tank.player_number);
+            commands.entity(entity).insert(AiController::default());
+        } else if !is_bot && has_ai {
+            commands.entity(entity).remove::<AiController>();
+        }
+    }
+}
+
+/// Sends state of the game to spectators. Landscape is sent only
+/// if it has changed or there are new spectators.
+#[allow(clippy::too_many_arguments)]
+fn send_snapshots_system(
+    time: Res<Time>,
+    slots: Res<NetSlots>,
+    game_field: Option<Res<GameField>>,
+    tanks_query: Query<(&Tank, &Position, &Health)>,
+    mut spectator_connected_events: EventReader<SpectatorConnectedEvent>,
+    mut last_snapshot: Local<f64>,
+    mut last_heights: Local<Vec<u16>>,
+    mut send_events: EventWriter<SendNetMessageTo>,
+) {
+    let has_new_spectators = spectator_connected_events.read().count() > 0;
+    let now = time.elapsed_seconds_f64();
+    if slots.spectators.is_empty() || now - *last_snapshot < SNAPSHOT_INTERVAL {
+        return;
+    }
+    *last_snapshot = now;
+
+    let tanks = tank_snapshots(&tanks_query);
+    let surface_heights = game_field.and_then(|game_field| {
+        let heights = surface_heights(&game_field.landscape);
+        if !has_new_spectators && heights == *last_heights {
+            return None;
+        }
+        *last_heights = heights.clone();
+        Some(heights)
+    });
+
+    let snapshot = StateSnapshot {
+        tanks,
+        surface_heights,
+    };
+    for &client_id in slots.spectators() {
+        send_events.send(SendNetMessageTo {
+            client_id,
+            message: NetMessage::StateSnapshot(snapshot.clone()),
+        });
+    }
+}
+
+fn tank_snapshots(tanks_query: &Query<(&Tank, &Position, &Health)>) -> Vec<TankSnapshot> {
+    let mut tanks: Vec<TankSnapshot> = tanks_query
+        .iter()
+        .map(|(tank, &Position(position), health)| TankSnapshot {
+            player: tank.player_number,
+            position: position.to_array(),
+            gun_angle: tank.gun_angle_deg(),
+            power: tank.power,
+            health: health.value,
+        })
+        .collect();
+    tanks.sort_by_key(|tank| tank.player);
+    tanks
+}
+
+fn surface_heights(landscape: &Landscape) -> Vec<u16> {
+    (0..landscape.size().0 as i32)
+        .map(|x| landscape.surface_height(x))
+        .collect()
+}
+
+/// Replaces state of the game of spectator or reconnected player
+/// by snapshot from the host.
+fn apply_snapshot_system(
+    mut commands: Commands,
+    mut received_events: EventReader<NetMessageReceived>,
+    game_field: Option<ResMut<GameField>>,
+    mut tanks_query: Query<(Entity, &mut Tank, &mut Position, &mut Health)>,
+) {
+    let Some(snapshot) = received_events
+        .read()
+        .filter_map(|NetMessageReceived(message)| match message {
+            NetMessage::StateSnapshot(snapshot) => Some(snapshot),
+            _ => None,
+        })
+        .last()
+    else {
+        return;
+    };
+    for (entity, mut tank, mut position, mut health) in tanks_query.iter_mut() {
+        let Some(state) = snapshot
+            .tanks
+            .iter()
+            .find(|state| state.player == tank.player_number)
+        else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        tank.set_gun_angle(state.gun_angle);
+        tank.set_gun_power(state.power);
+        position.0 = Vec2::from_array(state.position);
+        health.value = state.health;
+    }
+    if let (Some(mut game_field), Some(heights)) = (game_field, &snapshot.surface_heights) {
+        game_field.landscape.set_surface_heights(heights);
+    }
+}
+
+/// Checks that client with given `Hello` may play on the host.
+pub fn check_compatibility(host: &Hello, client: &Hello) -> Result<(), HandshakeError> {
+    if host.protocol_version != client.protocol_version {
+        return Err(HandshakeError::IncompatibleVersion {
+            local_version: host.game_version.clone(),
+            remote_version: client.game_version.clone(),
+        });
+    }
+
+    let host_mods = &host.capabilities.mods;
+    let client_mods = &client.capabilities.mods;
+    if host_mods != client_mods {
+        let difference = |a: &[String], b: &[String]| -> Vec<String> {
+            a.iter().filter(|m| !b.contains(m)).cloned().collect()
+        };
+        return Err(HandshakeError::IncompatibleMods {
+            missing: difference(host_mods, client_mods),
+            extra: difference(client_mods, host_mods),
+        });
+    }
+
+    if host.capabilities.rules_hash != client.capabilities.rules_hash {
+        return Err(HandshakeError::RulesMismatch);
+    }
+    Ok(())
+}
+
+/// Returns answer of the host to `Hello` of client.
+pub fn answer_hello(host: &Hello, client: &Hello) -> NetMessage {
+    match check_compatibility(host, client) {
+        Ok(()) => NetMessage::HelloAccepted,
+        Err(err) => NetMessage::HelloRejected {
+            reason: err.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GameMode;
+
+    #[test]
+    fn test_handshake() {
+        let rules = GameRules::default();
+        let host = Hello::new(Capabilities::new(&rules, vec!["nukes@1.0".into()]));
+        let message = NetMessage::Hello(host.clone());
+        assert_eq!(
+            NetMessage::decode(&message.encode().unwrap()).unwrap(),
+            message
+        );
+        assert_eq!(answer_hello(&host, &host), NetMessage::HelloAccepted);
+
+        let old_client = Hello {
+            protocol_version: 0,
+            game_version: "0.0.1".into(),
+            ..host.clone()
+        };
+        assert_eq!(
+            answer_hello(&host, &old_client),
+            NetMessage::HelloRejected {
+                reason: format!(
+                    "Incompatible version of the game: 0.0.1 (required {})",
+                    host.game_version
+                )
+            }
+        );
+
+        let modded_client = Hello::new(Capabilities::new(&rules, vec!["lasers@2.0".into()]));
+        assert_eq!(
+            check_compatibility(&host, &modded_client),
+            Err(HandshakeError::IncompatibleMods {
+                missing: vec!["nukes@1.0".into()],
+                extra: vec!["lasers@2.0".into()],
+            })
+        );
+
+        let practice_rules = GameRules {
+            mode: GameMode::Practice,
+            ..default()
+        };
+        let practice_client =
+            Hello::new(Capabilities::new(&practice_rules, vec!["nukes@1.0".into()]));
+        assert_eq!(
+            check_compatibility(&host, &practice_client),
+            Err(HandshakeError::RulesMismatch)
+        );
+    }
+
+    #[test]
+    fn test_spectators() {
+        let rules = GameRules::default();
+        let spectator = Hello::spectator(Capabilities::new(&rules, vec![]));
+        assert_eq!(
+            answer_hello(&Hello::new(spectator.capabilities.clone()), &spectator),
+            NetMessage::HelloAccepted
+        );
+        // Clients without the role are players.
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&NetMessage::Hello(spectator.clone()).encode().unwrap())
+                .unwrap();
+        json.as_object_mut().unwrap().remove("role");
+        let NetMessage::Hello(old_client) = serde_json::from_value(json).unwrap() else {
+            panic!("Hello is expected");
+        };
+        assert_eq!(old_client.role, SessionRole::Player);
+
+        let mut slots = NetSlots::new(2, false);
+        slots.set(2, SlotState::Human(20));
+        slots.add_spectator(30);
+        slots.add_spectator(30);
+        assert_eq!(slots.spectators(), &[30]);
+        assert_eq!(slots.player_of(20), Some(2));
+        assert_eq!(slots.player_of(30), None);
+        slots.remove_spectator(30);
+        assert!(slots.spectators().is_empty());
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let mut slots = NetSlots::new(2, false);
+        slots.reconnect_turns = 2;
+        for player in 1..=2 {
+            slots.set(player, SlotState::Human(player as ClientId));
+            slots.issue_token(player, 100 + player as SessionToken);
+        }
+        // Only slots of disconnected players may be taken back.
+        assert_eq!(slots.reconnect(101, 5), None);
+
+        slots.set(1, SlotState::Disconnected(1));
+        assert!(slots.is_ai_controlled(1));
+        slots.count_missed_turn(1);
+        slots.count_missed_turn(2);
+        assert_eq!(slots.reconnect(999, 5), None);
+        assert_eq!(slots.reconnect(101, 5), Some(1));
+        assert_eq!(slots.get(1), Some(SlotState::Human(5)));
+        assert!(!slots.is_ai_controlled(1));
+
+        slots.set(2, SlotState::Disconnected(2));
+        for _ in 0..3 {
+            slots.count_missed_turn(2);
+        }
+        assert!(slots.is_bot(2));
+        assert_eq!(slots.reconnect(102, 6), None);
+    }
+
+    #[test]
+    fn test_kick() {
+        let message = NetMessage::Shot(Shot {
+            player: 1,
+            angle: 10.,
+            power: 50.,
+            weapon: Some(2),
+        });
+        assert_eq!(
+            NetMessage::decode(&message.encode().unwrap()).unwrap(),
+            message
+        );
+
+        let mut slots = NetSlots::new(2, false);
+        slots.set(1, SlotState::Human(10));
+        slots.issue_token(1, 101);
+        slots.kick(1);
+        assert!(slots.is_bot(1));
+        assert_eq!(slots.player_of(10), None);
+        // Kicked player can't reconnect.
+        slots.set(1, SlotState::Disconnected(10));
+        assert_eq!(slots.reconnect(101, 10), None);
+    }
+}
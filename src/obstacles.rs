@@ -0,0 +1,304 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::Rng;
+
+use crate::collider::Collider;
+use crate::components::Position;
+use crate::explosion::ExplosionDamageEvent;
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::AppState;
+use crate::landscape::{Landscape, SubsidenceFinishedEvent};
+use crate::mines::settle_height;
+use crate::rules::GameRules;
+use crate::tank::{Health, Tank};
+
+/// Minimal horizontal distance between an obstacle and
+/// tanks or other obstacles.
+const MIN_DISTANCE: f32 = 60.;
+/// Obstacles are not placed closer than this to sides of the field.
+const PADDING: f32 = 30.;
+/// Count of attempts to find a free place for an obstacle.
+const PLACE_ATTEMPTS: usize = 50;
+
+pub struct ObstaclesPlugin;
+
+impl Plugin for ObstaclesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ObstacleDestroyedEvent>()
+            .add_systems(OnEnter(AppState::TanksThrowing), spawn_obstacles_system)
+            .add_systems(Update, (damage_obstacles_system, settle_obstacles_system))
+            .add_systems(OnEnter(AppState::RoundSetup), despawn_obstacles_system);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObstacleKind {
+    Bunker,
+    Wall,
+    Tree,
+}
+
+impl ObstacleKind {
+    const ALL: [ObstacleKind; 3] = [Self::Bunker, Self::Wall, Self::Tree];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bunker => "Bunker",
+            Self::Wall => "Wall",
+            Self::Tree => "Tree",
+        }
+    }
+
+    fn hit_points(&self) -> u8 {
+        match self {
+            Self::Bunker => 250,
+            Self::Wall => 150,
+            Self::Tree => 40,
+        }
+    }
+
+    /// Outline of the obstacle relative to the middle of its base.
+    fn outline(&self) -> Vec<Vec2> {
+        let points: &[(f32, f32)] = match self {
+            Self::Bunker => &[
+                (-20., 0.),
+                (20., 0.),
+                (16., 12.),
+                (8., 16.),
+                (-8., 16.),
+                (-16., 12.),
+            ],
+            Self::Wall => &[(-4., 0.), (4., 0.), (4., 40.), (-4., 40.)],
+            Self::Tree => &[
+                (-2., 0.),
+                (2., 0.),
+                (2., 14.),
+                (10., 14.),
+                (0., 36.),
+                (-10., 14.),
+                (-2., 14.),
+            ],
+        };
+        points.iter().map(|&(x, y)| Vec2::new(x, y)).collect()
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Self::Bunker => Color::rgb(0.55, 0.55, 0.5),
+            Self::Wall => Color::rgb(0.6, 0.3, 0.2),
+            Self::Tree => Color::rgb(0.2, 0.5, 0.15),
+        }
+    }
+}
+
+/// Structure standing on the landscape. It stops missiles and
+/// is damaged by explosions like tanks.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Obstacle {
+    pub kind: ObstacleKind,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ObstacleDestroyedEvent {
+    pub kind: ObstacleKind,
+    pub position: Vec2,
+    /// Tank which has caused the explosion.
+    pub owner: Option<Entity>,
+}
+
+/// Returns X coordinates of obstacles which are far enough from
+/// each other and from the occupied points.
+fn obstacle_points(width: f32, occupied: &[f32], count: u8, rng: &mut impl Rng) -> Vec<f32> {
+    let mut taken = occupied.to_vec();
+    let mut points = Vec::with_capacity(count as usize);
+    if width <= 2. * PADDING {
+        return points;
+    }
+    for _ in 0..count {
+        let free_point = (0..PLACE_ATTEMPTS)
+            .map(|_| rng.gen_range(PADDING..width - PADDING).round())
+            .find(|x| taken.iter().all(|t| (t - x).abs() >= MIN_DISTANCE));
+        if let Some(x) = free_point {
+            taken.push(x);
+            points.push(x);
+        }
+    }
+    points
+}
+
+/// Fills pixels of landscape covered by the collider. Returns count
+/// of pixels that were empty.
+fn crumble(landscape: &mut Landscape, collider: &Collider, position: Vec2) -> usize {
+    let rect = collider.bounding_rect(position);
+    let (left, right) = (rect.left.floor() as i32, rect.right.ceil() as i32);
+    let mut filled = 0;
+    for y in rect.bottom.floor() as i32..rect.top.ceil() as i32 {
+        let mut run_start = None;
+        for x in left..=right {
+            let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let inside = x < right && collider.contains(center - position);
+            match (inside, run_start) {
+                (true, None) => run_start = Some(x),
+                (false, Some(start)) => {
+                    filled += landscape.fill_pixels_line((start, y), (x - start) as u16);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    filled
+}
+
+fn spawn_obstacles_system(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    mut game_rng: ResMut<GameRng>,
+    rules: Res<GameRules>,
+    tanks_query: Query<&Position, With<Tank>>,
+) {
+    if rules.obstacles == 0 {
+        return;
+    }
+    let occupied: Vec<f32> = tanks_query.iter().map(|p| p.0.x).collect();
+    let landscape = &game_field.landscape;
+    let (width, height) = landscape.size();
+    let points = obstacle_points(width as f32, &occupied, rules.obstacles, &mut *game_rng);
+    for x in points {
+        let kind = ObstacleKind::ALL[game_rng.gen_range(0..ObstacleKind::ALL.len())];
+        let y = settle_height(landscape, x as i32, height as i32 - 1);
+        let position = Vec2::new(x, y as f32);
+        debug!("Place {} at {:?}", kind.name(), position);
+        let outline = kind.outline();
+        let shape = shapes::Polygon {
+            points: outline.clone(),
+            closed: true,
+        };
+        let obstacle_entity = commands
+            .spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shape),
+                    spatial: SpatialBundle::from_transform(Transform::from_translation(
+                        position.extend(0.5),
+                    )),
+                    ..default()
+                },
+                Fill::color(kind.color()),
+                Position(position),
+                Collider::polygon(outline),
+                Health {
+                    value: kind.hit_points(),
+                    shield: 0,
+                    invincible: false,
+                },
+                Obstacle { kind },
+            ))
+            .id();
+        commands
+            .entity(game_field.parent_entity)
+            .add_child(obstacle_entity);
+    }
+}
+
+/// Destroyed obstacles crumble into pixels of landscape,
+/// which then fall down together with the rest of landscape.
+fn damage_obstacles_system(
+    mut commands: Commands,
+    game_field: Option<ResMut<GameField>>,
+    mut damage_events: EventReader<ExplosionDamageEvent>,
+    mut obstacles_query: Query<(Entity, &Obstacle, &Position, &Collider, &mut Health)>,
+    mut destroyed_events: EventWriter<ObstacleDestroyedEvent>,
+) {
+    let Some(mut game_field) = game_field else {
+        return;
+    };
+    for event in damage_events.read() {
+        let Ok((entity, obstacle, &Position(position), collider, mut health)) =
+            obstacles_query.get_mut(event.entity)
+        else {
+            continue;
+        };
+        if health.value > 0 && health.damage(event.damage) == 0 {
+            info!(
+                "{} at {:?} has been destroyed",
+                obstacle.kind.name(),
+                position
+            );
+            commands.entity(entity).despawn_recursive();
+            let landscape = &mut game_field.landscape;
+            if crumble(landscape, collider, position) > 0 {
+                landscape.subsidence();
+            }
+            destroyed_events.send(ObstacleDestroyedEvent {
+                kind: obstacle.kind,
+                position,
+                owner: event.owner,
+            });
+        }
+    }
+}
+
+/// Obstacles fall together with the landscape under them.
+fn settle_obstacles_system(
+    game_field: Option<Res<GameField>>,
+    mut finished_events: EventReader<SubsidenceFinishedEvent>,
+    mut obstacles_query: Query<&mut Position, With<Obstacle>>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    if finished_events.read().count() == 0 {
+        return;
+    }
+    for mut position in obstacles_query.iter_mut() {
+        let (x, y) = (position.0.x as i32, position.0.y as i32);
+        let settled = settle_height(&game_field.landscape, x, y) as f32;
+        if settled != position.0.y {
+            position.0.y = settled;
+        }
+    }
+}
+
+fn despawn_obstacles_system(
+    mut commands: Commands,
+    obstacles_query: Query<Entity, With<Obstacle>>,
+) {
+    for entity in obstacles_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::landscape_buffer::LandscapeStorage;
+
+    #[test]
+    fn test_obstacle_points() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let occupied = [100., 300.];
+        let points = obstacle_points(400., &occupied, 3, &mut rng);
+        assert!(!points.is_empty());
+        for (i, &x) in points.iter().enumerate() {
+            assert!((PADDING..=400. - PADDING).contains(&x));
+            let others = occupied.iter().chain(&points[i + 1..]);
+            assert!(others.into_iter().all(|&t| (t - x).abs() >= MIN_DISTANCE));
+        }
+        assert!(obstacle_points(50., &[], 3, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_crumble() {
+        let mut landscape = Landscape::new(200, 100, 1, LandscapeStorage::default(), None).unwrap();
+        landscape.set_surface_heights(&[20; 200]);
+        let collider = Collider::polygon(ObstacleKind::Wall.outline());
+        let position = Vec2::new(100., 20.);
+        assert_eq!(crumble(&mut landscape, &collider, position), 8 * 40);
+        assert!(landscape.is_not_empty(97, 59));
+        assert!(!landscape.is_not_empty(95, 30));
+        assert!(!landscape.is_not_empty(100, 60));
+    }
+}
@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::components::{Owner, Position};
+use crate::game_field::GameField;
+use crate::game_plugin::{AimingMode, AppState};
+use crate::input::PlayerAction;
+use crate::missile::{spawn_missile, Missile};
+use crate::tank::{AimingTank, AllTanksPlacedEvent, TankShotEvent};
+use crate::turn::{TurnEndedEvent, TurnManager};
+use crate::weapons::{TankWeapon, WeaponKind, Weapons};
+use crate::G;
+
+/// Distance of reticle movement per one step of aiming.
+const RETICLE_STEP: f32 = 2.;
+/// Initial speed of dropped projectile.
+const DROP_POWER: f32 = 10.;
+
+pub struct OrbitalStrikePlugin;
+
+impl Plugin for OrbitalStrikePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AimingMode::Reticle), spawn_reticle_system)
+            .add_systems(OnExit(AimingMode::Reticle), despawn_reticle_system)
+            .add_systems(OnExit(AppState::Aiming), reset_aiming_mode_system)
+            .add_systems(OnEnter(AppState::RoundSetup), despawn_strikes_system)
+            .add_systems(
+                OnEnter(AppState::MainAction),
+                finish_turn_without_projectile_system
+                    .run_if(resource_exists::<TurnWithoutProjectile>),
+            )
+            .add_systems(
+                Update,
+                (
+                    aiming_mode_system,
+                    (move_reticle_system, call_strike_system)
+                        .chain()
+                        .run_if(in_state(AimingMode::Reticle)),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Aiming)),
+            )
+            .add_systems(Update, drop_strikes_system);
+    }
+}
+
+/// Target of weapon selected by the current tank.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Reticle {
+    pub x: f32,
+    pub kind: WeaponKind,
+    /// Direction of airstrike sweep along x-axis (1 or -1).
+    pub direction: f32,
+}
+
+/// Called orbital strike which is shown by warning marker
+/// until its projectile is dropped.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct OrbitalStrike {
+    pub x: f32,
+    /// Number of turn at the end of which the projectile is dropped.
+    pub drop_turn: usize,
+}
+
+/// The turn has been ended without projectiles in the air,
+/// so the main action of the turn must be finished at once.
+#[derive(Resource)]
+pub(crate) struct TurnWithoutProjectile;
+
+/// Switches aiming mode according to the weapon of the aiming tank.
+fn aiming_mode_system(
+    weapons: Res<Weapons>,
+    aiming_mode: Res<State<AimingMode>>,
+    mut next_aiming_mode: ResMut<NextState<AimingMode>>,
+    aiming_tanks: Query<&TankWeapon, With<AimingTank>>,
+) {
+    let kind = aiming_tanks
+        .iter()
+        .next()
+        .map(|weapon| weapon.ready_weapon_kind(&weapons));
+    let mode = if matches!(
+        kind,
+        Some(WeaponKind::OrbitalStrike | WeaponKind::Airstrike)
+    ) {
+        AimingMode::Reticle
+    } else {
+        AimingMode::Gun
+    };
+    if *aiming_mode.get() != mode {
+        next_aiming_mode.set(mode);
+    }
+}
+
+fn reset_aiming_mode_system(mut next_aiming_mode: ResMut<NextState<AimingMode>>) {
+    next_aiming_mode.set(AimingMode::Gun);
+}
+
+fn spawn_reticle_system(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    weapons: Res<Weapons>,
+    aiming_tanks: Query<(&Position, &TankWeapon), With<AimingTank>>,
+) {
+    let Ok((tank_position, weapon)) = aiming_tanks.get_single() else {
+        return;
+    };
+    let kind = weapon.ready_weapon_kind(&weapons);
+    let x = tank_position.0.x;
+    let height = game_field.height as f32;
+    let line = shapes::Line(Vec2::new(0., -height), Vec2::ZERO);
+    let reticle_entity = commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&line),
+                spatial: SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
+                    x, height, 3.,
+                ))),
+                ..default()
+            },
+            Stroke::new(Color::rgba(1., 1., 0., 0.6), 1.),
+            Position(Vec2::new(x, height)),
+            Reticle {
+                x,
+                kind,
+                direction: 1.,
+            },
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(reticle_entity);
+}
+
+fn despawn_reticle_system(mut commands: Commands, reticles: Query<Entity, With<Reticle>>) {
+    for entity in reticles.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn move_reticle_system(
+    game_field: Res<GameField>,
+    mut actions: EventReader<PlayerAction>,
+    mut reticles: Query<(&mut Reticle, &mut Position)>,
+) {
+    let mut delta = 0.;
+    let mut direction = None;
+    for action in actions.read() {
+        match *action {
+            PlayerAction::RotateGun(d) => delta += d,
+            PlayerAction::ChangePower(d) if d != 0. => direction = Some(d.signum()),
+            _ => {}
+        }
+    }
+    for (mut reticle, mut position) in reticles.iter_mut() {
+        reticle.x = (reticle.x + delta * RETICLE_STEP).clamp(0., game_field.width as f32 - 1.);
+        position.0.x = reticle.x;
+        if let Some(direction) = direction {
+            reticle.direction = direction;
+        }
+    }
+}
+
+/// Calls orbital strike onto the reticle when player fires.
+fn call_strike_system(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    turn_manager: Res<TurnManager>,
+    mut actions: EventReader<PlayerAction>,
+    reticles: Query<&Reticle>,
+    aiming_tanks: Query<Entity, With<AimingTank>>,
+    mut shot_events: EventWriter<TankShotEvent>,
+) {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    let (Ok(reticle), Ok(tank_entity)) = (reticles.get_single(), aiming_tanks.get_single()) else {
+        return;
+    };
+    if !fire || reticle.kind != WeaponKind::OrbitalStrike {
+        return;
+    }
+    info!("Orbital strike has been called to x={}", reticle.x);
+    let height = game_field.height as f32;
+    let marker = shapes::Polygon {
+        points: vec![Vec2::new(-6., 0.), Vec2::new(6., 0.), Vec2::new(0., -10.)],
+        closed: true,
+    };
+    let strike_entity = commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&marker),
+                spatial: SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
+                    reticle.x, height, 3.,
+                ))),
+                ..default()
+            },
+            Fill::color(Color::RED),
+            Position(Vec2::new(reticle.x, height)),
+            Owner(tank_entity),
+            OrbitalStrike {
+                x: reticle.x,
+                drop_turn: turn_manager.turn_number() + 1,
+            },
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(strike_entity);
+    commands.insert_resource(TurnWithoutProjectile);
+    shot_events.send(TankShotEvent { tank_entity });
+}
+
+fn finish_turn_without_projectile_system(
+    mut commands: Commands,
+    mut placed_events: EventWriter<AllTanksPlacedEvent>,
+) {
+    commands.remove_resource::<TurnWithoutProjectile>();
+    placed_events.send(AllTanksPlacedEvent);
+}
+
+/// Drops projectiles of orbital strikes at the end of the turn
+/// following the turn in which they have been called.
+fn drop_strikes_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    weapons: Res<Weapons>,
+    mut ended_events: EventReader<TurnEndedEvent>,
+    strikes: Query<(Entity, &OrbitalStrike, &Owner)>,
+) {
+    let Some(game_field) = game_field else {
+        ended_events.clear();
+        return;
+    };
+    for event in ended_events.read() {
+        for (entity, strike, &owner) in strikes.iter() {
+            if strike.drop_turn != event.turn_number {
+                continue;
+            }
+            debug!("Drop orbital strike to x={}", strike.x);
+            let position = Vec2::new(strike.x, game_field.height as f32 - 1.);
+            let missile = Missile::new(position, 180., DROP_POWER, Vec2::new(0., -G));
+            let weapon = weapons.of_kind(WeaponKind::OrbitalStrike);
+            spawn_missile(&mut commands, &game_field, missile, weapon, Some(owner));
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn despawn_strikes_system(mut commands: Commands, strikes: Query<Entity, With<OrbitalStrike>>) {
+    for entity in strikes.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,328 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::Rng;
+
+use crate::components::{Opacity, Position, Scale};
+use crate::day_night::{DayCycle, TINT_Z};
+use crate::environment::Weather;
+use crate::explosion::ExplosionMaxRadiusEvent;
+use crate::game_field::GameField;
+use crate::missile::MissileMovedEvent;
+use crate::tank::TankDamagedEvent;
+use crate::G;
+
+const DEBRIS_COUNT: usize = 24;
+const SPARKS_COUNT: usize = 12;
+/// Scale of gravity for particles to make their fall visible.
+const PARTICLE_GRAVITY: f32 = 10. * G;
+/// Count of rain drops or snowflakes per second.
+const RAIN_RATE: f32 = 200.;
+const SNOW_RATE: f32 = 60.;
+const RAIN_SPEED: f32 = 400.;
+const SNOW_SPEED: f32 = 40.;
+/// Precipitation falls behind the landscape.
+const PRECIPITATION_Z: f32 = -0.5;
+
+/// Decorative particles: debris of landscape, smoke trails of missiles,
+/// sparks of damaged tanks and precipitation.
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                emit_debris_system,
+                emit_smoke_system,
+                emit_sparks_system,
+                emit_precipitation_system,
+                update_particles_system,
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Particle {
+    age: f32,
+    lifetime: f32,
+    velocity: Vec2,
+    /// Acceleration of falling down.
+    gravity: f32,
+    /// Scale of particle at the end of its life.
+    end_scale: f32,
+    start_opacity: f32,
+}
+
+impl Particle {
+    /// Moves the particle and returns `false` if its life is over.
+    fn advance(&mut self, position: &mut Vec2, delta: f32) -> bool {
+        self.age += delta;
+        self.velocity.y -= self.gravity * delta;
+        *position += self.velocity * delta;
+        self.age < self.lifetime
+    }
+
+    #[inline]
+    fn life_part(&self) -> f32 {
+        (self.age / self.lifetime).min(1.)
+    }
+}
+
+struct ParticleSpawn {
+    position: Vec2,
+    velocity: Vec2,
+    color: Color,
+    radius: f32,
+    lifetime: f32,
+    gravity: f32,
+    end_scale: f32,
+    z: f32,
+}
+
+fn spawn_particle(commands: &mut Commands, game_field: &GameField, spawn: ParticleSpawn) {
+    let circle = shapes::Circle {
+        radius: spawn.radius,
+        ..default()
+    };
+    let particle_entity = commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&circle),
+                spatial: SpatialBundle::from_transform(Transform::from_translation(
+                    spawn.position.extend(spawn.z),
+                )),
+                ..default()
+            },
+            Fill::color(spawn.color),
+            Particle {
+                age: 0.,
+                lifetime: spawn.lifetime,
+                velocity: spawn.velocity,
+                gravity: spawn.gravity,
+                end_scale: spawn.end_scale,
+                start_opacity: spawn.color.a(),
+            },
+            Position(spawn.position),
+            Scale(1.),
+            Opacity(spawn.color.a()),
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(particle_entity);
+}
+
+fn random_direction(rng: &mut impl Rng, min_angle: f32, max_angle: f32) -> Vec2 {
+    let angle = rng.gen_range(min_angle..max_angle);
+    Vec2::new(angle.cos(), angle.sin())
+}
+
+/// Throws pieces of destroyed landscape out of the explosion.
+fn emit_debris_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    mut radius_events: EventReader<ExplosionMaxRadiusEvent>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let mut rng = rand::thread_rng();
+    for event in radius_events.read() {
+        for _ in 0..DEBRIS_COUNT {
+            let direction = random_direction(&mut rng, 0.1 * PI, 0.9 * PI);
+            spawn_particle(
+                &mut commands,
+                &game_field,
+                ParticleSpawn {
+                    position: event.position + direction * event.max_radius,
+                    velocity: direction * rng.gen_range(60. ..160.),
+                    color: Color::rgb(0.45, 0.3, 0.15),
+                    radius: rng.gen_range(1. ..2.5),
+                    lifetime: 1.5,
+                    gravity: PARTICLE_GRAVITY,
+                    end_scale: 1.,
+                    z: 1.5,
+                },
+            );
+        }
+    }
+}
+
+/// Leaves trail of smoke behind flying missiles. Bright tracers
+/// are left instead of smoke at night.
+fn emit_smoke_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    day_cycle: Option<Res<DayCycle>>,
+    mut moved_events: EventReader<MissileMovedEvent>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let is_night = day_cycle.is_some_and(|day_cycle| day_cycle.darkness() > 0.5);
+    for event in moved_events.read() {
+        let Some(&(x, y)) = event.path.last() else {
+            continue;
+        };
+        let position = Vec2::new(x as f32, y as f32);
+        let spawn = if is_night {
+            ParticleSpawn {
+                position,
+                velocity: Vec2::ZERO,
+                color: Color::rgb(1., 0.9, 0.5),
+                radius: 1.,
+                lifetime: 0.6,
+                gravity: 0.,
+                end_scale: 0.5,
+                z: TINT_Z + 0.5,
+            }
+        } else {
+            ParticleSpawn {
+                position,
+                velocity: Vec2::new(0., 5.),
+                color: Color::rgba(0.7, 0.7, 0.7, 0.5),
+                radius: 1.5,
+                lifetime: 1.,
+                gravity: 0.,
+                end_scale: 3.,
+                z: 1.5,
+            }
+        };
+        spawn_particle(&mut commands, &game_field, spawn);
+    }
+}
+
+/// Sparks fly out of tanks damaged by explosions.
+fn emit_sparks_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    mut damaged_events: EventReader<TankDamagedEvent>,
+    tanks_query: Query<&Position>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let mut rng = rand::thread_rng();
+    for event in damaged_events.read() {
+        let Ok(&Position(tank_position)) = tanks_query.get(event.tank_entity) else {
+            continue;
+        };
+        for _ in 0..SPARKS_COUNT {
+            let direction = random_direction(&mut rng, 0., 2. * PI);
+            spawn_particle(
+                &mut commands,
+                &game_field,
+                ParticleSpawn {
+                    position: tank_position,
+                    velocity: direction * rng.gen_range(80. ..200.),
+                    color: Color::rgb(1., 0.85, 0.3),
+                    radius: 1.,
+                    lifetime: 0.4,
+                    gravity: PARTICLE_GRAVITY,
+                    end_scale: 0.5,
+                    z: 1.5,
+                },
+            );
+        }
+    }
+}
+
+/// Rain drops and snowflakes fall from the top of the game field
+/// and are blown by the wind.
+fn emit_precipitation_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    game_field: Option<Res<GameField>>,
+) {
+    let rate = match *weather {
+        Weather::Clear => return,
+        Weather::Rain => RAIN_RATE,
+        Weather::Snow => SNOW_RATE,
+    };
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let mut rng = rand::thread_rng();
+    let expected_count = rate * time.delta_seconds();
+    let count = expected_count as usize + rng.gen_bool(expected_count.fract() as f64) as usize;
+    let height = game_field.height as f32;
+    for _ in 0..count {
+        let position = Vec2::new(rng.gen_range(0. ..game_field.width as f32), height);
+        let spawn = if *weather == Weather::Rain {
+            ParticleSpawn {
+                position,
+                velocity: Vec2::new(game_field.wind_power * 10., -RAIN_SPEED),
+                color: Color::rgba(0.6, 0.7, 0.9, 0.6),
+                radius: 1.,
+                lifetime: height / RAIN_SPEED,
+                gravity: 0.,
+                end_scale: 1.,
+                z: PRECIPITATION_Z,
+            }
+        } else {
+            ParticleSpawn {
+                position,
+                velocity: Vec2::new(
+                    game_field.wind_power * 5. + rng.gen_range(-10. ..10.),
+                    -SNOW_SPEED,
+                ),
+                color: Color::rgba(1., 1., 1., 0.9),
+                radius: rng.gen_range(1. ..2.),
+                lifetime: height / SNOW_SPEED,
+                gravity: 0.,
+                end_scale: 1.,
+                z: PRECIPITATION_Z,
+            }
+        };
+        spawn_particle(&mut commands, &game_field, spawn);
+    }
+}
+
+fn update_particles_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles_query: Query<(
+        Entity,
+        &mut Particle,
+        &mut Position,
+        &mut Scale,
+        &mut Opacity,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut particle, mut position, mut scale, mut opacity) in particles_query.iter_mut() {
+        if !particle.advance(&mut position.0, delta) {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let life_part = particle.life_part();
+        scale.0 = 1. + (particle.end_scale - 1.) * life_part;
+        opacity.0 = particle.start_opacity * (1. - life_part);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_particle_advance() {
+        let mut particle = Particle {
+            age: 0.,
+            lifetime: 1.,
+            velocity: Vec2::new(10., 0.),
+            gravity: 20.,
+            end_scale: 1.,
+            start_opacity: 1.,
+        };
+        let mut position = Vec2::ZERO;
+        assert!(particle.advance(&mut position, 0.5));
+        assert_eq!(position, Vec2::new(5., -5.));
+        assert_eq!(particle.life_part(), 0.5);
+        assert!(!particle.advance(&mut position, 0.5));
+    }
+}
@@ -0,0 +1,172 @@
+//! Synthesis of placeholder icons for weapons which have no icon image,
+//! so the hotbar and the shop never show blank slots.
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+const MAX_INITIALS: usize = 2;
+
+/// 3x5 bitmap glyphs, every row is stored in three lower bits.
+const GLYPHS: [(char, [u8; 5]); 36] = [
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b110, 0b001, 0b010, 0b100, 0b111]),
+    ('3', [0b110, 0b001, 0b010, 0b001, 0b110]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b110, 0b001, 0b110]),
+    ('6', [0b011, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b110]),
+];
+
+/// Returns upper-cased first letters of the first words of the name.
+pub fn initials(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter_map(|word| word.chars().next())
+        .take(MAX_INITIALS)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Returns color of the icon which is stable for the same name.
+fn icon_color(name: &str) -> [u8; 4] {
+    // FNV-1a
+    let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+    Color::hsl((hash % 360) as f32, 0.6, 0.4).as_rgba_u8()
+}
+
+fn glyph(c: char) -> Option<&'static [u8; 5]> {
+    GLYPHS
+        .iter()
+        .find(|(glyph_char, _)| *glyph_char == c)
+        .map(|(_, rows)| rows)
+}
+
+/// Generates square icon with colored round silhouette and initials
+/// of the weapon's name.
+pub fn placeholder_icon(name: &str, size: u32) -> Image {
+    let mut data = vec![0u8; (size * size * 4) as usize];
+    let color = icon_color(name);
+    let mut set_pixel = |x: u32, y: u32, rgba: [u8; 4]| {
+        if x < size && y < size {
+            let offset = ((y * size + x) * 4) as usize;
+            data[offset..offset + 4].copy_from_slice(&rgba);
+        }
+    };
+
+    let center = size as f32 / 2.;
+    let radius = center - 1.;
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x as f32 + 0.5 - center;
+            let dy = y as f32 + 0.5 - center;
+            if dx * dx + dy * dy <= radius * radius {
+                set_pixel(x, y, color);
+            }
+        }
+    }
+
+    let initials = initials(name);
+    let glyphs: Vec<_> = initials.chars().filter_map(glyph).collect();
+    if !glyphs.is_empty() {
+        // Glyphs are separated by one empty column.
+        let text_width = glyphs.len() as u32 * (GLYPH_WIDTH + 1) - 1;
+        let scale = (size / 2 / text_width).max(1);
+        let left = (size.saturating_sub(text_width * scale)) / 2;
+        let top = (size.saturating_sub(GLYPH_HEIGHT * scale)) / 2;
+        for (i, rows) in glyphs.iter().enumerate() {
+            let glyph_left = left + i as u32 * (GLYPH_WIDTH + 1) * scale;
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) == 0 {
+                        continue;
+                    }
+                    for sy in 0..scale {
+                        for sx in 0..scale {
+                            set_pixel(
+                                glyph_left + col * scale + sx,
+                                top + row as u32 * scale + sy,
+                                [255; 4],
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        Default::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initials() {
+        assert_eq!(initials("Baby Missile"), "BM");
+        assert_eq!(initials("nuke"), "N");
+        assert_eq!(initials("mega-death bomb"), "MD");
+        assert_eq!(initials(""), "");
+    }
+
+    #[test]
+    fn test_placeholder_icon() {
+        let size = 32;
+        let icon = placeholder_icon("Baby Missile", size);
+        assert_eq!(icon.data.len(), (size * size * 4) as usize);
+        let pixel = |x: u32, y: u32| {
+            let offset = ((y * size + x) * 4) as usize;
+            &icon.data[offset..offset + 4]
+        };
+        // Corners are transparent, edge of circle has color of weapon.
+        assert_eq!(pixel(0, 0), &[0; 4]);
+        assert_eq!(pixel(size / 2, 2), &icon_color("Baby Missile"));
+        assert!(icon.data.chunks_exact(4).any(|p| p == [255; 4]));
+        assert_eq!(
+            placeholder_icon("Baby Missile", size).data,
+            icon.data,
+            "icon must be stable"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::components::{Angle, Owner, Position};
+use crate::explosion::Explosion;
+use crate::game_field::GameField;
+use crate::game_plugin::AppState;
+use crate::landscape::Landscape;
+use crate::mines::settle_height;
+use crate::missile::Missile;
+use crate::tank::AllTanksPlacedEvent;
+
+/// Missiles fly into a portal within this distance from its center.
+pub const PORTAL_RADIUS: f32 = 10.;
+/// Distance between the surface of landscape and the center of portal.
+const SURFACE_OFFSET: f32 = 4.;
+/// Half of distance between points used to calculate slope of landscape.
+const SLOPE_SPAN: i32 = 4;
+const PORTAL_COLORS: [Color; 2] = [Color::rgb(0.2, 0.6, 1.), Color::rgb(1., 0.6, 0.1)];
+
+pub struct PortalPlugin;
+
+impl Plugin for PortalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (link_portals_system, finish_portal_placing_system))
+            .add_systems(OnEnter(AppState::RoundSetup), despawn_portals_system);
+    }
+}
+
+/// Missile which places a portal where it lands.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct PortalCharge;
+
+/// Portal on the surface of landscape. Missiles flying into it fly out
+/// of the linked portal of the same player.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Portal {
+    pub position: Vec2,
+    /// Direction out of landscape.
+    pub normal: Vec2,
+    pub linked: Option<Entity>,
+    /// Serial number of portal used to find the oldest one.
+    serial: u32,
+}
+
+/// Marks that a portal has been placed during the current frame.
+#[derive(Debug, Clone, Copy, Component)]
+struct NewPortal;
+
+impl Portal {
+    #[inline]
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.position.distance_squared(point) <= PORTAL_RADIUS * PORTAL_RADIUS
+    }
+
+    /// Position of a missile flying out of the portal.
+    #[inline]
+    pub fn exit_position(&self) -> Vec2 {
+        self.position + self.normal * (PORTAL_RADIUS + 2.)
+    }
+}
+
+/// Maps velocity of a missile flying into the portal with `entry_normal`
+/// to velocity of flying out of the portal with `exit_normal`.
+/// Speed is preserved, the component towards the entry portal becomes
+/// the component along normal of the exit portal.
+pub fn map_velocity(velocity: Vec2, entry_normal: Vec2, exit_normal: Vec2) -> Vec2 {
+    let normal_speed = velocity.dot(entry_normal);
+    let tangent_speed = velocity.dot(entry_normal.perp());
+    -normal_speed * exit_normal + tangent_speed * exit_normal.perp()
+}
+
+/// Returns height of the surface of landscape in given column.
+fn surface_height(landscape: &Landscape, x: i32, mut y: i32) -> i32 {
+    let (_, height) = landscape.size();
+    while y < height as i32 && landscape.is_not_empty(x, y) {
+        y += 1;
+    }
+    settle_height(landscape, x, y)
+}
+
+/// Returns position of center and normal of portal placed
+/// on the surface of landscape near given point.
+fn portal_placement(landscape: &Landscape, point: Vec2) -> (Vec2, Vec2) {
+    let (width, _) = landscape.size();
+    let x = (point.x as i32).clamp(0, width as i32 - 1);
+    let y = point.y as i32;
+    let left = surface_height(landscape, (x - SLOPE_SPAN).max(0), y);
+    let right = surface_height(landscape, (x + SLOPE_SPAN).min(width as i32 - 1), y);
+    let normal = Vec2::new((left - right) as f32, 2. * SLOPE_SPAN as f32).normalize();
+    let surface = Vec2::new(x as f32, surface_height(landscape, x, y) as f32);
+    (surface + normal * SURFACE_OFFSET, normal)
+}
+
+pub fn spawn_portal(commands: &mut Commands, game_field: &GameField, point: Vec2, owner: Owner) {
+    let (position, normal) = portal_placement(&game_field.landscape, point);
+    debug!("Place portal at {:?}", position);
+    let ellipse = shapes::Ellipse {
+        radii: Vec2::new(PORTAL_RADIUS, 3.),
+        ..default()
+    };
+    let angle = normal.y.atan2(normal.x).to_degrees() - 90.;
+    let portal_entity = commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&ellipse),
+                spatial: SpatialBundle::from_transform(Transform::from_translation(
+                    position.extend(0.5),
+                )),
+                ..default()
+            },
+            Fill::color(Color::rgba(1., 1., 1., 0.3)),
+            Stroke::new(PORTAL_COLORS[0], 2.),
+            Portal {
+                position,
+                normal,
+                linked: None,
+                serial: 0,
+            },
+            Position(position),
+            Angle(angle),
+            owner,
+            NewPortal,
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(portal_entity);
+}
+
+/// Links a new portal with the previous portal of the same player.
+/// The oldest portal of the player disappears, if the player has placed
+/// the third one.
+fn link_portals_system(
+    mut commands: Commands,
+    mut serial: Local<u32>,
+    new_portals: Query<Entity, With<NewPortal>>,
+    mut portals: Query<(Entity, &mut Portal, &mut Stroke, &Owner)>,
+) {
+    for new_entity in new_portals.iter() {
+        commands.entity(new_entity).remove::<NewPortal>();
+        let Ok((_, mut new_portal, _, &new_owner)) = portals.get_mut(new_entity) else {
+            continue;
+        };
+        *serial += 1;
+        new_portal.serial = *serial;
+        let mut own_portals: Vec<(Entity, u32)> = portals
+            .iter()
+            .filter(|&(entity, _, _, &owner)| owner.0 == new_owner.0 && entity != new_entity)
+            .map(|(entity, portal, _, _)| (entity, portal.serial))
+            .collect();
+        own_portals.sort_by_key(|&(_, serial)| serial);
+        while own_portals.len() > 1 {
+            let (old_entity, _) = own_portals.remove(0);
+            commands.entity(old_entity).despawn_recursive();
+        }
+        let linked = own_portals.first().map(|&(entity, _)| entity);
+        if let Some(linked_entity) = linked {
+            if let Ok((_, mut linked_portal, mut stroke, _)) = portals.get_mut(linked_entity) {
+                linked_portal.linked = Some(new_entity);
+                stroke.color = PORTAL_COLORS[0];
+            }
+        }
+        if let Ok((_, mut new_portal, mut stroke, _)) = portals.get_mut(new_entity) {
+            new_portal.linked = linked;
+            stroke.color = PORTAL_COLORS[linked.is_some() as usize];
+        }
+    }
+}
+
+/// Finishes the turn if nothing else is flying or exploding,
+/// because the charge doesn't explode.
+fn finish_portal_placing_system(
+    new_portals: Query<(), Added<Portal>>,
+    missiles: Query<(), With<Missile>>,
+    explosions: Query<(), With<Explosion>>,
+    mut placed_events: EventWriter<AllTanksPlacedEvent>,
+) {
+    if !new_portals.is_empty() && missiles.is_empty() && explosions.is_empty() {
+        placed_events.send(AllTanksPlacedEvent);
+    }
+}
+
+fn despawn_portals_system(mut commands: Commands, portals: Query<Entity, With<Portal>>) {
+    for entity in portals.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_velocity() {
+        // Both portals lay on flat ground
+        let velocity = map_velocity(Vec2::new(3., -4.), Vec2::Y, Vec2::Y);
+        assert!(velocity.abs_diff_eq(Vec2::new(3., 4.), 1e-5));
+
+        // Exit portal is on the vertical wall facing to the left
+        let velocity = map_velocity(Vec2::new(3., -4.), Vec2::Y, Vec2::NEG_X);
+        assert!(velocity.abs_diff_eq(Vec2::new(-4., 3.), 1e-5));
+        assert!((velocity.length() - 5.).abs() < 1e-5);
+    }
+}
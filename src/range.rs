@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::Rng;
+
+use crate::collider::Collider;
+use crate::components::Position;
+use crate::explosion::ExplosionHitEvent;
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::AppState;
+use crate::geometry::rect::MyRect;
+use crate::landscape::Landscape;
+use crate::mines::settle_height;
+use crate::rules::{GameMode, GameRules};
+use crate::tank::setup_tanks;
+
+const TARGETS_COUNT: usize = 3;
+const TARGET_RADIUS: f32 = 15.;
+/// Score which is given for hitting of a target.
+const TARGET_SCORE: u32 = 100;
+/// Part of the field's width near the tank which is free from targets.
+const SAFE_ZONE: f32 = 0.3;
+/// Range of height of targets above the surface of landscape.
+const MIN_TARGET_HEIGHT: f32 = 30.;
+const MAX_TARGET_HEIGHT: f32 = 250.;
+
+/// Stationary targets of the target range mode.
+pub struct TargetRangePlugin;
+
+impl Plugin for TargetRangePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TargetHitEvent>()
+            .init_resource::<RangeScore>()
+            .add_systems(
+                OnEnter(AppState::RoundSetup),
+                spawn_targets_system
+                    .after(setup_tanks)
+                    .run_if(is_target_range),
+            )
+            .add_systems(Update, hit_targets_system.run_if(is_target_range));
+    }
+}
+
+fn is_target_range(rules: Res<GameRules>) -> bool {
+    rules.mode == GameMode::TargetRange
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Target;
+
+/// Score of the player in the target range.
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct RangeScore {
+    pub score: u32,
+    pub hits: u32,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TargetHitEvent {
+    pub position: Vec2,
+    /// Total score of the player after the hit.
+    pub score: u32,
+}
+
+fn target_rect(position: Vec2) -> MyRect {
+    MyRect::from_center_size(position, Vec2::splat(2. * TARGET_RADIUS))
+}
+
+/// Returns random position of a target which hangs over
+/// the landscape far enough from the tank at the left side of field.
+fn random_target_position(landscape: &Landscape, rng: &mut impl Rng) -> Vec2 {
+    let (width, height) = landscape.size();
+    let (width, height) = (width as f32, height as f32);
+    let x = rng.gen_range(width * SAFE_ZONE..width - TARGET_RADIUS);
+    let surface = settle_height(landscape, x as i32, height as i32 - 1) as f32;
+    let max_y = height - TARGET_RADIUS;
+    let min_y = (surface + TARGET_RADIUS + MIN_TARGET_HEIGHT).min(max_y);
+    let y = rng.gen_range(min_y..=(surface + MAX_TARGET_HEIGHT).clamp(min_y, max_y));
+    Vec2::new(x, y)
+}
+
+fn spawn_target(commands: &mut Commands, game_field: &GameField, rng: &mut GameRng) {
+    let position = random_target_position(&game_field.landscape, rng);
+    let circle = shapes::Circle {
+        radius: TARGET_RADIUS,
+        center: Vec2::ZERO,
+    };
+    let target_entity = commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&circle),
+                spatial: SpatialBundle::from_transform(Transform::from_translation(
+                    position.extend(2.),
+                )),
+                ..default()
+            },
+            Fill::color(Color::WHITE),
+            Stroke::new(Color::RED, 5.),
+            Position(position),
+            Collider::circle(TARGET_RADIUS),
+            Target,
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(target_entity);
+}
+
+fn spawn_targets_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    game_rng: Option<ResMut<GameRng>>,
+    mut score: ResMut<RangeScore>,
+) {
+    let (Some(game_field), Some(mut game_rng)) = (game_field, game_rng) else {
+        return;
+    };
+    *score = RangeScore::default();
+    for _ in 0..TARGETS_COUNT {
+        spawn_target(&mut commands, &game_field, &mut game_rng);
+    }
+}
+
+/// Targets hit by explosions give score and are replaced by new ones.
+fn hit_targets_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    game_rng: Option<ResMut<GameRng>>,
+    mut score: ResMut<RangeScore>,
+    mut explosion_events: EventReader<ExplosionHitEvent>,
+    targets_query: Query<(Entity, &Position), With<Target>>,
+    mut hit_events: EventWriter<TargetHitEvent>,
+) {
+    let (Some(game_field), Some(mut game_rng)) = (game_field, game_rng) else {
+        return;
+    };
+    for event in explosion_events.read() {
+        for (entity, &Position(position)) in targets_query.iter() {
+            let percents = event
+                .explosion
+                .get_intersection_percents(event.position, target_rect(position));
+            if percents == 0 {
+                continue;
+            }
+            score.score += TARGET_SCORE;
+            score.hits += 1;
+            hit_events.send(TargetHitEvent {
+                position,
+                score: score.score,
+            });
+            commands.entity(entity).despawn_recursive();
+            spawn_target(&mut commands, &game_field, &mut game_rng);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::landscape_buffer::LandscapeStorage;
+
+    #[test]
+    fn test_random_target_position() {
+        let landscape = Landscape::new(600, 400, 5, LandscapeStorage::default(), None).unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let position = random_target_position(&landscape, &mut rng);
+            assert!(position.x >= 600. * SAFE_ZONE && position.x <= 600. - TARGET_RADIUS);
+            assert!(position.y <= 400. - TARGET_RADIUS);
+            let bottom = (position.y - TARGET_RADIUS) as i32;
+            assert!(!landscape.is_not_empty(position.x as i32, bottom));
+        }
+    }
+}
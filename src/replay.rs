@@ -0,0 +1,246 @@
+//! Recording and playback of replays.
+//!
+//! Replay contains the seed of the round and inputs of every turn.
+//! All random values of the round (landscape, order of players and wind)
+//! are derived from the seed, and timers of the game are advanced by frame
+//! time, so the round can be re-simulated by applying recorded turns.
+use std::path::Path;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::desync::{StateHash, StateHashLog};
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::AppState;
+use crate::rules::{validate_shot, Shot};
+use crate::settings::Settings;
+use crate::simulation::ShootCommand;
+use crate::storage;
+use crate::tank::{AimingTank, Tank, TankSet, TankShotEvent};
+
+pub const REPLAY_FORMAT_VERSION: u32 = 1;
+/// Pause before a shot during playback (seconds).
+const PLAYBACK_AIMING_TIME: f32 = 1.;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Replay>()
+            .add_systems(
+                Update,
+                (
+                    start_recording_system.run_if(resource_added::<GameField>),
+                    playback_system
+                        .before(TankSet::Aiming)
+                        .run_if(in_state(AppState::Aiming))
+                        .run_if(resource_exists::<ReplayPlayback>),
+                ),
+            )
+            .add_systems(PostUpdate, record_turns_system)
+            .add_systems(Last, export_replay_on_exit_system);
+    }
+}
+
+/// Inputs of one turn.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTurn {
+    pub player: u8,
+    pub angle: f32,
+    pub power: f32,
+    pub wind: f32,
+    /// Hash of the game state at start of the turn.
+    #[serde(default)]
+    pub state_hash: Option<StateHash>,
+}
+
+/// Replay of the game. While the game is running this resource
+/// records turns of players.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Resource)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub field_width: u16,
+    pub field_height: u16,
+    pub turns: Vec<ReplayTurn>,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            version: REPLAY_FORMAT_VERSION,
+            seed: 0,
+            field_width: 0,
+            field_height: 0,
+            turns: vec![],
+        }
+    }
+}
+
+impl Replay {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        storage::write(path, self.to_json()?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let json = storage::read_to_string(path)?;
+        Ok(Self::from_json(&json)?)
+    }
+}
+
+/// Insert this resource into the app to play back the replay instead of
+/// taking input from players. The resource is removed after the last turn.
+#[derive(Debug, Clone, Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    next_turn: usize,
+    aiming_time: f32,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_turn: 0,
+            aiming_time: 0.,
+        }
+    }
+
+    #[inline]
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Returns the turn which will be played next.
+    #[inline]
+    pub fn next_turn(&self) -> Option<&ReplayTurn> {
+        self.replay.turns.get(self.next_turn)
+    }
+}
+
+fn start_recording_system(
+    mut replay: ResMut<Replay>,
+    game_field: Res<GameField>,
+    game_rng: Res<GameRng>,
+) {
+    *replay = Replay {
+        seed: game_rng.seed(),
+        field_width: game_field.width,
+        field_height: game_field.height,
+        ..default()
+    };
+}
+
+fn record_turns_system(
+    mut replay: ResMut<Replay>,
+    game_field: Option<Res<GameField>>,
+    hash_log: Res<StateHashLog>,
+    tanks_query: Query<&Tank>,
+    mut shot_events: EventReader<TankShotEvent>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    for event in shot_events.read() {
+        if let Ok(tank) = tanks_query.get(event.tank_entity) {
+            replay.turns.push(ReplayTurn {
+                player: tank.player_number,
+                angle: tank.gun_angle_deg(),
+                power: tank.power,
+                wind: game_field.wind_power,
+                state_hash: hash_log.last().copied(),
+            });
+        }
+    }
+}
+
+fn playback_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut game_field: ResMut<GameField>,
+    mut aiming_tanks: Query<&mut Tank, With<AimingTank>>,
+    mut shoot_commands: EventWriter<ShootCommand>,
+) {
+    let Ok(mut tank) = aiming_tanks.get_single_mut() else {
+        return;
+    };
+    let Some(&turn) = playback.replay.turns.get(playback.next_turn) else {
+        info!("Replay has finished");
+        commands.remove_resource::<ReplayPlayback>();
+        return;
+    };
+    let shot = Shot {
+        player: turn.player,
+        angle: turn.angle,
+        power: turn.power,
+        weapon: None,
+    };
+    if let Err(violation) = validate_shot(&shot, Some(tank.player_number), None) {
+        error!("Replay is corrupted or out of sync: {}", violation);
+        commands.remove_resource::<ReplayPlayback>();
+        return;
+    }
+
+    if tank.gun_angle_deg() != turn.angle || tank.power != turn.power {
+        tank.set_gun_angle(turn.angle);
+        tank.set_gun_power(turn.power);
+    }
+    playback.aiming_time += time.delta_seconds();
+    if playback.aiming_time < PLAYBACK_AIMING_TIME {
+        return;
+    }
+
+    game_field.wind_power = turn.wind;
+    shoot_commands.send(ShootCommand(shot));
+    playback.next_turn += 1;
+    playback.aiming_time = 0.;
+}
+
+fn export_replay_on_exit_system(
+    settings: Res<Settings>,
+    replay: Res<Replay>,
+    mut exit_events: EventReader<AppExit>,
+) {
+    if exit_events.read().count() == 0 {
+        return;
+    }
+    if let Some(path) = settings.replay_path.as_ref() {
+        if let Err(err) = replay.save(path) {
+            error!("Failed to save replay: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let replay = Replay {
+            seed: 42,
+            field_width: 1022,
+            field_height: 736,
+            turns: vec![ReplayTurn {
+                player: 3,
+                angle: -12.5,
+                power: 60.,
+                wind: 4.2,
+                state_hash: None,
+            }],
+            ..default()
+        };
+        let json = replay.to_json().unwrap();
+        assert_eq!(Replay::from_json(&json).unwrap(), replay);
+    }
+}
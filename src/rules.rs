@@ -0,0 +1,405 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::landscape_buffer::fnv1a_hash;
+use crate::weapons::{TankWeapon, Weapons};
+
+pub const MIN_GUN_ANGLE: f32 = -90.;
+pub const MAX_GUN_ANGLE: f32 = 90.;
+pub const MAX_GUN_POWER: f32 = 100.;
+pub const MIN_TEAMS_COUNT: u8 = 2;
+/// Time bank of every player in blitz match (seconds).
+pub const BLITZ_TIME_BANK: f32 = 180.;
+const CHAOS_TURN_TIME: f32 = 10.;
+const CHAOS_TURN_LIMIT: usize = 10;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Standard,
+    /// Mode for learning of controls, aiming tank shows its trajectory.
+    Practice,
+    /// Single tank shoots at stationary targets without switching
+    /// of turns, aiming tank shows its trajectory.
+    TargetRange,
+}
+
+/// Rules of the match.
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+pub struct GameRules {
+    pub mode: GameMode,
+    /// Time of one turn (seconds). When time is over, the tank shoots
+    /// with its current aim.
+    #[serde(default)]
+    pub turn_time_limit: Option<f32>,
+    /// Total time of every player for the whole match (seconds).
+    /// Clock of the player runs while their tank is aiming, like
+    /// chess clock. The player forfeits when their time is over.
+    #[serde(default)]
+    pub time_bank: Option<f32>,
+    /// Number of turns after which sudden death begins: every turn
+    /// damages all tanks until only one of them remains.
+    #[serde(default)]
+    pub turn_limit: Option<usize>,
+    #[serde(default)]
+    pub economy: EconomyRules,
+    /// Players are split into teams, the round is won by the last
+    /// team which has surviving tanks.
+    #[serde(default)]
+    pub teams: Option<TeamRules>,
+    #[serde(default)]
+    pub spawn: SpawnRules,
+    /// Pixels of landscape slide sideways while subsidence until slopes
+    /// are not steeper than this angle (degrees). Otherwise they only fall down.
+    #[serde(default)]
+    pub angle_of_repose: Option<f32>,
+    /// Missiles of different tanks detonate each other
+    /// when their paths meet.
+    #[serde(default)]
+    pub missile_interception: bool,
+    /// Count of obstacles (bunkers, walls, trees) placed
+    /// on the field at start of round.
+    #[serde(default)]
+    pub obstacles: u8,
+    /// Crate with a random item is dropped from the sky
+    /// every given number of turns.
+    #[serde(default)]
+    pub supply_drop_turns: Option<usize>,
+    /// Meteor showers and airstrikes happen at random
+    /// at the end of turns.
+    #[serde(default)]
+    pub hazards: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedTeamRules")]
+pub struct TeamRules {
+    /// Count of teams. Players are assigned to teams in turn,
+    /// so 5 players in 2 teams play 3v2.
+    pub count: u8,
+    /// Explosions damage tanks of teammates.
+    #[serde(default)]
+    pub friendly_fire: bool,
+}
+
+/// Team rules as they are stored, before validation.
+#[derive(Deserialize)]
+struct UncheckedTeamRules {
+    count: u8,
+    #[serde(default)]
+    friendly_fire: bool,
+}
+
+impl TryFrom<UncheckedTeamRules> for TeamRules {
+    type Error = String;
+
+    fn try_from(rules: UncheckedTeamRules) -> Result<Self, Self::Error> {
+        if rules.count < MIN_TEAMS_COUNT {
+            return Err(format!(
+                "count of teams must be at least {}",
+                MIN_TEAMS_COUNT
+            ));
+        }
+        Ok(Self {
+            count: rules.count,
+            friendly_fire: rules.friendly_fire,
+        })
+    }
+}
+
+impl TeamRules {
+    pub fn new(count: u8) -> Self {
+        Self {
+            count,
+            friendly_fire: false,
+        }
+    }
+
+    /// Returns number of the player's team, starting from 1.
+    /// Invalid player number 0 has no team and returns 0.
+    #[inline]
+    pub fn team_of(&self, player_number: u8) -> u8 {
+        player_number
+            .checked_sub(1)
+            .and_then(|index| index.checked_rem(self.count))
+            .map_or(0, |team_index| team_index + 1)
+    }
+
+    #[inline]
+    pub fn are_teammates(&self, player_a: u8, player_b: u8) -> bool {
+        self.team_of(player_a) == self.team_of(player_b)
+    }
+}
+
+/// How tanks are placed on the field at start of round.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpawnLayout {
+    /// Tanks are evenly spaced.
+    #[default]
+    Even,
+    /// Tanks are placed at random points.
+    Random,
+    /// Tanks are evenly spaced and teammates stand side by side.
+    ClusteredTeams,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpawnRules {
+    pub layout: SpawnLayout,
+    /// Minimal distance between tanks (pixels). It is reduced
+    /// if so many tanks can't keep it on the field.
+    pub min_distance: f32,
+    /// Maximal random shift of evenly spaced tanks (pixels).
+    #[serde(default)]
+    pub jitter: f32,
+    /// Ground under tanks is levelled at start of round.
+    #[serde(default)]
+    pub flatten_pads: bool,
+}
+
+impl Default for SpawnRules {
+    fn default() -> Self {
+        Self {
+            layout: SpawnLayout::Even,
+            min_distance: 100.,
+            jitter: 0.,
+            flatten_pads: false,
+        }
+    }
+}
+
+/// Rules of players' finances between rounds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EconomyRules {
+    /// Players may take loans in the shop, which are repaid
+    /// from future winnings, and earn interest on their savings.
+    pub bank: bool,
+    /// Interest which is added to the debt when a loan is taken.
+    pub loan_interest_rate: f32,
+    /// Maximal debt of a player including interest.
+    pub max_debt: u32,
+    /// Interest on savings of players without debts,
+    /// which is paid at the end of every round.
+    pub savings_interest_rate: f32,
+    /// Money which is paid to a player when their tank is destroyed.
+    #[serde(default)]
+    pub insurance: Option<u32>,
+    /// Bounty for destroying the tank of points leader per one point.
+    #[serde(default)]
+    pub bounty_per_point: Option<u32>,
+    /// Players buy weapons and items in the shop between rounds,
+    /// every shot of a weapon spends one of them.
+    #[serde(default)]
+    pub shop: bool,
+}
+
+impl Default for EconomyRules {
+    fn default() -> Self {
+        Self {
+            bank: false,
+            loan_interest_rate: 0.2,
+            max_debt: 20000,
+            savings_interest_rate: 0.05,
+            insurance: None,
+            bounty_per_point: None,
+            shop: false,
+        }
+    }
+}
+
+impl GameRules {
+    /// Preset of "blitz" match with chess clocks instead of
+    /// time limit of every turn.
+    pub fn blitz() -> Self {
+        Self {
+            turn_time_limit: None,
+            time_bank: Some(BLITZ_TIME_BANK),
+            ..default()
+        }
+    }
+
+    /// Preset of chaotic match with short turns and early sudden death.
+    pub fn chaos() -> Self {
+        Self {
+            turn_time_limit: Some(CHAOS_TURN_TIME),
+            turn_limit: Some(CHAOS_TURN_LIMIT),
+            ..default()
+        }
+    }
+
+    /// Returns `true` if both players are the same player or teammates.
+    pub fn is_friendly(&self, player_a: u8, player_b: u8) -> bool {
+        player_a == player_b
+            || self
+                .teams
+                .is_some_and(|teams| teams.are_teammates(player_a, player_b))
+    }
+
+    /// Returns hash of rules which is stable between runs and platforms,
+    /// so it may be compared with hash of rules of other players.
+    pub fn stable_hash(&self) -> u64 {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        fnv1a_hash(&json)
+    }
+}
+
+/// Shot requested by a player, bot or remote client.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Shot {
+    pub player: u8,
+    pub angle: f32,
+    pub power: f32,
+    /// Index of fired weapon in `Weapons`, `None` is the standard missile.
+    #[serde(default)]
+    pub weapon: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShotViolation {
+    AngleOutOfRange,
+    PowerOutOfRange,
+    NotPlayersTurn,
+    WeaponNotInInventory,
+}
+
+impl ShotViolation {
+    /// Returns `true` if the violation can't be made by an honest client,
+    /// which limits aim by the same rules. Shots out of turn may be
+    /// caused by network delays.
+    pub fn is_cheating(&self) -> bool {
+        *self != Self::NotPlayersTurn
+    }
+}
+
+impl fmt::Display for ShotViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AngleOutOfRange => write!(
+                f,
+                "angle of gun must be in range {}..={}",
+                MIN_GUN_ANGLE, MAX_GUN_ANGLE
+            ),
+            Self::PowerOutOfRange => write!(f, "power must be in range 0..={}", MAX_GUN_POWER),
+            Self::NotPlayersTurn => write!(f, "it is not turn of the player"),
+            Self::WeaponNotInInventory => write!(f, "the tank has no such weapon"),
+        }
+    }
+}
+
+/// Checks that the shot may be done by rules of the game.
+/// `current_player` is number of player whose tank is aiming now,
+/// `inventory` is weapon state of this tank.
+pub fn validate_shot(
+    shot: &Shot,
+    current_player: Option<u8>,
+    inventory: Option<(&TankWeapon, &Weapons)>,
+) -> Result<(), ShotViolation> {
+    if current_player != Some(shot.player) {
+        return Err(ShotViolation::NotPlayersTurn);
+    }
+    if !(MIN_GUN_ANGLE..=MAX_GUN_ANGLE).contains(&shot.angle) {
+        return Err(ShotViolation::AngleOutOfRange);
+    }
+    if !(0. ..=MAX_GUN_POWER).contains(&shot.power) {
+        return Err(ShotViolation::PowerOutOfRange);
+    }
+    if let Some(index) = shot.weapon {
+        let has_weapon = inventory.is_some_and(|(tank_weapon, weapons)| {
+            index < weapons.0.len() && tank_weapon.has_ammo(index)
+        });
+        if !has_weapon {
+            return Err(ShotViolation::WeaponNotInInventory);
+        }
+    }
+    Ok(())
+}
+
+#[inline]
+pub fn clamp_gun_angle(angle: f32) -> f32 {
+    angle.clamp(MIN_GUN_ANGLE, MAX_GUN_ANGLE)
+}
+
+#[inline]
+pub fn clamp_gun_power(power: f32) -> f32 {
+    power.clamp(0., MAX_GUN_POWER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_team_rules() {
+        let teams: TeamRules = serde_json::from_str(r#"{"count": 3}"#).unwrap();
+        assert_eq!(teams, TeamRules::new(3));
+        assert_eq!(teams.team_of(5), 2);
+        assert_eq!(teams.team_of(0), 0);
+        for count in [0, 1] {
+            let json = format!(r#"{{"count": {}}}"#, count);
+            assert!(serde_json::from_str::<TeamRules>(&json).is_err());
+        }
+        assert_eq!(TeamRules::new(0).team_of(1), 0);
+    }
+
+    #[test]
+    fn test_validate_shot() {
+        let shot = Shot {
+            player: 2,
+            angle: -90.,
+            power: 100.,
+            weapon: None,
+        };
+        assert_eq!(validate_shot(&shot, Some(2), None), Ok(()));
+        assert_eq!(
+            validate_shot(&shot, Some(3), None),
+            Err(ShotViolation::NotPlayersTurn)
+        );
+        assert_eq!(
+            validate_shot(&shot, None, None),
+            Err(ShotViolation::NotPlayersTurn)
+        );
+
+        for angle in [-90.1, 91., f32::NAN] {
+            let shot = Shot { angle, ..shot };
+            assert_eq!(
+                validate_shot(&shot, Some(2), None),
+                Err(ShotViolation::AngleOutOfRange)
+            );
+        }
+        for power in [-0.1, 100.1, f32::INFINITY] {
+            let shot = Shot { power, ..shot };
+            assert_eq!(
+                validate_shot(&shot, Some(2), None),
+                Err(ShotViolation::PowerOutOfRange)
+            );
+        }
+
+        let weapons = Weapons::default();
+        let mut tank_weapon = TankWeapon::default();
+        let shot = Shot {
+            weapon: Some(1),
+            ..shot
+        };
+        let inventory = Some((&tank_weapon, &weapons));
+        assert_eq!(validate_shot(&shot, Some(2), inventory), Ok(()));
+        let unknown_weapon = Shot {
+            weapon: Some(weapons.0.len()),
+            ..shot
+        };
+        assert_eq!(
+            validate_shot(&unknown_weapon, Some(2), inventory),
+            Err(ShotViolation::WeaponNotInInventory)
+        );
+        tank_weapon.set_ammo([(0, 1)].into_iter().collect());
+        assert_eq!(
+            validate_shot(&shot, Some(2), Some((&tank_weapon, &weapons))),
+            Err(ShotViolation::WeaponNotInInventory)
+        );
+        assert_eq!(
+            validate_shot(&shot, Some(2), None),
+            Err(ShotViolation::WeaponNotInInventory)
+        );
+    }
+}
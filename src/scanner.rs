@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::cloak::Cloaked;
+use crate::components::Position;
+use crate::input::PlayerAction;
+use crate::mines::Mine;
+use crate::tank::{shoot_system, AimingTank, Tank, TankSet};
+use crate::turn::TurnEndedEvent;
+use crate::weapons::{TankWeapon, WeaponKind, Weapons};
+
+/// Radius of outline around revealed objects.
+const OUTLINE_RADIUS: f32 = 8.;
+/// Pulses of outline per second.
+const PULSE_FREQUENCY: f32 = 2.;
+
+pub struct ScannerPlugin;
+
+impl Plugin for ScannerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScanEvent>()
+            .add_systems(
+                Update,
+                use_scanner_system
+                    .after(shoot_system)
+                    .in_set(TankSet::Aiming),
+            )
+            .add_systems(Update, (hide_revealed_system, pulse_outlines_system));
+    }
+}
+
+/// Hidden object (mine or cloaked tank) which has been revealed
+/// by scanner until the end of turn.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Revealed;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct RevealOutline;
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ScanEvent {
+    pub player_number: u8,
+    pub position: Vec2,
+    pub radius: f32,
+}
+
+/// Scans the area around the aiming tank when its player fires
+/// the scanner. Scanning doesn't end the turn, the standard missile
+/// is selected after it.
+#[allow(clippy::type_complexity)]
+fn use_scanner_system(
+    mut commands: Commands,
+    weapons: Res<Weapons>,
+    mut actions: EventReader<PlayerAction>,
+    mut aiming_tanks: Query<(&Tank, &Position, &mut TankWeapon), With<AimingTank>>,
+    hidden_query: Query<(Entity, &Position), (Or<(With<Mine>, With<Cloaked>)>, Without<Revealed>)>,
+    mut scan_events: EventWriter<ScanEvent>,
+) {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    if !fire {
+        return;
+    }
+    for (tank, &Position(tank_position), mut weapon) in aiming_tanks.iter_mut() {
+        if weapon.ready_weapon_kind(&weapons) != WeaponKind::Scanner {
+            continue;
+        }
+        let Some(radius) = weapon
+            .fire(&weapons)
+            .and_then(|index| weapons.0.get(index))
+            .map(|definition| definition.radius)
+        else {
+            continue;
+        };
+        weapon.deselect();
+        info!(
+            "Player {} scans area around {:?}",
+            tank.player_number, tank_position
+        );
+
+        let outline = shapes::Circle {
+            radius: OUTLINE_RADIUS,
+            ..default()
+        };
+        for (entity, position) in hidden_query.iter() {
+            if position.0.distance(tank_position) > radius {
+                continue;
+            }
+            let outline_entity = commands
+                .spawn((
+                    ShapeBundle {
+                        path: GeometryBuilder::build_as(&outline),
+                        ..default()
+                    },
+                    Stroke::new(Color::rgb(0.3, 1., 0.3), 1.),
+                    RevealOutline,
+                ))
+                .id();
+            commands
+                .entity(entity)
+                .insert(Revealed)
+                .add_child(outline_entity);
+        }
+        scan_events.send(ScanEvent {
+            player_number: tank.player_number,
+            position: tank_position,
+            radius,
+        });
+    }
+}
+
+fn hide_revealed_system(
+    mut commands: Commands,
+    mut ended_events: EventReader<TurnEndedEvent>,
+    revealed_query: Query<Entity, With<Revealed>>,
+    outlines_query: Query<Entity, With<RevealOutline>>,
+) {
+    if ended_events.read().count() == 0 {
+        return;
+    }
+    for entity in revealed_query.iter() {
+        commands.entity(entity).remove::<Revealed>();
+    }
+    for entity in outlines_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pulse_outlines_system(
+    time: Res<Time>,
+    mut outlines_query: Query<&mut Transform, With<RevealOutline>>,
+) {
+    let phase = time.elapsed_seconds() * PULSE_FREQUENCY * std::f32::consts::TAU;
+    let scale = 1. + 0.25 * phase.sin();
+    for mut transform in outlines_query.iter_mut() {
+        transform.scale = Vec3::new(scale, scale, 1.);
+    }
+}
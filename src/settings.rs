@@ -0,0 +1,193 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::landscape_buffer::LandscapeStorage;
+
+pub const MIN_UI_SCALE: f32 = 0.75;
+pub const MAX_UI_SCALE: f32 = 2.;
+pub const MIN_FIELD_SIZE: FieldSize = FieldSize {
+    width: 200,
+    height: 100,
+};
+pub const MAX_FIELD_SIZE: FieldSize = FieldSize {
+    width: 8192,
+    height: 4096,
+};
+
+/// User settings of the game.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Settings {
+    pub input_repeat: InputRepeatSettings,
+    pub audio: AudioSettings,
+    /// Path of file to export events timeline on exit.
+    pub timeline_path: Option<PathBuf>,
+    /// Path of file to save replay of the game on exit.
+    pub replay_path: Option<PathBuf>,
+    /// Storage of landscape pixels. `LandscapeStorage::Bytes` is kept
+    /// for comparison with the default bitset storage.
+    pub landscape_storage: LandscapeStorage,
+    /// Explosions and subsidence are drawn into the texture of landscape
+    /// by compute shaders, instead of uploading of changed pixels.
+    pub gpu_landscape: bool,
+    /// Seed of random values of the round. Random seed is used if it is `None`.
+    pub seed: Option<u64>,
+    /// Time of day changes during the match.
+    pub day_night_cycle: bool,
+    /// Weather is chosen randomly at the start of every round
+    /// instead of using the `Weather` resource as is.
+    pub random_weather: bool,
+    pub hud_layout: HudLayout,
+    pub ui_scale: UiScaleFactor,
+    pub field_size: FieldSize,
+}
+
+/// Size of game field (pixels). It doesn't depend on size of window,
+/// so fields larger than window are scrolled and zoomed by camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl FieldSize {
+    /// Parses size given as `<width>x<height>`, e.g. `4096x1024`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (width, height) = value.split_once('x')?;
+        let size = Self {
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+        };
+        let is_valid = (MIN_FIELD_SIZE.width..=MAX_FIELD_SIZE.width).contains(&size.width)
+            && (MIN_FIELD_SIZE.height..=MAX_FIELD_SIZE.height).contains(&size.height);
+        is_valid.then_some(size)
+    }
+}
+
+impl Default for FieldSize {
+    /// Field which fits default window under the status panel.
+    fn default() -> Self {
+        Self {
+            width: 1022,
+            height: 736,
+        }
+    }
+}
+
+/// Scale of the status panel, labels of tanks and menus,
+/// so the game is playable on high-DPI displays and small windows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiScaleFactor(f32);
+
+impl UiScaleFactor {
+    /// Creates scale clamped to `MIN_UI_SCALE..=MAX_UI_SCALE`.
+    pub fn new(scale: f32) -> Self {
+        Self(scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE))
+    }
+
+    #[inline]
+    pub fn get(self) -> f32 {
+        self.0
+    }
+}
+
+impl Default for UiScaleFactor {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Layout of information about the game on the screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HudLayout {
+    /// Status panel of the current tank with aiming widgets.
+    #[default]
+    Standard,
+    /// Layout for casting and spectating: status of all players
+    /// at the top of the screen and larger announcements.
+    Broadcast,
+}
+
+/// Timings of repeating an action while its button is held.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputRepeatSettings {
+    /// Delay between pressing the button and the first repeat.
+    pub delay: Duration,
+    /// Interval between repeats.
+    pub interval: Duration,
+    /// Interval between repeats after the button has been held
+    /// for `fast_after` time.
+    pub fast_interval: Duration,
+    pub fast_after: Duration,
+}
+
+impl Default for InputRepeatSettings {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(500),
+            interval: Duration::from_millis(25),
+            fast_interval: Duration::from_millis(5),
+            fast_after: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Volumes of sound channels in range `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioSettings {
+    /// Volume of sound effects: shots, explosions, etc.
+    pub sfx_volume: f32,
+    /// Volume of music and ambient sounds.
+    pub music_volume: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            sfx_volume: 1.,
+            music_volume: 1.,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Resulting volume of sound effects.
+    pub fn sfx(&self) -> f32 {
+        self.channel_volume(self.sfx_volume)
+    }
+
+    /// Resulting volume of music and ambient sounds.
+    pub fn music(&self) -> f32 {
+        self.channel_volume(self.music_volume)
+    }
+
+    fn channel_volume(&self, volume: f32) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            volume.clamp(0., 1.)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_volumes() {
+        let mut audio = AudioSettings {
+            sfx_volume: 0.5,
+            music_volume: 2.,
+            muted: false,
+        };
+        assert_eq!(audio.sfx(), 0.5);
+        assert_eq!(audio.music(), 1.);
+        audio.muted = true;
+        assert_eq!(audio.sfx(), 0.);
+        assert_eq!(audio.music(), 0.);
+    }
+}
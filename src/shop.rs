@@ -0,0 +1,847 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::economy::{EconomyError, Finances, LOAN_AMOUNT};
+use crate::game_field::GameField;
+use crate::game_plugin::AppState;
+use crate::launch::LaunchOptions;
+use crate::rules::{EconomyRules, GameRules};
+use crate::tank::{Health, Tank};
+use crate::upgrades::{TankUpgrades, Upgrade};
+use crate::weapons::{TankWeapon, WeaponIcons, Weapons};
+
+pub const SHIELD_PRICE: u32 = 3000;
+/// Points of damage which are absorbed by a shield.
+pub const SHIELD_STRENGTH: u8 = 50;
+pub const PARACHUTE_PRICE: u32 = 1500;
+pub const FUEL_CAN_PRICE: u32 = 1000;
+/// Additional time (seconds) of thrust of jetpack given by one can of fuel.
+pub const FUEL_CAN_TIME: f32 = 1.;
+const FONT_SIZE: f32 = 20.;
+/// Size of icons of weapons in the list of items.
+const ICON_SIZE: f32 = 20.;
+const ROW_COLOR: Color = Color::NONE;
+const HOVERED_ROW_COLOR: Color = Color::rgba(1., 1., 1., 0.15);
+const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+
+/// Inventories of players which are kept across rounds.
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inventories>().add_systems(
+            Update,
+            (equip_tanks_system, sync_inventories_system).chain(),
+        );
+    }
+}
+
+/// Screen between rounds where players buy weapons and items.
+pub struct ShopScreenPlugin;
+
+impl Plugin for ShopScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            open_shop_system
+                .run_if(in_state(AppState::RoundOver))
+                .run_if(shop_is_enabled),
+        )
+        .add_systems(OnEnter(AppState::Shop), setup_shop_screen)
+        .add_systems(
+            Update,
+            (
+                shop_input_system,
+                shop_buttons_system,
+                update_shop_screen_system,
+            )
+                .chain()
+                .run_if(in_state(AppState::Shop)),
+        )
+        .add_systems(OnExit(AppState::Shop), despawn_shop_screen);
+    }
+}
+
+fn shop_is_enabled(rules: Res<GameRules>) -> bool {
+    rules.economy.shop
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShopItem {
+    /// Index of weapon in `Weapons`.
+    Weapon(usize),
+    Shield,
+    Parachute,
+    FuelCan,
+    Upgrade(Upgrade),
+}
+
+impl ShopItem {
+    /// Returns all items which may be bought.
+    pub fn all(weapons: &Weapons) -> Vec<ShopItem> {
+        (0..weapons.0.len())
+            .map(ShopItem::Weapon)
+            .chain([ShopItem::Shield, ShopItem::Parachute, ShopItem::FuelCan])
+            .chain(Upgrade::ALL.map(ShopItem::Upgrade))
+            .collect()
+    }
+
+    pub fn name<'a>(&self, weapons: &'a Weapons) -> &'a str {
+        match self {
+            Self::Weapon(index) => &weapons.0[*index].name,
+            Self::Shield => "Shield",
+            Self::Parachute => "Parachute",
+            Self::FuelCan => "Can of Fuel",
+            Self::Upgrade(upgrade) => upgrade.name(),
+        }
+    }
+
+    pub fn price(&self, weapons: &Weapons) -> u32 {
+        match self {
+            Self::Weapon(index) => weapons.0[*index].price,
+            Self::Shield => SHIELD_PRICE,
+            Self::Parachute => PARACHUTE_PRICE,
+            Self::FuelCan => FUEL_CAN_PRICE,
+            Self::Upgrade(upgrade) => upgrade.price(),
+        }
+    }
+}
+
+/// Tank lands without damage while it has parachutes.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Parachutes(pub u32);
+
+/// Every can of fuel prolongs one flight of jetpack.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct FuelCans(pub u32);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PlayerInventory {
+    /// Count of every weapon by its index in `Weapons`.
+    pub weapons: HashMap<usize, u32>,
+    pub shields: u32,
+    pub parachutes: u32,
+    pub fuel_cans: u32,
+    pub upgrades: TankUpgrades,
+}
+
+impl PlayerInventory {
+    pub fn count(&self, item: ShopItem) -> u32 {
+        match item {
+            ShopItem::Weapon(index) => self.weapons.get(&index).copied().unwrap_or_default(),
+            ShopItem::Shield => self.shields,
+            ShopItem::Parachute => self.parachutes,
+            ShopItem::FuelCan => self.fuel_cans,
+            ShopItem::Upgrade(upgrade) => self.upgrades.level(upgrade) as u32,
+        }
+    }
+
+    fn add(&mut self, item: ShopItem) {
+        match item {
+            ShopItem::Weapon(index) => *self.weapons.entry(index).or_default() += 1,
+            ShopItem::Shield => self.shields += 1,
+            ShopItem::Parachute => self.parachutes += 1,
+            ShopItem::FuelCan => self.fuel_cans += 1,
+            ShopItem::Upgrade(upgrade) => {
+                self.upgrades.upgrade(upgrade);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Resource)]
+pub struct Inventories(HashMap<u8, PlayerInventory>);
+
+impl Inventories {
+    pub fn count(&self, player_number: u8, item: ShopItem) -> u32 {
+        self.0
+            .get(&player_number)
+            .map_or(0, |inventory| inventory.count(item))
+    }
+
+    pub fn upgrades(&self, player_number: u8) -> TankUpgrades {
+        self.0
+            .get(&player_number)
+            .map(|inventory| inventory.upgrades)
+            .unwrap_or_default()
+    }
+
+    pub fn player_mut(&mut self, player_number: u8) -> &mut PlayerInventory {
+        self.0.entry(player_number).or_default()
+    }
+}
+
+pub fn buy(
+    item: ShopItem,
+    player_number: u8,
+    weapons: &Weapons,
+    finances: &mut Finances,
+    inventories: &mut Inventories,
+) -> Result<(), EconomyError> {
+    let inventory = inventories.player_mut(player_number);
+    if let ShopItem::Upgrade(upgrade) = item {
+        if !inventory.upgrades.can_upgrade(upgrade) {
+            return Err(EconomyError::MaxLevelReached);
+        }
+    }
+    finances
+        .player_mut(player_number)
+        .buy(item.price(weapons))?;
+    inventory.add(item);
+    Ok(())
+}
+
+/// Buys the suggested loadout for the player, returns number of bought weapons.
+fn buy_suggested_loadout(
+    player_number: u8,
+    weapons: &Weapons,
+    finances: &mut Finances,
+    inventories: &mut Inventories,
+) -> u32 {
+    let budget = finances.player(player_number).money;
+    let mut bought = 0;
+    for (definition, count) in weapons.suggested_loadout(budget) {
+        let Some(index) = weapons.0.iter().position(|w| w == definition) else {
+            continue;
+        };
+        for _ in 0..count {
+            let result = buy(
+                ShopItem::Weapon(index),
+                player_number,
+                weapons,
+                finances,
+                inventories,
+            );
+            if result.is_ok() {
+                bought += 1;
+            }
+        }
+    }
+    bought
+}
+
+/// Bots buy the suggested loadout and a parachute.
+fn buy_for_bot(
+    player_number: u8,
+    weapons: &Weapons,
+    finances: &mut Finances,
+    inventories: &mut Inventories,
+) {
+    let _ = buy(
+        ShopItem::Parachute,
+        player_number,
+        weapons,
+        finances,
+        inventories,
+    );
+    buy_suggested_loadout(player_number, weapons, finances, inventories);
+}
+
+/// Gives bought items to tanks of the new round.
+fn equip_tanks_system(
+    mut commands: Commands,
+    rules: Res<GameRules>,
+    mut inventories: ResMut<Inventories>,
+    mut new_tanks_query: Query<(Entity, &Tank, &mut TankWeapon, &mut Health), Added<Tank>>,
+) {
+    for (tank_entity, tank, mut weapon, mut health) in new_tanks_query.iter_mut() {
+        let inventory = inventories.player_mut(tank.player_number);
+        if rules.economy.shop {
+            weapon.set_ammo(inventory.weapons.clone());
+        }
+        if inventory.shields > 0 {
+            inventory.shields -= 1;
+            health.shield = SHIELD_STRENGTH;
+        }
+        commands.entity(tank_entity).insert((
+            Parachutes(inventory.parachutes),
+            FuelCans(inventory.fuel_cans),
+        ));
+    }
+}
+
+/// Spent weapons and items are removed from inventories.
+#[allow(clippy::type_complexity)]
+fn sync_inventories_system(
+    mut inventories: ResMut<Inventories>,
+    tanks_query: Query<
+        (&Tank, &TankWeapon, Option<&Parachutes>, Option<&FuelCans>),
+        Or<(Changed<TankWeapon>, Changed<Parachutes>, Changed<FuelCans>)>,
+    >,
+) {
+    for (tank, weapon, parachutes, fuel_cans) in tanks_query.iter() {
+        let inventory = inventories.player_mut(tank.player_number);
+        if let Some(ammo) = weapon.ammo() {
+            inventory.weapons.clone_from(ammo);
+        }
+        if let Some(&Parachutes(count)) = parachutes {
+            inventory.parachutes = count;
+        }
+        if let Some(&FuelCans(count)) = fuel_cans {
+            inventory.fuel_cans = count;
+        }
+    }
+}
+
+fn open_shop_system(
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.is_some_and(|input| input.just_pressed(KeyCode::Enter)) {
+        debug!("Switch to Shop");
+        next_state.set(AppState::Shop);
+    }
+}
+
+/// Players visit the shop one by one.
+#[derive(Debug, Resource)]
+struct ShopSession {
+    players: Vec<u8>,
+    current: usize,
+    /// Index of selected item.
+    cursor: usize,
+    message: String,
+}
+
+impl ShopSession {
+    fn current_player(&self) -> Option<u8> {
+        self.players.get(self.current).copied()
+    }
+}
+
+#[derive(Component)]
+struct ShopScreen;
+
+/// Row of item in the list of the shop, index of the item in `ShopItem::all()`.
+#[derive(Component)]
+struct ShopItemRow(usize);
+
+#[derive(Component)]
+struct SuggestedLoadoutButton;
+
+#[derive(Component)]
+struct LoanButton;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum ShopText {
+    Header,
+    Item(usize),
+    /// Comparison of the hovered weapon with the selected one.
+    Tooltip,
+    Footer,
+}
+
+fn setup_shop_screen(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    weapons: Res<Weapons>,
+    icons: Option<Res<WeaponIcons>>,
+    rules: Res<GameRules>,
+) {
+    let mut players = game_field.player_numbers.clone();
+    players.sort_unstable();
+    commands.insert_resource(ShopSession {
+        players,
+        current: 0,
+        cursor: 0,
+        message: String::new(),
+    });
+    let text_style = TextStyle {
+        font: game_field.font.clone(),
+        font_size: FONT_SIZE,
+        color: Color::WHITE,
+    };
+    let text = |shop_text: ShopText| (TextBundle::from_section("", text_style.clone()), shop_text);
+    let items = ShopItem::all(&weapons);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(80.),
+                    left: Val::Px(120.),
+                    padding: UiRect::all(Val::Px(10.)),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(10.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.8).into(),
+                ..default()
+            },
+            ShopScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(text(ShopText::Header));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(20.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (index, &item) in items.iter().enumerate() {
+                                let icon = match item {
+                                    ShopItem::Weapon(weapon_index) => {
+                                        icons.as_ref().and_then(|icons| icons.get(weapon_index))
+                                    }
+                                    _ => None,
+                                };
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: Style {
+                                                align_items: AlignItems::Center,
+                                                column_gap: Val::Px(5.),
+                                                ..default()
+                                            },
+                                            background_color: ROW_COLOR.into(),
+                                            ..default()
+                                        },
+                                        ShopItemRow(index),
+                                    ))
+                                    .with_children(|parent| {
+                                        // Items without icons keep the space of icon,
+                                        // so names are aligned.
+                                        let style = Style {
+                                            width: Val::Px(ICON_SIZE),
+                                            height: Val::Px(ICON_SIZE),
+                                            ..default()
+                                        };
+                                        match icon {
+                                            Some(icon) => parent.spawn(ImageBundle {
+                                                style,
+                                                image: UiImage::new(icon),
+                                                ..default()
+                                            }),
+                                            None => parent.spawn(NodeBundle { style, ..default() }),
+                                        };
+                                        parent.spawn(text(ShopText::Item(index)));
+                                    });
+                            }
+                        });
+                    parent.spawn(text(ShopText::Tooltip));
+                });
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(10.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let button = || ButtonBundle {
+                        style: Style {
+                            padding: UiRect::all(Val::Px(5.)),
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    };
+                    parent
+                        .spawn((button(), SuggestedLoadoutButton))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Buy suggested loadout",
+                                text_style.clone(),
+                            ));
+                        });
+                    if rules.economy.bank {
+                        parent
+                            .spawn((button(), LoanButton))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    format!("Take loan of {}", LOAN_AMOUNT),
+                                    text_style.clone(),
+                                ));
+                            });
+                    }
+                });
+            parent.spawn(text(ShopText::Footer));
+        });
+}
+
+fn despawn_shop_screen(mut commands: Commands, screens_query: Query<Entity, With<ShopScreen>>) {
+    for entity in screens_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<ShopSession>();
+}
+
+/// Buys the item and returns message about result of the purchase.
+fn buy_item(
+    item: ShopItem,
+    player_number: u8,
+    weapons: &Weapons,
+    finances: &mut Finances,
+    inventories: &mut Inventories,
+) -> String {
+    match buy(item, player_number, weapons, finances, inventories) {
+        Ok(()) => format!("{} has been bought", item.name(weapons)),
+        Err(err) => format!("Can't buy {}: {}", item.name(weapons), err),
+    }
+}
+
+/// Takes loan for the player and returns message about the result.
+fn take_loan(player_number: u8, rules: &EconomyRules, finances: &mut Finances) -> String {
+    match finances
+        .player_mut(player_number)
+        .take_loan(LOAN_AMOUNT, rules)
+    {
+        Ok(()) => format!("Loan of {} has been taken", LOAN_AMOUNT),
+        Err(err) => format!("Can't take loan: {}", err),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn shop_input_system(
+    weapons: Res<Weapons>,
+    rules: Res<GameRules>,
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    launch_options: Option<Res<LaunchOptions>>,
+    mut session: ResMut<ShopSession>,
+    mut finances: ResMut<Finances>,
+    mut inventories: ResMut<Inventories>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(player_number) = session.current_player() else {
+        debug!("Switch to RoundSetup");
+        next_state.set(AppState::RoundSetup);
+        return;
+    };
+    if launch_options.is_some_and(|options| options.is_bot(player_number)) {
+        buy_for_bot(player_number, &weapons, &mut finances, &mut inventories);
+        session.current += 1;
+        return;
+    }
+    let Some(keyboard_input) = keyboard_input else {
+        return;
+    };
+    let items = ShopItem::all(&weapons);
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        session.cursor = session.cursor.checked_sub(1).unwrap_or(items.len() - 1);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        session.cursor = (session.cursor + 1) % items.len();
+    }
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        session.message = buy_item(
+            items[session.cursor],
+            player_number,
+            &weapons,
+            &mut finances,
+            &mut inventories,
+        );
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyL) {
+        session.message = take_loan(player_number, &rules.economy, &mut finances);
+    }
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        session.current += 1;
+        session.message.clear();
+    }
+}
+
+/// Clicked item is selected and bought.
+#[allow(clippy::too_many_arguments)]
+fn shop_buttons_system(
+    weapons: Res<Weapons>,
+    rules: Res<GameRules>,
+    launch_options: Option<Res<LaunchOptions>>,
+    mut session: ResMut<ShopSession>,
+    mut finances: ResMut<Finances>,
+    mut inventories: ResMut<Inventories>,
+    rows_query: Query<(&Interaction, &ShopItemRow), Changed<Interaction>>,
+    loadout_query: Query<&Interaction, (Changed<Interaction>, With<SuggestedLoadoutButton>)>,
+    loan_query: Query<&Interaction, (Changed<Interaction>, With<LoanButton>)>,
+) {
+    let Some(player_number) = session
+        .current_player()
+        .filter(|&player_number| !launch_options.is_some_and(|o| o.is_bot(player_number)))
+    else {
+        return;
+    };
+    let items = ShopItem::all(&weapons);
+    for (_, row) in rows_query
+        .iter()
+        .filter(|(&interaction, _)| interaction == Interaction::Pressed)
+    {
+        session.cursor = row.0;
+        session.message = buy_item(
+            items[row.0],
+            player_number,
+            &weapons,
+            &mut finances,
+            &mut inventories,
+        );
+    }
+    if loadout_query
+        .iter()
+        .any(|&interaction| interaction == Interaction::Pressed)
+    {
+        let bought =
+            buy_suggested_loadout(player_number, &weapons, &mut finances, &mut inventories);
+        session.message = if bought > 0 {
+            format!("{} weapons of suggested loadout have been bought", bought)
+        } else {
+            "Can't buy suggested loadout: not enough money".to_string()
+        };
+    }
+    if loan_query
+        .iter()
+        .any(|&interaction| interaction == Interaction::Pressed)
+    {
+        session.message = take_loan(player_number, &rules.economy, &mut finances);
+    }
+}
+
+fn item_text(
+    item: ShopItem,
+    is_selected: bool,
+    player_number: u8,
+    weapons: &Weapons,
+    inventories: &Inventories,
+) -> String {
+    let marker = if is_selected { ">" } else { " " };
+    format!(
+        "{} {:<20} {:>6}  x{}",
+        marker,
+        item.name(weapons),
+        item.price(weapons),
+        inventories.count(player_number, item)
+    )
+}
+
+/// Returns tooltip which compares the hovered weapon with the selected one.
+/// Weapon is compared with itself if the selected item is not a weapon.
+fn tooltip_text(hovered: ShopItem, selected: ShopItem, weapons: &Weapons) -> String {
+    let ShopItem::Weapon(index) = hovered else {
+        return String::new();
+    };
+    let weapon = &weapons.0[index];
+    match selected {
+        ShopItem::Weapon(selected_index) => weapon.comparison_tooltip(&weapons.0[selected_index]),
+        _ => weapon.comparison_tooltip(weapon),
+    }
+}
+
+fn update_shop_screen_system(
+    weapons: Res<Weapons>,
+    rules: Res<GameRules>,
+    session: Res<ShopSession>,
+    finances: Res<Finances>,
+    inventories: Res<Inventories>,
+    mut rows_query: Query<(&Interaction, &ShopItemRow, &mut BackgroundColor)>,
+    mut texts_query: Query<(&ShopText, &mut Text)>,
+) {
+    let Some(player_number) = session.current_player() else {
+        return;
+    };
+    let items = ShopItem::all(&weapons);
+    let mut hovered = None;
+    for (&interaction, row, mut background_color) in rows_query.iter_mut() {
+        let color = if interaction != Interaction::None {
+            hovered = Some(row.0);
+            HOVERED_ROW_COLOR
+        } else {
+            ROW_COLOR
+        };
+        if background_color.0 != color {
+            background_color.0 = color;
+        }
+    }
+    // Tooltip of the selected item is shown if nothing is hovered,
+    // so it is available with keyboard too.
+    let hovered = items[hovered.unwrap_or(session.cursor)];
+    let selected = items[session.cursor];
+
+    for (&shop_text, mut text) in texts_query.iter_mut() {
+        let value = match shop_text {
+            ShopText::Header => {
+                let player_finances = finances.player(player_number);
+                let mut value = format!(
+                    "Shop - Player {}\nMoney: {}",
+                    player_number, player_finances.money
+                );
+                if rules.economy.bank {
+                    value += &format!("  Debt: {}", player_finances.debt);
+                }
+                value
+            }
+            ShopText::Item(index) => item_text(
+                items[index],
+                index == session.cursor,
+                player_number,
+                &weapons,
+                &inventories,
+            ),
+            ShopText::Tooltip => tooltip_text(hovered, selected, &weapons),
+            ShopText::Footer => {
+                let mut value = "Up/Down - select, Space or click - buy, ".to_string();
+                if rules.economy.bank {
+                    value += "L - take loan, ";
+                }
+                value += "Enter - done";
+                if !session.message.is_empty() {
+                    value += "\n";
+                    value += &session.message;
+                }
+                value
+            }
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chassis::ChassisSpec;
+    use crate::economy::START_MONEY;
+    use crate::rules::EconomyRules;
+
+    #[test]
+    fn test_buy() {
+        let weapons = Weapons::default();
+        let mut finances = Finances::default();
+        let mut inventories = Inventories::default();
+        buy(
+            ShopItem::Shield,
+            1,
+            &weapons,
+            &mut finances,
+            &mut inventories,
+        )
+        .unwrap();
+        buy(
+            ShopItem::Weapon(1),
+            1,
+            &weapons,
+            &mut finances,
+            &mut inventories,
+        )
+        .unwrap();
+        assert_eq!(finances.player(1).money, START_MONEY - SHIELD_PRICE - 1900);
+        assert_eq!(inventories.count(1, ShopItem::Shield), 1);
+        assert_eq!(inventories.count(1, ShopItem::Weapon(1)), 1);
+        assert_eq!(inventories.count(2, ShopItem::Weapon(1)), 0);
+        assert_eq!(
+            buy(
+                ShopItem::Weapon(2),
+                1,
+                &weapons,
+                &mut finances,
+                &mut inventories
+            ),
+            Err(EconomyError::NotEnoughMoney)
+        );
+        assert_eq!(inventories.count(1, ShopItem::Weapon(2)), 0);
+
+        let gyroscope = ShopItem::Upgrade(Upgrade::Gyroscope);
+        buy(gyroscope, 3, &weapons, &mut finances, &mut inventories).unwrap();
+        assert!(inventories.upgrades(3).gyroscope);
+        assert_eq!(
+            buy(gyroscope, 3, &weapons, &mut finances, &mut inventories),
+            Err(EconomyError::MaxLevelReached)
+        );
+
+        buy_for_bot(2, &weapons, &mut finances, &mut inventories);
+        assert_eq!(inventories.count(2, ShopItem::Parachute), 1);
+        assert!(inventories.count(2, ShopItem::Weapon(0)) > 0);
+    }
+
+    #[test]
+    fn test_tooltip_and_suggested_loadout() {
+        let weapons = Weapons::default();
+        let missile = ShopItem::Weapon(1);
+        let nuke = ShopItem::Weapon(3);
+        assert_eq!(
+            tooltip_text(nuke, missile, &weapons),
+            weapons.0[3].comparison_tooltip(&weapons.0[1])
+        );
+        assert_eq!(
+            tooltip_text(nuke, ShopItem::Shield, &weapons),
+            weapons.0[3].comparison_tooltip(&weapons.0[3])
+        );
+        assert_eq!(tooltip_text(ShopItem::Shield, nuke, &weapons), "");
+
+        let mut finances = Finances::default();
+        let mut inventories = Inventories::default();
+        let bought = buy_suggested_loadout(1, &weapons, &mut finances, &mut inventories);
+        assert_eq!(bought, 6);
+        assert_eq!(inventories.count(1, ShopItem::Weapon(1)), 3);
+    }
+
+    #[test]
+    fn test_take_loan() {
+        let mut rules = EconomyRules::default();
+        let mut finances = Finances::default();
+        assert_eq!(
+            take_loan(1, &rules, &mut finances),
+            "Can't take loan: loans are disabled by rules of the match"
+        );
+        rules.bank = true;
+        take_loan(1, &rules, &mut finances);
+        assert_eq!(finances.player(1).money, START_MONEY + LOAN_AMOUNT);
+        assert!(finances.player(1).debt > LOAN_AMOUNT);
+    }
+
+    #[test]
+    fn test_bought_weapons_are_equipped() {
+        let weapons = Weapons::default();
+        let nuke = weapons.0.iter().position(|w| w.name == "Nuke").unwrap();
+        let mut finances = Finances::default();
+        finances.player_mut(1).receive(START_MONEY);
+        let mut inventories = Inventories::default();
+        let item = ShopItem::Weapon(nuke);
+        buy(item, 1, &weapons, &mut finances, &mut inventories).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins(ShopPlugin)
+            .insert_resource(GameRules {
+                economy: EconomyRules {
+                    shop: true,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert_resource(inventories);
+        let tank_entity = app
+            .world
+            .spawn((
+                Tank::new(1, &ChassisSpec::default()),
+                TankWeapon::default(),
+                Health {
+                    value: 100,
+                    shield: 0,
+                    invincible: false,
+                },
+            ))
+            .id();
+        app.update();
+
+        let mut tank_weapon = app.world.get_mut::<TankWeapon>(tank_entity).unwrap();
+        tank_weapon.select_next(&weapons);
+        assert_eq!(tank_weapon.ready_weapon(), Some(nuke));
+        // Bought weapon explodes with its own radius instead of
+        // the radius of the standard missile.
+        assert_eq!(weapons.0[nuke].explosion().max_radius(), 150.);
+        assert_eq!(tank_weapon.fire(&weapons), Some(nuke));
+        assert!(!tank_weapon.has_ammo(nuke));
+        app.update();
+        assert_eq!(app.world.resource::<Inventories>().count(1, item), 0);
+    }
+}
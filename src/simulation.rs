@@ -0,0 +1,474 @@
+//! Programmatic control of the game, e.g. for integration tests
+//! and training of bots.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use crate::components::Position;
+use crate::game_plugin::{AppState, TankWarGamePlugin};
+use crate::input::PlayerAction;
+use crate::rules::{validate_shot, Shot, ShotViolation};
+use crate::settings::Settings;
+use crate::tank::{shoot_system, AimingTank, Health, Tank, TankSet};
+use crate::timeline::{EventTimeline, TimelineEvent};
+use crate::weapons::{TankWeapon, Weapons};
+
+/// Duration of one frame of `Simulation`.
+pub const SIMULATION_FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShootCommand>()
+            .add_event::<ShotRejectedEvent>()
+            .add_systems(
+                Update,
+                shoot_command_system
+                    .before(shoot_system)
+                    .in_set(TankSet::Aiming),
+            );
+    }
+}
+
+/// Makes the aiming tank shoot with given angle of gun, power and weapon.
+/// The shot is validated by rules of the game before.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ShootCommand(pub Shot);
+
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ShotRejectedEvent {
+    pub shot: Shot,
+    pub violation: ShotViolation,
+}
+
+fn shoot_command_system(
+    weapons: Res<Weapons>,
+    mut commands: EventReader<ShootCommand>,
+    mut aiming_tanks: Query<(&mut Tank, &mut TankWeapon), With<AimingTank>>,
+    mut actions: EventWriter<PlayerAction>,
+    mut rejected_events: EventWriter<ShotRejectedEvent>,
+) {
+    let mut aiming_tank = aiming_tanks.get_single_mut().ok();
+    for &ShootCommand(shot) in commands.read() {
+        let current_player = aiming_tank.as_ref().map(|(tank, _)| tank.player_number);
+        let inventory = aiming_tank
+            .as_ref()
+            .map(|(_, tank_weapon)| (tank_weapon.as_ref(), weapons.as_ref()));
+        let tank = match validate_shot(&shot, current_player, inventory) {
+            Ok(()) => aiming_tank.take(),
+            Err(violation) => {
+                warn!("Shot {:?} has been rejected: {}", shot, violation);
+                rejected_events.send(ShotRejectedEvent { shot, violation });
+                None
+            }
+        };
+        if let Some((mut tank, mut tank_weapon)) = tank {
+            tank.set_gun_angle(shot.angle);
+            tank.set_gun_power(shot.power);
+            // Don't restart charging of the already selected weapon.
+            if tank_weapon.ready_weapon() != shot.weapon {
+                match shot.weapon {
+                    Some(index) => tank_weapon.select(index, &weapons),
+                    None => tank_weapon.deselect(),
+                }
+            }
+            actions.send(PlayerAction::Fire);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TankStatus {
+    pub player: u8,
+    pub health: u8,
+    pub position: Vec2,
+}
+
+/// The game running without window, rendering and audio.
+/// Every update of the app advances time of the game by `SIMULATION_FRAME_TIME`,
+/// so results of simulation depend only on its seed and shots.
+pub struct Simulation {
+    app: App,
+}
+
+impl Simulation {
+    pub fn new(field_width: u16, field_height: u16, seed: u64) -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TankWarGamePlugin::default().headless(field_width, field_height),
+        ))
+        .insert_resource(Settings {
+            seed: Some(seed),
+            ..default()
+        })
+        .insert_resource(TimeUpdateStrategy::ManualDuration(SIMULATION_FRAME_TIME));
+        Self { app }
+    }
+
+    #[inline]
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    /// Runs one frame of the game.
+    #[inline]
+    pub fn update(&mut self) {
+        self.app.update();
+    }
+
+    /// Returns `true` if the game waits for a shot of the current tank.
+    pub fn is_waiting_for_shot(&mut self) -> bool {
+        let world = &mut self.app.world;
+        let is_aiming = world
+            .get_resource::<State<AppState>>()
+            .map(|state| *state.get() == AppState::Aiming)
+            .unwrap_or_default();
+        is_aiming
+            && world
+                .query_filtered::<(), With<AimingTank>>()
+                .iter(world)
+                .next()
+                .is_some()
+    }
+
+    /// Runs the game until it waits for a shot.
+    /// Returns `false` if it hasn't happened during `max_frames`.
+    pub fn run_until_waiting_for_shot(&mut self, max_frames: usize) -> bool {
+        for _ in 0..max_frames {
+            if self.is_waiting_for_shot() {
+                return true;
+            }
+            self.update();
+        }
+        self.is_waiting_for_shot()
+    }
+
+    /// Makes the current tank shoot and runs the game until it waits
+    /// for the next shot. Returns events of timeline happened since the shot,
+    /// or `None` if the shot is not valid or the game hasn't finished
+    /// the turn during `max_frames`.
+    pub fn shoot(
+        &mut self,
+        angle: f32,
+        power: f32,
+        max_frames: usize,
+    ) -> Option<Vec<TimelineEvent>> {
+        self.shoot_weapon(angle, power, None, max_frames)
+    }
+
+    /// The same as `shoot`, but fires weapon with given index in `Weapons`,
+    /// `None` means the standard missile.
+    pub fn shoot_weapon(
+        &mut self,
+        angle: f32,
+        power: f32,
+        weapon: Option<usize>,
+        max_frames: usize,
+    ) -> Option<Vec<TimelineEvent>> {
+        if !self.is_waiting_for_shot() {
+            return None;
+        }
+        let current_player = self.current_player();
+        let shot = Shot {
+            player: current_player?,
+            angle,
+            power,
+            weapon,
+        };
+        let world = &mut self.app.world;
+        let mut tank_weapons = world.query_filtered::<&TankWeapon, With<AimingTank>>();
+        let inventory = tank_weapons
+            .iter(world)
+            .next()
+            .map(|tank_weapon| (tank_weapon, world.resource::<Weapons>()));
+        validate_shot(&shot, current_player, inventory).ok()?;
+        let first_event = self.app.world.resource::<EventTimeline>().events.len();
+        self.app.world.send_event(ShootCommand(shot));
+
+        let mut frames = 0;
+        while self.is_waiting_for_shot() {
+            if frames == max_frames {
+                return None;
+            }
+            self.update();
+            frames += 1;
+        }
+        if !self.run_until_waiting_for_shot(max_frames - frames) {
+            return None;
+        }
+        let timeline = self.app.world.resource::<EventTimeline>();
+        Some(timeline.events[first_event..].to_vec())
+    }
+
+    /// Returns number of player whose tank is aiming.
+    pub fn current_player(&mut self) -> Option<u8> {
+        let world = &mut self.app.world;
+        world
+            .query_filtered::<&Tank, With<AimingTank>>()
+            .iter(world)
+            .next()
+            .map(|tank| tank.player_number)
+    }
+
+    /// Returns status of all alive tanks ordered by numbers of players.
+    pub fn tanks(&mut self) -> Vec<TankStatus> {
+        let world = &mut self.app.world;
+        let mut tanks: Vec<TankStatus> = world
+            .query::<(&Tank, &Health, &Position)>()
+            .iter(world)
+            .map(|(tank, health, position)| TankStatus {
+                player: tank.player_number,
+                health: health.value,
+                position: position.0,
+            })
+            .collect();
+        tanks.sort_by_key(|tank| tank.player);
+        tanks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_field::GameField;
+    use crate::net::{
+        ClientId, ClientSession, KickClientEvent, NetMessage, NetMessageFrom, NetMessageReceived,
+        NetSlots, PlayerDisconnectedEvent, SendNetMessageTo, SlotState, StateSnapshot,
+    };
+    use crate::rules::GameRules;
+    use crate::timeline::TimelineEventKind;
+
+    #[test]
+    fn test_simulation_is_deterministic() {
+        let mut outcomes = vec![];
+        for _ in 0..2 {
+            let mut simulation = Simulation::new(800, 500, 42);
+            assert!(simulation.run_until_waiting_for_shot(5000));
+            let player = simulation.current_player().unwrap();
+
+            let events = simulation.shoot(-30., 60., 5000).unwrap();
+            assert_eq!(
+                events[0].kind,
+                TimelineEventKind::Shot {
+                    player,
+                    angle: -30.,
+                    power: 60.
+                }
+            );
+            outcomes.push((events, simulation.tanks()));
+        }
+        assert_eq!(outcomes[0], outcomes[1]);
+    }
+
+    #[test]
+    fn test_weapons_differ_by_radius() {
+        let mut destroyed = vec![];
+        for name in ["Baby Missile", "Nuke"] {
+            let mut simulation = Simulation::new(800, 500, 42);
+            assert!(simulation.run_until_waiting_for_shot(5000));
+            let weapons = simulation.app_mut().world.resource::<Weapons>();
+            let weapon = weapons.0.iter().position(|w| w.name == name);
+            let solid_pixels = |simulation: &mut Simulation| {
+                let landscape = &simulation.app_mut().world.resource::<GameField>().landscape;
+                let (width, height) = landscape.size();
+                (0..width as i32)
+                    .flat_map(|x| (0..height as i32).map(move |y| (x, y)))
+                    .filter(|&(x, y)| landscape.is_not_empty(x, y))
+                    .count()
+            };
+            let before = solid_pixels(&mut simulation);
+            simulation.shoot_weapon(-30., 60., weapon, 5000).unwrap();
+            destroyed.push(before - solid_pixels(&mut simulation));
+        }
+        assert!(destroyed[0] > 0);
+        assert!(destroyed[1] > destroyed[0], "{:?}", destroyed);
+    }
+
+    #[test]
+    fn test_invalid_shot_is_rejected() {
+        let mut simulation = Simulation::new(800, 500, 42);
+        assert!(simulation.run_until_waiting_for_shot(5000));
+        let player = simulation.current_player().unwrap();
+        let app = simulation.app_mut();
+        app.world.send_event(ShootCommand(Shot {
+            player: player % 5 + 1,
+            angle: 0.,
+            power: 50.,
+            weapon: None,
+        }));
+        app.update();
+
+        let rejected: Vec<ShotRejectedEvent> = app
+            .world
+            .resource_mut::<Events<ShotRejectedEvent>>()
+            .drain()
+            .collect();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].violation, ShotViolation::NotPlayersTurn);
+        assert!(simulation.is_waiting_for_shot());
+    }
+
+    #[test]
+    fn test_sudden_death_damages_all_tanks() {
+        let mut outcomes = vec![];
+        for turn_limit in [None, Some(1)] {
+            let mut simulation = Simulation::new(800, 500, 42);
+            simulation.app_mut().insert_resource(GameRules {
+                turn_limit,
+                ..default()
+            });
+            assert!(simulation.run_until_waiting_for_shot(5000));
+            simulation.shoot(-30., 60., 5000).unwrap();
+            outcomes.push(simulation.tanks());
+        }
+        assert_eq!(outcomes[0].len(), outcomes[1].len());
+        for (normal, sudden_death) in outcomes[0].iter().zip(&outcomes[1]) {
+            assert_eq!(sudden_death.health, normal.health - 10);
+        }
+    }
+
+    #[test]
+    fn test_empty_time_bank_forfeits() {
+        let mut simulation = Simulation::new(800, 500, 42);
+        simulation.app_mut().insert_resource(GameRules {
+            time_bank: Some(1.),
+            ..GameRules::blitz()
+        });
+        assert!(simulation.run_until_waiting_for_shot(5000));
+        let tanks_count = simulation.tanks().len();
+        let player = simulation.current_player().unwrap();
+        // Player doesn't shoot until their time is over.
+        for _ in 0..5000 {
+            simulation.update();
+            if simulation.current_player() != Some(player) {
+                break;
+            }
+        }
+        let tanks = simulation.tanks();
+        assert_eq!(tanks.len(), tanks_count - 1);
+        assert!(tanks.iter().all(|tank| tank.player != player));
+    }
+
+    #[test]
+    fn test_host_validates_remote_shots() {
+        let mut simulation = Simulation::new(800, 500, 7);
+        let mut slots = NetSlots::new(5, false);
+        for player in 1..=5 {
+            slots.set(player, SlotState::Human(player as ClientId));
+        }
+        simulation.app_mut().insert_resource(slots);
+        assert!(simulation.run_until_waiting_for_shot(5000));
+        let player = simulation.current_player().unwrap();
+        let other_player = player % 5 + 1;
+        let shot = Shot {
+            player,
+            angle: 0.,
+            power: 50.,
+            weapon: None,
+        };
+        let send_shot = |app: &mut App, client: u8, shot: Shot| {
+            app.world.send_event(NetMessageFrom {
+                client_id: client as ClientId,
+                message: NetMessage::Shot(shot),
+            });
+            app.update();
+        };
+        let app = simulation.app_mut();
+
+        // Shot out of turn is rejected.
+        let early_shot = Shot {
+            player: other_player,
+            ..shot
+        };
+        send_shot(app, other_player, early_shot);
+        let sent: Vec<SendNetMessageTo> = app
+            .world
+            .resource_mut::<Events<SendNetMessageTo>>()
+            .drain()
+            .collect();
+        assert!(matches!(
+            sent[..],
+            [SendNetMessageTo {
+                message: NetMessage::ShotRejected { .. },
+                ..
+            }]
+        ));
+        assert!(!app.world.resource::<NetSlots>().is_bot(other_player));
+
+        // Client which shoots for other player is kicked.
+        send_shot(app, other_player, shot);
+        assert!(app.world.resource::<NetSlots>().is_bot(other_player));
+        let kicked: Vec<KickClientEvent> = app
+            .world
+            .resource_mut::<Events<KickClientEvent>>()
+            .drain()
+            .collect();
+        assert_eq!(kicked[0].client_id, other_player as ClientId);
+        assert!(simulation.is_waiting_for_shot());
+
+        send_shot(simulation.app_mut(), player, shot);
+        for _ in 0..10 {
+            simulation.update();
+        }
+        assert!(!simulation.is_waiting_for_shot());
+    }
+
+    #[test]
+    fn test_host_ignores_state_from_clients() {
+        let mut simulation = Simulation::new(800, 500, 7);
+        simulation
+            .app_mut()
+            .insert_resource(NetSlots::new(5, false));
+        assert!(simulation.run_until_waiting_for_shot(5000));
+        let tanks = simulation.tanks();
+        let app = simulation.app_mut();
+        app.world
+            .send_event(NetMessageReceived(NetMessage::StateSnapshot(
+                StateSnapshot {
+                    tanks: vec![],
+                    surface_heights: Some(vec![0; 800]),
+                },
+            )));
+        app.world
+            .send_event(NetMessageReceived(NetMessage::SessionToken {
+                player: 1,
+                token: 1,
+            }));
+        app.update();
+        assert!(!app.world.contains_resource::<ClientSession>());
+        assert_eq!(simulation.tanks(), tanks);
+    }
+
+    #[test]
+    fn test_bots_take_empty_and_disconnected_slots() {
+        let mut simulation = Simulation::new(800, 500, 7);
+        let mut slots = NetSlots::new(5, false);
+        for player in 1..=5 {
+            slots.set(player, SlotState::Human(player as ClientId));
+        }
+        simulation.app_mut().insert_resource(slots);
+        assert!(simulation.run_until_waiting_for_shot(5000));
+
+        let player = simulation.current_player().unwrap();
+        let app = simulation.app_mut();
+        app.world.resource_mut::<NetSlots>().fill_with_bots = true;
+        app.world.send_event(PlayerDisconnectedEvent { player });
+        // Bot shoots instead of the disconnected player
+        for _ in 0..5000 {
+            simulation.update();
+            if simulation.current_player() != Some(player) {
+                break;
+            }
+        }
+        assert!(simulation.run_until_waiting_for_shot(5000));
+        assert_ne!(simulation.current_player(), Some(player));
+        let timeline = simulation.app_mut().world.resource::<EventTimeline>();
+        assert!(matches!(
+            timeline.events[0].kind,
+            TimelineEventKind::Shot { player: p, .. } if p == player
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::components::Position;
+use crate::explosion::Explosion;
+use crate::game_plugin::AppState;
+use crate::tank::{Health, Tank};
+
+/// Speed of the game during slow motion relative to normal speed.
+const SLOW_SPEED: f32 = 0.2;
+/// Duration of slow motion in real time (seconds).
+const DURATION: f32 = 2.5;
+/// Time (seconds) at the end of slow motion during which
+/// normal speed is smoothly restored.
+const RESTORE_TIME: f32 = 0.8;
+
+/// Slows down the game when an explosion is going to destroy the last
+/// enemy tank, so the finish of the round looks dramatic.
+pub struct SlowMotionPlugin;
+
+impl Plugin for SlowMotionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                detect_finishing_explosion_system,
+                slow_motion_system.run_if(resource_exists::<SlowMotion>),
+            )
+                .chain(),
+        )
+        .add_systems(OnEnter(AppState::RoundSetup), stop_slow_motion_system);
+    }
+}
+
+/// Active slow motion, the camera zooms in to its `focus`.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct SlowMotion {
+    pub focus: Vec2,
+    /// Real time (seconds) passed since slow motion has started.
+    elapsed: f32,
+}
+
+impl SlowMotion {
+    pub fn new(focus: Vec2) -> Self {
+        Self { focus, elapsed: 0. }
+    }
+
+    /// Relative speed of the game.
+    pub fn speed(&self) -> f32 {
+        if self.is_finished() {
+            return 1.;
+        }
+        let restore_start = DURATION - RESTORE_TIME;
+        if self.elapsed <= restore_start {
+            return SLOW_SPEED;
+        }
+        let progress = ((self.elapsed - restore_start) / RESTORE_TIME).min(1.);
+        SLOW_SPEED + (1. - SLOW_SPEED) * progress
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= DURATION
+    }
+}
+
+/// Starts slow motion if a new explosion destroys all tanks except one.
+fn detect_finishing_explosion_system(
+    mut commands: Commands,
+    slow_motion: Option<Res<SlowMotion>>,
+    new_explosions_query: Query<(&Explosion, &Position), Added<Explosion>>,
+    tanks_query: Query<(&Tank, &Health, &Position)>,
+) {
+    if slow_motion.is_some() {
+        return;
+    }
+    let alive = tanks_query
+        .iter()
+        .filter(|(_, health, _)| health.value > 0)
+        .count();
+    if alive < 2 {
+        return;
+    }
+    for (explosion, &Position(explosion_pos)) in new_explosions_query.iter() {
+        let killed = tanks_query
+            .iter()
+            .filter(|&(tank, health, &Position(tank_pos))| {
+                let damage =
+                    explosion.get_intersection_percents(explosion_pos, tank.body_rect(tank_pos));
+                health.value > 0 && !health.invincible && damage >= health.value
+            })
+            .count();
+        if killed > 0 && alive - killed <= 1 {
+            debug!("Finishing explosion at {:?}", explosion_pos);
+            commands.insert_resource(SlowMotion::new(explosion_pos));
+            return;
+        }
+    }
+}
+
+fn slow_motion_system(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut slow_motion: ResMut<SlowMotion>,
+) {
+    slow_motion.elapsed += real_time.delta_seconds();
+    virtual_time.set_relative_speed(slow_motion.speed());
+    if slow_motion.is_finished() {
+        commands.remove_resource::<SlowMotion>();
+    }
+}
+
+fn stop_slow_motion_system(mut commands: Commands, mut virtual_time: ResMut<Time<Virtual>>) {
+    commands.remove_resource::<SlowMotion>();
+    virtual_time.set_relative_speed(1.);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slow_motion_speed() {
+        let mut slow_motion = SlowMotion::new(Vec2::ZERO);
+        assert_eq!(slow_motion.speed(), SLOW_SPEED);
+        slow_motion.elapsed = DURATION - RESTORE_TIME / 2.;
+        assert!((slow_motion.speed() - (1. + SLOW_SPEED) / 2.).abs() < 1e-5);
+        assert!(!slow_motion.is_finished());
+        slow_motion.elapsed = DURATION;
+        assert_eq!(slow_motion.speed(), 1.);
+        assert!(slow_motion.is_finished());
+    }
+}
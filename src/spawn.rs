@@ -0,0 +1,277 @@
+use rand::Rng;
+
+use crate::landscape::Landscape;
+use crate::rules::{SpawnLayout, SpawnRules, TeamRules};
+
+/// Maximal steepness of the surface under a spawned tank
+/// (difference of heights per pixel of width).
+const MAX_SPAWN_SLOPE: f32 = 0.5;
+/// Penalty of candidate spawn points which are too steep, so they
+/// are chosen only if there are no others.
+const STEEP_PENALTY: f32 = 10.;
+/// Distance between candidate spawn points checked by placement.
+const CANDIDATES_STEP: f32 = 4.;
+/// Count of attempts to find a random spawn point far enough from
+/// other tanks, tanks are evenly spaced if it fails.
+const RANDOM_ATTEMPTS: usize = 100;
+
+/// Returns X coordinates of spawn points of tanks of the given players
+/// in the same order.
+pub fn spawn_points(
+    landscape: &Landscape,
+    rules: &SpawnRules,
+    teams: Option<&TeamRules>,
+    player_numbers: &[u8],
+    tank_size: f32,
+    padding: f32,
+    rng: &mut impl Rng,
+) -> Vec<f32> {
+    let count = player_numbers.len() as u8;
+    let min_distance = rules
+        .min_distance
+        .min(max_separation(landscape, count, padding));
+    let even_points = |rng: &mut _| {
+        let points = find_spawn_points(landscape, count, tank_size, padding, min_distance);
+        jitter_points(
+            landscape,
+            points,
+            rules.jitter,
+            tank_size,
+            min_distance,
+            rng,
+        )
+    };
+    match (rules.layout, teams) {
+        (SpawnLayout::Random, _) => random_points(landscape, count, padding, min_distance, rng)
+            .unwrap_or_else(|| even_points(rng)),
+        (SpawnLayout::ClusteredTeams, Some(teams)) => {
+            let points = even_points(rng);
+            // Points from left to right are given to players in order of their teams.
+            let mut order: Vec<usize> = (0..player_numbers.len()).collect();
+            order.sort_by_key(|&i| (teams.team_of(player_numbers[i]), player_numbers[i]));
+            let mut result = vec![0.; points.len()];
+            for (&i, &x) in order.iter().zip(&points) {
+                result[i] = x;
+            }
+            result
+        }
+        _ => even_points(rng),
+    }
+}
+
+/// Returns the greatest distance between tanks which placement
+/// may guarantee.
+pub fn max_separation(landscape: &Landscape, count: u8, padding: f32) -> f32 {
+    spacing(landscape.size().0 as f32, count, padding) / 2.
+}
+
+fn spacing(width: f32, count: u8, padding: f32) -> f32 {
+    ((width - 2. * padding) / (count as f32 - 1.).max(1.)).round()
+}
+
+/// Returns steepness of the surface under a tank with the given center
+/// and half of width.
+pub fn surface_slope(landscape: &Landscape, x: f32, half_width: f32) -> f32 {
+    let (min, max) = footprint(x, half_width)
+        .map(|column| landscape.surface_height(column))
+        .fold((u16::MAX, 0), |(min, max), height| {
+            (min.min(height), max.max(height))
+        });
+    max.saturating_sub(min) as f32 / (2. * half_width).max(1.)
+}
+
+fn footprint(x: f32, half_width: f32) -> impl Iterator<Item = i32> {
+    (x - half_width).round() as i32..=(x + half_width).round() as i32
+}
+
+/// Returns X coordinates of spawn points of tanks from left to right.
+/// Every tank is placed near its place of even spacing on the flattest
+/// ground which is not too close to other tanks.
+fn find_spawn_points(
+    landscape: &Landscape,
+    count: u8,
+    tank_size: f32,
+    padding: f32,
+    min_distance: f32,
+) -> Vec<f32> {
+    let width = landscape.size().0 as f32;
+    let spacing = spacing(width, count, padding);
+    let half_width = tank_size / 2.;
+    let steps = (spacing / 2. / CANDIDATES_STEP) as i32;
+    let mut points: Vec<f32> = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        let ideal_x = padding + spacing * i as f32;
+        let best_x = (-steps..=steps)
+            .map(|step| ideal_x + step as f32 * CANDIDATES_STEP)
+            .filter(|&x| x >= half_width && x <= width - half_width)
+            .filter(|&x| {
+                points
+                    .iter()
+                    .all(|&point| (point - x).abs() >= min_distance)
+            })
+            .map(|x| {
+                let slope = surface_slope(landscape, x, half_width);
+                let penalty = if slope > MAX_SPAWN_SLOPE {
+                    STEEP_PENALTY
+                } else {
+                    0.
+                };
+                let cost = penalty + slope + (x - ideal_x).abs() / spacing.max(1.);
+                (x, cost)
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(ideal_x, |(x, _)| x);
+        points.push(best_x);
+    }
+    points
+}
+
+/// Randomly shifts points sorted from left to right, keeping
+/// the distance between them.
+fn jitter_points(
+    landscape: &Landscape,
+    mut points: Vec<f32>,
+    jitter: f32,
+    tank_size: f32,
+    min_distance: f32,
+    rng: &mut impl Rng,
+) -> Vec<f32> {
+    if jitter <= 0. {
+        return points;
+    }
+    let half_width = tank_size / 2.;
+    let width = landscape.size().0 as f32;
+    for i in 0..points.len() {
+        let x = points[i];
+        let left = i
+            .checked_sub(1)
+            .map_or(half_width, |prev| points[prev] + min_distance);
+        let right = points
+            .get(i + 1)
+            .map_or(width - half_width, |&next| next - min_distance);
+        let shifted = x + rng.gen_range(-jitter..=jitter);
+        points[i] = shifted.clamp(left.min(x), right.max(x));
+    }
+    points
+}
+
+fn random_points(
+    landscape: &Landscape,
+    count: u8,
+    padding: f32,
+    min_distance: f32,
+    rng: &mut impl Rng,
+) -> Option<Vec<f32>> {
+    let width = landscape.size().0 as f32;
+    let min_x = padding.min(width / 2.);
+    let max_x = (width - padding).max(width / 2.);
+    let mut points: Vec<f32> = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let x = (0..RANDOM_ATTEMPTS)
+            .map(|_| rng.gen_range(min_x..=max_x))
+            .find(|&x| {
+                points
+                    .iter()
+                    .all(|&point| (point - x).abs() >= min_distance)
+            })?;
+        points.push(x);
+    }
+    Some(points)
+}
+
+/// Levels the surface under a tank to the average height of its footprint.
+pub fn flatten_pad(landscape: &mut Landscape, x: f32, half_width: f32) {
+    let heights: Vec<u16> = footprint(x, half_width)
+        .map(|column| landscape.surface_height(column))
+        .collect();
+    let average = heights.iter().map(|&h| h as u32).sum::<u32>() / heights.len().max(1) as u32;
+    for column in footprint(x, half_width) {
+        landscape.set_surface_height(column, average as u16);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::landscape_buffer::LandscapeStorage;
+    use crate::MAX_PLAYERS_COUNT;
+
+    #[test]
+    fn test_spawn_points() {
+        let mut landscape = Landscape::new(400, 200, 1, LandscapeStorage::default(), None).unwrap();
+        // Flat ground with a cliff near the place of the second tank.
+        let heights: Vec<u16> = (0..400).map(|x| if x < 300 { 50 } else { 150 }).collect();
+        landscape.set_surface_heights(&heights);
+
+        let points = find_spawn_points(&landscape, 2, 20., 100., 100.);
+        assert_eq!(points[0], 100.);
+        assert!((points[1] - 300.).abs() > 10.);
+        assert!(surface_slope(&landscape, points[1], 10.) <= MAX_SPAWN_SLOPE);
+
+        let points = find_spawn_points(&landscape, 4, 20., 20., 60.);
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                assert!((a - b).abs() >= 60.);
+            }
+        }
+
+        flatten_pad(&mut landscape, 290., 10.);
+        assert_eq!(surface_slope(&landscape, 290., 10.), 0.);
+    }
+
+    /// Tanks keep the required distance for every layout, count of players
+    /// and random landscape.
+    #[test]
+    fn test_spawn_separation_property() {
+        let layouts = [
+            SpawnLayout::Even,
+            SpawnLayout::Random,
+            SpawnLayout::ClusteredTeams,
+        ];
+        let mut rng = StdRng::seed_from_u64(7);
+        for seed in 0..10 {
+            let landscape =
+                Landscape::new(800, 400, seed, LandscapeStorage::default(), None).unwrap();
+            for count in 2..=MAX_PLAYERS_COUNT {
+                let player_numbers: Vec<u8> = (1..=count).rev().collect();
+                let teams = TeamRules::new(rng.gen_range(2..=count));
+                for layout in layouts {
+                    let rules = SpawnRules {
+                        layout,
+                        min_distance: rng.gen_range(0. ..300.),
+                        jitter: rng.gen_range(0. ..50.),
+                        flatten_pads: false,
+                    };
+                    let points = spawn_points(
+                        &landscape,
+                        &rules,
+                        Some(&teams),
+                        &player_numbers,
+                        30.,
+                        50.,
+                        &mut rng,
+                    );
+                    assert_eq!(points.len(), count as usize);
+                    let min_distance = rules
+                        .min_distance
+                        .min(max_separation(&landscape, count, 50.));
+                    for (i, a) in points.iter().enumerate() {
+                        assert!((0. ..=800.).contains(a), "{:?}: {}", layout, a);
+                        for b in &points[i + 1..] {
+                            assert!(
+                                (a - b).abs() >= min_distance - 0.01,
+                                "{:?}: {:?} {}",
+                                layout,
+                                points,
+                                min_distance
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
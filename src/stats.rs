@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::components::Owner;
+use crate::economy::Finances;
+use crate::game_field::GameField;
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::landscape::TerrainDestroyedEvent;
+use crate::missile::InterceptionEvent;
+use crate::rules::{GameRules, TeamRules};
+use crate::tank::{Tank, TankDamagedEvent, TankShotEvent};
+
+/// Collects statistics of players during the match.
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchStats>()
+            .add_systems(Update, collect_stats_system);
+    }
+}
+
+/// Shows statistics of players while `I` key is held and at the end of round.
+pub struct StatsOverlayPlugin;
+
+impl Plugin for StatsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            setup_stats_overlay.after(setup_game_field),
+        )
+        .add_systems(Update, update_stats_overlay_system);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub shots: u32,
+    /// Count of shots which have damaged enemy tanks.
+    pub hits: u32,
+    pub damage_dealt: u32,
+    pub damage_taken: u32,
+    /// Count of pixels of landscape destroyed by explosions of the player.
+    pub terrain_destroyed: u32,
+    /// Count of missiles of other players intercepted by missiles of the player.
+    pub interceptions: u32,
+}
+
+impl PlayerStats {
+    /// Part of shots which have damaged enemy tanks.
+    pub fn hit_rate(&self) -> f32 {
+        if self.shots == 0 {
+            return 0.;
+        }
+        self.hits as f32 / self.shots as f32
+    }
+}
+
+#[derive(Debug, Default, Clone, Resource)]
+pub struct MatchStats {
+    players: HashMap<u8, PlayerStats>,
+    /// The last shot has already damaged an enemy tank.
+    shot_has_hit: bool,
+}
+
+impl MatchStats {
+    pub fn player(&self, player_number: u8) -> PlayerStats {
+        self.players
+            .get(&player_number)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns statistics of players ordered by their numbers.
+    pub fn players(&self) -> Vec<(u8, PlayerStats)> {
+        let mut players: Vec<(u8, PlayerStats)> = self
+            .players
+            .iter()
+            .map(|(&number, &stats)| (number, stats))
+            .collect();
+        players.sort_by_key(|&(number, _)| number);
+        players
+    }
+
+    pub fn record_shot(&mut self, player_number: u8) {
+        self.players.entry(player_number).or_default().shots += 1;
+        self.shot_has_hit = false;
+    }
+
+    pub fn record_damage(&mut self, attacker: Option<u8>, victim: u8, damage: u8) {
+        self.players.entry(victim).or_default().damage_taken += damage as u32;
+        let Some(attacker) = attacker.filter(|&attacker| attacker != victim) else {
+            return;
+        };
+        let stats = self.players.entry(attacker).or_default();
+        stats.damage_dealt += damage as u32;
+        if !self.shot_has_hit {
+            stats.hits += 1;
+            self.shot_has_hit = true;
+        }
+    }
+
+    pub fn record_interception(&mut self, player_number: u8) {
+        self.players.entry(player_number).or_default().interceptions += 1;
+    }
+
+    pub fn record_terrain_destroyed(&mut self, player_number: u8, pixels: usize) {
+        self.players
+            .entry(player_number)
+            .or_default()
+            .terrain_destroyed += pixels as u32;
+    }
+}
+
+fn collect_stats_system(
+    mut stats: ResMut<MatchStats>,
+    mut shot_events: EventReader<TankShotEvent>,
+    mut damaged_events: EventReader<TankDamagedEvent>,
+    mut destroyed_events: EventReader<TerrainDestroyedEvent>,
+    mut interception_events: EventReader<InterceptionEvent>,
+    tanks_query: Query<&Tank>,
+) {
+    for event in shot_events.read() {
+        if let Ok(tank) = tanks_query.get(event.tank_entity) {
+            stats.record_shot(tank.player_number);
+        }
+    }
+    for event in damaged_events.read() {
+        stats.record_damage(event.attacker, event.player_number, event.damage);
+    }
+    for event in destroyed_events.read() {
+        let owner = event.owner.and_then(|owner| tanks_query.get(owner).ok());
+        if let Some(tank) = owner {
+            stats.record_terrain_destroyed(tank.player_number, event.pixels);
+        }
+    }
+    for event in interception_events.read() {
+        for Owner(owner) in event.owners {
+            if let Ok(tank) = tanks_query.get(owner) {
+                stats.record_interception(tank.player_number);
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct StatsOverlay;
+
+fn setup_stats_overlay(
+    mut commands: Commands,
+    game_field: Res<GameField>,
+    overlay_query: Query<(), With<StatsOverlay>>,
+) {
+    if !overlay_query.is_empty() {
+        return;
+    }
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(120.),
+                left: Val::Px(120.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: game_field.font.clone(),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            ),
+            background_color: Color::rgba(0., 0., 0., 0.8).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        StatsOverlay,
+    ));
+}
+
+fn stats_table(stats: &MatchStats) -> String {
+    let mut table = "Player  Shots  Hit rate  Dealt  Taken  Terrain  Intercepts".to_string();
+    for (number, player) in stats.players() {
+        table += &format!(
+            "\n{:>6}  {:>5}  {:>7.0}%  {:>5}  {:>5}  {:>7}  {:>10}",
+            number,
+            player.shots,
+            player.hit_rate() * 100.,
+            player.damage_dealt,
+            player.damage_taken,
+            player.terrain_destroyed,
+            player.interceptions
+        );
+    }
+    table
+}
+
+fn team_scores(finances: &Finances, teams: &TeamRules) -> String {
+    let scores: Vec<String> = (1..=teams.count)
+        .map(|team| format!("Team {}: {}", team, finances.team_points(team, teams)))
+        .collect();
+    format!("\n\nPoints  {}", scores.join("  "))
+}
+
+fn update_stats_overlay_system(
+    stats: Res<MatchStats>,
+    finances: Res<Finances>,
+    rules: Res<GameRules>,
+    state: Res<State<AppState>>,
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    mut overlay_query: Query<(&mut Text, &mut Visibility), With<StatsOverlay>>,
+) {
+    // Tab is already used to select the next weapon.
+    let is_key_held = keyboard_input.is_some_and(|input| input.pressed(KeyCode::KeyI));
+    let is_visible = is_key_held || *state.get() == AppState::RoundOver;
+    for (mut text, mut visibility) in overlay_query.iter_mut() {
+        // Statistics could be changed while the overlay was hidden.
+        let becomes_visible = is_visible && *visibility == Visibility::Hidden;
+        *visibility = if is_visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if becomes_visible || (is_visible && (stats.is_changed() || finances.is_changed())) {
+            let mut table = stats_table(&stats);
+            if let Some(teams) = &rules.teams {
+                table += &team_scores(&finances, teams);
+            }
+            text.sections[0].value = table;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_stats() {
+        let mut stats = MatchStats::default();
+        stats.record_shot(1);
+        stats.record_damage(Some(1), 2, 30);
+        // The second explosion of the same shot isn't another hit.
+        stats.record_damage(Some(1), 3, 20);
+        // Damage of own tank isn't dealt to enemies.
+        stats.record_damage(Some(1), 1, 10);
+        stats.record_terrain_destroyed(1, 500);
+        stats.record_interception(1);
+        stats.record_shot(1);
+
+        let player = stats.player(1);
+        assert_eq!(player.shots, 2);
+        assert_eq!(player.hits, 1);
+        assert_eq!(player.hit_rate(), 0.5);
+        assert_eq!(player.damage_dealt, 50);
+        assert_eq!(player.damage_taken, 10);
+        assert_eq!(player.terrain_destroyed, 500);
+        assert_eq!(player.interceptions, 1);
+        assert_eq!(stats.player(2).damage_taken, 30);
+        assert_eq!(stats.player(4), PlayerStats::default());
+        assert_eq!(
+            stats.players().iter().map(|&(n, _)| n).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+}
@@ -1,22 +1,64 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use crate::broadcast_hud::is_broadcast_hud;
+use crate::economy::Finances;
 use crate::game_field::GameField;
+use crate::game_plugin::{setup_game_field, AppState, HeadlessField};
+use crate::rules::{GameRules, MAX_GUN_ANGLE, MAX_GUN_POWER, MIN_GUN_ANGLE};
 use crate::tank::{CurrentTank, Health, Tank};
+use crate::turn_timer::{host_time, HostClock, TurnTimer};
+use crate::weapons::{TankWeapon, WeaponIcons, Weapons};
+
+/// Height of the status panel at the top of the window
+/// without scale of user interface.
+pub const STATUS_PANEL_HEIGHT: f32 = 30.;
+/// Radius of the arc of gun angle gauge.
+const ANGLE_GAUGE_RADIUS: f32 = 16.;
+/// Step between ticks of the arc of gun angle gauge (degrees).
+const ANGLE_TICK_STEP: f32 = 30.;
+const POWER_BAR_WIDTH: f32 = 80.;
+const POWER_BAR_HEIGHT: f32 = 10.;
+const GAUGE_COLOR: Color = Color::rgb(0.9, 0.8, 0.2);
+const GAUGE_BACKGROUND_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const WEAPON_ICON_SIZE: f32 = 20.;
 
 pub struct StatusPanelPlugin;
 
+/// Systems which update the status panel. The set doesn't run
+/// with the broadcast HUD and in headless mode.
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct StatusPanelSet;
+
 impl Plugin for StatusPanelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
+        app.configure_sets(
+            Update,
+            StatusPanelSet
+                .run_if(not(is_broadcast_hud))
+                .run_if(not(resource_exists::<HeadlessField>)),
+        )
+        .add_systems(
+            OnEnter(AppState::RoundSetup),
+            setup_status_panel
+                .after(setup_game_field)
+                .run_if(not(is_broadcast_hud)),
+        )
+        .add_systems(
             Update,
             (
                 update_gun_angle_text,
+                update_gun_angle_gauge,
                 update_gun_power_text,
+                update_gun_power_gauge,
                 update_wind_power_text,
                 update_player_number_text,
                 update_tank_health_text,
-            ),
+                update_turn_time_text,
+                update_bounty_text,
+                update_weapon_widgets,
+            )
+                .in_set(StatusPanelSet),
         );
     }
 }
@@ -25,30 +67,44 @@ impl Plugin for StatusPanelPlugin {
 pub struct GunAngleText;
 #[derive(Component)]
 pub struct GunPowerText;
+/// Needle of gun angle gauge which rotates around the center of its arc.
+#[derive(Component)]
+pub struct GunAngleNeedle;
+/// Filled part of gun power bar.
+#[derive(Component)]
+pub struct GunPowerBar;
 #[derive(Component)]
 pub struct WindPowerText;
 #[derive(Component)]
 pub struct PlayerNumberText;
 #[derive(Component)]
 pub struct TankHealthText;
+#[derive(Component)]
+pub struct TurnTimeText;
+#[derive(Component)]
+pub struct BountyText;
+/// Icon of the selected weapon, it is hidden for the standard missile.
+#[derive(Component)]
+pub struct WeaponIcon;
+#[derive(Component)]
+pub struct WeaponText;
 
 pub fn setup_status_panel(
     mut commands: Commands,
     game_field: Res<GameField>,
-    primary_window_query: Query<&Window, With<PrimaryWindow>>,
+    primary_window_query: Query<(), With<PrimaryWindow>>,
 ) {
-    let Ok(window) = primary_window_query.get_single() else {
+    if primary_window_query.is_empty() {
         return;
-    };
-    let panel_bottom = window.height() - 30.;
+    }
     let mut panel = commands.spawn(NodeBundle {
         style: Style {
             width: Val::Percent(100.0),
-            height: Val::Px(30.0),
+            height: Val::Px(STATUS_PANEL_HEIGHT),
             // size: Size::new(Val::Percent(100.0), Val::Px(30.0)),
             position_type: PositionType::Absolute,
             left: Val::Px(0.0),
-            bottom: Val::Px(panel_bottom),
+            top: Val::Px(0.0),
             // position: UiRect {
             //     left: Val::Px(0.0),
             //     bottom: Val::Px(panel_bottom),
@@ -68,16 +124,28 @@ pub fn setup_status_panel(
 
     panel.with_children(|parent| {
         // Gun Angle
-        parent.spawn((
-            spawn_text("Angle:", game_field.font.clone(), 110.0),
-            GunAngleText,
-        ));
+        spawn_angle_gauge(parent);
+        parent.spawn((spawn_text("", game_field.font.clone(), 70.0), GunAngleText));
 
         // Gun Power
+        spawn_power_gauge(parent);
+        parent.spawn((spawn_text("", game_field.font.clone(), 60.0), GunPowerText));
+
+        // Selected weapon
         parent.spawn((
-            spawn_text("Power:", game_field.font.clone(), 110.0),
-            GunPowerText,
+            ImageBundle {
+                style: Style {
+                    width: Val::Px(WEAPON_ICON_SIZE),
+                    height: Val::Px(WEAPON_ICON_SIZE),
+                    margin: UiRect::right(Val::Px(5.)),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            WeaponIcon,
         ));
+        parent.spawn((spawn_text("", game_field.font.clone(), 170.0), WeaponText));
 
         // Wind Power
         parent.spawn((
@@ -96,9 +164,106 @@ pub fn setup_status_panel(
             spawn_text("Health:", game_field.font.clone(), 120.0),
             TankHealthText,
         ));
+
+        // Remaining time of the turn
+        parent.spawn((spawn_text("", game_field.font.clone(), 110.0), TurnTimeText));
+
+        // Bounty on the points leader
+        parent.spawn((spawn_text("", game_field.font.clone(), 220.0), BountyText));
     });
 }
 
+/// Spawns arc with ticks and needle which shows angle of the gun.
+fn spawn_angle_gauge(parent: &mut ChildBuilder) {
+    let radius = ANGLE_GAUGE_RADIUS;
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(2. * radius),
+                height: Val::Px(radius),
+                margin: UiRect::horizontal(Val::Px(6.)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|gauge| {
+            let mut angle = MIN_GUN_ANGLE;
+            while angle <= MAX_GUN_ANGLE {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                gauge.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(radius + radius * sin - 1.),
+                        top: Val::Px(radius - radius * cos - 1.),
+                        width: Val::Px(2.),
+                        height: Val::Px(2.),
+                        ..default()
+                    },
+                    background_color: GAUGE_BACKGROUND_COLOR.into(),
+                    ..default()
+                });
+                angle += ANGLE_TICK_STEP;
+            }
+            // Pivot of the needle is the center of the arc
+            // at the bottom of the gauge.
+            gauge
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Px(2. * radius),
+                            height: Val::Px(2. * radius),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    GunAngleNeedle,
+                ))
+                .with_children(|pivot| {
+                    pivot.spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(radius),
+                            top: Val::Px(radius - 1.),
+                            width: Val::Px(radius),
+                            height: Val::Px(2.),
+                            ..default()
+                        },
+                        background_color: GAUGE_COLOR.into(),
+                        ..default()
+                    });
+                });
+        });
+}
+
+fn spawn_power_gauge(parent: &mut ChildBuilder) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(POWER_BAR_WIDTH),
+                height: Val::Px(POWER_BAR_HEIGHT),
+                margin: UiRect::horizontal(Val::Px(6.)),
+                ..default()
+            },
+            background_color: GAUGE_BACKGROUND_COLOR.into(),
+            ..default()
+        })
+        .with_children(|bar| {
+            bar.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    background_color: GAUGE_COLOR.into(),
+                    ..default()
+                },
+                GunPowerBar,
+            ));
+        });
+}
+
 fn spawn_text(text_value: &str, font: Handle<Font>, width: f32) -> TextBundle {
     TextBundle {
         style: Style {
@@ -120,55 +285,262 @@ fn spawn_text(text_value: &str, font: Handle<Font>, width: f32) -> TextBundle {
     }
 }
 
+/// Replaces value of the text only if it is different,
+/// so the unchanged text isn't re-rendered.
+fn set_text(text: &mut Mut<Text>, value: String) {
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
 pub fn update_gun_angle_text(
-    current_tank_query: Query<&Tank, With<CurrentTank>>,
-    mut text_query: Query<&mut Text, With<GunAngleText>>,
+    current_tank_query: Query<(Ref<Tank>, Ref<CurrentTank>)>,
+    mut text_query: Query<(&mut Text, Ref<GunAngleText>)>,
 ) {
-    if let Some(tank) = current_tank_query.iter().next() {
-        if let Some(mut text) = text_query.iter_mut().next() {
-            text.sections[0].value = format!("Angle: {}", tank.gun_angle_deg());
+    let Ok((tank, current)) = current_tank_query.get_single() else {
+        return;
+    };
+    let is_changed = tank.is_changed() || current.is_added();
+    for (mut text, marker) in text_query.iter_mut() {
+        if is_changed || marker.is_added() {
+            set_text(&mut text, format!("{:.1}°", tank.gun_angle_deg()));
         }
     }
 }
 
 pub fn update_gun_power_text(
-    current_tank_query: Query<&Tank, With<CurrentTank>>,
-    mut text_query: Query<&mut Text, With<GunPowerText>>,
+    current_tank_query: Query<(Ref<Tank>, Ref<CurrentTank>)>,
+    mut text_query: Query<(&mut Text, Ref<GunPowerText>)>,
+) {
+    let Ok((tank, current)) = current_tank_query.get_single() else {
+        return;
+    };
+    let is_changed = tank.is_changed() || current.is_added();
+    for (mut text, marker) in text_query.iter_mut() {
+        if is_changed || marker.is_added() {
+            set_text(&mut text, format!("{:.1}", tank.power));
+        }
+    }
+}
+
+/// Returns rotation of the needle, which points to the right
+/// without rotation, for the given angle of gun.
+fn needle_rotation(gun_angle_deg: f32) -> Quat {
+    Quat::from_rotation_z((gun_angle_deg - 90.).to_radians())
+}
+
+pub fn update_gun_angle_gauge(
+    current_tank_query: Query<(Ref<Tank>, Ref<CurrentTank>)>,
+    mut needle_query: Query<(&mut Transform, Ref<GunAngleNeedle>)>,
+) {
+    let Ok((tank, current)) = current_tank_query.get_single() else {
+        return;
+    };
+    let is_changed = tank.is_changed() || current.is_added();
+    for (mut transform, marker) in needle_query.iter_mut() {
+        if is_changed || marker.is_added() {
+            transform.rotation = needle_rotation(tank.gun_angle_deg());
+        }
+    }
+}
+
+pub fn update_gun_power_gauge(
+    current_tank_query: Query<(Ref<Tank>, Ref<CurrentTank>)>,
+    mut bar_query: Query<(&mut Style, Ref<GunPowerBar>)>,
 ) {
-    if let Some(tank) = current_tank_query.iter().next() {
-        if let Some(mut text) = text_query.iter_mut().next() {
-            text.sections[0].value = format!("Power: {}", tank.power);
+    let Ok((tank, current)) = current_tank_query.get_single() else {
+        return;
+    };
+    let is_changed = tank.is_changed() || current.is_added();
+    for (mut style, marker) in bar_query.iter_mut() {
+        if is_changed || marker.is_added() {
+            style.width = Val::Percent(100. * tank.power / MAX_GUN_POWER);
         }
     }
 }
 
 pub fn update_wind_power_text(
-    game_filed: Res<GameField>,
-    mut text_query: Query<&mut Text, With<WindPowerText>>,
+    game_field: Res<GameField>,
+    mut text_query: Query<(&mut Text, Ref<WindPowerText>)>,
 ) {
-    if let Some(mut text) = text_query.iter_mut().next() {
-        text.sections[0].value = format!("Wind: {}", game_filed.wind_power * 10.0);
+    for (mut text, marker) in text_query.iter_mut() {
+        if game_field.is_changed() || marker.is_added() {
+            set_text(&mut text, format!("Wind: {}", game_field.wind_power * 10.0));
+        }
     }
 }
 
 pub fn update_player_number_text(
-    current_tank_query: Query<&Tank, With<CurrentTank>>,
-    mut text_query: Query<&mut Text, With<PlayerNumberText>>,
+    current_tank_query: Query<(&Tank, Ref<CurrentTank>)>,
+    mut text_query: Query<(&mut Text, Ref<PlayerNumberText>)>,
 ) {
-    if let Some(tank) = current_tank_query.iter().next() {
-        if let Some(mut text) = text_query.iter_mut().next() {
-            text.sections[0].value = format!("Player: {}", tank.player_number);
+    let Ok((tank, current)) = current_tank_query.get_single() else {
+        return;
+    };
+    for (mut text, marker) in text_query.iter_mut() {
+        if current.is_added() || marker.is_added() {
+            set_text(&mut text, format!("Player: {}", tank.player_number));
         }
     }
 }
 
 pub fn update_tank_health_text(
-    health_query: Query<&Health, With<CurrentTank>>,
-    mut text_query: Query<&mut Text, With<TankHealthText>>,
+    health_query: Query<(Ref<Health>, Ref<CurrentTank>)>,
+    mut text_query: Query<(&mut Text, Ref<TankHealthText>)>,
+) {
+    let Ok((health, current)) = health_query.get_single() else {
+        return;
+    };
+    let is_changed = health.is_changed() || current.is_added();
+    for (mut text, marker) in text_query.iter_mut() {
+        if is_changed || marker.is_added() {
+            set_text(&mut text, format!("Health: {}", health.value));
+        }
+    }
+}
+
+/// Remaining time is checked every frame, but the text
+/// is changed once a second.
+pub fn update_turn_time_text(
+    time: Res<Time>,
+    timer: Option<Res<TurnTimer>>,
+    clock: Option<Res<HostClock>>,
+    mut text_query: Query<&mut Text, With<TurnTimeText>>,
+) {
+    let value = match timer {
+        Some(timer) => {
+            let remaining = timer.remaining(host_time(&time, clock.as_deref()));
+            format!("Time: {}", remaining.ceil())
+        }
+        None => String::new(),
+    };
+    for mut text in text_query.iter_mut() {
+        set_text(&mut text, value.clone());
+    }
+}
+
+pub fn update_bounty_text(
+    rules: Res<GameRules>,
+    finances: Res<Finances>,
+    mut text_query: Query<(&mut Text, Ref<BountyText>)>,
+) {
+    let is_changed = rules.is_changed() || finances.is_changed();
+    for (mut text, marker) in text_query.iter_mut() {
+        if !is_changed && !marker.is_added() {
+            continue;
+        }
+        let bounty = finances.leader().and_then(|leader| {
+            let bounty = finances.bounty(leader, &rules.economy)?;
+            Some((leader, bounty))
+        });
+        let value = match bounty {
+            Some((leader, bounty)) => format!("Bounty: Player {} ${}", leader, bounty),
+            None => String::new(),
+        };
+        set_text(&mut text, value);
+    }
+}
+
+/// Returns name of the selected weapon and number of turns
+/// left before it may be fired.
+fn weapon_status(weapon: &TankWeapon, weapons: &Weapons) -> String {
+    let Some((index, definition)) = weapon
+        .selected()
+        .and_then(|index| Some((index, weapons.0.get(index)?)))
+    else {
+        return "Standard Missile".to_string();
+    };
+    let turns_left = weapon.charge_left().max(weapon.cooldown(index));
+    if turns_left > 0 {
+        format!("{} ({})", definition.name, turns_left)
+    } else {
+        definition.name.clone()
+    }
+}
+
+pub fn update_weapon_widgets(
+    weapons: Res<Weapons>,
+    icons: Option<Res<WeaponIcons>>,
+    current_tank_query: Query<&TankWeapon, With<CurrentTank>>,
+    mut icon_query: Query<(&mut UiImage, &mut Visibility), With<WeaponIcon>>,
+    mut text_query: Query<&mut Text, With<WeaponText>>,
 ) {
-    if let Some(health) = health_query.iter().next() {
-        if let Some(mut text) = text_query.iter_mut().next() {
-            text.sections[0].value = format!("Health: {}", health.value);
+    let Ok(weapon) = current_tank_query.get_single() else {
+        return;
+    };
+    let icon = weapon
+        .selected()
+        .and_then(|index| icons.as_ref()?.get(index));
+    for (mut image, mut visibility) in icon_query.iter_mut() {
+        let new_visibility = match icon.as_ref() {
+            Some(icon) => {
+                if image.texture != *icon {
+                    image.texture = icon.clone();
+                }
+                Visibility::Inherited
+            }
+            None => Visibility::Hidden,
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
         }
     }
+    let value = weapon_status(weapon, &weapons);
+    for mut text in text_query.iter_mut() {
+        set_text(&mut text, value.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::chassis::ChassisSpec;
+
+    #[test]
+    fn test_text_is_changed_only_with_tank() {
+        let mut world = World::new();
+        let tank = world
+            .spawn((Tank::new(1, &ChassisSpec::default()), CurrentTank))
+            .id();
+        let text = world
+            .spawn((Text::from_section("", TextStyle::default()), GunPowerText))
+            .id();
+        world.run_system_once(update_gun_power_text);
+        let value = world.get::<Text>(text).unwrap().sections[0].value.clone();
+        assert_eq!(
+            value,
+            format!("{:.1}", world.get::<Tank>(tank).unwrap().power)
+        );
+
+        world.clear_trackers();
+        world.run_system_once(update_gun_power_text);
+        assert!(!world.entity(text).get_ref::<Text>().unwrap().is_changed());
+
+        world.get_mut::<Tank>(tank).unwrap().power = 10.;
+        world.run_system_once(update_gun_power_text);
+        assert_eq!(world.get::<Text>(text).unwrap().sections[0].value, "10.0");
+    }
+
+    #[test]
+    fn test_weapon_status() {
+        let weapons = Weapons::default();
+        let mut weapon = TankWeapon::default();
+        assert_eq!(weapon_status(&weapon, &weapons), "Standard Missile");
+        let nuke = weapons.0.iter().position(|w| w.name == "Nuke").unwrap();
+        weapon.select(nuke, &weapons);
+        assert_eq!(weapon_status(&weapon, &weapons), "Nuke");
+        weapon.fire(&weapons);
+        assert_eq!(weapon_status(&weapon, &weapons), "Nuke (2)");
+    }
+
+    #[test]
+    fn test_needle_rotation() {
+        // Needle of vertical gun points up.
+        let direction = needle_rotation(0.) * Vec3::X;
+        assert!(direction.abs_diff_eq(Vec3::NEG_Y, 1e-6));
+        let direction = needle_rotation(MAX_GUN_ANGLE) * Vec3::X;
+        assert!(direction.abs_diff_eq(Vec3::X, 1e-6));
+    }
 }
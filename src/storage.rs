@@ -0,0 +1,125 @@
+//! Storage of saved files: campaign progress, levels, replays and timelines.
+//!
+//! Files are stored in the file system on desktop. In the browser
+//! (`wasm32` target) they are stored in `localStorage` by keys made of
+//! their paths, so saves survive reloading of the page.
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    imp::read(path.as_ref())
+}
+
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    imp::read_to_string(path.as_ref())
+}
+
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    imp::write(path.as_ref(), contents.as_ref())
+}
+
+/// Returns paths of files of the directory.
+pub fn list_dir<P: AsRef<Path>>(dir: P) -> io::Result<Vec<PathBuf>> {
+    imp::list_dir(dir.as_ref())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod imp {
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    pub fn read_to_string(path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+
+    pub fn list_dir(dir: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .filter(|path| path.as_ref().map_or(true, |path| path.is_file()))
+            .collect()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod imp {
+    use std::fmt::Debug;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    /// Prefix of keys of files in `localStorage`.
+    const KEY_PREFIX: &str = "bevy_tank_war:";
+
+    fn local_storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Unsupported, "localStorage is unavailable")
+            })
+    }
+
+    fn js_error(err: impl Debug) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, format!("{:?}", err))
+    }
+
+    fn key(path: &Path) -> String {
+        format!("{}{}", KEY_PREFIX, path.to_string_lossy())
+    }
+
+    pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+        read_to_string(path).map(String::into_bytes)
+    }
+
+    pub fn read_to_string(path: &Path) -> io::Result<String> {
+        local_storage()?
+            .get_item(&key(path))
+            .map_err(js_error)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+    }
+
+    pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+        let contents = std::str::from_utf8(contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        local_storage()?
+            .set_item(&key(path), contents)
+            .map_err(js_error)
+    }
+
+    pub fn list_dir(dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let storage = local_storage()?;
+        let count = storage.length().map_err(js_error)?;
+        Ok((0..count)
+            .filter_map(|index| storage.key(index).ok().flatten())
+            .filter_map(|key| key.strip_prefix(KEY_PREFIX).map(PathBuf::from))
+            .filter(|path| path.parent() == Some(dir))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_round_trip() {
+        let dir = std::env::temp_dir().join("bevy_tank_war_storage_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("progress.ron");
+        write(&path, "(completed_levels: 2)").unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), "(completed_levels: 2)");
+        assert_eq!(read(&path).unwrap(), b"(completed_levels: 2)");
+        assert!(list_dir(&dir).unwrap().contains(&path));
+        assert_eq!(
+            read(dir.join("missing.ron")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,291 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::Rng;
+
+use crate::ballistics::Ballistics;
+use crate::collider::Collider;
+use crate::components::Position;
+use crate::explosion::ExplosionDamageEvent;
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::AppState;
+use crate::geometry::rect::MyRect;
+use crate::landscape::SubsidenceFinishedEvent;
+use crate::rules::GameRules;
+use crate::shop::{FuelCans, Parachutes, ShopItem, SHIELD_STRENGTH};
+use crate::tank::{fall_step, falling_ballistics, Health, Tank};
+use crate::turn::TurnStartedEvent;
+use crate::weapons::{TankWeapon, Weapons};
+
+const CRATE_SIZE: f32 = 12.;
+
+pub struct SupplyPlugin;
+
+impl Plugin for SupplyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SupplyDropEvent>()
+            .add_systems(
+                Update,
+                (
+                    drop_supply_system,
+                    settle_crates_system,
+                    falling_crates_system,
+                    collect_crates_system,
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(AppState::RoundSetup), despawn_crates_system);
+    }
+}
+
+/// Crate with an item which is given to the first tank
+/// touching it or hitting it by explosion.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct SupplyCrate {
+    pub item: ShopItem,
+}
+
+/// Crate is falling down from the sky or after subsidence of landscape.
+#[derive(Debug, Clone, Component)]
+struct CrateFalling(Ballistics);
+
+/// Player has collected the supply crate.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SupplyDropEvent {
+    pub player_number: u8,
+    pub item: ShopItem,
+    pub position: Vec2,
+}
+
+/// Returns random item of crate. Weapons are dropped only if
+/// weapons of tanks are limited, upgrades are never dropped.
+fn random_item(weapons: &Weapons, limited_ammo: bool, rng: &mut impl Rng) -> ShopItem {
+    let items: Vec<ShopItem> = ShopItem::all(weapons)
+        .into_iter()
+        .filter(|item| match item {
+            ShopItem::Weapon(_) => limited_ammo,
+            ShopItem::Upgrade(_) => false,
+            _ => true,
+        })
+        .collect();
+    items[rng.gen_range(0..items.len())]
+}
+
+/// Crate falls from the left bottom corner of its rect.
+fn crate_falling(position: Vec2) -> CrateFalling {
+    let left_bottom = position - CRATE_SIZE / 2.;
+    CrateFalling(falling_ballistics(left_bottom + Vec2::Y))
+}
+
+fn drop_supply_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    game_rng: Option<ResMut<GameRng>>,
+    rules: Res<GameRules>,
+    weapons: Res<Weapons>,
+    mut turn_started_events: EventReader<TurnStartedEvent>,
+) {
+    let (Some(game_field), Some(mut game_rng), Some(drop_turns)) =
+        (game_field, game_rng, rules.supply_drop_turns)
+    else {
+        return;
+    };
+    for event in turn_started_events.read() {
+        if event.extra_shot || event.turn_number % drop_turns != 0 {
+            continue;
+        }
+        let half_size = CRATE_SIZE / 2.;
+        let x = game_rng
+            .gen_range(half_size..game_field.width as f32 - half_size)
+            .round();
+        let position = Vec2::new(x, game_field.height as f32 - half_size - 1.);
+        let item = random_item(&weapons, rules.economy.shop, &mut *game_rng);
+        info!("Supply crate is dropped at {:?}", position);
+        let shape = shapes::Rectangle {
+            extents: Vec2::splat(CRATE_SIZE),
+            ..default()
+        };
+        let crate_entity = commands
+            .spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shape),
+                    spatial: SpatialBundle::from_transform(Transform::from_translation(
+                        position.extend(0.5),
+                    )),
+                    ..default()
+                },
+                Fill::color(Color::rgb(0.6, 0.45, 0.2)),
+                Position(position),
+                Collider::rect(Vec2::splat(half_size)),
+                SupplyCrate { item },
+                crate_falling(position),
+            ))
+            .id();
+        commands
+            .entity(game_field.parent_entity)
+            .add_child(crate_entity);
+    }
+}
+
+/// Landed crates fall again when the landscape under them subsides.
+#[allow(clippy::type_complexity)]
+fn settle_crates_system(
+    mut commands: Commands,
+    mut finished_events: EventReader<SubsidenceFinishedEvent>,
+    crates_query: Query<(Entity, &Position), (With<SupplyCrate>, Without<CrateFalling>)>,
+) {
+    if finished_events.read().count() == 0 {
+        return;
+    }
+    for (entity, &Position(position)) in crates_query.iter() {
+        commands.entity(entity).insert(crate_falling(position));
+    }
+}
+
+/// Crates fall like thrown tanks.
+fn falling_crates_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_field: Option<ResMut<GameField>>,
+    mut crates_query: Query<(Entity, &mut CrateFalling, &mut Position)>,
+) {
+    let Some(mut game_field) = game_field else {
+        return;
+    };
+    for (entity, mut falling, mut position) in crates_query.iter_mut() {
+        let mut offset: f32 = 0.;
+        let mut landed = false;
+        falling.0.tick(time.delta_seconds());
+        for (x, y) in falling.0.positions_iter(None, None) {
+            match fall_step(&mut game_field.landscape, (x, y), CRATE_SIZE) {
+                Some(true) => offset += 1.,
+                Some(false) => {
+                    landed = true;
+                    break;
+                }
+                None => {}
+            }
+        }
+        if offset > 0. {
+            position.0.y -= offset;
+        }
+        if landed {
+            debug!("Supply crate has landed at {:?}", position.0);
+            commands.entity(entity).remove::<CrateFalling>();
+        }
+    }
+}
+
+/// Crate is collected by the tank which has hit it by explosion
+/// or by the tank touching it.
+#[allow(clippy::type_complexity)]
+fn collect_crates_system(
+    mut commands: Commands,
+    mut damage_events: EventReader<ExplosionDamageEvent>,
+    crates_query: Query<(Entity, &SupplyCrate, &Position)>,
+    mut tanks_query: Query<(
+        Entity,
+        &Tank,
+        &Position,
+        &Collider,
+        &mut TankWeapon,
+        &mut Health,
+        Option<&mut Parachutes>,
+        Option<&mut FuelCans>,
+    )>,
+    mut supply_events: EventWriter<SupplyDropEvent>,
+) {
+    let mut collected: Vec<(Entity, Entity)> = Vec::new();
+    for event in damage_events.read() {
+        let Some(owner) = event.owner.filter(|&owner| tanks_query.contains(owner)) else {
+            continue;
+        };
+        if crates_query.contains(event.entity) && collected.iter().all(|c| c.0 != event.entity) {
+            collected.push((event.entity, owner));
+        }
+    }
+    for (crate_entity, _, &Position(position)) in crates_query.iter() {
+        if collected.iter().any(|c| c.0 == crate_entity) {
+            continue;
+        }
+        let crate_rect = MyRect::from_center_size(position, Vec2::splat(CRATE_SIZE));
+        // Player with the least number wins if several tanks touch the crate.
+        let touching_tank = tanks_query
+            .iter()
+            .filter(|(_, _, tank_position, collider, ..)| {
+                collider
+                    .bounding_rect(tank_position.0)
+                    .intersects(&crate_rect)
+            })
+            .min_by_key(|(_, tank, ..)| tank.player_number);
+        if let Some((tank_entity, ..)) = touching_tank {
+            collected.push((crate_entity, tank_entity));
+        }
+    }
+
+    for (crate_entity, tank_entity) in collected {
+        let Ok((_, &supply_crate, &Position(position))) = crates_query.get(crate_entity) else {
+            continue;
+        };
+        let Ok((_, tank, _, _, mut weapon, mut health, parachutes, fuel_cans)) =
+            tanks_query.get_mut(tank_entity)
+        else {
+            continue;
+        };
+        match supply_crate.item {
+            ShopItem::Weapon(index) => weapon.add_ammo(index),
+            ShopItem::Shield => health.shield = health.shield.max(SHIELD_STRENGTH),
+            ShopItem::Parachute => match parachutes {
+                Some(mut parachutes) => parachutes.0 += 1,
+                None => {
+                    commands.entity(tank_entity).insert(Parachutes(1));
+                }
+            },
+            ShopItem::FuelCan => match fuel_cans {
+                Some(mut fuel_cans) => fuel_cans.0 += 1,
+                None => {
+                    commands.entity(tank_entity).insert(FuelCans(1));
+                }
+            },
+            // Upgrades are not dropped.
+            ShopItem::Upgrade(_) => {}
+        }
+        info!("Player {} has collected supply crate", tank.player_number);
+        commands.entity(crate_entity).despawn_recursive();
+        supply_events.send(SupplyDropEvent {
+            player_number: tank.player_number,
+            item: supply_crate.item,
+            position,
+        });
+    }
+}
+
+fn despawn_crates_system(mut commands: Commands, crates_query: Query<Entity, With<SupplyCrate>>) {
+    for entity in crates_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_random_item() {
+        let weapons = Weapons::default();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let item = random_item(&weapons, false, &mut rng);
+            assert!(matches!(
+                item,
+                ShopItem::Shield | ShopItem::Parachute | ShopItem::FuelCan
+            ));
+        }
+        let dropped_weapon = (0..50)
+            .map(|_| random_item(&weapons, true, &mut rng))
+            .any(|item| matches!(item, ShopItem::Weapon(_)));
+        assert!(dropped_weapon);
+    }
+}
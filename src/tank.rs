@@ -1,28 +1,65 @@
-use angular_units::Deg;
 use std::f32::consts::PI;
+use std::fmt;
 
 use bevy::prelude::*;
+use bevy::sprite::Mesh2dHandle;
+use rand::Rng;
 
+use crate::anti_gravity::AntiGravityCharge;
 use crate::ballistics::Ballistics;
-use crate::components::{Angle, HueOffset, Position};
-use crate::explosion::{spawn_explosion, ExplosionHitEvent};
-use crate::game_field::GameField;
-use crate::game_plugin::AppState;
+use crate::chassis::{Chassis, ChassisSpec, MEDIUM_TANK_SIZE};
+use crate::collider::{update_spatial_query_system, Collider, EllipseSet};
+use crate::components::{Angle, HueOffset, Owner, Position};
+use crate::environment::Weather;
+use crate::explosion::{spawn_explosion, spawn_explosion_of, Explosion, ExplosionDamageEvent};
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::{AimingMode, AppState};
 use crate::geometry::rect::MyRect;
 use crate::geometry::Ellipse;
-use crate::input::InputWithRepeating;
+use crate::grappling_hook::GrapplingHook;
+use crate::input::PlayerAction;
 use crate::landscape;
-use crate::missile::{kill_missile, spawn_missile, HasCollision, Missile, MissileMovedEvent};
-use crate::{G, MAX_PLAYERS_COUNT};
-use prisma::encoding::{EncodableColor, SrgbEncoding};
-use prisma::{FromColor, Hsv, Rgb};
-
-const TANK_SIZE: f32 = 41.;
-const GUN_SIZE: f32 = 21.;
+use crate::launch::LaunchOptions;
+use crate::level_map::LevelMap;
+use crate::materials::{GlowMaterial, HueOffsetMaterial};
+use crate::mines::MineLayer;
+use crate::missile::{spawn_missile, Missile};
+use crate::portal::PortalCharge;
+use crate::rules::{GameMode, GameRules, TeamRules};
+use crate::shop::{Inventories, Parachutes};
+use crate::spawn::{flatten_pad, spawn_points};
+use crate::teams::{team_hue_offset, Team};
+use crate::turn::TurnManager;
+use crate::upgrades::TankUpgrades;
+use crate::weapons::{TankWeapon, WeaponKind, Weapons};
+use crate::{rules, DEFAULT_PLAYERS_COUNT, G};
+
+/// Size of tank's textures.
+const TANK_SIZE: f32 = MEDIUM_TANK_SIZE;
+/// Hue of the tank's texture before applying of hue offset of player.
+const TANK_TEXTURE_HUE: u16 = 145;
 const POWER_SCALE: f32 = 300. / 100.;
 const TIME_SCALE: f32 = 3.0;
-/// Damage per one pixel of height with which tank was dropped.
-const TANK_THROWING_DAMAGE_POWER: f32 = 0.1;
+/// Impact speed of falling tank below which landing doesn't damage it.
+pub const SOFT_LANDING_SPEED: f32 = 20.;
+/// Maximal impact speed of falling tank.
+const TERMINAL_VELOCITY: f32 = 150.;
+/// Damage per one unit of squared impact speed above soft-landing speed.
+const IMPACT_DAMAGE_POWER: f32 = 0.0026;
+/// Average intensity of glow around the current tank.
+const GLOW_INTENSITY: f32 = 1.5;
+/// Pulses of glow around the current tank per second.
+const GLOW_FREQUENCY: f32 = 1.;
+/// Maximal tilt of tank resting on a slope of landscape.
+const MAX_TILT_DEG: f32 = 30.;
+/// Chance of destroyed tank to detonate its stored ammo.
+const CHAIN_REACTION_CHANCE: f64 = 0.3;
+/// Delay (seconds) of secondary explosion after destruction of tank.
+const CHAIN_REACTION_DELAY: f32 = 0.3;
+const SECONDARY_MIN_RADIUS: f32 = 60.;
+const SECONDARY_MAX_RADIUS: f32 = 120.;
+/// Growth of radius of secondary explosion per stored weapon.
+const SECONDARY_RADIUS_PER_AMMO: f32 = 5.;
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum TankSet {
@@ -34,6 +71,55 @@ pub enum TankSet {
 #[derive(Event)]
 pub struct AllTanksPlacedEvent;
 
+#[derive(Event)]
+pub struct TankLandedEvent {
+    pub tank_entity: Entity,
+    pub impact_speed: f32,
+}
+
+#[derive(Event)]
+pub struct TankDamagedEvent {
+    pub tank_entity: Entity,
+    pub player_number: u8,
+    pub damage: u8,
+    /// Number of player whose explosion has damaged the tank.
+    pub attacker: Option<u8>,
+}
+
+#[derive(Event)]
+pub struct TankDestroyedEvent {
+    pub tank_entity: Entity,
+    pub player_number: u8,
+    /// Number of player who has dealt the last damage to the tank.
+    pub killer: Option<u8>,
+    /// Numbers of other players who have damaged the tank.
+    pub assists: Vec<u8>,
+}
+
+impl TankDestroyedEvent {
+    /// Returns `true` if the tank has been destroyed by its own explosion.
+    #[inline]
+    pub fn is_self_kill(&self) -> bool {
+        self.killer == Some(self.player_number)
+    }
+}
+
+impl fmt::Display for TankDestroyedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.killer {
+            Some(killer) if killer == self.player_number => {
+                write!(f, "Player {} destroyed itself", killer)
+            }
+            Some(killer) => write!(
+                f,
+                "Player {} destroyed Player {}",
+                killer, self.player_number
+            ),
+            None => write!(f, "Player {} was destroyed", self.player_number),
+        }
+    }
+}
+
 #[derive(Event)]
 pub struct TankShotEvent {
     pub tank_entity: Entity,
@@ -44,12 +130,17 @@ pub struct TanksPlugin;
 impl Plugin for TanksPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<AllTanksPlacedEvent>()
+            .add_event::<TankLandedEvent>()
             .add_event::<TankShotEvent>()
+            .add_event::<TankDamagedEvent>()
+            .add_event::<TankDestroyedEvent>()
             .configure_sets(
                 Update,
                 (
                     TankSet::Throwing.run_if(in_state(AppState::TanksThrowing)),
-                    TankSet::Aiming.run_if(in_state(AppState::Aiming)),
+                    TankSet::Aiming
+                        .run_if(in_state(AppState::Aiming))
+                        .run_if(in_state(AimingMode::Gun)),
                 ),
             )
             .add_systems(
@@ -58,26 +149,39 @@ impl Plugin for TanksPlugin {
             )
             .add_systems(
                 Update,
-                (
-                    gun_rotate_system,
-                    gun_sprite_angle_system,
-                    gun_power_system,
-                    shoot_system,
-                )
-                    .in_set(TankSet::Aiming),
+                (gun_rotate_system, gun_power_system, shoot_system).in_set(TankSet::Aiming),
             )
+            .add_systems(Update, gun_sprite_angle_system.after(TankSet::Aiming))
             .add_systems(
                 Update,
                 (
-                    check_missile_collides_with_tanks_system,
+                    update_tank_collider_system.before(update_spatial_query_system),
                     damage_tank_by_explosion_system,
-                    set_texture_hue_system,
                 ),
             )
             .add_systems(PostUpdate, remove_dead_tank_system);
     }
 }
 
+/// Renders tanks with hue of their players and highlights
+/// the current tank by pulsing glow.
+pub struct TankVisualsPlugin;
+
+impl Plugin for TankVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                add_tank_materials_system,
+                (current_tank_glow_system, pulse_glow_system).chain(),
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Component)]
+pub struct CurrentTankGlow;
+
 #[derive(Clone, Copy, Component)]
 pub struct TankGun;
 
@@ -90,15 +194,47 @@ pub struct AimingTank;
 #[derive(Clone, Copy, Component)]
 pub struct Health {
     pub value: u8,
+    /// Points of shield which absorbs damage before health.
+    pub shield: u8,
     pub invincible: bool,
 }
 
+/// Players who have damaged the tank, in order of their last damage.
+#[derive(Debug, Default, Clone, Component)]
+pub struct Attackers(Vec<u8>);
+
+impl Attackers {
+    pub fn add(&mut self, player_number: u8) {
+        self.0.retain(|&p| p != player_number);
+        self.0.push(player_number);
+    }
+
+    /// Player who has dealt the last damage.
+    #[inline]
+    pub fn last(&self) -> Option<u8> {
+        self.0.last().copied()
+    }
+
+    /// Players who have damaged the tank before the last one,
+    /// except the tank's owner.
+    pub fn assists(&self, player_number: u8) -> Vec<u8> {
+        let count = self.0.len().saturating_sub(1);
+        self.0[..count]
+            .iter()
+            .copied()
+            .filter(|&p| p != player_number)
+            .collect()
+    }
+}
+
 impl Health {
     /// Returns value of health after damage has been applied.
     #[inline]
     pub fn damage(&mut self, v: u8) -> u8 {
         if !self.invincible {
-            self.value = self.value.saturating_sub(v);
+            let absorbed = v.min(self.shield);
+            self.shield -= absorbed;
+            self.value = self.value.saturating_sub(v - absorbed);
         }
         self.value
     }
@@ -106,57 +242,81 @@ impl Health {
 
 #[derive(Debug, Clone, Component)]
 pub struct TankThrowing {
-    pub start_position: Vec2,
     pub tank_width: f32,
     pub ballistics: Ballistics,
 }
 
+impl TankThrowing {
+    /// Returns current speed of falling tank.
+    pub fn impact_speed(&self) -> f32 {
+        let (_, velocity) = self.ballistics.pos_and_velocity();
+        velocity.length()
+    }
+}
+
+/// Motion of body falling down from given point without start velocity.
+/// Speed of the body is limited by terminal velocity.
+pub fn falling_ballistics(start_position: Vec2) -> Ballistics {
+    Ballistics::new(start_position, Vec2::ZERO, Vec2::new(0., -G))
+        .time_scale(TIME_SCALE)
+        .max_speed(TERMINAL_VELOCITY)
+}
+
+/// Returns damage caused to tank by landing with given speed.
+pub fn impact_damage(impact_speed: f32) -> u8 {
+    let impact_speed = impact_speed.min(TERMINAL_VELOCITY);
+    if impact_speed <= SOFT_LANDING_SPEED {
+        return 0;
+    }
+    let excess = impact_speed * impact_speed - SOFT_LANDING_SPEED * SOFT_LANDING_SPEED;
+    (excess * IMPACT_DAMAGE_POWER).min(255.).round() as u8
+}
+
 #[derive(Debug, Clone, Component)]
 pub struct Tank {
     pub player_number: u8,
     pub power: f32,
-    pub dead: bool,
+    size: f32,
+    gun_length: f32,
     body_bounds: Vec<Ellipse>,
     gun_bounds: Vec<Ellipse>,
     gun_angle_deg: f32,
+    /// Counterclockwise rotation of tank's body.
+    tilt_deg: f32,
 }
 
 impl Tank {
-    #[inline]
-    pub fn size() -> Vec2 {
-        Vec2::new(TANK_SIZE, TANK_SIZE)
-    }
-
-    pub fn new(player_number: u8) -> Tank {
-        let body_bounds = vec![
-            Ellipse::new((0., -5.5), 9.5, 9.),    // top bound
-            Ellipse::new((-9.5, -13.), 10., 6.5), // left bound
-            Ellipse::new((9.5, -13.), 10., 6.5),  // right bound
-            Ellipse::new((0., -13.), 19.5, 7.5),  // center bound
-        ];
-        let gun_bounds = vec![
-            Ellipse::new((0., 14.), 2.5, 5.),
-            Ellipse::new((0., 5.), 2., 8.),
-        ];
+    pub fn new(player_number: u8, spec: &ChassisSpec) -> Tank {
         Tank {
             player_number,
-            body_bounds,
-            gun_bounds,
+            size: spec.size,
+            gun_length: spec.gun_length,
+            body_bounds: spec.body_bounds.clone(),
+            gun_bounds: spec.gun_bounds.clone(),
             gun_angle_deg: 0.0,
+            tilt_deg: 0.0,
             power: 40.0,
-            dead: false,
         }
     }
 
+    #[inline]
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.size, self.size)
+    }
+
     pub fn gun_barrel_pos(&self, tank_position: Vec2) -> Vec2 {
-        let rad = self.gun_angle_deg * PI / 180.0;
-        let gun_vec = Vec2::new(GUN_SIZE * rad.sin(), GUN_SIZE * rad.cos());
+        let rad = self.gun_angle_rad();
+        let gun_vec = Vec2::new(self.gun_length * rad.sin(), self.gun_length * rad.cos());
         tank_position + gun_vec
     }
 
     /// Increment angle of gun
     pub fn inc_gun_angle(&mut self, delta_degrees: f32) {
-        self.gun_angle_deg = (self.gun_angle_deg + delta_degrees).min(90.).max(-90.);
+        self.set_gun_angle(self.gun_angle_deg + delta_degrees);
+    }
+
+    pub fn set_gun_angle(&mut self, degrees: f32) {
+        self.gun_angle_deg = rules::clamp_gun_angle(round_to_tenths(degrees));
     }
 
     pub fn gun_angle_deg(&self) -> f32 {
@@ -167,9 +327,23 @@ impl Tank {
         self.gun_angle_deg * PI / 180.
     }
 
+    pub fn tilt_deg(&self) -> f32 {
+        self.tilt_deg
+    }
+
+    /// Tilts tank's body along the surface with given normal.
+    pub fn set_tilt_from_normal(&mut self, normal: Vec2) {
+        let degrees = (-normal.x).atan2(normal.y) * 180. / PI;
+        self.tilt_deg = round_to_tenths(degrees.clamp(-MAX_TILT_DEG, MAX_TILT_DEG));
+    }
+
     /// Increment power of gun of current tank
     pub fn inc_gun_power(&mut self, delta: f32) {
-        self.power = (self.power + delta).min(100.).max(0.);
+        self.set_gun_power(self.power + delta);
+    }
+
+    pub fn set_gun_power(&mut self, power: f32) {
+        self.power = rules::clamp_gun_power(round_to_tenths(power));
     }
 
     pub fn shoot(&self, tank_position: Vec2, acceleration: Vec2) -> Missile {
@@ -182,147 +356,89 @@ impl Tank {
     }
 
     pub fn throw_down(&self, start_position: Vec2) -> TankThrowing {
-        let left_bottom = start_position - Self::size() / 2.;
+        let left_bottom = start_position - self.size() / 2.;
         let start_height = left_bottom.y + 1.;
         TankThrowing {
-            start_position,
-            tank_width: TANK_SIZE,
-            ballistics: Ballistics::new([left_bottom.x, start_height], [0., 0.], [0., -G])
-                .time_scale(TIME_SCALE),
+            tank_width: self.size,
+            ballistics: falling_ballistics(Vec2::new(left_bottom.x, start_height)),
         }
     }
 
     #[inline]
     pub fn body_rect(&self, position: Vec2) -> MyRect {
-        let half_size = TANK_SIZE / 2.;
-        MyRect {
-            left: position.x - half_size,
-            right: position.x + half_size,
-            top: position.y + half_size,
-            bottom: position.y - half_size,
-        }
+        let tilt_rad = self.tilt_deg * PI / 180.;
+        let size = self.size * (tilt_rad.cos().abs() + tilt_rad.sin().abs());
+        MyRect::from_center_size(position, Vec2::splat(size))
     }
 
-    #[inline]
-    fn left_bottom(&self, tank_position: Vec2) -> Vec2 {
-        Vec2::new(
-            tank_position.x - TANK_SIZE / 2.,
-            tank_position.y - TANK_SIZE / 2.,
-        )
-    }
-
-    /// Returns `true` if given point locates inside of tank's body or gun.
-    pub fn has_collision<P: Into<Vec2>>(&self, tank_position: Vec2, point: P) -> bool {
-        let point = point.into();
-        let local_point = point - tank_position;
-        // If point outside of tank's rectangle
-        if local_point.abs().max_element() > TANK_SIZE / 2. {
-            return false;
-        }
-
-        // Check tank's body bounds
-        if self
-            .body_bounds
-            .iter()
-            .any(|b| b.point_position(local_point) <= 0.)
-        {
-            return true;
-        }
-
-        // Check the tank's gun bounds.
-        // Rotate local_point into the coordinate system of tank's gun.
-        let rotation = Quat::from_rotation_z(self.gun_angle_deg * PI / 180.);
-        let rotated_point = rotation.mul_vec3(Vec3::new(local_point.x, local_point.y, 0.));
-        let rotated_point = Vec2::new(rotated_point.x, rotated_point.y);
-        self.gun_bounds
-            .iter()
-            .any(|b| b.point_position(rotated_point) <= 0.)
-    }
-}
-
-struct TankCollider {
-    body_bounds: Vec<Ellipse>,
-    gun_bounds: Vec<Ellipse>,
-    gun_angle_deg: f32,
-}
-
-impl TankCollider {
-    pub fn new() -> Self {
-        let body_bounds = vec![
-            Ellipse::new((0., -5.5), 9.5, 9.),    // top bound
-            Ellipse::new((-9.5, -13.), 10., 6.5), // left bound
-            Ellipse::new((9.5, -13.), 10., 6.5),  // right bound
-            Ellipse::new((0., -13.), 19.5, 7.5),  // center bound
-        ];
-        let gun_bounds = vec![
-            Ellipse::new((0., 14.), 2.5, 5.),
-            Ellipse::new((0., 5.), 2., 8.),
+    /// Returns collider of tank's body and gun. The gun keeps
+    /// its angle regardless of the tilt of body.
+    pub fn collider(&self) -> Collider {
+        let tilt_rad = self.tilt_deg * PI / 180.;
+        let sets = vec![
+            EllipseSet {
+                ellipses: self.body_bounds.clone(),
+                rotation: 0.,
+            },
+            EllipseSet {
+                ellipses: self.gun_bounds.clone(),
+                rotation: -self.gun_angle_rad() - tilt_rad,
+            },
         ];
-        Self {
-            body_bounds,
-            gun_bounds,
-            gun_angle_deg: 0.,
-        }
+        Collider::ellipses(self.size / 2., sets).with_rotation(tilt_rad)
     }
 }
 
-impl HasCollision for TankCollider {
-    fn has_collision(&self, entity_position: Vec2, point: Vec2) -> bool {
-        let local_point = point - entity_position;
-        // If point outside inside of tank's rectangle
-        if local_point.abs().max_element() > TANK_SIZE / 2. {
-            return false;
-        }
-
-        // Check tank's body bounds
-        if self
-            .body_bounds
-            .iter()
-            .any(|b| b.point_position(local_point) <= 0.)
-        {
-            return true;
-        }
-
-        // Check tank's gun bounds.
-        // Rotate local_point into coordinate system of tank's gun.
-        let rotation = Quat::from_rotation_z(self.gun_angle_deg * PI / 180.);
-        let rotated_point = rotation.mul_vec3(Vec3::new(local_point.x, local_point.y, 0.));
-        let rotated_point = Vec2::new(rotated_point.x, rotated_point.y);
-        self.gun_bounds
-            .iter()
-            .any(|b| b.point_position(rotated_point) <= 0.)
-    }
+/// Rounds value to one decimal place to prevent accumulation
+/// of floating point errors by fine aiming.
+#[inline]
+fn round_to_tenths(value: f32) -> f32 {
+    (value * 10.).round() / 10.
 }
 
 #[derive(Bundle, Clone)]
 struct TankBundle {
     tank: Tank,
     health: Health,
+    attackers: Attackers,
+    weapon: TankWeapon,
+    upgrades: TankUpgrades,
     position: Position,
     tank_throwing: TankThrowing,
-    sprite: SpriteBundle,
+    collider: Collider,
+    angle: Angle,
+    spatial: SpatialBundle,
 }
 
 impl TankBundle {
-    pub fn new(player_number: u8, position: Vec2, texture: Handle<Image>) -> Self {
-        let tank = Tank::new(player_number);
+    pub fn new(
+        player_number: u8,
+        position: Vec2,
+        chassis: Chassis,
+        upgrades: TankUpgrades,
+    ) -> Self {
+        let spec = chassis.spec();
+        let tank = Tank::new(player_number, &spec);
         let tank_throwing = tank.throw_down(position);
-        let mut transform = Transform::default();
+        let collider = tank.collider();
+        // Textures of tank are scaled to the size of its chassis.
+        let mut transform = Transform::from_scale(Vec3::splat(spec.size / TANK_SIZE));
         transform.translation.z = 0.1;
-        let sprite = SpriteBundle {
-            texture,
-            transform,
-            ..Default::default()
-        };
         Self {
             tank,
             health: Health {
-                value: 100,
+                value: upgrades.max_health(spec.health),
+                shield: 0,
                 invincible: true,
             },
+            attackers: Attackers::default(),
+            weapon: TankWeapon::default(),
+            upgrades,
             position: Position(position),
             tank_throwing,
-            sprite,
+            collider,
+            angle: Angle(0.),
+            spatial: SpatialBundle::from_transform(transform),
         }
     }
 }
@@ -331,56 +447,111 @@ impl TankBundle {
 struct TankGunBundle {
     gun: TankGun,
     angle: Angle,
-    sprite: SpriteBundle,
+    spatial: SpatialBundle,
 }
 
 impl TankGunBundle {
-    pub fn new(texture: Handle<Image>) -> Self {
+    pub fn new() -> Self {
         let mut transform = Transform::default();
         transform.translation.z = -0.1;
-        let sprite = SpriteBundle {
-            texture,
-            transform,
-            ..Default::default()
-        };
         Self {
             gun: TankGun,
             angle: Angle(0.),
-            sprite,
+            spatial: SpatialBundle::from_transform(transform),
         }
     }
 }
 
-pub fn setup_tanks(mut commands: Commands, mut game_field: ResMut<GameField>) {
-    let tank_material = game_field.tank_texture.clone();
-    let gun_material = game_field.gun_texture.clone();
+/// Returns hue offset of textures of the player's tank. Hues of players
+/// are spread evenly over the color wheel by count of players of the round.
+#[inline]
+pub fn player_hue_offset(player_number: u8, players_count: u8, teams: Option<&TeamRules>) -> u16 {
+    match teams {
+        Some(teams) => team_hue_offset(player_number, teams),
+        None => (player_number as u16 - 1) * (360 / players_count.max(1) as u16),
+    }
+}
+
+/// Returns color which matches the color of the player's tank.
+pub fn player_color(player_number: u8, players_count: u8, teams: Option<&TeamRules>) -> Color {
+    let hue = (TANK_TEXTURE_HUE + player_hue_offset(player_number, players_count, teams)) % 360;
+    Color::hsl(hue as f32, 0.7, 0.6)
+}
 
-    let count_of_tanks = 5u8;
-    game_field.start_round(count_of_tanks);
+#[allow(clippy::too_many_arguments)]
+pub fn setup_tanks(
+    mut commands: Commands,
+    mut game_field: ResMut<GameField>,
+    mut game_rng: ResMut<GameRng>,
+    mut turn_manager: ResMut<TurnManager>,
+    inventories: Res<Inventories>,
+    rules: Res<GameRules>,
+    level_map: Option<Res<LevelMap>>,
+    launch_options: Option<Res<LaunchOptions>>,
+) {
+    let count_of_tanks = if rules.mode == GameMode::TargetRange {
+        1
+    } else {
+        launch_options
+            .as_ref()
+            .map_or(DEFAULT_PLAYERS_COUNT, |options| options.players)
+    };
+    game_field.start_round(count_of_tanks, &mut game_rng);
 
-    let tank_size = Tank::size();
     let padding: f32 = 100.5;
-    let size_between_tanks =
-        ((game_field.width as f32 - 2. * padding) / (count_of_tanks - 1) as f32).round();
-    let tank_y = (game_field.height - 50) as f32 + tank_size.y / 2.;
-    let start_position = Vec2::new(padding, tank_y);
+    let bottom_y = (game_field.height - 50) as f32;
 
     let parent_entity = game_field.parent_entity;
     let player_numbers = game_field.player_numbers.clone();
+    let chassis: Vec<Chassis> = player_numbers
+        .iter()
+        .map(|&player_number| {
+            launch_options
+                .as_ref()
+                .map_or_else(Chassis::default, |options| options.chassis(player_number))
+        })
+        .collect();
+    let max_tank_size = chassis.iter().map(|c| c.size()).fold(0., f32::max);
+    // Tanks are placed at spawn points of the hand-made level, if it has them.
+    let mut points = level_map
+        .map(|level_map| level_map.scaled_spawn_points(game_field.width))
+        .unwrap_or_default();
+    if points.len() < player_numbers.len() {
+        let found = spawn_points(
+            &game_field.landscape,
+            &rules.spawn,
+            rules.teams.as_ref(),
+            &player_numbers,
+            max_tank_size,
+            padding,
+            &mut *game_rng,
+        );
+        points.extend_from_slice(&found[points.len()..]);
+    }
+    if rules.spawn.flatten_pads {
+        for (&x, chassis) in points.iter().zip(&chassis) {
+            flatten_pad(&mut game_field.landscape, x, chassis.size() / 2.);
+        }
+    }
+    let mut tanks = Vec::with_capacity(player_numbers.len());
     for (i, &player_number) in player_numbers.iter().enumerate() {
-        let tank_position = start_position + Vec2::new(size_between_tanks * i as f32, 0.);
+        let chassis = chassis[i];
+        let x = points[i];
+        let tank_position = Vec2::new(x, bottom_y + chassis.size() / 2.);
 
-        let hue_offset = (player_number as u16 - 1) * (360 / MAX_PLAYERS_COUNT as u16);
+        let hue_offset = player_hue_offset(player_number, count_of_tanks, rules.teams.as_ref());
         let tank_entity = commands
             .spawn((
-                TankBundle::new(player_number, tank_position, tank_material.clone()),
+                TankBundle::new(
+                    player_number,
+                    tank_position,
+                    chassis,
+                    inventories.upgrades(player_number),
+                ),
                 HueOffset(hue_offset),
             ))
             .with_children(|parent| {
-                parent.spawn((
-                    TankGunBundle::new(gun_material.clone()),
-                    HueOffset(hue_offset),
-                ));
+                parent.spawn((TankGunBundle::new(), HueOffset(hue_offset)));
             })
             .id();
         if i == 0 {
@@ -389,86 +560,137 @@ pub fn setup_tanks(mut commands: Commands, mut game_field: ResMut<GameField>) {
                 .insert(CurrentTank)
                 .insert(AimingTank);
         }
+        if let Some(teams) = &rules.teams {
+            commands
+                .entity(tank_entity)
+                .insert(Team(teams.team_of(player_number)));
+        }
 
         commands.entity(parent_entity).add_child(tank_entity);
-        game_field.tanks.push(Some(tank_entity));
+        tanks.push((tank_entity, player_number));
     }
+    turn_manager.start_round(tanks);
+    turn_manager.set_teams(rules.teams);
 }
 
 pub fn gun_rotate_system(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut repeated_input: ResMut<InputWithRepeating<KeyCode>>,
-    mut aiming_tanks: Query<&mut Tank, With<AimingTank>>,
+    mut actions: EventReader<PlayerAction>,
+    mut aiming_tanks: Query<(&mut Tank, &TankUpgrades), With<AimingTank>>,
 ) {
-    let mut delta: f32 = 0.;
-
-    if repeated_input.pressed(&keyboard_input, KeyCode::ArrowLeft) {
-        delta = -1.;
-    }
-    if repeated_input.pressed(&keyboard_input, KeyCode::ArrowRight) {
-        delta = 1.;
-    }
+    let delta: f32 = actions
+        .read()
+        .filter_map(|action| match action {
+            PlayerAction::RotateGun(delta) => Some(*delta),
+            _ => None,
+        })
+        .sum();
     if delta == 0. {
         return;
     }
 
-    for mut tank in aiming_tanks.iter_mut() {
-        tank.inc_gun_angle(delta);
+    for (mut tank, upgrades) in aiming_tanks.iter_mut() {
+        tank.inc_gun_angle(upgrades.aim_delta(delta));
     }
 }
 
+/// Rotates sprites of tank's body and gun. The gun is a child
+/// of tilted body, so its tilt is compensated.
+#[allow(clippy::type_complexity)]
 pub fn gun_sprite_angle_system(
-    tank_query: Query<(&Tank, &Children), Changed<Tank>>,
+    mut tank_query: Query<(&Tank, &mut Angle, &Children), (Changed<Tank>, Without<TankGun>)>,
     mut gun_angle_query: Query<&mut Angle, With<TankGun>>,
 ) {
-    for (tank, children) in tank_query.iter() {
+    for (tank, mut tank_angle, children) in tank_query.iter_mut() {
+        if tank_angle.0 != tank.tilt_deg() {
+            tank_angle.0 = tank.tilt_deg();
+        }
         for child in children.iter() {
             if let Ok(mut gun_angle) = gun_angle_query.get_mut(*child) {
-                gun_angle.0 = -tank.gun_angle_deg();
+                gun_angle.0 = -tank.gun_angle_deg() - tank.tilt_deg();
             }
         }
     }
 }
 
 pub fn gun_power_system(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut repeated_input: ResMut<InputWithRepeating<KeyCode>>,
-    mut aiming_tanks: Query<&mut Tank, With<AimingTank>>,
+    mut actions: EventReader<PlayerAction>,
+    mut aiming_tanks: Query<(&mut Tank, &TankUpgrades), With<AimingTank>>,
 ) {
-    let mut delta: f32 = 0.;
-
-    if repeated_input.pressed(&keyboard_input, KeyCode::ArrowUp) {
-        delta = 1.;
-    }
-    if repeated_input.pressed(&keyboard_input, KeyCode::ArrowDown) {
-        delta = -1.;
-    }
-
+    let delta: f32 = actions
+        .read()
+        .filter_map(|action| match action {
+            PlayerAction::ChangePower(delta) => Some(*delta),
+            _ => None,
+        })
+        .sum();
     if delta == 0. {
         return;
     }
 
-    for mut tank in aiming_tanks.iter_mut() {
-        tank.inc_gun_power(delta);
+    for (mut tank, upgrades) in aiming_tanks.iter_mut() {
+        tank.inc_gun_power(upgrades.aim_delta(delta));
     }
 }
 
 pub fn shoot_system(
     mut commands: Commands,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut actions: EventReader<PlayerAction>,
     game_field: Res<GameField>,
-    mut aiming_tanks: Query<(&Tank, &Position, Entity), With<AimingTank>>,
+    weapons: Res<Weapons>,
+    weather: Res<Weather>,
+    mut aiming_tanks: Query<(&Tank, &Position, &TankWeapon, Entity), With<AimingTank>>,
     mut shot_events: EventWriter<TankShotEvent>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        for (tank, tank_position, entity) in aiming_tanks.iter_mut() {
+    let fire = actions
+        .read()
+        .filter(|&&action| action == PlayerAction::Fire)
+        .count()
+        > 0;
+    if fire {
+        for (tank, tank_position, weapon, entity) in aiming_tanks.iter_mut() {
+            let definition = weapon.ready_weapon().and_then(|index| weapons.0.get(index));
+            let kind = weapon.ready_weapon_kind(&weapons);
+            if matches!(
+                kind,
+                WeaponKind::Scanner
+                    | WeaponKind::Cloak
+                    | WeaponKind::Decoy
+                    | WeaponKind::Jetpack
+                    | WeaponKind::Earthmover
+                    | WeaponKind::MeteorShower
+            ) {
+                // Items without projectiles are used by their own systems.
+                continue;
+            }
             let acceleration = Vec2::new(game_field.wind_power, -G);
-            let missile = tank.shoot(tank_position.0, acceleration);
-            spawn_missile(&mut commands, &game_field, missile);
-            commands.spawn(AudioBundle {
-                source: game_field.tank_fire_sound.clone(),
-                ..Default::default()
-            });
+            let missile = tank
+                .shoot(tank_position.0, acceleration)
+                .with_drag(weather.drag());
+            let missile_entity = spawn_missile(
+                &mut commands,
+                &game_field,
+                missile,
+                definition,
+                Some(Owner(entity)),
+            );
+            match kind {
+                WeaponKind::Mine => {
+                    commands.entity(missile_entity).insert(MineLayer);
+                }
+                WeaponKind::GrapplingHook => {
+                    commands.entity(missile_entity).insert(GrapplingHook);
+                }
+                WeaponKind::Portal => {
+                    commands.entity(missile_entity).insert(PortalCharge);
+                }
+                WeaponKind::AntiGravity => {
+                    let radius = definition.map_or(0., |definition| definition.radius);
+                    commands
+                        .entity(missile_entity)
+                        .insert(AntiGravityCharge { radius });
+                }
+                _ => {}
+            }
             shot_events.send(TankShotEvent {
                 tank_entity: entity,
             });
@@ -490,44 +712,66 @@ fn throw_down_tanks_system(
     }
 }
 
+/// Moves falling body of given width down to the row of pixels
+/// with the left end in given point. Returns `Some(false)` if the body
+/// has landed, `None` if the point is outside of the field.
+pub fn fall_step(
+    landscape: &mut landscape::Landscape,
+    point: (i32, i32),
+    width: f32,
+) -> Option<bool> {
+    if point.1 <= 0 {
+        return Some(false);
+    }
+    let max_empty_count = (0.3 * width).round() as usize;
+    let line_length = width as u16;
+    let empty_count = landscape.count_empty_pixels_in_line(point, line_length)?;
+    if empty_count <= max_empty_count {
+        return Some(false);
+    }
+    if empty_count < width as usize {
+        // Landscape under the body is not empty - clear it
+        landscape.clear_pixels_line(point, line_length);
+    }
+    Some(true)
+}
+
+#[allow(clippy::type_complexity)]
 fn tanks_throwing_system(
     mut commands: Commands,
+    time: Res<Time>,
     mut game_field: ResMut<GameField>,
-    mut tanks_query: Query<(Entity, &mut TankThrowing, &mut Position, &mut Health)>,
+    mut tanks_query: Query<(
+        Entity,
+        &mut Tank,
+        &mut TankThrowing,
+        &mut Position,
+        &mut Health,
+        Option<&mut Parachutes>,
+    )>,
     mut all_placed_event: EventWriter<AllTanksPlacedEvent>,
+    mut landed_events: EventWriter<TankLandedEvent>,
 ) {
     let mut tanks_count: usize = 0;
     let mut placed_tanks_count: usize = 0;
 
-    for (entity, mut throwing, mut tank_position, mut health) in tanks_query.iter_mut() {
+    for (entity, mut tank, mut throwing, mut tank_position, mut health, parachutes) in
+        tanks_query.iter_mut()
+    {
         tanks_count += 1;
         let tank_width = throwing.tank_width;
-        let max_empty_count = (0.3 * tank_width).round() as usize;
         let mut offset: f32 = 0.0;
         let mut stop_throwing = false;
 
+        throwing.ballistics.tick(time.delta_seconds());
         for (x, y) in throwing.ballistics.positions_iter(None, None) {
-            if y <= 0 {
-                stop_throwing = true;
-                break;
-            }
-
-            let landscape = &mut game_field.landscape;
-            let pixels_under_tank = landscape.get_pixels_line_mut((x, y), tank_width as u16);
-            if let Some(pixels) = pixels_under_tank {
-                let empty_count = bytecount::count(pixels, 0);
-                if empty_count > max_empty_count {
-                    if empty_count < tank_width as usize {
-                        // Landscape under tank is not empty - clear it
-                        pixels.iter_mut().for_each(|c| *c = 0);
-                        landscape.set_changed();
-                    }
-                    // Get down tank
-                    offset += 1.0;
-                } else {
+            match fall_step(&mut game_field.landscape, (x, y), tank_width) {
+                Some(true) => offset += 1.0,
+                Some(false) => {
                     stop_throwing = true;
                     break;
                 }
+                None => {}
             }
         }
 
@@ -539,17 +783,31 @@ fn tanks_throwing_system(
         if stop_throwing {
             placed_tanks_count += 1;
             commands.entity(entity).remove::<TankThrowing>();
+            let normal = game_field
+                .landscape
+                .surface_normal(tank_position.0.x.round() as i32);
+            tank.set_tilt_from_normal(normal);
+            let impact_speed = throwing.impact_speed();
             if health.invincible {
                 health.invincible = false;
             } else {
-                let cur_height = tank_position.0.y;
-                let path_len = throwing.start_position.y - cur_height;
-                let damage_value: u8 =
-                    (path_len * TANK_THROWING_DAMAGE_POWER).min(255.).round() as u8;
-                if damage_value > 0 {
-                    health.damage(damage_value);
+                let damage_value = impact_damage(impact_speed);
+                match parachutes.filter(|parachutes| parachutes.0 > 0) {
+                    Some(mut parachutes) if damage_value > 0 => {
+                        debug!("Parachute has saved the tank from damage");
+                        parachutes.0 -= 1;
+                    }
+                    _ => {
+                        if damage_value > 0 {
+                            health.damage(damage_value);
+                        }
+                    }
                 }
             }
+            landed_events.send(TankLandedEvent {
+                tank_entity: entity,
+                impact_speed,
+            });
         }
     }
 
@@ -559,121 +817,314 @@ fn tanks_throwing_system(
     }
 }
 
-pub fn check_missile_collides_with_tanks_system(
-    mut commands: Commands,
-    mut ev_missile_moved: EventReader<MissileMovedEvent>,
-    tank_position_query: Query<(&Tank, &Position)>,
-) {
-    for ev in ev_missile_moved.read() {
-        for &(x, y) in ev.path.iter() {
-            let is_hit = tank_position_query
-                .iter()
-                .any(|(tank, position)| tank.has_collision(position.0, (x as f32, y as f32)));
-            if is_hit {
-                debug!("Missile hit a tank in point {:?}", (x, y));
-                kill_missile(&mut commands, ev.missile, x, y);
-                break;
-            }
-        }
+/// Keeps colliders of tanks in sync with angles of their bodies and guns.
+fn update_tank_collider_system(mut tanks_query: Query<(&Tank, &mut Collider), Changed<Tank>>) {
+    for (tank, mut collider) in tanks_query.iter_mut() {
+        *collider = tank.collider();
     }
 }
 
+/// Returns max radius of secondary explosion of destroyed tank
+/// which has given count of stored weapons.
+fn secondary_explosion_radius(stored_ammo: u32) -> f32 {
+    let radius = SECONDARY_MIN_RADIUS + stored_ammo as f32 * SECONDARY_RADIUS_PER_AMMO;
+    radius.min(SECONDARY_MAX_RADIUS)
+}
+
+#[allow(clippy::type_complexity)]
 fn remove_dead_tank_system(
     mut commands: Commands,
-    mut game_field: ResMut<GameField>,
-    health_query: Query<(&Health, &Position, Entity), Changed<Health>>,
+    game_field: Res<GameField>,
+    mut game_rng: Option<ResMut<GameRng>>,
+    mut turn_manager: ResMut<TurnManager>,
+    health_query: Query<
+        (
+            &Tank,
+            &Health,
+            &Attackers,
+            &Position,
+            Option<&TankWeapon>,
+            Entity,
+        ),
+        Changed<Health>,
+    >,
+    tanks_query: Query<(Entity, &Tank)>,
+    mut destroyed_events: EventWriter<TankDestroyedEvent>,
 ) {
-    for (health, position, entity) in health_query.iter() {
+    for (tank, health, attackers, position, weapon, entity) in health_query.iter() {
         if health.value == 0 {
             debug!("Explode tank");
-            spawn_explosion(&mut commands, &game_field, position.0);
-            game_field.remove_tank_by_entity(entity);
+            spawn_explosion(&mut commands, &game_field, position.0, None);
+            // Stored ammo may detonate a bit later, so destruction
+            // of nearby tanks may cascade.
+            let chain_reaction = game_rng
+                .as_mut()
+                .is_some_and(|rng| rng.gen_bool(CHAIN_REACTION_CHANCE));
+            if chain_reaction {
+                let stored_ammo = weapon
+                    .and_then(|weapon| weapon.ammo())
+                    .map_or(0, |ammo| ammo.values().sum());
+                let radius = secondary_explosion_radius(stored_ammo);
+                info!(
+                    "Ammo of player {} has detonated, radius {}",
+                    tank.player_number, radius
+                );
+                // Damage of the detonation is attributed to the killer.
+                let owner = attackers.last().and_then(|killer| {
+                    tanks_query
+                        .iter()
+                        .find(|&(killer_entity, killer_tank)| {
+                            killer_entity != entity && killer_tank.player_number == killer
+                        })
+                        .map(|(killer_entity, _)| Owner(killer_entity))
+                });
+                let explosion = Explosion::delayed(radius, CHAIN_REACTION_DELAY);
+                spawn_explosion_of(&mut commands, &game_field, position.0, explosion, owner);
+            }
+            let event = TankDestroyedEvent {
+                tank_entity: entity,
+                player_number: tank.player_number,
+                killer: attackers.last(),
+                assists: attackers.assists(tank.player_number),
+            };
+            info!("{}", event);
+            destroyed_events.send(event);
+            turn_manager.remove_tank(entity);
             commands.entity(entity).despawn_recursive();
         }
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn damage_tank_by_explosion_system(
-    mut tanks_query: Query<(&Tank, &mut Health, &Position)>,
-    mut explosion_events: EventReader<ExplosionHitEvent>,
+    rules: Res<GameRules>,
+    mut tanks_query: Query<(Entity, &Tank, &mut Health, &mut Attackers, Option<&Team>)>,
+    mut damage_events: EventReader<ExplosionDamageEvent>,
+    mut damaged_events: EventWriter<TankDamagedEvent>,
 ) {
-    for event in explosion_events.read() {
-        let explosion = event.explosion;
-        let explosion_pos = event.position;
-        // Check the intersection of explosion with tanks and decrease their health.
-        for (tank, mut health, &Position(tank_position)) in tanks_query.iter_mut() {
-            let percents =
-                explosion.get_intersection_percents(explosion_pos, tank.body_rect(tank_position));
-            if percents > 0 {
-                debug!(
-                    "Damage tank #{} by explosion on {} points",
-                    tank.player_number, percents
-                );
-                health.damage(percents);
-            }
+    for event in damage_events.read() {
+        let owner = event.owner.and_then(|owner| tanks_query.get(owner).ok());
+        let attacker = owner.map(|(_, tank, ..)| tank.player_number);
+        let attacker_team = owner.and_then(|(.., team)| team.copied());
+        let Ok((entity, tank, mut health, mut attackers, team)) = tanks_query.get_mut(event.entity)
+        else {
+            continue;
+        };
+        let is_teammate = Some(entity) != event.owner
+            && rules.teams.as_ref().is_some_and(|teams| {
+                team.zip(attacker_team.as_ref())
+                    .is_some_and(|(team, attacker_team)| {
+                        team.is_protected_from(attacker_team, teams)
+                    })
+            });
+        if is_teammate {
+            continue;
         }
+        debug!(
+            "Damage tank #{} by explosion on {} points",
+            tank.player_number, event.damage
+        );
+        health.damage(event.damage);
+        if let Some(attacker) = attacker {
+            attackers.add(attacker);
+        }
+        damaged_events.send(TankDamagedEvent {
+            tank_entity: entity,
+            player_number: tank.player_number,
+            damage: event.damage,
+            attacker,
+        });
     }
 }
 
-fn set_texture_hue_system(
+/// Adds meshes with `HueOffsetMaterial` to tanks and their guns,
+/// so the hue of player is applied by GPU.
+fn add_tank_materials_system(
     mut commands: Commands,
-    textures: ResMut<Assets<Image>>,
-    mut asset_events: EventReader<AssetEvent<Image>>,
-    images_query: Query<(Entity, &Handle<Image>, &HueOffset)>,
-    asset_server: Res<AssetServer>,
+    game_field: Option<Res<GameField>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<HueOffsetMaterial>>,
+    mut mesh: Local<Option<Handle<Mesh>>>,
+    new_sprites_query: Query<(Entity, &HueOffset, Has<TankGun>), Added<HueOffset>>,
 ) {
-    for event in asset_events.read() {
-        if let AssetEvent::LoadedWithDependencies { id } = event {
-            if let Some(image) = textures.get(*id) {
-                for (entity, image_handle, hue_offset) in images_query.iter() {
-                    if image_handle.id() != *id {
-                        continue;
-                    }
-                    let new_image = rotate_hue(image, hue_offset.0);
-                    commands
-                        .entity(entity)
-                        .remove::<HueOffset>()
-                        .remove::<Handle<Image>>()
-                        .insert(asset_server.add(new_image));
-                }
-            }
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let mesh = mesh.get_or_insert_with(|| meshes.add(Rectangle::new(TANK_SIZE, TANK_SIZE)));
+    for (entity, hue_offset, is_gun) in new_sprites_query.iter() {
+        let texture = if is_gun {
+            game_field.gun_texture.clone()
+        } else {
+            game_field.tank_texture.clone()
+        };
+        let material = materials.add(HueOffsetMaterial {
+            offset: hue_offset.0 as f32 / 360.,
+            texture,
+            alpha: 1.,
+        });
+        commands
+            .entity(entity)
+            .insert((Mesh2dHandle(mesh.clone()), material));
+    }
+}
+
+/// Moves glow to the current tank.
+fn current_tank_glow_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    rules: Res<GameRules>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+    current_tanks_query: Query<(Entity, &Tank), With<CurrentTank>>,
+    glows_query: Query<(Entity, &Parent), With<CurrentTankGlow>>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    let current_tank = current_tanks_query.get_single().ok();
+    let mut has_glow = false;
+    for (glow_entity, parent) in glows_query.iter() {
+        if current_tank.is_some_and(|(entity, _)| entity == parent.get()) {
+            has_glow = true;
+        } else {
+            commands.entity(glow_entity).despawn_recursive();
         }
     }
+    let Some((tank_entity, tank)) = current_tank else {
+        return;
+    };
+    if has_glow {
+        return;
+    }
+    let material = materials.add(GlowMaterial {
+        color: player_color(
+            tank.player_number,
+            game_field.players_count(),
+            rules.teams.as_ref(),
+        ),
+        intensity: GLOW_INTENSITY,
+        texture: game_field.tank_texture.clone(),
+    });
+    let glow_entity = commands
+        .spawn((
+            Mesh2dHandle(meshes.add(Rectangle::new(TANK_SIZE, TANK_SIZE))),
+            material,
+            // Glow is drawn behind the tank and its gun.
+            SpatialBundle::from_transform(Transform::from_translation(Vec3::new(0., 0., -0.2))),
+            CurrentTankGlow,
+        ))
+        .id();
+    commands.entity(tank_entity).add_child(glow_entity);
 }
 
-fn rotate_hue(image: &Image, hue_offset: u16) -> Image {
-    let mut new_image = image.clone();
-    for pixel in new_image.data.chunks_exact_mut(4) {
-        let mut rgb = *Rgb::new(
-            pixel[0] as f32 / 255.,
-            pixel[1] as f32 / 255.,
-            pixel[2] as f32 / 255.,
-        )
-        .srgb_encoded()
-        .decode()
-        .color();
-        let mut hsv: Hsv<f32, Deg<f32>> = Hsv::from_color(&rgb);
-        let hue = (hsv.hue() + Deg(hue_offset as f32)).0 as u32 % 360;
-        hsv.set_hue(Deg(hue as f32));
-        rgb = *Rgb::from_color(&hsv)
-            .linear()
-            .encode(SrgbEncoding::new())
-            .color();
-        pixel[0] = (rgb.red() * 255.0).min(255.).round() as u8;
-        pixel[1] = (rgb.green() * 255.0).min(255.).round() as u8;
-        pixel[2] = (rgb.blue() * 255.0).min(255.).round() as u8;
-    }
-    new_image
+fn pulse_glow_system(
+    time: Res<Time>,
+    mut materials: ResMut<Assets<GlowMaterial>>,
+    glows_query: Query<&Handle<GlowMaterial>, With<CurrentTankGlow>>,
+) {
+    let phase = time.elapsed_seconds() * GLOW_FREQUENCY * 2. * PI;
+    let intensity = GLOW_INTENSITY * (1. + 0.5 * phase.sin());
+    for handle in glows_query.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.intensity = intensity;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::MAX_PLAYERS_COUNT;
+
+    #[test]
+    fn test_impact_damage() {
+        assert_eq!(impact_damage(0.), 0);
+        assert_eq!(impact_damage(SOFT_LANDING_SPEED), 0);
+        assert!(impact_damage(SOFT_LANDING_SPEED + 10.) > 0);
+        assert!(impact_damage(100.) < impact_damage(120.));
+        assert_eq!(
+            impact_damage(TERMINAL_VELOCITY),
+            impact_damage(TERMINAL_VELOCITY * 2.)
+        );
+    }
+
+    #[test]
+    fn test_secondary_explosion_radius() {
+        assert_eq!(secondary_explosion_radius(0), SECONDARY_MIN_RADIUS);
+        assert!(secondary_explosion_radius(4) > secondary_explosion_radius(2));
+        assert_eq!(secondary_explosion_radius(1000), SECONDARY_MAX_RADIUS);
+    }
+
+    #[test]
+    fn test_shield_absorbs_damage() {
+        let mut health = Health {
+            value: 100,
+            shield: 30,
+            invincible: false,
+        };
+        assert_eq!(health.damage(20), 100);
+        assert_eq!(health.damage(20), 90);
+        assert_eq!(health.shield, 0);
+        assert_eq!(health.damage(20), 70);
+    }
+
+    #[test]
+    fn test_kill_attribution() {
+        let mut attackers = Attackers::default();
+        assert_eq!(attackers.last(), None);
+        for player_number in [2, 1, 3, 2] {
+            attackers.add(player_number);
+        }
+        assert_eq!(attackers.last(), Some(2));
+        assert_eq!(attackers.assists(1), vec![3]);
+
+        let mut event = TankDestroyedEvent {
+            tank_entity: Entity::from_raw(1),
+            player_number: 1,
+            killer: attackers.last(),
+            assists: attackers.assists(1),
+        };
+        assert!(!event.is_self_kill());
+        assert_eq!(event.to_string(), "Player 2 destroyed Player 1");
+        event.killer = Some(1);
+        assert!(event.is_self_kill());
+        assert_eq!(event.to_string(), "Player 1 destroyed itself");
+        event.killer = None;
+        assert_eq!(event.to_string(), "Player 1 was destroyed");
+    }
+
+    #[test]
+    fn test_player_colors_are_distinct() {
+        for count in 2..=MAX_PLAYERS_COUNT {
+            let colors: Vec<Color> = (1..=count)
+                .map(|player_number| player_color(player_number, count, None))
+                .collect();
+            for (i, color) in colors.iter().enumerate() {
+                assert!(!colors[i + 1..].contains(color));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fine_aiming() {
+        let mut tank = Tank::new(1, &ChassisSpec::default());
+        for _ in 0..10 {
+            tank.inc_gun_angle(0.1);
+            tank.inc_gun_power(-0.1);
+        }
+        assert_eq!(tank.gun_angle_deg(), 1.);
+        assert_eq!(tank.power, 39.);
+
+        tank.inc_gun_angle(100.);
+        assert_eq!(tank.gun_angle_deg(), 90.);
+        tank.inc_gun_power(-100.);
+        assert_eq!(tank.power, 0.);
+    }
 
     #[test]
     fn test_has_collision() {
         let tank_position = Vec2::new(10.0 + TANK_SIZE / 2., 20.0 - TANK_SIZE / 2.);
-        let mut tank = Tank::new(1);
+        let mut tank = Tank::new(1, &ChassisSpec::default());
 
         let inner_points = [
             (20., 27.), // body center
@@ -686,7 +1137,8 @@ mod tests {
         ];
         for point in inner_points.iter() {
             assert!(
-                tank.has_collision(tank_position, (10. + point.0, 20. - point.1)),
+                tank.collider()
+                    .contains(Vec2::new(10. + point.0, 20. - point.1) - tank_position),
                 "point=({}, {})",
                 point.0,
                 point.1
@@ -701,7 +1153,8 @@ mod tests {
         ];
         for point in inner_points.iter() {
             assert!(
-                tank.has_collision(tank_position, (10. + point.0, 20. - point.1)),
+                tank.collider()
+                    .contains(Vec2::new(10. + point.0, 20. - point.1) - tank_position),
                 "point=({}, {})",
                 point.0,
                 point.1
@@ -715,11 +1168,38 @@ mod tests {
         ];
         for point in inner_points.iter() {
             assert!(
-                tank.has_collision(tank_position, (10. + point.0, 20. - point.1)),
+                tank.collider()
+                    .contains(Vec2::new(10. + point.0, 20. - point.1) - tank_position),
                 "point=({}, {})",
                 point.0,
                 point.1
             );
         }
     }
+
+    #[test]
+    fn test_chassis_collision() {
+        let point = Vec2::new(0., -23.);
+        let medium = Tank::new(1, &ChassisSpec::default());
+        let heavy = Tank::new(1, &Chassis::Heavy.spec());
+        assert!(!medium.collider().contains(point));
+        assert!(heavy.collider().contains(point));
+        assert!(heavy.gun_barrel_pos(Vec2::ZERO).y > medium.gun_barrel_pos(Vec2::ZERO).y);
+    }
+
+    #[test]
+    fn test_tilt() {
+        let mut tank = Tank::new(1, &ChassisSpec::default());
+        // Right side of body rotated by 30 degrees counterclockwise.
+        let point = Vec2::from_angle(PI / 6.).rotate(Vec2::new(17., -12.));
+        assert!(!tank.collider().contains(point));
+
+        // Slope rises to the right by 30 degrees.
+        tank.set_tilt_from_normal(Vec2::new(-0.5, 0.75f32.sqrt()));
+        assert_eq!(tank.tilt_deg(), 30.);
+        assert!(tank.collider().contains(point));
+        // Tilt is limited on steep slopes.
+        tank.set_tilt_from_normal(Vec2::new(1., 0.));
+        assert_eq!(tank.tilt_deg(), -MAX_TILT_DEG);
+    }
 }
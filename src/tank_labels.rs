@@ -0,0 +1,181 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::components::Position;
+use crate::decoy::Decoy;
+use crate::game_field::GameField;
+use crate::game_plugin::{setup_game_field, AppState};
+use crate::rules::GameRules;
+use crate::settings::Settings;
+use crate::tank::{player_color, CurrentTank, Tank};
+use crate::turn_timer::{host_time, HostClock, MatchClocks};
+use crate::weapons::TankWeapon;
+
+const LABEL_FONT_SIZE: f32 = 14.;
+/// Distance between the center of tank and its label.
+const LABEL_OFFSET: f32 = 32.;
+/// Distance between the center of current tank and the lowest
+/// position of the turn marker.
+const MARKER_OFFSET: f32 = 48.;
+const MARKER_BOUNCE_HEIGHT: f32 = 8.;
+/// Count of bounces of the turn marker per second.
+const MARKER_BOUNCE_FREQUENCY: f32 = 1.5;
+
+/// Names of players above tanks and marker of the tank whose turn it is.
+pub struct TankLabelsPlugin;
+
+impl Plugin for TankLabelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            setup_turn_marker.after(setup_game_field),
+        )
+        .add_systems(
+            Update,
+            (
+                setup_tank_labels_system,
+                update_tank_labels_system,
+                turn_marker_system,
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Component)]
+pub struct TankLabel;
+
+#[derive(Clone, Copy, Component)]
+pub struct TurnMarker;
+
+fn setup_tank_labels_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    new_tanks_query: Query<(Entity, &Tank), Added<Tank>>,
+    new_decoys_query: Query<(Entity, &Decoy), Added<Decoy>>,
+) {
+    let Some(game_field) = game_field else {
+        return;
+    };
+    // Decoys have the same labels as real tanks.
+    let new_tanks = new_tanks_query
+        .iter()
+        .map(|(entity, tank)| (entity, tank.player_number))
+        .chain(
+            new_decoys_query
+                .iter()
+                .map(|(entity, decoy)| (entity, decoy.player_number)),
+        );
+    for (tank_entity, player_number) in new_tanks {
+        let label_entity = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        format!("Player {}", player_number),
+                        TextStyle {
+                            font: game_field.font.clone(),
+                            font_size: LABEL_FONT_SIZE * settings.ui_scale.get(),
+                            color: player_color(
+                                player_number,
+                                game_field.players_count(),
+                                rules.teams.as_ref(),
+                            ),
+                        },
+                    ),
+                    transform: Transform::from_translation(Vec3::new(0., LABEL_OFFSET, 1.)),
+                    ..default()
+                },
+                TankLabel,
+            ))
+            .id();
+        commands.entity(tank_entity).add_child(label_entity);
+    }
+}
+
+/// Returns text of label of the player with remaining time of blitz clock
+/// and count of turns left until the charging weapon will be fired.
+fn label_text(player_number: u8, clock: Option<f64>, charge_left: u32) -> String {
+    let mut label = format!("Player {}", player_number);
+    if let Some(remaining) = clock {
+        let seconds = remaining.ceil() as u32;
+        label += &format!(" {}:{:02}", seconds / 60, seconds % 60);
+    }
+    if charge_left > 0 {
+        label += &format!(" (charging: {})", charge_left);
+    }
+    label
+}
+
+/// Shows in the label of tank the clock of player and that its weapon
+/// is charging, so opponents may react before it will be fired.
+fn update_tank_labels_system(
+    time: Res<Time>,
+    clocks: Res<MatchClocks>,
+    host_clock: Option<Res<HostClock>>,
+    tanks_query: Query<(&Tank, &TankWeapon)>,
+    mut labels_query: Query<(&Parent, &mut Text), With<TankLabel>>,
+) {
+    let now = host_time(&time, host_clock.as_deref());
+    for (parent, mut text) in labels_query.iter_mut() {
+        let Ok((tank, weapon)) = tanks_query.get(parent.get()) else {
+            continue;
+        };
+        let clock = clocks.remaining(tank.player_number, now);
+        let label = label_text(tank.player_number, clock, weapon.charge_left());
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+}
+
+fn setup_turn_marker(mut commands: Commands, game_field: Res<GameField>) {
+    let arrow = shapes::Polygon {
+        points: vec![Vec2::new(-8., 10.), Vec2::new(8., 10.), Vec2::new(0., 0.)],
+        closed: true,
+    };
+    let marker_entity = commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&arrow),
+                spatial: SpatialBundle {
+                    transform: Transform::from_translation(Vec3::new(0., 0., 3.)),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                ..default()
+            },
+            Fill::color(Color::WHITE),
+            Position::default(),
+            TurnMarker,
+        ))
+        .id();
+    commands
+        .entity(game_field.parent_entity)
+        .add_child(marker_entity);
+}
+
+#[allow(clippy::type_complexity)]
+fn turn_marker_system(
+    time: Res<Time>,
+    game_field: Option<Res<GameField>>,
+    rules: Res<GameRules>,
+    current_tank_query: Query<(&Tank, &Position), (With<CurrentTank>, Without<TurnMarker>)>,
+    mut marker_query: Query<(&mut Position, &mut Fill, &mut Visibility), With<TurnMarker>>,
+) {
+    let current_tank = current_tank_query.get_single().ok();
+    for (mut position, mut fill, mut visibility) in marker_query.iter_mut() {
+        let Some((tank, tank_position)) = current_tank else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        let players_count = game_field.as_ref().map_or(1, |field| field.players_count());
+        fill.color = player_color(tank.player_number, players_count, rules.teams.as_ref());
+        let phase = time.elapsed_seconds() * MARKER_BOUNCE_FREQUENCY * PI;
+        let bounce = MARKER_BOUNCE_HEIGHT * phase.sin().abs();
+        position.0 = tank_position.0 + Vec2::new(0., MARKER_OFFSET + bounce);
+    }
+}
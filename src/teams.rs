@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+
+use crate::rules::TeamRules;
+
+/// Difference of hues of teammates' tanks.
+const TEAMMATES_HUE_SPREAD: u16 = 25;
+
+/// Team of the tank in team mode, starting from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct Team(pub u8);
+
+impl Team {
+    /// Returns `true` if explosions of the attacker's tank
+    /// don't damage tank of this team.
+    pub fn is_protected_from(&self, attacker: &Team, teams: &TeamRules) -> bool {
+        self == attacker && !teams.friendly_fire
+    }
+}
+
+/// Returns hue offset of the player's tank in team mode.
+/// Teams are spread evenly over the color wheel, while hues
+/// of teammates are close to each other.
+pub fn team_hue_offset(player_number: u8, teams: &TeamRules) -> u16 {
+    let player_index = player_number.saturating_sub(1) as u16;
+    let count = teams.count.max(1) as u16;
+    let team_hue = (player_index % count) * (360 / count);
+    let index_in_team = player_index / count;
+    (team_hue + index_in_team * TEAMMATES_HUE_SPREAD) % 360
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_team_hues_are_clustered() {
+        let teams = TeamRules::new(2);
+        let hues: Vec<u16> = (1..=5).map(|p| team_hue_offset(p, &teams)).collect();
+        assert_eq!(hues, vec![0, 180, 25, 205, 50]);
+
+        let team = Team(teams.team_of(3));
+        assert_eq!(team, Team(1));
+        assert!(team.is_protected_from(&Team(1), &teams));
+        assert!(!team.is_protected_from(&Team(2), &teams));
+        let friendly_fire = TeamRules {
+            friendly_fire: true,
+            ..teams
+        };
+        assert!(!team.is_protected_from(&Team(1), &friendly_fire));
+    }
+}
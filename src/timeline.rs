@@ -0,0 +1,176 @@
+//! Timeline of notable match events (shots, hits, kills) for video editors
+//! and casting tools.
+//!
+//! The timeline is exported as JSON of the following stable format:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "events": [
+//!     {"time": 3.5, "type": "shot", "player": 2, "angle": -45.0, "power": 60.0},
+//!     {"time": 5.25, "type": "hit", "player": 4, "damage": 30},
+//!     {"time": 5.25, "type": "kill", "player": 4}
+//!   ]
+//! }
+//! ```
+//!
+//! `time` is count of seconds since start of the game. New fields and event
+//! types can be added in the future without changing of `version`,
+//! so consumers must ignore unknown ones.
+use std::path::Path;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+use crate::storage;
+use crate::tank::{Tank, TankDamagedEvent, TankDestroyedEvent, TankShotEvent};
+
+pub const TIMELINE_FORMAT_VERSION: u32 = 1;
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EventTimeline>()
+            .add_systems(PostUpdate, record_timeline_events_system)
+            .add_systems(Last, export_timeline_on_exit_system);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineEventKind {
+    Shot { player: u8, angle: f32, power: f32 },
+    Hit { player: u8, damage: u8 },
+    Kill { player: u8 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub time: f32,
+    #[serde(flatten)]
+    pub kind: TimelineEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Resource)]
+pub struct EventTimeline {
+    pub version: u32,
+    pub events: Vec<TimelineEvent>,
+}
+
+impl Default for EventTimeline {
+    fn default() -> Self {
+        Self {
+            version: TIMELINE_FORMAT_VERSION,
+            events: vec![],
+        }
+    }
+}
+
+impl EventTimeline {
+    pub fn push(&mut self, time: f32, kind: TimelineEventKind) {
+        self.events.push(TimelineEvent { time, kind });
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        storage::write(path, self.to_json()?)
+    }
+}
+
+fn record_timeline_events_system(
+    time: Res<Time>,
+    mut timeline: ResMut<EventTimeline>,
+    tanks_query: Query<&Tank>,
+    mut shot_events: EventReader<TankShotEvent>,
+    mut damaged_events: EventReader<TankDamagedEvent>,
+    mut destroyed_events: EventReader<TankDestroyedEvent>,
+) {
+    let now = time.elapsed_seconds();
+    for event in shot_events.read() {
+        if let Ok(tank) = tanks_query.get(event.tank_entity) {
+            timeline.push(
+                now,
+                TimelineEventKind::Shot {
+                    player: tank.player_number,
+                    angle: tank.gun_angle_deg(),
+                    power: tank.power,
+                },
+            );
+        }
+    }
+    for event in damaged_events.read() {
+        timeline.push(
+            now,
+            TimelineEventKind::Hit {
+                player: event.player_number,
+                damage: event.damage,
+            },
+        );
+    }
+    for event in destroyed_events.read() {
+        timeline.push(
+            now,
+            TimelineEventKind::Kill {
+                player: event.player_number,
+            },
+        );
+    }
+}
+
+fn export_timeline_on_exit_system(
+    settings: Res<Settings>,
+    timeline: Res<EventTimeline>,
+    mut exit_events: EventReader<AppExit>,
+) {
+    if exit_events.read().count() == 0 {
+        return;
+    }
+    if let Some(path) = settings.timeline_path.as_ref() {
+        if let Err(err) = timeline.save(path) {
+            error!("Failed to export events timeline: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_format() {
+        let mut timeline = EventTimeline::default();
+        timeline.push(
+            3.5,
+            TimelineEventKind::Shot {
+                player: 2,
+                angle: -45.,
+                power: 60.,
+            },
+        );
+        timeline.push(
+            5.25,
+            TimelineEventKind::Hit {
+                player: 4,
+                damage: 30,
+            },
+        );
+        timeline.push(5.25, TimelineEventKind::Kill { player: 4 });
+
+        let json = serde_json::to_string(&timeline).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"events":[{"time":3.5,"type":"shot","player":2,"angle":-45.0,"power":60.0},{"time":5.25,"type":"hit","player":4,"damage":30},{"time":5.25,"type":"kill","player":4}]}"#
+        );
+        assert_eq!(EventTimeline::from_json(&json).unwrap(), timeline);
+    }
+}
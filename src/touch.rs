@@ -0,0 +1,237 @@
+//! Touch controls for mobile devices and browsers.
+//!
+//! Dragging away from the aiming tank pulls the gun like a slingshot:
+//! the gun aims opposite to the drag and longer drag gives more power.
+//! A button on the screen fires and pinching zooms the camera.
+//! All of them send the same [`PlayerAction`] events as keyboard
+//! and gamepad.
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+
+use crate::camera::{zoomed_scale, MainCamera, SpectatorCamera};
+use crate::components::Position;
+use crate::game_field::GameField;
+use crate::input::{human_is_aiming, PlayerAction};
+use crate::net::SpectatorSession;
+use crate::replay::ReplayPlayback;
+use crate::rules::{clamp_gun_angle, MAX_GUN_POWER};
+use crate::tank::{AimingTank, Tank};
+
+/// Distance from the tank within which a touch grabs its gun.
+const GRAB_RADIUS: f32 = 40.;
+/// Length of drag which gives the maximal power.
+const FULL_POWER_DRAG: f32 = 200.;
+/// Shorter drags don't change the aim, so a tap keeps it.
+const MIN_DRAG: f32 = 5.;
+const FIRE_BUTTON_SIZE: f32 = 80.;
+const FIRE_BUTTON_COLOR: Color = Color::rgba(0.8, 0.2, 0.1, 0.7);
+
+pub struct TouchInputPlugin;
+
+impl Plugin for TouchInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchState>().add_systems(
+            Update,
+            (
+                spawn_fire_button_system,
+                pinch_zoom_system,
+                (slingshot_system, fire_button_system)
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<SpectatorSession>))
+                    .run_if(human_is_aiming),
+            )
+                .chain()
+                .run_if(resource_exists::<Touches>),
+        );
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+struct TouchState {
+    /// Touch which drags the gun of aiming tank.
+    aiming_touch: Option<u64>,
+    /// Distance between two touches of pinch in the previous frame.
+    pinch_distance: Option<f32>,
+}
+
+#[derive(Component)]
+struct FireButton;
+
+/// Returns angle and power of the gun for a drag from the tank
+/// to the given point, or `None` if the drag is too short.
+fn slingshot_aim(tank_position: Vec2, touch_position: Vec2) -> Option<(f32, f32)> {
+    let pull = tank_position - touch_position;
+    let length = pull.length();
+    if length < MIN_DRAG {
+        return None;
+    }
+    // Angle of gun is counted from the vertical, positive angles aim to the right.
+    let angle = clamp_gun_angle(pull.x.atan2(pull.y).to_degrees());
+    let power = (length / FULL_POWER_DRAG * MAX_GUN_POWER).min(MAX_GUN_POWER);
+    Some((angle, power))
+}
+
+/// Returns zoom factor of camera when distance between fingers
+/// changes, spreading fingers zooms in.
+fn pinch_zoom_factor(prev_distance: f32, distance: f32) -> f32 {
+    if prev_distance <= 0. || distance <= 0. {
+        return 1.;
+    }
+    prev_distance / distance
+}
+
+/// Fire button is shown after the first touch of the screen,
+/// so it doesn't clutter the screen on desktop.
+fn spawn_fire_button_system(
+    mut commands: Commands,
+    touches: Res<Touches>,
+    game_field: Option<Res<GameField>>,
+    button_query: Query<(), With<FireButton>>,
+) {
+    if !button_query.is_empty() || touches.iter_just_pressed().next().is_none() {
+        return;
+    }
+    let Some(game_field) = game_field else {
+        return;
+    };
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(20.),
+                    bottom: Val::Px(20.),
+                    width: Val::Px(FIRE_BUTTON_SIZE),
+                    height: Val::Px(FIRE_BUTTON_SIZE),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: FIRE_BUTTON_COLOR.into(),
+                ..default()
+            },
+            FireButton,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "FIRE",
+                TextStyle {
+                    font: game_field.font.clone(),
+                    font_size: 24.,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn fire_button_system(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<FireButton>)>,
+    mut actions: EventWriter<PlayerAction>,
+) {
+    if button_query
+        .iter()
+        .any(|&interaction| interaction == Interaction::Pressed)
+    {
+        actions.send(PlayerAction::Fire);
+    }
+}
+
+fn slingshot_system(
+    touches: Res<Touches>,
+    mut state: ResMut<TouchState>,
+    aiming_tank_query: Query<(&Tank, &Position), With<AimingTank>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut actions: EventWriter<PlayerAction>,
+) {
+    let (Ok((tank, &Position(tank_position))), Ok((camera, camera_transform))) =
+        (aiming_tank_query.get_single(), camera_query.get_single())
+    else {
+        state.aiming_touch = None;
+        return;
+    };
+    let to_world = |touch: &Touch| camera.viewport_to_world_2d(camera_transform, touch.position());
+
+    if state.aiming_touch.is_none() && touches.iter().count() == 1 {
+        state.aiming_touch = touches
+            .iter_just_pressed()
+            .find(|touch| {
+                to_world(touch).is_some_and(|point| point.distance(tank_position) <= GRAB_RADIUS)
+            })
+            .map(|touch| touch.id());
+    }
+    let Some(touch_id) = state.aiming_touch else {
+        return;
+    };
+    let Some(touch) = touches.get_pressed(touch_id) else {
+        state.aiming_touch = None;
+        return;
+    };
+    let Some((angle, power)) =
+        to_world(touch).and_then(|point| slingshot_aim(tank_position, point))
+    else {
+        return;
+    };
+    let angle_delta = angle - tank.gun_angle_deg();
+    if angle_delta.abs() > f32::EPSILON {
+        actions.send(PlayerAction::RotateGun(angle_delta));
+    }
+    let power_delta = power - tank.power;
+    if power_delta.abs() > f32::EPSILON {
+        actions.send(PlayerAction::ChangePower(power_delta));
+    }
+}
+
+/// Pinch of two fingers zooms the camera and switches it
+/// into spectator mode, like zooming by keyboard.
+fn pinch_zoom_system(
+    touches: Res<Touches>,
+    mut state: ResMut<TouchState>,
+    spectator_camera: Option<ResMut<SpectatorCamera>>,
+    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
+) {
+    let mut pressed = touches.iter();
+    let (Some(first), Some(second), None) = (pressed.next(), pressed.next(), pressed.next()) else {
+        state.pinch_distance = None;
+        return;
+    };
+    // The second finger cancels dragging of the gun.
+    state.aiming_touch = None;
+    let distance = first.position().distance(second.position());
+    let Some(prev_distance) = state.pinch_distance.replace(distance) else {
+        return;
+    };
+    let zoom_factor = pinch_zoom_factor(prev_distance, distance);
+    if zoom_factor == 1. {
+        return;
+    }
+    // Main camera is spawned together with spectator camera.
+    let Some(mut spectator_camera) = spectator_camera else {
+        return;
+    };
+    spectator_camera.enabled = true;
+    for mut projection in camera_query.iter_mut() {
+        projection.scale = zoomed_scale(projection.scale, zoom_factor, &spectator_camera);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slingshot_aim() {
+        let tank = Vec2::new(100., 100.);
+        assert_eq!(slingshot_aim(tank, tank + Vec2::new(1., 1.)), None);
+        // Pulling straight down aims up.
+        assert_eq!(slingshot_aim(tank, Vec2::new(100., 0.)), Some((0., 50.)));
+        // Pulling to the left aims to the right with full power.
+        let (angle, power) = slingshot_aim(tank, Vec2::new(-300., 100.)).unwrap();
+        assert_eq!((angle, power), (90., MAX_GUN_POWER));
+        // Pulling up can't aim into the ground.
+        let (angle, _) = slingshot_aim(tank, Vec2::new(90., 150.)).unwrap();
+        assert_eq!(angle, 90.);
+
+        assert_eq!(pinch_zoom_factor(100., 200.), 0.5);
+        assert_eq!(pinch_zoom_factor(0., 200.), 1.);
+    }
+}
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+use crate::components::Position;
+use crate::environment::Weather;
+use crate::game_field::GameField;
+use crate::rules::{GameMode, GameRules};
+use crate::tank::{AimingTank, Tank};
+use crate::G;
+
+/// Flight time simulated for preview (seconds).
+const PREVIEW_DURATION: f32 = 10.;
+/// Max count of simulated points of trajectory.
+const MAX_PREVIEW_POINTS: usize = 3000;
+/// Count of trajectory points between dots.
+const DOTS_SPACING: usize = 6;
+
+pub struct TrajectoryPreviewPlugin;
+
+impl Plugin for TrajectoryPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRules>().add_systems(
+            Update,
+            draw_trajectory_preview_system.run_if(is_practice_mode),
+        );
+    }
+}
+
+fn is_practice_mode(rules: Res<GameRules>) -> bool {
+    matches!(rules.mode, GameMode::Practice | GameMode::TargetRange)
+}
+
+fn draw_trajectory_preview_system(
+    game_field: Res<GameField>,
+    weather: Res<Weather>,
+    aiming_tanks: Query<(&Tank, &Position), With<AimingTank>>,
+    mut gizmos: Gizmos,
+) {
+    let landscape = &game_field.landscape;
+    let (width, height) = landscape.size();
+    let borders = (width as i32, height as i32);
+    let acceleration = Vec2::new(game_field.wind_power, -G);
+    let color = Color::rgba(1., 1., 1., 0.6);
+
+    for (tank, tank_position) in aiming_tanks.iter() {
+        let missile = tank
+            .shoot(tank_position.0, acceleration)
+            .with_drag(weather.drag());
+        let path = missile.predict_path(PREVIEW_DURATION, borders, MAX_PREVIEW_POINTS);
+        let dots = path
+            .into_iter()
+            .take_while(|&(x, y)| y > 0 && !landscape.is_not_empty(x, y))
+            .step_by(DOTS_SPACING);
+        for (x, y) in dots {
+            gizmos.circle_2d(Vec2::new(x as f32, y as f32), 1., color);
+        }
+    }
+}
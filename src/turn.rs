@@ -0,0 +1,402 @@
+use bevy::prelude::*;
+
+use crate::game_plugin::AppState;
+use crate::rules::{GameMode, GameRules, TeamRules};
+use crate::tank::{AimingTank, CurrentTank, Health, Tank, TankDamagedEvent, TankShotEvent};
+use crate::upgrades::TankUpgrades;
+
+/// Damage of every tank at the end of each turn of sudden death.
+const SUDDEN_DEATH_DAMAGE: u8 = 10;
+
+pub struct TurnPlugin;
+
+impl Plugin for TurnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TurnManager>()
+            .add_event::<TurnStartedEvent>()
+            .add_event::<TurnEndedEvent>()
+            .add_event::<RoundEndedEvent>()
+            .add_event::<SuddenDeathEvent>()
+            .add_systems(OnEnter(AppState::Aiming), start_turn_system)
+            .add_systems(
+                Update,
+                (end_turn_system, sudden_death_system)
+                    .chain()
+                    .run_if(in_state(AppState::Aiming)),
+            );
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TurnStartedEvent {
+    pub tank_entity: Entity,
+    pub player_number: u8,
+    pub turn_number: usize,
+    /// Tank with auto-loader makes the second shot of its turn.
+    pub extra_shot: bool,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TurnEndedEvent {
+    pub tank_entity: Entity,
+    pub player_number: u8,
+    pub turn_number: usize,
+}
+
+/// Round has ended because one or zero tanks (or teams) remain.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RoundEndedEvent {
+    /// Number of player whose tank has survived, `None` if all tanks
+    /// have been destroyed or several teammates have survived.
+    pub winner: Option<u8>,
+    /// Number of team whose tanks have survived in team mode.
+    pub winning_team: Option<u8>,
+}
+
+/// Turn limit of the round has been reached with several surviving tanks.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SuddenDeathEvent;
+
+#[derive(Debug, Clone, Copy)]
+struct TurnSlot {
+    tank_entity: Entity,
+    player_number: u8,
+    alive: bool,
+}
+
+/// Order of turns of tanks in the round.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct TurnManager {
+    slots: Vec<TurnSlot>,
+    current: Option<usize>,
+    turn_number: usize,
+    /// The current tank makes the second shot of its turn.
+    extra_shot: bool,
+    teams: Option<TeamRules>,
+}
+
+impl TurnManager {
+    /// Starts new round with tanks which make turns in the given order.
+    pub fn start_round(&mut self, tanks: impl IntoIterator<Item = (Entity, u8)>) {
+        self.slots = tanks
+            .into_iter()
+            .map(|(tank_entity, player_number)| TurnSlot {
+                tank_entity,
+                player_number,
+                alive: true,
+            })
+            .collect();
+        self.current = None;
+        self.turn_number = 0;
+        self.extra_shot = false;
+    }
+
+    /// Sets teams of players, the round continues while tanks
+    /// of several teams remain.
+    pub fn set_teams(&mut self, teams: Option<TeamRules>) {
+        self.teams = teams;
+    }
+
+    /// Passes the turn to the next alive tank and returns it.
+    pub fn next_turn(&mut self) -> Option<(Entity, u8)> {
+        let count = self.slots.len();
+        let start = self.current.map_or(0, |i| i + 1);
+        let next = (start..start + count)
+            .map(|i| i % count)
+            .find(|&i| self.slots[i].alive)?;
+        self.current = Some(next);
+        self.turn_number += 1;
+        self.extra_shot = false;
+        let slot = self.slots[next];
+        Some((slot.tank_entity, slot.player_number))
+    }
+
+    /// Gives the current tank the second shot of its turn,
+    /// if it is alive and hasn't made the second shot yet.
+    pub fn extra_shot(&mut self) -> Option<(Entity, u8)> {
+        let slot = self.slots.get(self.current?)?;
+        if self.extra_shot || !slot.alive {
+            return None;
+        }
+        self.extra_shot = true;
+        Some((slot.tank_entity, slot.player_number))
+    }
+
+    /// Returns `true` if the current tank makes the second shot of its turn.
+    #[inline]
+    pub fn is_extra_shot(&self) -> bool {
+        self.extra_shot
+    }
+
+    /// Marks tank as dead, so it will be skipped in the order of turns.
+    pub fn remove_tank(&mut self, tank_entity: Entity) {
+        if let Some(slot) = self
+            .slots
+            .iter_mut()
+            .find(|slot| slot.tank_entity == tank_entity)
+        {
+            slot.alive = false;
+        }
+    }
+
+    pub fn current_tank(&self) -> Option<(Entity, u8)> {
+        let slot = self.slots.get(self.current?)?;
+        Some((slot.tank_entity, slot.player_number))
+    }
+
+    /// Number of the current turn, starting from 1.
+    #[inline]
+    pub fn turn_number(&self) -> usize {
+        self.turn_number
+    }
+
+    pub fn alive_players(&self) -> impl Iterator<Item = u8> + '_ {
+        self.slots
+            .iter()
+            .filter(|slot| slot.alive)
+            .map(|slot| slot.player_number)
+    }
+
+    /// Returns numbers of players in order of their turns starting from
+    /// the current one, with flags of alive tanks.
+    pub fn turn_order(&self) -> Vec<(u8, bool)> {
+        let count = self.slots.len();
+        let start = self.current.unwrap_or(0);
+        (start..start + count)
+            .map(|i| self.slots[i % count])
+            .map(|slot| (slot.player_number, slot.alive))
+            .collect()
+    }
+
+    /// Returns numbers of teams which have alive tanks.
+    pub fn alive_teams(&self) -> Vec<u8> {
+        let Some(teams) = self.teams else {
+            return Vec::new();
+        };
+        let mut alive_teams: Vec<u8> = self
+            .alive_players()
+            .map(|player_number| teams.team_of(player_number))
+            .collect();
+        alive_teams.sort_unstable();
+        alive_teams.dedup();
+        alive_teams
+    }
+
+    /// Returns `true` if one or zero tanks remain,
+    /// or all remaining tanks are teammates.
+    pub fn is_round_over(&self) -> bool {
+        if self.teams.is_some() {
+            return self.alive_teams().len() <= 1;
+        }
+        self.alive_players().count() <= 1
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_turn_system(
+    mut commands: Commands,
+    rules: Res<GameRules>,
+    mut turn_manager: ResMut<TurnManager>,
+    cur_tank_query: Query<Entity, With<CurrentTank>>,
+    upgrades_query: Query<&TankUpgrades>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut started_events: EventWriter<TurnStartedEvent>,
+    mut round_ended_events: EventWriter<RoundEndedEvent>,
+) {
+    for cur_tank_entity in cur_tank_query.iter() {
+        commands.entity(cur_tank_entity).remove::<CurrentTank>();
+        commands.entity(cur_tank_entity).remove::<AimingTank>();
+    }
+
+    // The only tank of target range shoots until the player is bored.
+    if rules.mode != GameMode::TargetRange && turn_manager.is_round_over() {
+        let mut alive_players = turn_manager.alive_players();
+        let winner = match (alive_players.next(), alive_players.next()) {
+            (Some(player_number), None) => Some(player_number),
+            _ => None,
+        };
+        let winning_team = turn_manager.alive_teams().first().copied();
+        debug!("Round has been won by {:?} ({:?})", winner, winning_team);
+        round_ended_events.send(RoundEndedEvent {
+            winner,
+            winning_team,
+        });
+        next_state.set(AppState::RoundOver);
+        return;
+    }
+
+    let has_auto_loader = turn_manager
+        .current_tank()
+        .and_then(|(tank_entity, _)| upgrades_query.get(tank_entity).ok())
+        .is_some_and(|upgrades| upgrades.auto_loader);
+    let extra_shot = if has_auto_loader {
+        turn_manager.extra_shot()
+    } else {
+        None
+    };
+    let next_tank = extra_shot.or_else(|| turn_manager.next_turn());
+    debug!("Switch current tank");
+    if let Some((tank_entity, player_number)) = next_tank {
+        commands
+            .entity(tank_entity)
+            .insert(CurrentTank)
+            .insert(AimingTank);
+        started_events.send(TurnStartedEvent {
+            tank_entity,
+            player_number,
+            turn_number: turn_manager.turn_number(),
+            extra_shot: turn_manager.is_extra_shot(),
+        });
+    }
+}
+
+fn end_turn_system(
+    mut commands: Commands,
+    turn_manager: Res<TurnManager>,
+    mut shot_events: EventReader<TankShotEvent>,
+    upgrades_query: Query<&TankUpgrades>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut ended_events: EventWriter<TurnEndedEvent>,
+) {
+    let mut has_shoots = false;
+    for event in shot_events.read() {
+        if let Some(mut entity) = commands.get_entity(event.tank_entity) {
+            entity.remove::<AimingTank>();
+        }
+        has_shoots = true;
+    }
+    if has_shoots {
+        let current_tank = turn_manager.current_tank().filter(|&(tank_entity, _)| {
+            // The turn goes on with the second shot of auto-loader.
+            turn_manager.is_extra_shot()
+                || !upgrades_query
+                    .get(tank_entity)
+                    .is_ok_and(|upgrades| upgrades.auto_loader)
+        });
+        if let Some((tank_entity, player_number)) = current_tank {
+            ended_events.send(TurnEndedEvent {
+                tank_entity,
+                player_number,
+                turn_number: turn_manager.turn_number(),
+            });
+        }
+        debug!("Switch to MainAction");
+        next_state.set(AppState::MainAction);
+    }
+}
+
+/// Damages all tanks at the end of every turn after the turn limit.
+fn sudden_death_system(
+    rules: Res<GameRules>,
+    mut ended_events: EventReader<TurnEndedEvent>,
+    mut tanks_query: Query<(Entity, &Tank, &mut Health)>,
+    mut damaged_events: EventWriter<TankDamagedEvent>,
+    mut sudden_death_events: EventWriter<SuddenDeathEvent>,
+) {
+    let Some(turn_limit) = rules.turn_limit else {
+        ended_events.clear();
+        return;
+    };
+    for event in ended_events.read() {
+        if event.turn_number < turn_limit {
+            continue;
+        }
+        if event.turn_number == turn_limit {
+            info!("Sudden death");
+            sudden_death_events.send(SuddenDeathEvent);
+        }
+        for (entity, tank, mut health) in tanks_query.iter_mut() {
+            health.damage(SUDDEN_DEATH_DAMAGE);
+            damaged_events.send(TankDamagedEvent {
+                tank_entity: entity,
+                player_number: tank.player_number,
+                damage: SUDDEN_DEATH_DAMAGE,
+                attacker: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_tanks_are_skipped() {
+        let tanks: Vec<(Entity, u8)> = (1..=3).map(|i| (Entity::from_raw(i as u32), i)).collect();
+        let mut turn_manager = TurnManager::default();
+        turn_manager.start_round(tanks.clone());
+
+        assert_eq!(turn_manager.next_turn(), Some(tanks[0]));
+        turn_manager.remove_tank(tanks[1].0);
+        assert_eq!(turn_manager.next_turn(), Some(tanks[2]));
+        assert_eq!(turn_manager.next_turn(), Some(tanks[0]));
+        assert_eq!(turn_manager.turn_number(), 3);
+        assert!(!turn_manager.is_round_over());
+
+        turn_manager.remove_tank(tanks[0].0);
+        assert!(turn_manager.is_round_over());
+        assert_eq!(turn_manager.alive_players().collect::<Vec<_>>(), vec![3]);
+        assert_eq!(turn_manager.next_turn(), Some(tanks[2]));
+
+        turn_manager.remove_tank(tanks[2].0);
+        assert_eq!(turn_manager.next_turn(), None);
+    }
+
+    #[test]
+    fn test_extra_shot() {
+        let tanks: Vec<(Entity, u8)> = (1..=2).map(|i| (Entity::from_raw(i as u32), i)).collect();
+        let mut turn_manager = TurnManager::default();
+        turn_manager.start_round(tanks.clone());
+        assert_eq!(turn_manager.extra_shot(), None);
+
+        assert_eq!(turn_manager.next_turn(), Some(tanks[0]));
+        assert_eq!(turn_manager.extra_shot(), Some(tanks[0]));
+        assert!(turn_manager.is_extra_shot());
+        assert_eq!(turn_manager.turn_number(), 1);
+        assert_eq!(turn_manager.extra_shot(), None);
+
+        assert_eq!(turn_manager.next_turn(), Some(tanks[1]));
+        assert!(!turn_manager.is_extra_shot());
+        turn_manager.remove_tank(tanks[1].0);
+        assert_eq!(turn_manager.extra_shot(), None);
+    }
+
+    #[test]
+    fn test_round_of_teams() {
+        let tanks: Vec<(Entity, u8)> = (1..=5).map(|i| (Entity::from_raw(i as u32), i)).collect();
+        let mut turn_manager = TurnManager::default();
+        turn_manager.start_round(tanks.clone());
+        turn_manager.set_teams(Some(TeamRules::new(2)));
+        assert_eq!(turn_manager.alive_teams(), vec![1, 2]);
+
+        // Players 2 and 4 play for the second team.
+        turn_manager.remove_tank(tanks[1].0);
+        assert!(!turn_manager.is_round_over());
+        turn_manager.remove_tank(tanks[3].0);
+        assert!(turn_manager.is_round_over());
+        assert_eq!(turn_manager.alive_teams(), vec![1]);
+    }
+
+    #[test]
+    fn test_turn_order() {
+        let tanks: Vec<(Entity, u8)> = [3, 1, 2]
+            .into_iter()
+            .map(|i| (Entity::from_raw(i as u32), i))
+            .collect();
+        let mut turn_manager = TurnManager::default();
+        turn_manager.start_round(tanks.clone());
+        assert_eq!(
+            turn_manager.turn_order(),
+            vec![(3, true), (1, true), (2, true)]
+        );
+
+        turn_manager.next_turn();
+        turn_manager.next_turn();
+        turn_manager.remove_tank(tanks[2].0);
+        assert_eq!(
+            turn_manager.turn_order(),
+            vec![(1, true), (2, false), (3, true)]
+        );
+    }
+}
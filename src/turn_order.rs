@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::broadcast_hud::is_broadcast_hud;
+use crate::game_field::GameField;
+use crate::rules::{GameRules, TeamRules};
+use crate::tank::player_color;
+use crate::turn::TurnManager;
+
+const ICON_SIZE: f32 = 24.;
+/// Icon of the current tank is bigger than icons of others.
+const CURRENT_ICON_SIZE: f32 = 32.;
+const DEAD_ICON_COLOR: Color = Color::rgba(0.4, 0.4, 0.4, 0.5);
+
+/// Strip of icons of tanks in order of their next turns.
+pub struct TurnOrderPlugin;
+
+impl Plugin for TurnOrderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (setup_turn_order_strip, update_turn_order_system)
+                .chain()
+                .run_if(not(is_broadcast_hud)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct TurnOrderStrip;
+
+/// Returns sizes and colors of icons of tanks in order of their turns
+/// starting from the current tank. Icons of dead tanks are greyed out.
+fn turn_order_icons(
+    turn_manager: &TurnManager,
+    players_count: u8,
+    teams: Option<&TeamRules>,
+) -> Vec<(f32, Color)> {
+    turn_manager
+        .turn_order()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (player_number, alive))| {
+            let size = if i == 0 && alive {
+                CURRENT_ICON_SIZE
+            } else {
+                ICON_SIZE
+            };
+            let color = if alive {
+                player_color(player_number, players_count, teams)
+            } else {
+                DEAD_ICON_COLOR
+            };
+            (size, color)
+        })
+        .collect()
+}
+
+fn setup_turn_order_strip(mut commands: Commands, strip_query: Query<(), With<TurnOrderStrip>>) {
+    if !strip_query.is_empty() {
+        return;
+    }
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(35.),
+                right: Val::Px(10.),
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(4.),
+                ..default()
+            },
+            ..default()
+        },
+        TurnOrderStrip,
+    ));
+}
+
+/// Rebuilds icons when the turn passes or a tank is destroyed.
+fn update_turn_order_system(
+    mut commands: Commands,
+    game_field: Option<Res<GameField>>,
+    rules: Res<GameRules>,
+    turn_manager: Res<TurnManager>,
+    strip_query: Query<Entity, With<TurnOrderStrip>>,
+    new_strip_query: Query<(), Added<TurnOrderStrip>>,
+) {
+    let (Some(game_field), Ok(strip)) = (game_field, strip_query.get_single()) else {
+        return;
+    };
+    if !turn_manager.is_changed() && new_strip_query.is_empty() {
+        return;
+    }
+    let icons = turn_order_icons(
+        &turn_manager,
+        game_field.players_count(),
+        rules.teams.as_ref(),
+    );
+    commands.entity(strip).despawn_descendants();
+    commands.entity(strip).with_children(|parent| {
+        for (size, color) in icons {
+            parent.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Px(size),
+                    height: Val::Px(size),
+                    ..default()
+                },
+                image: UiImage::new(game_field.tank_texture.clone()),
+                background_color: color.into(),
+                ..default()
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_order_icons() {
+        let tanks: Vec<(Entity, u8)> = (1..=3).map(|i| (Entity::from_raw(i as u32), i)).collect();
+        let mut turn_manager = TurnManager::default();
+        turn_manager.start_round(tanks.clone());
+        turn_manager.next_turn();
+        turn_manager.next_turn();
+        turn_manager.remove_tank(tanks[0].0);
+
+        assert_eq!(
+            turn_manager.turn_order(),
+            vec![(2, true), (3, true), (1, false)]
+        );
+        let icons = turn_order_icons(&turn_manager, 3, None);
+        assert_eq!(
+            icons,
+            vec![
+                (CURRENT_ICON_SIZE, player_color(2, 3, None)),
+                (ICON_SIZE, player_color(3, 3, None)),
+                (ICON_SIZE, DEAD_ICON_COLOR),
+            ]
+        );
+    }
+}
@@ -0,0 +1,419 @@
+//! Countdown of the turn, synchronized between the host and clients.
+//!
+//! Deadline of the turn is measured by the host clock. Clients estimate
+//! offset of their clock from the host clock by `TimeSyncRequest` messages
+//! and smoothly correct it, so remaining time is the same on all peers
+//! regardless of network latency. Only the host decides that time is over.
+//!
+//! In blitz match every player also has a time bank for the whole match,
+//! which is spent while their tank is aiming.
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::game_plugin::AppState;
+use crate::net::{NetMessage, NetMessageReceived, SendNetMessage};
+use crate::rules::{GameRules, Shot};
+use crate::simulation::ShootCommand;
+use crate::tank::{AimingTank, Health, Tank, TankSet, TankShotEvent};
+use crate::turn::{TurnManager, TurnStartedEvent};
+use crate::weapons::TankWeapon;
+
+/// Interval between requests of clock synchronization (seconds).
+const TIME_SYNC_INTERVAL: f64 = 2.;
+/// Part of the measured clock error corrected by every synchronization.
+const DRIFT_CORRECTION: f64 = 0.25;
+/// Error of clock (seconds) above which the clock is set at once
+/// instead of smooth correction.
+const MAX_CLOCK_ERROR: f64 = 0.5;
+
+pub struct TurnTimerPlugin;
+
+impl Plugin for TurnTimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchClocks>().add_systems(
+            Update,
+            (
+                (
+                    start_turn_timer_system,
+                    turn_timeout_system
+                        .before(TankSet::Aiming)
+                        .run_if(in_state(AppState::Aiming)),
+                    answer_time_sync_system,
+                    start_match_clock_system,
+                    stop_match_clock_system,
+                    forfeit_system
+                        .before(TankSet::Aiming)
+                        .run_if(in_state(AppState::Aiming)),
+                )
+                    .run_if(not(resource_exists::<HostClock>)),
+                (request_time_sync_system, receive_turn_timer_system)
+                    .run_if(resource_exists::<HostClock>),
+            ),
+        );
+    }
+}
+
+/// Countdown of the current turn.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct TurnTimer {
+    pub turn_number: usize,
+    /// Time of the host clock when the turn is over.
+    pub deadline: f64,
+}
+
+impl TurnTimer {
+    /// Returns remaining time of the turn (seconds).
+    pub fn remaining(&self, host_time: f64) -> f64 {
+        (self.deadline - host_time).max(0.)
+    }
+}
+
+/// Chess clocks of players in blitz match.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct MatchClocks {
+    /// Remaining time of players (seconds) excluding the running clock.
+    remaining: HashMap<u8, f64>,
+    /// Player whose clock runs and time of the host clock
+    /// when it has been started.
+    running: Option<(u8, f64)>,
+}
+
+impl MatchClocks {
+    /// Returns remaining time of the player (seconds) or `None`
+    /// if the player hasn't made turns yet.
+    pub fn remaining(&self, player: u8, host_time: f64) -> Option<f64> {
+        let remaining = *self.remaining.get(&player)?;
+        let elapsed = match self.running {
+            Some((running_player, started)) if running_player == player => host_time - started,
+            _ => 0.,
+        };
+        Some((remaining - elapsed).max(0.))
+    }
+
+    /// Starts clock of the player. Player without a clock gets
+    /// the full `time_bank`.
+    pub fn start(&mut self, player: u8, time_bank: f64, host_time: f64) {
+        self.stop(host_time);
+        self.remaining.entry(player).or_insert(time_bank);
+        self.running = Some((player, host_time));
+    }
+
+    /// Stops the running clock, returns its player and remaining time.
+    pub fn stop(&mut self, host_time: f64) -> Option<(u8, f64)> {
+        let (player, _) = self.running?;
+        let remaining = self.remaining(player, host_time)?;
+        self.remaining.insert(player, remaining);
+        self.running = None;
+        Some((player, remaining))
+    }
+
+    /// Returns the player whose clock runs.
+    #[inline]
+    pub fn running_player(&self) -> Option<u8> {
+        self.running.map(|(player, _)| player)
+    }
+}
+
+/// Estimation of the host clock on the client. Insert this resource
+/// on the client, it disables decisions about timeouts of turns.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct HostClock {
+    /// Difference between the host clock and the local clock.
+    offset: f64,
+    synchronized: bool,
+    pending_request: Option<f64>,
+    last_request: f64,
+}
+
+impl HostClock {
+    /// Returns time of the host clock for given time of the local clock.
+    #[inline]
+    pub fn host_time(&self, local_time: f64) -> f64 {
+        local_time + self.offset
+    }
+
+    #[inline]
+    pub fn is_synchronized(&self) -> bool {
+        self.synchronized
+    }
+
+    /// Updates estimation of the clock by the answer of the host.
+    /// Half of round-trip time is taken as latency of the answer.
+    pub fn synchronize(&mut self, client_time: f64, host_time: f64, local_time: f64) {
+        let round_trip_time = (local_time - client_time).max(0.);
+        let offset = host_time + round_trip_time / 2. - local_time;
+        let error = offset - self.offset;
+        if !self.synchronized || error.abs() > MAX_CLOCK_ERROR {
+            self.offset = offset;
+            self.synchronized = true;
+        } else {
+            self.offset += error * DRIFT_CORRECTION;
+        }
+    }
+}
+
+/// Returns current time of the host clock.
+pub fn host_time(time: &Time, clock: Option<&HostClock>) -> f64 {
+    let local_time = time.elapsed_seconds_f64();
+    clock.map_or(local_time, |clock| clock.host_time(local_time))
+}
+
+fn start_turn_timer_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    rules: Res<GameRules>,
+    mut started_events: EventReader<TurnStartedEvent>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    let Some(event) = started_events.read().last() else {
+        return;
+    };
+    let Some(time_limit) = rules.turn_time_limit else {
+        commands.remove_resource::<TurnTimer>();
+        return;
+    };
+    let timer = TurnTimer {
+        turn_number: event.turn_number,
+        deadline: time.elapsed_seconds_f64() + time_limit as f64,
+    };
+    commands.insert_resource(timer);
+    send_events.send(SendNetMessage(NetMessage::TurnTimerStarted {
+        turn_number: timer.turn_number,
+        deadline: timer.deadline,
+    }));
+}
+
+/// Makes the shot with current aim of the tank whose time is over.
+fn turn_timeout_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    timer: Option<Res<TurnTimer>>,
+    turn_manager: Res<TurnManager>,
+    aiming_tanks: Query<(&Tank, &TankWeapon), With<AimingTank>>,
+    mut shoot_commands: EventWriter<ShootCommand>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    let Some(timer) = timer else {
+        return;
+    };
+    if timer.turn_number != turn_manager.turn_number()
+        || timer.remaining(time.elapsed_seconds_f64()) > 0.
+    {
+        return;
+    }
+    let Ok((tank, tank_weapon)) = aiming_tanks.get_single() else {
+        return;
+    };
+    info!("Time of player {} is over", tank.player_number);
+    shoot_commands.send(ShootCommand(Shot {
+        player: tank.player_number,
+        angle: tank.gun_angle_deg(),
+        power: tank.power,
+        weapon: tank_weapon.ready_weapon(),
+    }));
+    send_events.send(SendNetMessage(NetMessage::TurnTimedOut {
+        turn_number: timer.turn_number,
+    }));
+    commands.remove_resource::<TurnTimer>();
+}
+
+fn start_match_clock_system(
+    time: Res<Time>,
+    rules: Res<GameRules>,
+    mut clocks: ResMut<MatchClocks>,
+    mut started_events: EventReader<TurnStartedEvent>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    let Some(event) = started_events.read().last() else {
+        return;
+    };
+    let Some(time_bank) = rules.time_bank else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+    clocks.start(event.player_number, time_bank as f64, now);
+    let remaining = clocks
+        .remaining(event.player_number, now)
+        .unwrap_or_default();
+    send_events.send(SendNetMessage(NetMessage::MatchClockStarted {
+        player: event.player_number,
+        remaining,
+        host_time: now,
+    }));
+}
+
+/// Clock of the player stops when their tank has shot.
+fn stop_match_clock_system(
+    time: Res<Time>,
+    mut clocks: ResMut<MatchClocks>,
+    mut shot_events: EventReader<TankShotEvent>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    if shot_events.read().count() == 0 {
+        return;
+    }
+    if let Some((player, remaining)) = clocks.stop(time.elapsed_seconds_f64()) {
+        send_events.send(SendNetMessage(NetMessage::MatchClockStopped {
+            player,
+            remaining,
+        }));
+    }
+}
+
+/// Destroys the aiming tank of the player whose time bank is empty.
+/// The turn goes on as after an ordinary shot.
+fn forfeit_system(
+    time: Res<Time>,
+    mut clocks: ResMut<MatchClocks>,
+    mut aiming_tanks: Query<(Entity, &Tank, &mut Health), With<AimingTank>>,
+    mut shot_events: EventWriter<TankShotEvent>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    let now = time.elapsed_seconds_f64();
+    let Some(player) = clocks.running_player() else {
+        return;
+    };
+    if clocks.remaining(player, now).unwrap_or_default() > 0. {
+        return;
+    }
+    clocks.stop(now);
+    for (tank_entity, tank, mut health) in aiming_tanks.iter_mut() {
+        if tank.player_number != player {
+            continue;
+        }
+        info!("Time bank of player {} is empty", player);
+        health.value = 0;
+        shot_events.send(TankShotEvent { tank_entity });
+    }
+    send_events.send(SendNetMessage(NetMessage::PlayerForfeited { player }));
+}
+
+fn answer_time_sync_system(
+    time: Res<Time>,
+    mut received_events: EventReader<NetMessageReceived>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    for NetMessageReceived(message) in received_events.read() {
+        if let &NetMessage::TimeSyncRequest { client_time } = message {
+            send_events.send(SendNetMessage(NetMessage::TimeSyncResponse {
+                client_time,
+                host_time: time.elapsed_seconds_f64(),
+            }));
+        }
+    }
+}
+
+fn request_time_sync_system(
+    time: Res<Time>,
+    mut clock: ResMut<HostClock>,
+    mut send_events: EventWriter<SendNetMessage>,
+) {
+    let local_time = time.elapsed_seconds_f64();
+    let has_requested = clock.synchronized || clock.pending_request.is_some();
+    if has_requested && local_time - clock.last_request < TIME_SYNC_INTERVAL {
+        return;
+    }
+    clock.last_request = local_time;
+    clock.pending_request = Some(local_time);
+    send_events.send(SendNetMessage(NetMessage::TimeSyncRequest {
+        client_time: local_time,
+    }));
+}
+
+fn receive_turn_timer_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut clock: ResMut<HostClock>,
+    mut clocks: ResMut<MatchClocks>,
+    mut received_events: EventReader<NetMessageReceived>,
+) {
+    for NetMessageReceived(message) in received_events.read() {
+        match *message {
+            NetMessage::TimeSyncResponse {
+                client_time,
+                host_time,
+            } if clock.pending_request == Some(client_time) => {
+                // The host answers to all clients, answers to others are skipped.
+                clock.pending_request = None;
+                clock.synchronize(client_time, host_time, time.elapsed_seconds_f64());
+            }
+            NetMessage::TurnTimerStarted {
+                turn_number,
+                deadline,
+            } => {
+                commands.insert_resource(TurnTimer {
+                    turn_number,
+                    deadline,
+                });
+            }
+            NetMessage::TurnTimedOut { .. } => {
+                commands.remove_resource::<TurnTimer>();
+            }
+            NetMessage::MatchClockStarted {
+                player,
+                remaining,
+                host_time,
+            } => {
+                clocks.remaining.insert(player, remaining);
+                clocks.running = Some((player, host_time));
+            }
+            NetMessage::MatchClockStopped { player, remaining } => {
+                clocks.remaining.insert(player, remaining);
+                clocks.running = None;
+            }
+            NetMessage::PlayerForfeited { player } => {
+                clocks.remaining.insert(player, 0.);
+                clocks.running = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_clock_synchronization() {
+        let mut clock = HostClock::default();
+        // The host clock is ahead by 10 seconds, latency is 0.1 seconds.
+        clock.synchronize(1.0, 11.1, 1.2);
+        assert!(clock.is_synchronized());
+        assert!((clock.host_time(2.) - 12.).abs() < 1e-9);
+
+        // Jitter of latency is corrected smoothly.
+        clock.synchronize(3.0, 13.1, 3.4);
+        assert!((clock.host_time(4.) - 13.975).abs() < 1e-9);
+
+        // Large error is corrected at once.
+        clock.synchronize(5.0, 25.1, 5.2);
+        assert!((clock.host_time(6.) - 26.).abs() < 1e-9);
+
+        let timer = TurnTimer {
+            turn_number: 1,
+            deadline: 30.,
+        };
+        assert!((timer.remaining(clock.host_time(9.)) - 1.).abs() < 1e-9);
+        assert_eq!(timer.remaining(clock.host_time(11.)), 0.);
+    }
+
+    #[test]
+    fn test_match_clocks() {
+        let mut clocks = MatchClocks::default();
+        assert_eq!(clocks.remaining(1, 0.), None);
+
+        clocks.start(1, 60., 10.);
+        assert_eq!(clocks.remaining(1, 25.), Some(45.));
+        assert_eq!(clocks.stop(30.), Some((1, 40.)));
+        // Stopped clock doesn't run.
+        assert_eq!(clocks.remaining(1, 100.), Some(40.));
+
+        clocks.start(2, 60., 100.);
+        // Starting of the next clock stops the previous one.
+        clocks.start(1, 60., 110.);
+        assert_eq!(clocks.remaining(2, 200.), Some(50.));
+        assert_eq!(clocks.remaining(1, 200.), Some(0.));
+        assert_eq!(clocks.running_player(), Some(1));
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+
+/// Additional health given by every level of armor.
+const ARMOR_HEALTH: u8 = 25;
+const MAX_ARMOR_LEVEL: u8 = 3;
+/// Additional part of jetpack fuel given by every level of engine.
+const ENGINE_FUEL: f32 = 0.25;
+const MAX_ENGINE_LEVEL: u8 = 3;
+/// Scale of aiming steps of a tank with gyroscope.
+const GYROSCOPE_AIM_SCALE: f32 = 0.5;
+/// Step of aiming which can't be made finer, angle and power
+/// of gun are rounded to tenths.
+const MIN_AIM_STEP: f32 = 0.1;
+
+/// Persistent upgrade of tank which may be bought in the shop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+    /// Increases maximal health.
+    Armor,
+    /// Increases fuel of jetpack.
+    Engine,
+    /// Makes aiming steps finer.
+    Gyroscope,
+    /// Allows two shots per turn.
+    AutoLoader,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 4] = [
+        Upgrade::Armor,
+        Upgrade::Engine,
+        Upgrade::Gyroscope,
+        Upgrade::AutoLoader,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Armor => "Armor",
+            Self::Engine => "Engine",
+            Self::Gyroscope => "Gyroscope",
+            Self::AutoLoader => "Auto-loader",
+        }
+    }
+
+    pub fn price(&self) -> u32 {
+        match self {
+            Self::Armor => 6000,
+            Self::Engine => 3000,
+            Self::Gyroscope => 4000,
+            Self::AutoLoader => 25000,
+        }
+    }
+
+    fn max_level(&self) -> u8 {
+        match self {
+            Self::Armor => MAX_ARMOR_LEVEL,
+            Self::Engine => MAX_ENGINE_LEVEL,
+            Self::Gyroscope | Self::AutoLoader => 1,
+        }
+    }
+}
+
+/// Upgrades of a tank bought by its player.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+pub struct TankUpgrades {
+    pub armor: u8,
+    pub engine: u8,
+    pub gyroscope: bool,
+    pub auto_loader: bool,
+}
+
+impl TankUpgrades {
+    pub fn level(&self, upgrade: Upgrade) -> u8 {
+        match upgrade {
+            Upgrade::Armor => self.armor,
+            Upgrade::Engine => self.engine,
+            Upgrade::Gyroscope => self.gyroscope as u8,
+            Upgrade::AutoLoader => self.auto_loader as u8,
+        }
+    }
+
+    pub fn can_upgrade(&self, upgrade: Upgrade) -> bool {
+        self.level(upgrade) < upgrade.max_level()
+    }
+
+    /// Raises level of the upgrade, returns `false` if it
+    /// has maximal level already.
+    pub fn upgrade(&mut self, upgrade: Upgrade) -> bool {
+        if !self.can_upgrade(upgrade) {
+            return false;
+        }
+        match upgrade {
+            Upgrade::Armor => self.armor += 1,
+            Upgrade::Engine => self.engine += 1,
+            Upgrade::Gyroscope => self.gyroscope = true,
+            Upgrade::AutoLoader => self.auto_loader = true,
+        }
+        true
+    }
+
+    /// Returns maximal health of tank with given health of its chassis.
+    pub fn max_health(&self, chassis_health: u8) -> u8 {
+        chassis_health + ARMOR_HEALTH * self.armor
+    }
+
+    /// Multiplier of jetpack fuel.
+    pub fn fuel_scale(&self) -> f32 {
+        1. + ENGINE_FUEL * self.engine as f32
+    }
+
+    /// Returns change of gun's angle or power for the given
+    /// change requested by player.
+    pub fn aim_delta(&self, delta: f32) -> f32 {
+        if !self.gyroscope {
+            return delta;
+        }
+        let scaled = delta * GYROSCOPE_AIM_SCALE;
+        scaled.signum() * scaled.abs().max(MIN_AIM_STEP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrades() {
+        let mut upgrades = TankUpgrades::default();
+        assert_eq!(upgrades.max_health(100), 100);
+        assert_eq!(upgrades.aim_delta(1.), 1.);
+
+        for _ in 0..MAX_ARMOR_LEVEL {
+            assert!(upgrades.upgrade(Upgrade::Armor));
+        }
+        assert!(!upgrades.upgrade(Upgrade::Armor));
+        assert_eq!(upgrades.max_health(100), 175);
+
+        assert!(upgrades.upgrade(Upgrade::Engine));
+        assert_eq!(upgrades.fuel_scale(), 1.25);
+
+        assert!(upgrades.upgrade(Upgrade::Gyroscope));
+        assert!(!upgrades.can_upgrade(Upgrade::Gyroscope));
+        assert_eq!(upgrades.aim_delta(1.), 0.5);
+        assert_eq!(upgrades.aim_delta(-0.1), -0.1);
+    }
+}
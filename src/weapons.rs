@@ -0,0 +1,532 @@
+use std::fmt::Write;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::explosion::Explosion;
+use crate::game_plugin::AppState;
+use crate::input::PlayerAction;
+use crate::placeholder_icon::placeholder_icon;
+use crate::tank::{AimingTank, TankShotEvent};
+use crate::turn::TurnStartedEvent;
+
+/// Size (pixels) of generated icons of weapons.
+const ICON_SIZE: u32 = 32;
+/// Maximal count of each weapon in the suggested loadout.
+const MAX_SUGGESTED_COUNT: u32 = 3;
+
+pub struct WeaponsPlugin;
+
+impl Plugin for WeaponsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Weapons>().add_systems(
+            Update,
+            (
+                charge_weapons_system,
+                select_weapon_system.run_if(in_state(AppState::Aiming)),
+                fire_weapon_system,
+            ),
+        );
+    }
+}
+
+/// Generates icons of weapons which are shown in user interface.
+pub struct WeaponIconsPlugin;
+
+impl Plugin for WeaponIconsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            load_weapon_icons_system.run_if(resource_changed::<Weapons>),
+        );
+    }
+}
+
+/// How the weapon is aimed and delivered to its target.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeaponKind {
+    /// Missile is fired from the gun of tank.
+    #[default]
+    Missile,
+    /// Projectile drops vertically from above the game field onto
+    /// the target, which is selected by reticle, after one turn.
+    OrbitalStrike,
+    /// Plane flies across the top of the game field and drops a line
+    /// of missiles around the target selected by reticle.
+    Airstrike,
+    /// Missile embeds in the landscape as a mine, which detonates
+    /// when a tank is thrown near it.
+    Mine,
+    /// Reveals hidden objects around the tank until the end of turn.
+    /// It doesn't end the turn.
+    Scanner,
+    /// Makes the tank semi-invisible for opponents during a few turns
+    /// or until it fires.
+    Cloak,
+    /// Inflatable copy of the tank which draws fire of opponents.
+    Decoy,
+    /// Short-range hook which pulls the tank along a swing arc
+    /// if it anchors in the landscape above the tank.
+    GrapplingHook,
+    /// Lifts the tank in the direction of its gun.
+    Jetpack,
+    /// Levels landscape around the tank.
+    Earthmover,
+    /// Creates a zone where gravity is inverted for missiles.
+    AntiGravity,
+    /// Places a portal linked with the previous portal of the player.
+    Portal,
+    /// Meteors fall onto random points of the whole game field.
+    MeteorShower,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeaponDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub kind: WeaponKind,
+    /// Maximal radius of the explosion.
+    pub radius: f32,
+    /// Damage of a tank which is completely covered by the explosion.
+    pub damage: u8,
+    pub price: u32,
+    /// Path to the icon asset, placeholder icon is generated
+    /// if it is absent.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Number of turns of the tank which are required to charge
+    /// the weapon after its selection.
+    #[serde(default)]
+    pub charge_turns: u32,
+    /// Number of turns of the tank during which the weapon
+    /// can't be fired again.
+    #[serde(default)]
+    pub cooldown_turns: u32,
+}
+
+impl WeaponDefinition {
+    pub fn new(name: &str, radius: f32, damage: u8, price: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: WeaponKind::Missile,
+            radius,
+            damage,
+            price,
+            icon: None,
+            charge_turns: 0,
+            cooldown_turns: 0,
+        }
+    }
+
+    pub fn with_kind(self, kind: WeaponKind) -> Self {
+        Self { kind, ..self }
+    }
+
+    pub fn with_charge_turns(self, charge_turns: u32) -> Self {
+        Self {
+            charge_turns,
+            ..self
+        }
+    }
+
+    pub fn with_cooldown_turns(self, cooldown_turns: u32) -> Self {
+        Self {
+            cooldown_turns,
+            ..self
+        }
+    }
+
+    /// Returns explosion caused by projectile of the weapon.
+    pub fn explosion(&self) -> Explosion {
+        Explosion::new(self.radius).with_damage(self.damage)
+    }
+
+    pub fn price_per_damage(&self) -> f32 {
+        self.price as f32 / self.damage.max(1) as f32
+    }
+
+    /// Returns handle of the weapon's icon. Icon is generated
+    /// if the weapon doesn't have it.
+    pub fn icon(&self, asset_server: &AssetServer) -> Handle<Image> {
+        match self.icon.as_ref() {
+            Some(path) => asset_server.load(path.clone()),
+            None => asset_server.add(placeholder_icon(&self.name, ICON_SIZE)),
+        }
+    }
+
+    /// Returns text of tooltip which compares this weapon
+    /// with the currently selected one.
+    pub fn comparison_tooltip(&self, selected: &WeaponDefinition) -> String {
+        let mut tooltip = self.name.clone();
+        let rows = [
+            ("Radius", self.radius, selected.radius),
+            ("Damage", self.damage as f32, selected.damage as f32),
+            (
+                "Price per damage",
+                self.price_per_damage(),
+                selected.price_per_damage(),
+            ),
+        ];
+        for (title, value, selected_value) in rows {
+            let _ = write!(tooltip, "\n{}: {:.1}", title, value);
+            if self != selected {
+                let _ = write!(tooltip, " ({:+.1})", value - selected_value);
+            }
+        }
+        tooltip
+    }
+}
+
+/// Weapons which may be bought by players.
+#[derive(Debug, Clone, Resource)]
+pub struct Weapons(pub Vec<WeaponDefinition>);
+
+impl Default for Weapons {
+    fn default() -> Self {
+        Self(vec![
+            WeaponDefinition::new("Baby Missile", 25., 40, 400),
+            WeaponDefinition::new("Missile", 50., 100, 1900),
+            WeaponDefinition::new("Baby Nuke", 100., 100, 10000),
+            WeaponDefinition::new("Nuke", 150., 100, 12000).with_cooldown_turns(2),
+            WeaponDefinition::new("Orbital Strike", 60., 100, 15000)
+                .with_kind(WeaponKind::OrbitalStrike)
+                .with_cooldown_turns(3),
+            WeaponDefinition::new("Airstrike", 50., 100, 20000)
+                .with_kind(WeaponKind::Airstrike)
+                .with_cooldown_turns(3),
+            WeaponDefinition::new("Mine", 50., 100, 5000).with_kind(WeaponKind::Mine),
+            WeaponDefinition::new("Scanner", 150., 0, 4000)
+                .with_kind(WeaponKind::Scanner)
+                .with_cooldown_turns(3),
+            WeaponDefinition::new("Cloak", 0., 0, 6000)
+                .with_kind(WeaponKind::Cloak)
+                .with_cooldown_turns(4),
+            WeaponDefinition::new("Decoy", 0., 0, 3500).with_kind(WeaponKind::Decoy),
+            WeaponDefinition::new("Grappling Hook", 0., 0, 4500)
+                .with_kind(WeaponKind::GrapplingHook),
+            WeaponDefinition::new("Jetpack", 0., 0, 5000).with_kind(WeaponKind::Jetpack),
+            WeaponDefinition::new("Earthmover", 40., 0, 4000).with_kind(WeaponKind::Earthmover),
+            WeaponDefinition::new("Anti-gravity Field", 60., 0, 8000)
+                .with_kind(WeaponKind::AntiGravity)
+                .with_cooldown_turns(3),
+            WeaponDefinition::new("Portal", 0., 0, 4000).with_kind(WeaponKind::Portal),
+            WeaponDefinition::new("Meteor Shower", 50., 100, 18000)
+                .with_kind(WeaponKind::MeteorShower)
+                .with_cooldown_turns(3),
+        ])
+    }
+}
+
+impl Weapons {
+    pub fn get(&self, name: &str) -> Option<&WeaponDefinition> {
+        self.0.iter().find(|weapon| weapon.name == name)
+    }
+
+    /// Returns the first weapon of given kind.
+    pub fn of_kind(&self, kind: WeaponKind) -> Option<&WeaponDefinition> {
+        self.0.iter().find(|weapon| weapon.kind == kind)
+    }
+
+    /// Returns loadout for new players: a few of every weapon starting
+    /// from the cheapest per point of damage, which fits into the budget.
+    pub fn suggested_loadout(&self, budget: u32) -> Vec<(&WeaponDefinition, u32)> {
+        let mut weapons: Vec<&WeaponDefinition> = self.0.iter().collect();
+        weapons.sort_by(|a, b| a.price_per_damage().total_cmp(&b.price_per_damage()));
+        let mut remaining = budget;
+        let mut loadout = vec![];
+        for weapon in weapons {
+            let count = remaining
+                .checked_div(weapon.price)
+                .map_or(MAX_SUGGESTED_COUNT, |count| count.min(MAX_SUGGESTED_COUNT));
+            if count > 0 {
+                remaining -= weapon.price * count;
+                loadout.push((weapon, count));
+            }
+        }
+        loadout
+    }
+}
+
+/// Icons of weapons in the same order as in `Weapons`.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct WeaponIcons(pub Vec<Handle<Image>>);
+
+impl WeaponIcons {
+    pub fn get(&self, index: usize) -> Option<Handle<Image>> {
+        self.0.get(index).cloned()
+    }
+}
+
+/// Icons are loaded, or generated for weapons without them,
+/// once weapons are loaded.
+fn load_weapon_icons_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    weapons: Res<Weapons>,
+) {
+    let icons = weapons
+        .0
+        .iter()
+        .map(|weapon| weapon.icon(&asset_server))
+        .collect();
+    commands.insert_resource(WeaponIcons(icons));
+}
+
+/// State of weapons of a tank which is kept across turns.
+/// Tank fires the standard missile while the selected weapon
+/// is charging or cooling down.
+#[derive(Debug, Default, Clone, Component)]
+pub struct TankWeapon {
+    /// Index of selected weapon in `Weapons`.
+    selected: Option<usize>,
+    charge_left: u32,
+    cooldowns: HashMap<usize, u32>,
+    /// Count of every weapon which the tank has,
+    /// `None` means that weapons are unlimited.
+    ammo: Option<HashMap<usize, u32>>,
+}
+
+impl TankWeapon {
+    pub fn select(&mut self, index: usize, weapons: &Weapons) {
+        self.selected = Some(index);
+        self.charge_left = weapons.0.get(index).map_or(0, |w| w.charge_turns);
+    }
+
+    /// Selects the next weapon which the tank has, the standard
+    /// missile is selected after the last one.
+    pub fn select_next(&mut self, weapons: &Weapons) {
+        let next = self.selected.map_or(0, |index| index + 1);
+        match (next..weapons.0.len()).find(|&index| self.has_ammo(index)) {
+            Some(index) => self.select(index, weapons),
+            None => self.deselect(),
+        }
+    }
+
+    /// Selects the standard missile.
+    pub fn deselect(&mut self) {
+        self.selected = None;
+        self.charge_left = 0;
+    }
+
+    #[inline]
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Number of turns left before the selected weapon is charged.
+    #[inline]
+    pub fn charge_left(&self) -> u32 {
+        self.charge_left
+    }
+
+    /// Number of turns left before the weapon may be fired again.
+    pub fn cooldown(&self, index: usize) -> u32 {
+        self.cooldowns.get(&index).copied().unwrap_or_default()
+    }
+
+    /// Limits count of weapons of the tank.
+    pub fn set_ammo(&mut self, ammo: HashMap<usize, u32>) {
+        self.ammo = Some(ammo);
+    }
+
+    /// Count of every weapon which the tank has,
+    /// `None` means that weapons are unlimited.
+    #[inline]
+    pub fn ammo(&self) -> Option<&HashMap<usize, u32>> {
+        self.ammo.as_ref()
+    }
+
+    /// Adds one weapon to the tank if its weapons are limited.
+    pub fn add_ammo(&mut self, index: usize) {
+        if let Some(ammo) = self.ammo.as_mut() {
+            *ammo.entry(index).or_default() += 1;
+        }
+    }
+
+    pub fn has_ammo(&self, index: usize) -> bool {
+        self.ammo
+            .as_ref()
+            .is_none_or(|ammo| ammo.get(&index).is_some_and(|&count| count > 0))
+    }
+
+    /// Returns index of weapon which will be fired by the next shot,
+    /// `None` means the standard missile.
+    pub fn ready_weapon(&self) -> Option<usize> {
+        self.selected.filter(|&index| {
+            self.charge_left == 0 && self.cooldown(index) == 0 && self.has_ammo(index)
+        })
+    }
+
+    /// Returns kind of weapon which will be fired by the next shot.
+    pub fn ready_weapon_kind(&self, weapons: &Weapons) -> WeaponKind {
+        self.ready_weapon()
+            .and_then(|index| weapons.0.get(index))
+            .map_or(WeaponKind::Missile, |definition| definition.kind)
+    }
+
+    pub fn start_turn(&mut self) {
+        self.charge_left = self.charge_left.saturating_sub(1);
+        self.cooldowns.retain(|_, turns| {
+            *turns -= 1;
+            *turns > 0
+        });
+    }
+
+    /// Fires the ready weapon and returns its index.
+    pub fn fire(&mut self, weapons: &Weapons) -> Option<usize> {
+        let index = self.ready_weapon()?;
+        let cooldown_turns = weapons.0.get(index).map_or(0, |w| w.cooldown_turns);
+        if cooldown_turns > 0 {
+            self.cooldowns.insert(index, cooldown_turns);
+        }
+        if let Some(count) = self.ammo.as_mut().and_then(|ammo| ammo.get_mut(&index)) {
+            *count -= 1;
+        }
+        Some(index)
+    }
+}
+
+fn charge_weapons_system(
+    mut turn_started_events: EventReader<TurnStartedEvent>,
+    mut weapons_query: Query<&mut TankWeapon>,
+) {
+    for event in turn_started_events.read().filter(|event| !event.extra_shot) {
+        if let Ok(mut weapon) = weapons_query.get_mut(event.tank_entity) {
+            weapon.start_turn();
+        }
+    }
+}
+
+fn select_weapon_system(
+    weapons: Res<Weapons>,
+    mut actions: EventReader<PlayerAction>,
+    mut weapons_query: Query<&mut TankWeapon, With<AimingTank>>,
+) {
+    for _ in actions
+        .read()
+        .filter(|&&action| action == PlayerAction::NextWeapon)
+    {
+        for mut weapon in weapons_query.iter_mut() {
+            weapon.select_next(&weapons);
+            match weapon.selected() {
+                Some(index) => debug!("Select weapon {}", weapons.0[index].name),
+                None => debug!("Select standard missile"),
+            }
+        }
+    }
+}
+
+fn fire_weapon_system(
+    weapons: Res<Weapons>,
+    mut shot_events: EventReader<TankShotEvent>,
+    mut weapons_query: Query<&mut TankWeapon>,
+) {
+    for event in shot_events.read() {
+        if let Ok(mut weapon) = weapons_query.get_mut(event.tank_entity) {
+            if let Some(index) = weapon.fire(&weapons) {
+                debug!("Fire weapon {}", weapons.0[index].name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparison_tooltip() {
+        let weapons = Weapons::default();
+        let missile = weapons.get("Missile").unwrap();
+        let nuke = weapons.get("Nuke").unwrap();
+        assert_eq!(
+            nuke.comparison_tooltip(missile),
+            "Nuke\nRadius: 150.0 (+100.0)\nDamage: 100.0 (+0.0)\nPrice per damage: 120.0 (+101.0)"
+        );
+        assert_eq!(
+            missile.comparison_tooltip(missile),
+            "Missile\nRadius: 50.0\nDamage: 100.0\nPrice per damage: 19.0"
+        );
+    }
+
+    #[test]
+    fn test_suggested_loadout() {
+        let weapons = Weapons::default();
+        let loadout: Vec<_> = weapons
+            .suggested_loadout(10000)
+            .into_iter()
+            .map(|(weapon, count)| (weapon.name.as_str(), count))
+            .collect();
+        assert_eq!(loadout, vec![("Baby Missile", 3), ("Missile", 3)]);
+        assert!(weapons.suggested_loadout(0).is_empty());
+    }
+
+    #[test]
+    fn test_charge_and_cooldown() {
+        let weapons = Weapons(vec![WeaponDefinition::new(
+            "Orbital Strike",
+            60.,
+            100,
+            20000,
+        )
+        .with_charge_turns(2)
+        .with_cooldown_turns(1)]);
+        let mut weapon = TankWeapon::default();
+        assert_eq!(weapon.ready_weapon(), None);
+
+        weapon.select(0, &weapons);
+        assert_eq!(weapon.charge_left(), 2);
+        assert_eq!(weapon.fire(&weapons), None);
+        weapon.start_turn();
+        assert_eq!(weapon.fire(&weapons), None);
+        weapon.start_turn();
+        assert_eq!(weapon.fire(&weapons), Some(0));
+
+        assert_eq!(weapon.cooldown(0), 1);
+        assert_eq!(weapon.ready_weapon(), None);
+        weapon.start_turn();
+        assert_eq!(weapon.ready_weapon(), Some(0));
+    }
+
+    #[test]
+    fn test_ready_weapon_kind() {
+        let weapons = Weapons::default();
+        let mut weapon = TankWeapon::default();
+        assert_eq!(weapon.ready_weapon_kind(&weapons), WeaponKind::Missile);
+        let scanner = weapons.0.iter().position(|w| w.name == "Scanner").unwrap();
+        weapon.select(scanner, &weapons);
+        assert_eq!(weapon.ready_weapon_kind(&weapons), WeaponKind::Scanner);
+        weapon.fire(&weapons);
+        weapon.deselect();
+        assert_eq!(weapon.ready_weapon_kind(&weapons), WeaponKind::Missile);
+    }
+
+    #[test]
+    fn test_select_next_weapon() {
+        let weapons = Weapons::default();
+        let mut weapon = TankWeapon::default();
+        for index in 0..weapons.0.len() {
+            weapon.select_next(&weapons);
+            assert_eq!(weapon.selected(), Some(index));
+        }
+        weapon.select_next(&weapons);
+        assert_eq!(weapon.selected(), None);
+        weapon.select_next(&weapons);
+        assert_eq!(weapon.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_limited_ammo() {
+        let weapons = Weapons::default();
+        let mut weapon = TankWeapon::default();
+        weapon.set_ammo([(0, 0), (2, 1)].into_iter().collect());
+        weapon.select_next(&weapons);
+        assert_eq!(weapon.selected(), Some(2));
+        assert_eq!(weapon.fire(&weapons), Some(2));
+        assert!(!weapon.has_ammo(2));
+        assert_eq!(weapon.ready_weapon(), None);
+        weapon.select_next(&weapons);
+        assert_eq!(weapon.selected(), None);
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::components::Position;
+use crate::environment::Weather;
+use crate::game_field::{GameField, GameRng};
+use crate::game_plugin::AppState;
+use crate::landscape::Landscape;
+use crate::mines::settle_height;
+use crate::settings::Settings;
+use crate::tank::{setup_tanks, Tank};
+use crate::turn::TurnEndedEvent;
+
+/// Depth (pixels) of snow accumulated on the landscape every turn.
+const SNOW_DEPTH: i32 = 2;
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::RoundSetup),
+            choose_weather_system
+                .after(setup_tanks)
+                .run_if(random_weather),
+        )
+        .add_systems(Update, accumulate_snow_system);
+    }
+}
+
+fn random_weather(settings: Res<Settings>) -> bool {
+    settings.random_weather
+}
+
+fn choose_weather_system(game_rng: Option<ResMut<GameRng>>, mut weather: ResMut<Weather>) {
+    let Some(mut game_rng) = game_rng else {
+        return;
+    };
+    if let Some(&new_weather) = Weather::ALL.choose(&mut *game_rng) {
+        info!("Weather of the round: {:?}", new_weather);
+        *weather = new_weather;
+    }
+}
+
+/// Adds layer of snow of given depth on top of the landscape.
+/// Columns covered by `(left, right)` ranges are skipped to not bury tanks.
+///
+/// Returns count of added pixels.
+fn add_snow_layer(landscape: &mut Landscape, depth: i32, covered: &[(i32, i32)]) -> usize {
+    let (width, height) = landscape.size();
+    let (width, height) = (width as i32, height as i32);
+    let mut added = 0;
+    for x in 0..width {
+        if covered.iter().any(|&(left, right)| x >= left && x < right) {
+            continue;
+        }
+        let surface = settle_height(landscape, x, height);
+        for y in surface..(surface + depth).min(height) {
+            added += landscape.fill_pixels_line((x, y), 1);
+        }
+    }
+    added
+}
+
+fn accumulate_snow_system(
+    weather: Res<Weather>,
+    game_field: Option<ResMut<GameField>>,
+    mut ended_events: EventReader<TurnEndedEvent>,
+    tanks_query: Query<(&Tank, &Position)>,
+) {
+    if ended_events.read().count() == 0 || *weather != Weather::Snow {
+        return;
+    }
+    let Some(mut game_field) = game_field else {
+        return;
+    };
+    let covered: Vec<(i32, i32)> = tanks_query
+        .iter()
+        .map(|(tank, position)| {
+            let rect = tank.body_rect(position.0);
+            (rect.left.floor() as i32, rect.right.ceil() as i32)
+        })
+        .collect();
+    let added = add_snow_layer(&mut game_field.landscape, SNOW_DEPTH, &covered);
+    debug!("{} pixels of snow have fallen", added);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::landscape_buffer::LandscapeStorage;
+
+    #[test]
+    fn test_add_snow_layer() {
+        let mut landscape = Landscape::new(300, 200, 5, LandscapeStorage::default(), None).unwrap();
+        let surfaces: Vec<i32> = (0..300)
+            .map(|x| settle_height(&landscape, x, 200))
+            .collect();
+        add_snow_layer(&mut landscape, SNOW_DEPTH, &[(100, 150)]);
+        for (x, &surface) in (0..300).zip(&surfaces) {
+            let expected = if (100..150).contains(&x) {
+                surface
+            } else {
+                (surface + SNOW_DEPTH).min(200)
+            };
+            assert_eq!(settle_height(&landscape, x, 200), expected);
+        }
+    }
+}